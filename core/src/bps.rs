@@ -0,0 +1,28 @@
+/*!
+Basis points, 1/100 of a percent.
+*/
+
+/// 100%.
+pub const ONE_HUNDRED_PERCENT: u32 = 10_000;
+
+/// `bps` basis points of `balance`, rounded down.
+#[inline]
+pub fn share(balance: u128, bps: u32) -> u128 {
+    // balance / 10000 * bps doesn't overflow, the remainder is added separately.
+    let unit = balance / ONE_HUNDRED_PERCENT as u128;
+    let remainder = balance % ONE_HUNDRED_PERCENT as u128;
+    unit * bps as u128 + remainder * bps as u128 / ONE_HUNDRED_PERCENT as u128
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_share() {
+        assert_eq!(share(1_000, 250), 25);
+        assert_eq!(share(999, 1), 0);
+        assert_eq!(share(u128::MAX, ONE_HUNDRED_PERCENT), u128::MAX);
+        assert_eq!(share(u128::MAX, 5_000), u128::MAX / 2);
+    }
+}
@@ -0,0 +1,58 @@
+/*!
+Canonical JSON, the bytes that get signed or hashed.
+*/
+
+use alloc::vec::Vec;
+
+/// Compact JSON with the keys of the objects sorted by their bytes, so the same value always
+/// gives the same bytes whichever side serializes it.
+pub fn canonical_json(value: &serde_json::Value) -> Vec<u8> {
+    fn write(value: &serde_json::Value, out: &mut Vec<u8>) {
+        match value {
+            serde_json::Value::Array(values) => {
+                out.push(b'[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push(b',');
+                    }
+                    write(value, out);
+                }
+                out.push(b']');
+            }
+            serde_json::Value::Object(map) => {
+                let mut entries: Vec<_> = map.iter().collect();
+                entries.sort_unstable_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+                out.push(b'{');
+                for (i, (key, value)) in entries.into_iter().enumerate() {
+                    if i > 0 {
+                        out.push(b',');
+                    }
+                    // strings and scalars have a single compact form
+                    out.extend(serde_json::to_vec(key).unwrap());
+                    out.push(b':');
+                    write(value, out);
+                }
+                out.push(b'}');
+            }
+            scalar => out.extend(serde_json::to_vec(scalar).unwrap()),
+        }
+    }
+    let mut out = Vec::new();
+    write(value, &mut out);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_canonical_json() {
+        let value: serde_json::Value =
+            serde_json::from_str(r#"{ "b": {"y": 1, "x": "é\n"}, "a": [2, 1.5, null] }"#).unwrap();
+        assert_eq!(
+            canonical_json(&value),
+            r#"{"a":[2,1.5,null],"b":{"x":"é\n","y":1}}"#.as_bytes()
+        );
+    }
+}
@@ -0,0 +1,51 @@
+#![no_std]
+/*!
+Pure helpers of the contract, without near-sdk, so off-chain code (indexers, signers, frontends
+through wasm) computes exactly what the contract does.
+
+The contract re-exports them from `cmn`: [`hash`], `merkle` (with `env::sha256`),
+`canonical_json`, `royalty::royalty_share` and `u256::mul_div` are these.
+
+# NOTES:
+  - `#![no_std]` with `alloc`, the hash function is passed in. The `sha2` feature adds
+    [`sha256`] for the callers that don't have one.
+  - `cargo test -p contract-core` runs the tests of the shared logic alone.
+
+# EXAMPLE:
+```
+use contract_core::{bps, merkle};
+use sha2::Digest;
+
+// or `contract_core::sha256` with the `sha2` feature
+let sha256 = |bytes: &[u8]| -> [u8; 32] { sha2::Sha256::digest(bytes).into() };
+
+let leaves: Vec<_> = ["alice.near", "bob.near"]
+    .iter()
+    .map(|account_id| merkle::leaf(account_id.as_bytes(), sha256))
+    .collect();
+let root = merkle::root(&leaves, sha256);
+assert!(merkle::verify(&leaves[0], &merkle::proof(&leaves, 0, sha256), &root, sha256));
+assert_eq!(bps::share(1_000, 250), 25);
+```
+*/
+
+extern crate alloc;
+
+pub mod bps;
+pub mod json;
+pub mod math;
+pub mod merkle;
+
+/// Helper functions for hashing
+#[inline]
+pub fn hash<I: AsRef<[u8]>, O: AsRef<[u8]>>(s: I, h: fn(&[u8]) -> O) -> O {
+    h(s.as_ref())
+}
+
+/// sha256 of `bytes`, the same as `env::sha256` of the contract.
+#[cfg(feature = "sha2")]
+#[inline]
+pub fn sha256(bytes: &[u8]) -> [u8; 32] {
+    use sha2::Digest;
+    sha2::Sha256::digest(bytes).into()
+}
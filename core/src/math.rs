@@ -0,0 +1,35 @@
+/*!
+256 bits integers, for the products of two balances.
+*/
+
+mod for_uint {
+    // the code generated by `uint` is not ours to lint
+    #![allow(clippy::all)]
+    uint::construct_uint! {
+        pub struct U256(4);
+    }
+}
+pub use for_uint::U256;
+
+/// `a * b / c` rounded down, none if it doesn't fit in a `u128`.
+#[inline]
+pub fn mul_div(a: u128, b: u128, c: u128) -> Option<u128> {
+    let result = U256::from(a) * U256::from(b) / U256::from(c);
+    (result <= U256::from(u128::MAX)).then(|| result.as_u128())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_mul_div() {
+        assert_eq!(mul_div(u128::MAX, 3, 4), Some(u128::MAX / 4 * 3 + 2));
+        assert_eq!(
+            mul_div(10u128.pow(30), 10u128.pow(30), 10u128.pow(24)),
+            Some(10u128.pow(36))
+        );
+        assert_eq!(mul_div(7, 1, 2), Some(3));
+        assert_eq!(mul_div(u128::MAX, 2, 1), None);
+    }
+}
@@ -0,0 +1,93 @@
+/*!
+Merkle trees over a 32 bytes hash, the contract uses sha256.
+
+# NOTES:
+  - Leaves are `h(0x00 || data)` and nodes `h(0x01 || min(a, b) || max(a, b))`,
+    the prefixes keep a node from passing as a leaf, and the sorted pairs make proofs
+    independent of the side of the siblings.
+  - The last node of an odd level is promoted as it is, the root of no leaves is all zeros.
+*/
+
+use alloc::{vec, vec::Vec};
+
+pub type Hash = [u8; 32];
+
+const LEAF_PREFIX: u8 = 0;
+const NODE_PREFIX: u8 = 1;
+
+#[inline]
+pub fn leaf(data: &[u8], h: fn(&[u8]) -> Hash) -> Hash {
+    h(&[&[LEAF_PREFIX], data].concat())
+}
+
+#[inline]
+pub fn node(a: &Hash, b: &Hash, h: fn(&[u8]) -> Hash) -> Hash {
+    let (min, max) = if a <= b { (a, b) } else { (b, a) };
+    h(&[&[NODE_PREFIX][..], min, max].concat())
+}
+
+fn next_level(level: &[Hash], h: fn(&[u8]) -> Hash) -> Vec<Hash> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [a, b] => node(a, b, h),
+            [a] => *a,
+            _ => unreachable!(),
+        })
+        .collect()
+}
+
+pub fn root(leaves: &[Hash], h: fn(&[u8]) -> Hash) -> Hash {
+    if leaves.is_empty() {
+        return [0; 32];
+    }
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        level = next_level(&level, h);
+    }
+    level[0]
+}
+
+/// Siblings of the leaf at `index` from the bottom, empty for a promoted node.
+pub fn proof(leaves: &[Hash], mut index: usize, h: fn(&[u8]) -> Hash) -> Vec<Hash> {
+    assert!(index < leaves.len(), "Leaf index out of bounds");
+    let mut proof = vec![];
+    let mut level = leaves.to_vec();
+    while level.len() > 1 {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        level = next_level(&level, h);
+        index /= 2;
+    }
+    proof
+}
+
+pub fn verify(leaf: &Hash, proof: &[Hash], root: &Hash, h: fn(&[u8]) -> Hash) -> bool {
+    &proof
+        .iter()
+        .fold(*leaf, |hash, sibling| node(&hash, sibling, h))
+        == root
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use sha2::Digest;
+
+    fn sha256(bytes: &[u8]) -> Hash {
+        sha2::Sha256::digest(bytes).into()
+    }
+
+    #[test]
+    fn check_proofs() {
+        for n in 1..=7 {
+            let leaves: Vec<_> = (0..n).map(|i| leaf(&[i as u8], sha256)).collect();
+            let root = root(&leaves, sha256);
+            for (i, leaf) in leaves.iter().enumerate() {
+                assert!(verify(leaf, &proof(&leaves, i, sha256), &root, sha256));
+            }
+        }
+    }
+}
@@ -0,0 +1,339 @@
+/*!
+TypeScript bindings generator for the contract ABI.
+
+Reads the ABI emitted by `cargo near abi` and writes a typed TS client with
+one method per contract function (standard methods generated by the impl macros
+as well as user methods), their argument/return types and payable flags.
+
+# USAGE:
+```shell
+cargo near abi
+cargo run --features bindgen --bin bindgen -- ./target/near/contract_abi.json ./build/contract.ts
+# or
+make bindgen
+```
+*/
+
+use near_sdk::serde_json::{Map, Value};
+use std::{env, fmt::Write, fs, process};
+
+const DEFAULT_ABI_PATH: &str = "./target/near/contract_abi.json";
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let abi_path = args.next().unwrap_or_else(|| DEFAULT_ABI_PATH.to_string());
+    let out_path = args.next();
+
+    let abi = fs::read_to_string(&abi_path).unwrap_or_else(|e| {
+        eprintln!("Cannot read ABI file `{}`: {}", abi_path, e);
+        process::exit(1);
+    });
+    let abi: Value = near_sdk::serde_json::from_str(&abi).unwrap_or_else(|e| {
+        eprintln!("Invalid ABI file `{}`: {}", abi_path, e);
+        process::exit(1);
+    });
+
+    let ts = generate(&abi);
+    match out_path {
+        Some(path) => fs::write(&path, ts).unwrap_or_else(|e| {
+            eprintln!("Cannot write bindings to `{}`: {}", path, e);
+            process::exit(1);
+        }),
+        None => print!("{}", ts),
+    }
+}
+
+/// Generates the whole TS module from the ABI root object.
+fn generate(abi: &Value) -> String {
+    let mut ts = String::new();
+    let name = abi["metadata"]["name"].as_str().unwrap_or("contract");
+    let version = abi["metadata"]["version"].as_str().unwrap_or("0.0.0");
+
+    _ = writeln!(
+        ts,
+        "// Generated by `bindgen` from the {} v{} ABI.",
+        name, version
+    );
+    _ = writeln!(ts, "// DO NOT EDIT.\n");
+
+    // type definitions
+    if let Some(defs) = abi["body"]["root_schema"]["definitions"].as_object() {
+        for (def_name, schema) in defs {
+            _ = writeln!(ts, "export type {} = {};", def_name, ts_type(schema));
+        }
+        ts.push('\n');
+    }
+
+    _ = writeln!(
+        ts,
+        "export interface ContractAccount {{
+  viewFunction(options: {{ contractId: string; methodName: string; args?: object }}): Promise<any>;
+  functionCall(options: {{
+    contractId: string;
+    methodName: string;
+    args?: object;
+    gas?: string;
+    attachedDeposit?: string;
+  }}): Promise<any>;
+}}
+
+export interface CallOptions {{
+  gas?: string;
+  attachedDeposit?: string;
+}}
+"
+    );
+
+    let functions = abi["body"]["functions"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+
+    // methods table
+    _ = writeln!(ts, "export const METHODS = {{");
+    for f in &functions {
+        _ = writeln!(
+            ts,
+            "  {}: {{ kind: \"{}\", payable: {}, init: {}, private: {} }},",
+            f["name"].as_str().unwrap_or_default(),
+            f["kind"].as_str().unwrap_or("call"),
+            has_modifier(f, "payable"),
+            has_modifier(f, "init"),
+            has_modifier(f, "private"),
+        );
+    }
+    _ = writeln!(ts, "}} as const;\n");
+
+    // args interfaces
+    for f in &functions {
+        let args = args_of(f);
+        if args.is_empty() {
+            continue;
+        }
+        _ = writeln!(ts, "export interface {}Args {{", pascal_case(fn_name(f)));
+        for arg in args {
+            let (ty, optional) = ts_arg_type(&arg["type_schema"]);
+            _ = writeln!(
+                ts,
+                "  {}{}: {};",
+                arg["name"].as_str().unwrap_or_default(),
+                if optional { "?" } else { "" },
+                ty
+            );
+        }
+        _ = writeln!(ts, "}}\n");
+    }
+
+    // client
+    _ = writeln!(ts, "export class ContractClient {{");
+    _ = writeln!(
+        ts,
+        "  constructor(public readonly account: ContractAccount, public readonly contractId: string) {{}}\n"
+    );
+    for f in &functions {
+        // private methods are callbacks and init methods are called on deploy.
+        if has_modifier(f, "private") || has_modifier(f, "init") {
+            continue;
+        }
+        let name = fn_name(f);
+        let args_ty = if args_of(f).is_empty() {
+            None
+        } else {
+            Some(format!("{}Args", pascal_case(name)))
+        };
+        let ret = match f["result"].get("type_schema") {
+            Some(schema) => ts_type(schema),
+            None => "void".to_string(),
+        };
+        let params = match &args_ty {
+            Some(ty) => format!("args: {}", ty),
+            None => String::new(),
+        };
+        let args_expr = if args_ty.is_some() { "args" } else { "{}" };
+
+        if f["kind"].as_str() == Some("view") {
+            _ = writeln!(
+                ts,
+                "  {}({}): Promise<{}> {{
+    return this.account.viewFunction({{ contractId: this.contractId, methodName: \"{}\", args: {} }});
+  }}\n",
+                name, params, ret, name, args_expr
+            );
+        } else {
+            let opts = if has_modifier(f, "payable") {
+                "options: CallOptions & { attachedDeposit: string }"
+            } else {
+                "options: Omit<CallOptions, \"attachedDeposit\"> = {}"
+            };
+            let params = if params.is_empty() {
+                opts.to_string()
+            } else {
+                format!("{}, {}", params, opts)
+            };
+            _ = writeln!(
+                ts,
+                "  {}({}): Promise<{}> {{
+    return this.account.functionCall({{ contractId: this.contractId, methodName: \"{}\", args: {}, ...options }});
+  }}\n",
+                name, params, ret, name, args_expr
+            );
+        }
+    }
+    if ts.ends_with("\n\n") {
+        ts.pop();
+    }
+    _ = writeln!(ts, "}}");
+    ts
+}
+
+#[inline]
+fn fn_name(f: &Value) -> &str {
+    f["name"].as_str().unwrap_or_default()
+}
+
+#[inline]
+fn args_of(f: &Value) -> Vec<Value> {
+    f["params"]["args"].as_array().cloned().unwrap_or_default()
+}
+
+#[inline]
+fn has_modifier(f: &Value, modifier: &str) -> bool {
+    f["modifiers"]
+        .as_array()
+        .map(|m| m.iter().any(|v| v.as_str() == Some(modifier)))
+        .unwrap_or(false)
+}
+
+fn pascal_case(s: &str) -> String {
+    s.split('_')
+        .map(|w| {
+            let mut c = w.chars();
+            match c.next() {
+                Some(first) => first.to_uppercase().chain(c).collect::<String>(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
+
+/// `Option<T>` arguments become optional fields.
+fn ts_arg_type(schema: &Value) -> (String, bool) {
+    let ty = ts_type(schema);
+    let optional = ty.ends_with(" | null");
+    (ty, optional)
+}
+
+/// Converts a JSON schema (as emitted by schemars) into a TS type expression.
+fn ts_type(schema: &Value) -> String {
+    let obj = match schema {
+        Value::Bool(true) => return "any".to_string(),
+        Value::Bool(false) => return "never".to_string(),
+        Value::Object(obj) => obj,
+        _ => return "any".to_string(),
+    };
+
+    if let Some(r) = obj.get("$ref").and_then(Value::as_str) {
+        return r.rsplit('/').next().unwrap_or("any").to_string();
+    }
+    if let Some(values) = obj.get("enum").and_then(Value::as_array) {
+        return union(values.iter().map(|v| v.to_string()));
+    }
+    if let Some(v) = obj.get("const") {
+        return v.to_string();
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(variants) = obj.get(key).and_then(Value::as_array) {
+            return union(variants.iter().map(ts_type));
+        }
+    }
+    if let Some(all) = obj.get("allOf").and_then(Value::as_array) {
+        return all.iter().map(ts_type).collect::<Vec<_>>().join(" & ");
+    }
+
+    match obj.get("type") {
+        Some(Value::String(t)) => ts_primitive(t, obj),
+        Some(Value::Array(types)) => union(
+            types
+                .iter()
+                .filter_map(Value::as_str)
+                .map(|t| ts_primitive(t, obj)),
+        ),
+        _ => "any".to_string(),
+    }
+}
+
+fn ts_primitive(t: &str, obj: &Map<String, Value>) -> String {
+    match t {
+        "string" => "string".to_string(),
+        "integer" | "number" => "number".to_string(),
+        "boolean" => "boolean".to_string(),
+        "null" => "null".to_string(),
+        "array" => match obj.get("items") {
+            Some(Value::Array(items)) => {
+                format!(
+                    "[{}]",
+                    items.iter().map(ts_type).collect::<Vec<_>>().join(", ")
+                )
+            }
+            Some(items) => format!("{}[]", wrap(ts_type(items))),
+            None => "any[]".to_string(),
+        },
+        "object" => ts_object(obj),
+        _ => "any".to_string(),
+    }
+}
+
+fn ts_object(obj: &Map<String, Value>) -> String {
+    let required: Vec<&str> = obj
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|r| r.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+
+    match obj.get("properties").and_then(Value::as_object) {
+        Some(props) if !props.is_empty() => {
+            let fields = props
+                .iter()
+                .map(|(k, v)| {
+                    let optional = if required.contains(&k.as_str()) {
+                        ""
+                    } else {
+                        "?"
+                    };
+                    format!("{}{}: {}", k, optional, ts_type(v))
+                })
+                .collect::<Vec<_>>()
+                .join("; ");
+            format!("{{ {} }}", fields)
+        }
+        _ => match obj.get("additionalProperties") {
+            Some(Value::Bool(false)) => "{}".to_string(),
+            Some(v @ Value::Object(_)) => format!("Record<string, {}>", ts_type(v)),
+            _ => "Record<string, any>".to_string(),
+        },
+    }
+}
+
+fn union(types: impl Iterator<Item = String>) -> String {
+    let mut out: Vec<String> = vec![];
+    for t in types {
+        if !out.contains(&t) {
+            out.push(t);
+        }
+    }
+    // keep `null` last so optional detection stays simple
+    if let Some(i) = out.iter().position(|t| t == "null") {
+        let null = out.remove(i);
+        out.push(null);
+    }
+    out.join(" | ")
+}
+
+#[inline]
+fn wrap(t: String) -> String {
+    if t.contains(' ') {
+        format!("({})", t)
+    } else {
+        t
+    }
+}
@@ -0,0 +1,80 @@
+/*!
+Raw state exporter, pages the `view_state` RPC result of a contract into `import_state` args.
+
+Reads the RPC response (or its `result`) from stdin and prints a page of the entries with keys
+starting with `prefix`, after the `from` key, both base64. When the page is full, the next one
+starts from its last key, printed to stderr.
+
+# USAGE:
+```shell
+curl -s https://rpc.mainnet.near.org -H 'Content-Type: application/json' -d '{
+    "jsonrpc": "2.0", "id": "0", "method": "query",
+    "params": { "request_type": "view_state", "finality": "final",
+                "account_id": "<contract>", "prefix_base64": "" }
+}' > ./build/view_state.json
+cargo run --features state_export --bin export_state -- [prefix] [from] [limit] \
+    < ./build/view_state.json > ./build/export_state.json
+# or
+make export_state prefix= from= limit=
+# then, on the paused (or not yet initialized) testnet contract
+near call <testnet contract> import_state "$(cat ./build/export_state.json)" --accountId <owner>
+```
+*/
+
+#[path = "../cmn/mod.rs"]
+mod cmn;
+use cmn::*;
+
+use near_sdk::serde_json::{self, json, Value};
+use std::{env, io, process};
+
+fn exit(message: String) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn base64_arg(index: usize) -> Option<Vec<u8>> {
+    env::args()
+        .nth(index)
+        .filter(|arg| !arg.is_empty())
+        .map(|arg| {
+            serde_json::from_value::<Base64VecU8>(Value::String(arg))
+                .unwrap_or_else(|e| exit(format!("Invalid base64 argument {}: {}", index, e)))
+                .0
+        })
+}
+
+fn main() {
+    let prefix = base64_arg(1).unwrap_or_default();
+    let from = base64_arg(2);
+    let limit = env::args().nth(3).filter(|arg| !arg.is_empty()).map(|arg| {
+        arg.parse()
+            .unwrap_or_else(|e| exit(format!("Invalid limit: {}", e)))
+    });
+
+    let response: Value = serde_json::from_reader(io::stdin())
+        .unwrap_or_else(|e| exit(format!("Invalid view_state response: {}", e)));
+    let values = response
+        .get("result")
+        .unwrap_or(&response)
+        .get("values")
+        .cloned()
+        .unwrap_or_else(|| exit("No `values` in the view_state response".to_string()));
+    let entries = serde_json::from_value::<Vec<Value>>(values)
+        .unwrap_or_else(|e| exit(format!("Invalid values: {}", e)))
+        .into_iter()
+        .map(|value| {
+            serde_json::from_value::<state_export::StateEntry>(json!([
+                value["key"],
+                value["value"]
+            ]))
+            .unwrap_or_else(|e| exit(format!("Invalid entry: {}", e)))
+        });
+
+    let page = state_export::export_state(entries, &prefix, from.as_deref(), limit);
+    let full = page.len() == limit.unwrap_or(state_export::DEFAULT_EXPORT_LIMIT);
+    if let Some((key, _)) = page.last().filter(|_| full) {
+        eprintln!("next from: {}", serde_json::to_value(key).unwrap());
+    }
+    println!("{}", json!({ "entries": page }));
+}
@@ -0,0 +1,19 @@
+/*!
+Deployment manifest generator.
+
+Prints `Contract::manifest()` as JSON.
+
+# USAGE:
+```shell
+cargo run --features manifest --bin manifest > ./build/manifest.json
+# or
+make manifest
+```
+*/
+#![allow(dead_code)]
+
+include!("../lib.rs");
+
+fn main() {
+    println!("{}", Contract::manifest().to_json());
+}
@@ -0,0 +1,40 @@
+/*!
+JSON Schema generator for init args, metadata structs and events.
+
+# USAGE:
+```shell
+cargo run --features schema --bin schema -- ./build/schema
+# or
+make schema
+```
+*/
+
+#[path = "../cmn/mod.rs"]
+mod cmn;
+use cmn::*;
+
+use std::{env, fs, path::PathBuf, process};
+
+const DEFAULT_OUT_DIR: &str = "./build/schema";
+
+fn main() {
+    let out_dir = PathBuf::from(
+        env::args()
+            .nth(1)
+            .unwrap_or_else(|| DEFAULT_OUT_DIR.to_string()),
+    );
+    fs::create_dir_all(&out_dir).unwrap_or_else(|e| {
+        eprintln!("Cannot create `{}`: {}", out_dir.display(), e);
+        process::exit(1);
+    });
+
+    for (name, schema) in schema::schemas() {
+        let path = out_dir.join(format!("{}.schema.json", name));
+        let json = near_sdk::serde_json::to_string_pretty(&schema).expect("Invalid schema");
+        fs::write(&path, json).unwrap_or_else(|e| {
+            eprintln!("Cannot write `{}`: {}", path.display(), e);
+            process::exit(1);
+        });
+        println!("{}", path.display());
+    }
+}
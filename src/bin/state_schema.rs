@@ -0,0 +1,23 @@
+/*!
+Borsh schema generator for the stored state.
+
+Prints the schema of `Contract` and the records of every enabled component as JSON.
+
+# USAGE:
+```shell
+cargo run --features state_schema --bin state_schema > ./build/state_schema.json
+# or
+make state_schema
+```
+*/
+#![allow(dead_code)]
+
+include!("../lib.rs");
+
+fn main() {
+    let schema = state_schema::Schema::new::<Contract>().components(state_schema::components());
+    println!(
+        "{}",
+        near_sdk::serde_json::to_string_pretty(&schema.to_json()).expect("Invalid schema")
+    );
+}
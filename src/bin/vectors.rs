@@ -0,0 +1,107 @@
+/*!
+Test vectors generator for off-chain implementations.
+
+Prints, as JSON, solution hashes, events and merkle roots of sample inputs, computed by the same
+code as the contract, so indexers, signers and frontends can check their implementations.
+
+# USAGE:
+```shell
+cargo run --bin vectors > ./build/vectors.json
+# or
+make vectors
+```
+*/
+
+#[path = "../cmn/mod.rs"]
+mod cmn;
+use cmn::*;
+
+use near_sdk::serde_json::{self, json, Value};
+
+const SOLUTIONS: [(&str, &str); 3] = [
+    ("", "near"),
+    ("", "correct horse battery staple"),
+    ("8f3b1c2d", "near"),
+];
+
+const ACCOUNTS: [&str; 5] = [
+    "alice.near",
+    "bob.near",
+    "carol.near",
+    "dave.near",
+    "eve.near",
+];
+
+/// The contract stores `sha256(text)` of the solution, a salt is prepended to the solution.
+fn solution_hashes() -> Value {
+    SOLUTIONS
+        .iter()
+        .map(|(salt, solution)| {
+            let text = format!("{}{}", salt, solution);
+            json!({
+                "salt": salt,
+                "solution": solution,
+                "sha256": hash(&text, env::sha256).encode_hex::<String>(),
+            })
+        })
+        .collect()
+}
+
+/// The `EVENT_JSON:` logs of sample events.
+fn events() -> Value {
+    let owner_id: AccountId = ACCOUNTS[0].parse().unwrap();
+    ft::events::FtMint {
+        owner_id: &owner_id,
+        amount: &U128(1_000),
+        memo: None,
+    }
+    .emit();
+    nft::events::NftMint {
+        owner_id: &owner_id,
+        token_ids: &["0"],
+        memo: None,
+    }
+    .emit();
+    events::emit(
+        events::standards::BOUNTY,
+        "bounty_post",
+        json!({ "id": "0", "poster_id": owner_id }),
+    );
+    near_sdk::test_utils::get_logs().into()
+}
+
+fn merkle_trees() -> Value {
+    (1..=ACCOUNTS.len())
+        .map(|n| {
+            let accounts = &ACCOUNTS[..n];
+            let leaves: Vec<_> = accounts
+                .iter()
+                .map(|account_id| merkle::leaf(account_id.as_bytes()))
+                .collect();
+            let proofs: Vec<Vec<String>> = (0..n)
+                .map(|i| {
+                    merkle::proof(&leaves, i)
+                        .iter()
+                        .map(|hash| hash.encode_hex())
+                        .collect()
+                })
+                .collect();
+            json!({
+                "accounts": accounts,
+                "leaves": leaves.iter().map(|hash| hash.encode_hex()).collect::<Vec<String>>(),
+                "root": merkle::root(&leaves).encode_hex::<String>(),
+                "proofs": proofs,
+            })
+        })
+        .collect()
+}
+
+fn main() {
+    let vectors = json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "solution_hashes": solution_hashes(),
+        "events": events(),
+        "merkle_trees": merkle_trees(),
+    });
+    println!("{}", serde_json::to_string_pretty(&vectors).unwrap());
+}
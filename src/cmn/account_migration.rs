@@ -0,0 +1,245 @@
+#![cfg(feature = "account_migration")]
+#![allow(dead_code)]
+/*!
+Moving everything an account holds to a new account, E.g. when its keys are compromised.
+
+`impl_account_migration!` generates `migrate_account(new_account_id, ..)` which moves, from the
+predecessor to `new_account_id`, the ft balance with its storage registration, the nft tokens and
+the given names of the registry.
+
+# NOTES:
+  - Every call is atomic, but the nft tokens move at most `nft_limit` per call
+    ([`NFT_BATCH_LIMIT`] by default). Call again until `nft_remaining` is 0.
+  - Approvals of the moved tokens and the nft operators of the old account are dropped,
+    since whoever compromised it could have added them.
+  - If `new_account_id` is already registered in ft, the released registration of the old
+    account is refunded to it.
+  - The registry has no index of names by owner, so the names to move are passed in `names`.
+  - Events are logged as `EVENT_JSON:` with the `account_migration` standard, along with the
+    transfers of every moved holding.
+
+# EXAMPLE:
+Needs the `nft` and `names` features too.
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    nft: nft::NonFungibleToken,
+    names: names::Names,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            nft: nft::NonFungibleToken::from_args(nft::InitArgs {
+                owner_id: Some(owner_id),
+                spec: None,
+                name: "Migrated".to_string(),
+                symbol: "MIG".to_string(),
+                icon: None,
+                base_uri: None,
+                reference: None,
+                reference_hash: None,
+                verify_hashes: false,
+                provenance: false,
+                attributes: false,
+            }),
+            names: names::Names::new(Default::default()),
+        }
+    }
+}
+
+nft::impl_non_fungible_token_contract!(Contract, nft);
+names::impl_names!(Contract, names);
+// impl_account_migration!(Contract[, ft = <field>][, nft = <field>][, names = <field>]);
+account_migration::impl_account_migration!(Contract, nft = nft, names = names);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+    fn token_metadata() -> nft::TokenMetadata {
+        nft::TokenMetadata {
+            title: Some("Token".to_string()),
+            description: None,
+            media: None,
+            media_hash: None,
+            copies: None,
+            issued_at: None,
+            expires_at: None,
+            starts_at: None,
+            updated_at: None,
+            extra: None,
+            reference: None,
+            reference_hash: None,
+        }
+    }
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+        for token_id in ["0", "1", "2"] {
+            run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(NEAR / 10));
+            contract.nft_mint(token_id.to_string(), accounts(1), token_metadata());
+        }
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR / 10)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.names_register("bob".to_string(), None);
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_migrate_account() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(1)),
+        );
+        let migration =
+            contract.migrate_account(accounts(2), Some(2), Some(vec!["bob".to_string()]));
+        assert_eq!(migration.nft_token_ids.len(), 2);
+        assert_eq!(migration.nft_remaining, U64(1));
+        assert_eq!(contract.names_resolve("bob".to_string()), Some(accounts(2)));
+
+        run_vm(vm.storage_usage(env::storage_usage()));
+        let migration = contract.migrate_account(accounts(2), Some(2), None);
+        assert_eq!(migration.nft_token_ids.len(), 1);
+        assert_eq!(migration.nft_remaining, U64(0));
+        assert_eq!(contract.nft.supply_for_owner(&accounts(1)), 0);
+        assert_eq!(contract.nft.supply_for_owner(&accounts(2)), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner of the name can do this")]
+    fn test_migrate_others_name() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(2)),
+        );
+        contract.migrate_account(accounts(3), None, Some(vec!["bob".to_string()]));
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::{
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+
+/// Default number of nft tokens moved per call.
+pub const NFT_BATCH_LIMIT: u32 = 50;
+
+/// What a `migrate_account` call moved.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct AccountMigration {
+    pub old_account_id: AccountId,
+    pub new_account_id: AccountId,
+    pub ft_amount: U128,
+    pub nft_token_ids: Vec<String>,
+    /// Tokens still owned by the old account.
+    pub nft_remaining: U64,
+    pub names: Vec<String>,
+}
+impl AccountMigration {
+    /// Nothing moved yet from the predecessor to `new_account_id`.
+    pub fn new(new_account_id: AccountId) -> Self {
+        let old_account_id = env::predecessor_account_id();
+        require!(
+            old_account_id != new_account_id,
+            "The new account must differ from the old one"
+        );
+        Self {
+            old_account_id,
+            new_account_id,
+            ft_amount: U128(0),
+            nft_token_ids: vec![],
+            nft_remaining: U64(0),
+            names: vec![],
+        }
+    }
+
+    /// Logs an `account_migration` standard event.
+    pub fn emit(&self) {
+        events::emit(
+            events::standards::ACCOUNT_MIGRATION,
+            "account_migrate",
+            self,
+        );
+    }
+}
+
+#[macro_export]
+macro_rules! impl_account_migration {
+    ($contract:ident $(, ft = $ft:ident)? $(, nft = $nft:ident)? $(, names = $names:ident)? $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_account_migration!`.
+            pub const ACCOUNT_MIGRATION_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: Some("account_migration"),
+                methods: &[$crate::manifest::Method::payable("migrate_account")],
+                storage: &[],
+            };
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Moves the holdings of the predecessor to `new_account_id`,
+            /// at most `nft_limit` nft tokens per call.
+            #[payable]
+            #[allow(unused_variables, unused_mut)]
+            pub fn migrate_account(
+                &mut self,
+                new_account_id: AccountId,
+                nft_limit: Option<u32>,
+                names: Option<Vec<String>>,
+            ) -> $crate::account_migration::AccountMigration {
+                near_sdk::assert_one_yocto();
+                let mut migration = $crate::account_migration::AccountMigration::new(new_account_id);
+                let old_account_id = migration.old_account_id.clone();
+                let new_account_id = migration.new_account_id.clone();
+                $(
+                    migration.ft_amount =
+                        U128(self.$ft.migrate_account(&old_account_id, &new_account_id));
+                )?
+                $(
+                    let (token_ids, remaining) = self.$nft.migrate_account(
+                        &old_account_id,
+                        &new_account_id,
+                        nft_limit.unwrap_or($crate::account_migration::NFT_BATCH_LIMIT),
+                    );
+                    migration.nft_token_ids = token_ids;
+                    migration.nft_remaining = U64(remaining);
+                )?
+                $(
+                    let names = names.unwrap_or_default();
+                    self.$names.migrate_account(&old_account_id, &new_account_id, &names);
+                    migration.names = names;
+                )?
+                migration.emit();
+                migration
+            }
+        }
+    };
+}
+pub use impl_account_migration;
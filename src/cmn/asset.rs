@@ -0,0 +1,91 @@
+#![allow(dead_code)]
+/*!
+NEAR or FT held by the contract.
+
+An [`Asset`] is an amount of NEAR or of a NEP-141 token, [`Asset::transfer`] sends it from the
+contract, so the components paying out rewards, bequests and the like share one type and one flow.
+
+# NOTES:
+  - FT is sent with `ft_transfer` and 1 yoctoNEAR, the receiver must be registered in the token.
+    Chain a callback to the returned promise when [`Asset::is_ft`] to handle a failed transfer,
+    a NEAR transfer to an existing account doesn't fail.
+  - The JSON is `{"near": "<amount>"}` or `{"ft": {"token_id": "<token>", "amount": "<amount>"}}`.
+*/
+
+use super::*;
+
+use near_sdk::{
+    ext_contract,
+    serde::{Deserialize, Serialize},
+};
+
+/// Gas for the `ft_transfer` of an FT asset.
+pub const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000);
+
+#[ext_contract(ext_ft)]
+pub trait FtTransfer {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum Asset {
+    Near(U128),
+    Ft { token_id: AccountId, amount: U128 },
+}
+impl Asset {
+    #[inline]
+    pub fn is_ft(&self) -> bool {
+        matches!(self, Self::Ft { .. })
+    }
+
+    /// Sends the asset from the contract to `receiver_id`, `memo` only goes with FT.
+    pub fn transfer(&self, receiver_id: AccountId, memo: Option<String>) -> Promise {
+        match self {
+            Self::Near(amount) => Promise::new(receiver_id).transfer(amount.0),
+            Self::Ft { token_id, amount } => ext_ft::ext(token_id.clone())
+                .with_attached_deposit(1)
+                .with_static_gas(GAS_FOR_FT_TRANSFER)
+                .ft_transfer(receiver_id, *amount, memo),
+        }
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_ft_transfer() {
+        run_vm(vm!("alice.near"));
+
+        let asset = Asset::Ft {
+            token_id: accounts(1),
+            amount: U128(10),
+        };
+        assert!(asset.is_ft());
+        let _ = asset.transfer(accounts(2), Some("memo".to_string()));
+
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(1));
+        match &receipts[0].actions[0] {
+            VmAction::FunctionCall {
+                function_name,
+                args,
+                gas,
+                deposit,
+            } => {
+                assert_eq!(function_name, "ft_transfer");
+                assert_eq!(
+                    args,
+                    br#"{"receiver_id":"charlie","amount":"10","memo":"memo"}"#
+                );
+                assert_eq!(*gas, GAS_FOR_FT_TRANSFER);
+                assert_eq!(*deposit, 1);
+            }
+            action => panic!("Unexpected action: {:?}", action),
+        }
+    }
+}
@@ -0,0 +1,381 @@
+#![cfg(feature = "attest")]
+#![allow(dead_code)]
+/*!
+Attestations: claims of issuers about subjects.
+
+An issuer writes a claim about a subject under a schema id (E.g. `"kyc"`), the claim only holds
+the hash of the data and an optional expiry. Verifiers, or the contract itself in its transfer
+guards, check them with [`Attestations::is_attested`].
+
+# NOTES:
+  - Only issuers added by the owner can attest, an issuer has at most one claim per subject and schema.
+  - The issuer pays the storage of the claim, it's refunded when the claim is revoked.
+  - A subject can revoke its consent to a claim at any time, which removes it.
+  - Events are logged as `EVENT_JSON:` with the `attest` standard.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    attestations: attest::Attestations,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            owner_id,
+            attestations: attest::Attestations::new(),
+        }
+    }
+
+    pub fn can_trade(&self, account_id: AccountId) -> bool {
+        self.attestations.is_attested(&account_id, "kyc")
+    }
+}
+
+// impl_attestations!(Contract, attestations[, owner = <AccountId field; default: the contract itself>]);
+attest::impl_attestations!(Contract, attestations, owner = owner_id);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+        run_vm(vm.attached_deposit(1));
+        contract.attest_add_issuer(accounts(1));
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_attest_and_revoke_consent() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR / 100)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.attest(
+            accounts(2),
+            "kyc".to_string(),
+            Base64VecU8(vec![0; 32]),
+            Some(U64(100)),
+        );
+        assert!(contract.can_trade(accounts(2)));
+        assert_eq!(contract.get_claims(accounts(2), "kyc".to_string()).len(), 1);
+
+        run_vm(vm.storage_usage(env::storage_usage()).block_timestamp(100));
+        assert!(!contract.can_trade(accounts(2)));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(2)),
+        );
+        contract.attest_revoke_consent("kyc".to_string(), accounts(1));
+        assert!(contract.get_claims(accounts(2), "kyc".to_string()).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only issuers can attest")]
+    fn test_attest_not_issuer() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.attached_deposit(NEAR / 100)
+                .predecessor_account_id(accounts(2)),
+        );
+        contract.attest(accounts(2), "kyc".to_string(), Base64VecU8(vec![0; 32]), None);
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::{
+    collections::{LookupMap, UnorderedSet},
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+
+/// Schema id, E.g. `"kyc"`.
+pub type SchemaId = String;
+
+pub const MAX_SCHEMA_ID_LENGTH: usize = 64;
+
+raw_storage_key!(pub enum StorageKey in storage_key::ATTEST {
+    Issuers,
+    Claims,
+});
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Claim {
+    pub issuer_id: AccountId,
+    /// sha256 of the data, the data itself stays off-chain.
+    pub data_hash: Base64VecU8,
+    /// Block timestamp (nanoseconds).
+    pub issued_at: U64,
+    /// Block timestamp (nanoseconds), `None` never expires.
+    pub expires_at: Option<U64>,
+    /// Storage paid by the issuer.
+    pub storage_deposit: U128,
+}
+impl Claim {
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.expires_at
+            .map_or(true, |expires_at| env::block_timestamp() < expires_at.0)
+    }
+}
+
+/// Logs an `attest` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    events::emit(events::standards::ATTEST, event, data);
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Attestations {
+    pub issuers: UnorderedSet<AccountId>,
+    /// Claims of every issuer per subject and schema.
+    pub claims: LookupMap<(AccountId, SchemaId), Vec<Claim>>,
+}
+impl Default for Attestations {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Attestations {
+    pub fn new() -> Self {
+        Self {
+            issuers: UnorderedSet::new(StorageKey::Issuers),
+            claims: LookupMap::new(StorageKey::Claims),
+        }
+    }
+
+    #[inline]
+    pub fn is_issuer(&self, account_id: &AccountId) -> bool {
+        self.issuers.contains(account_id)
+    }
+
+    /// All the claims, expired ones included.
+    #[inline]
+    pub fn get_claims(&self, subject_id: &AccountId, schema_id: &str) -> Vec<Claim> {
+        self.claims
+            .get(&(subject_id.clone(), schema_id.to_string()))
+            .unwrap_or_default()
+    }
+
+    /// Whether the subject has an unexpired claim under the schema.
+    pub fn is_attested(&self, subject_id: &AccountId, schema_id: &str) -> bool {
+        self.get_claims(subject_id, schema_id)
+            .iter()
+            .any(Claim::is_valid)
+    }
+
+    /// Writes the claim of the predecessor, replacing its previous one.
+    pub fn attest(
+        &mut self,
+        subject_id: AccountId,
+        schema_id: SchemaId,
+        data_hash: Base64VecU8,
+        expires_at: Option<U64>,
+    ) {
+        let issuer_id = env::predecessor_account_id();
+        require!(self.is_issuer(&issuer_id), "Only issuers can attest");
+        validate::validate!(schema_id => validate::non_empty, validate::max_len(MAX_SCHEMA_ID_LENGTH));
+        require!(data_hash.0.len() == 32, "data_hash must be 32 bytes");
+
+        let mut refund = self.remove(&subject_id, &schema_id, &issuer_id);
+        let initial_storage_usage = env::storage_usage();
+        let key = (subject_id.clone(), schema_id.clone());
+        let mut claims = self.claims.get(&key).unwrap_or_default();
+        let mut claim = Claim {
+            issuer_id: issuer_id.clone(),
+            data_hash,
+            issued_at: U64(env::block_timestamp()),
+            expires_at,
+            storage_deposit: U128(0),
+        };
+        claims.push(claim.clone());
+        self.claims.insert(&key, &claims);
+        let storage_deposit =
+            protocol::storage_cost(env::storage_usage().saturating_sub(initial_storage_usage));
+        // the deposit amount has the same size whatever it is
+        claim.storage_deposit = U128(storage_deposit);
+        *claims.last_mut().unwrap() = claim.clone();
+        self.claims.insert(&key, &claims);
+
+        refund += env::attached_deposit();
+        require!(
+            refund >= storage_deposit,
+            format!("Must attach {} yoctoNEAR to cover storage", storage_deposit)
+        );
+        if refund > storage_deposit {
+            Promise::new(issuer_id).transfer(refund - storage_deposit);
+        }
+
+        emit(
+            "attest",
+            serde_json::json!({ "subject_id": subject_id, "schema_id": schema_id, "claim": claim }),
+        );
+    }
+
+    /// Removes the claim of the issuer and returns its storage deposit, 0 if there's none.
+    fn remove(
+        &mut self,
+        subject_id: &AccountId,
+        schema_id: &str,
+        issuer_id: &AccountId,
+    ) -> Balance {
+        let key = (subject_id.clone(), schema_id.to_string());
+        let mut claims = self.claims.get(&key).unwrap_or_default();
+        let Some(index) = claims
+            .iter()
+            .position(|claim| &claim.issuer_id == issuer_id)
+        else {
+            return 0;
+        };
+        let claim = claims.swap_remove(index);
+        if claims.is_empty() {
+            self.claims.remove(&key);
+        } else {
+            self.claims.insert(&key, &claims);
+        }
+        claim.storage_deposit.0
+    }
+
+    /// Removes a claim and refunds its storage to the issuer.
+    pub fn revoke(&mut self, subject_id: AccountId, schema_id: SchemaId, issuer_id: AccountId) {
+        let storage_deposit = self.remove(&subject_id, &schema_id, &issuer_id);
+        require!(storage_deposit > 0, "Claim not found");
+        Promise::new(issuer_id.clone()).transfer(storage_deposit);
+
+        emit(
+            "attest_revoke",
+            serde_json::json!({
+                "subject_id": subject_id,
+                "schema_id": schema_id,
+                "issuer_id": issuer_id,
+                "revoker_id": env::predecessor_account_id(),
+            }),
+        );
+    }
+}
+
+#[macro_export]
+macro_rules! impl_attestations {
+        ($contract:ident, $attestations:ident $(, owner = $owner:ident)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_attestations!`.
+                pub const ATTEST_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("attest"),
+                    methods: &[
+                        $crate::manifest::Method::payable("attest_add_issuer").role("owner"),
+                        $crate::manifest::Method::payable("attest_remove_issuer").role("owner"),
+                        $crate::manifest::Method::payable("attest").role("issuer"),
+                        $crate::manifest::Method::payable("attest_revoke"),
+                        $crate::manifest::Method::payable("attest_revoke_consent"),
+                        $crate::manifest::Method::view("attest_issuers"),
+                        $crate::manifest::Method::view("get_claims"),
+                        $crate::manifest::Method::view("is_attested"),
+                    ],
+                    storage: &[
+                        // a 64 bytes schema id with 64 bytes subject and issuer
+                        $crate::manifest::StorageEstimate::new("attest", 350),
+                    ],
+                };
+
+                fn assert_attest_owner(&self) {
+                    near_sdk::assert_one_yocto();
+                    let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                        .cloned()
+                        .unwrap_or_else(env::current_account_id);
+                    require!(
+                        env::predecessor_account_id() == owner_id,
+                        "Only the owner can manage issuers"
+                    );
+                }
+            }
+            #[near_bindgen]
+            impl $contract {
+                #[payable]
+                pub fn attest_add_issuer(&mut self, issuer_id: AccountId) {
+                    self.assert_attest_owner();
+                    self.$attestations.issuers.insert(&issuer_id);
+                }
+
+                /// Claims of the issuer are kept until they're revoked or expire.
+                #[payable]
+                pub fn attest_remove_issuer(&mut self, issuer_id: AccountId) {
+                    self.assert_attest_owner();
+                    self.$attestations.issuers.remove(&issuer_id);
+                }
+
+                /// `data_hash` is the sha256 of the off-chain data. Requires the storage deposit.
+                #[payable]
+                pub fn attest(
+                    &mut self,
+                    subject_id: AccountId,
+                    schema_id: $crate::attest::SchemaId,
+                    data_hash: Base64VecU8,
+                    expires_at: Option<U64>,
+                ) {
+                    self.$attestations.attest(subject_id, schema_id, data_hash, expires_at)
+                }
+
+                /// Revokes a claim of the predecessor issuer.
+                #[payable]
+                pub fn attest_revoke(&mut self, subject_id: AccountId, schema_id: $crate::attest::SchemaId) {
+                    near_sdk::assert_one_yocto();
+                    self.$attestations
+                        .revoke(subject_id, schema_id, env::predecessor_account_id())
+                }
+
+                /// Revokes the consent of the predecessor subject to a claim.
+                #[payable]
+                pub fn attest_revoke_consent(
+                    &mut self,
+                    schema_id: $crate::attest::SchemaId,
+                    issuer_id: AccountId,
+                ) {
+                    near_sdk::assert_one_yocto();
+                    self.$attestations
+                        .revoke(env::predecessor_account_id(), schema_id, issuer_id)
+                }
+
+                pub fn attest_issuers(&self) -> Vec<AccountId> {
+                    self.$attestations.issuers.to_vec()
+                }
+
+                pub fn get_claims(
+                    &self,
+                    subject_id: AccountId,
+                    schema_id: $crate::attest::SchemaId,
+                ) -> Vec<$crate::attest::Claim> {
+                    self.$attestations.get_claims(&subject_id, &schema_id)
+                }
+
+                pub fn is_attested(&self, subject_id: AccountId, schema_id: $crate::attest::SchemaId) -> bool {
+                    self.$attestations.is_attested(&subject_id, &schema_id)
+                }
+            }
+        };
+    }
+pub use impl_attestations;
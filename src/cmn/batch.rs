@@ -0,0 +1,199 @@
+#![allow(dead_code)]
+/*!
+Builder of batched actions on an account.
+
+`create()` can only come first and `delete_account(..)` only last, the builder won't
+compile otherwise, so the runtime never rejects the batch for its ordering.
+
+# NOTES:
+  - `build()` needs at least one action.
+  - Every action still goes into a single receipt, like the chained methods of [`Promise`].
+
+# EXAMPLE:
+```
+# use cmn::*;
+#[near_bindgen]
+impl Contract {
+    /// Sub-account factory.
+    #[payable]
+    pub fn create_sub_account(&mut self, name: String, public_key: PublicKey) -> Promise {
+        let account_id: AccountId = format!("{}.{}", name, env::current_account_id())
+            .parse()
+            .unwrap();
+        batch::for_account(account_id)
+            .create()
+            .transfer(env::attached_deposit())
+            .add_full_access_key(public_key)
+            .deploy(CODE.to_vec())
+            .call("new", b"{}".to_vec(), 0, Gas(10_000_000_000_000))
+            .build()
+    }
+
+    /// Key rotation of the contract itself.
+    #[private]
+    pub fn rotate_key(&mut self, old_key: PublicKey, new_key: PublicKey) -> Promise {
+        batch::for_account(env::current_account_id())
+            .add_full_access_key(new_key)
+            .delete_key(old_key)
+            .build()
+    }
+}
+// batch::for_account(id).transfer(1).create() doesn't compile
+```
+*/
+
+use super::*;
+
+use near_sdk::PublicKey;
+use std::marker::PhantomData;
+
+/// Nothing is batched yet.
+pub struct Empty;
+/// The account is created by the batch.
+pub struct Created;
+/// Actions on an account.
+pub struct Actions;
+/// The account is deleted, nothing can come after.
+pub struct Deleted;
+
+/// States where more actions can be batched.
+pub trait Open {}
+impl Open for Empty {}
+impl Open for Created {}
+impl Open for Actions {}
+
+/// States that can be built.
+pub trait Buildable {}
+impl Buildable for Created {}
+impl Buildable for Actions {}
+impl Buildable for Deleted {}
+
+#[must_use = "call `.build()` to get the `Promise`"]
+pub struct Batch<S> {
+    promise: Promise,
+    state: PhantomData<S>,
+}
+
+/// Starts a batch of actions on `account_id`.
+#[inline]
+pub fn for_account(account_id: AccountId) -> Batch<Empty> {
+    Batch {
+        promise: Promise::new(account_id),
+        state: PhantomData,
+    }
+}
+
+impl<S> Batch<S> {
+    #[inline]
+    fn then_state<T>(promise: Promise) -> Batch<T> {
+        Batch {
+            promise,
+            state: PhantomData,
+        }
+    }
+}
+
+impl Batch<Empty> {
+    #[inline]
+    pub fn create(self) -> Batch<Created> {
+        Self::then_state(self.promise.create_account())
+    }
+}
+
+impl<S: Open> Batch<S> {
+    #[inline]
+    pub fn transfer(self, amount: Balance) -> Batch<Actions> {
+        Self::then_state(self.promise.transfer(amount))
+    }
+
+    #[inline]
+    pub fn add_full_access_key(self, public_key: PublicKey) -> Batch<Actions> {
+        Self::then_state(self.promise.add_full_access_key(public_key))
+    }
+
+    /// Function call access key, `method_names` being comma separated, empty for any method.
+    #[inline]
+    pub fn add_access_key(
+        self,
+        public_key: PublicKey,
+        allowance: Balance,
+        receiver_id: AccountId,
+        method_names: String,
+    ) -> Batch<Actions> {
+        Self::then_state(self.promise.add_access_key(
+            public_key,
+            allowance,
+            receiver_id,
+            method_names,
+        ))
+    }
+
+    #[inline]
+    pub fn delete_key(self, public_key: PublicKey) -> Batch<Actions> {
+        Self::then_state(self.promise.delete_key(public_key))
+    }
+
+    #[inline]
+    pub fn deploy(self, code: Vec<u8>) -> Batch<Actions> {
+        Self::then_state(self.promise.deploy_contract(code))
+    }
+
+    #[inline]
+    pub fn call(
+        self,
+        method_name: &str,
+        args: Vec<u8>,
+        deposit: Balance,
+        gas: Gas,
+    ) -> Batch<Actions> {
+        Self::then_state(
+            self.promise
+                .function_call(method_name.to_string(), args, deposit, gas),
+        )
+    }
+
+    /// Deletes the account, sending its balance to `beneficiary_id`. Must be the last action.
+    #[inline]
+    pub fn delete_account(self, beneficiary_id: AccountId) -> Batch<Deleted> {
+        Self::then_state(self.promise.delete_account(beneficiary_id))
+    }
+}
+
+impl<S: Buildable> Batch<S> {
+    #[inline]
+    pub fn build(self) -> Promise {
+        self.promise
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_sub_account_batch() {
+        run_vm(vm!("alice.near"));
+
+        let public_key: PublicKey = "ed25519:6E8sCci9badyRkXb3JoRpBj5p8C6Tw41ELDZoiihKEtp"
+            .parse()
+            .unwrap();
+        let _ = for_account("sub.alice.near".parse().unwrap())
+            .create()
+            .transfer(100)
+            .add_full_access_key(public_key)
+            .deploy(vec![0])
+            .call("new", b"{}".to_vec(), 0, Gas(1))
+            .build();
+
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        let actions = &receipts[0].actions;
+        assert_eq!(actions.len(), 5);
+        assert!(matches!(actions[0], VmAction::CreateAccount));
+        assert!(matches!(actions[1], VmAction::Transfer { deposit: 100 }));
+        assert!(matches!(actions[2], VmAction::AddKeyWithFullAccess { .. }));
+        assert!(matches!(actions[3], VmAction::DeployContract { .. }));
+        assert!(matches!(actions[4], VmAction::FunctionCall { .. }));
+    }
+}
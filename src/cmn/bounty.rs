@@ -0,0 +1,574 @@
+#![cfg(feature = "bounty")]
+#![allow(dead_code)]
+/*!
+Bounty board with NEAR or FT rewards.
+
+A poster posts a bounty, someone claims it and has until the deadline to submit a hash of the
+work, then the poster (or the reviewer, E.g. a DAO) approves it to pay the reward or rejects it
+to open the bounty again.
+
+# NOTES:
+  - The poster pays the storage of the bounty in NEAR, it's refunded when the bounty is paid
+    or cancelled. A NEAR reward is attached on top of it when posting.
+  - An FT reward is funded afterwards with `ft_transfer_call` from the poster,
+    `msg` being the bounty id. The bounty opens once it's fully funded, extra tokens are returned.
+    `impl_bounty!` generates `ft_on_transfer` for it, so the contract can't receive FT otherwise.
+  - With a `reputation` field (and feature), an approved claimant earns reputation.
+  - A claim that isn't submitted before its deadline is forfeited: anyone can claim the bounty
+    again and the poster can cancel it.
+  - Events are logged as `EVENT_JSON:` with the `bounty` standard.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    bounties: bounty::Bounties,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new() -> Self {
+        require_init!();
+        Self {
+            bounties: bounty::Bounties::new(),
+        }
+    }
+}
+
+// impl_bounty!(Contract, bounties[, reputation = <reputation::Reputation field>]);
+bounty::impl_bounty!(Contract, bounties);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+    fn get_vm(predecessor: AccountId) -> VMContextBuilder {
+        vm!(predecessor)
+            .current_account_id("current".parse().unwrap())
+            .clone()
+    }
+
+    #[test]
+    fn test_near_bounty() {
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new();
+
+        // alice posts 1 NEAR bounty
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(2 * NEAR)
+                .predecessor_account_id(accounts(0)),
+        );
+        let id = contract.bounty_post(
+            "Fix the bug".to_string(),
+            asset::Asset::Near(NEAR.into()),
+            U64(1_000),
+            None,
+        );
+        assert_eq!(contract.bounty(id).unwrap().status, bounty::Status::Open);
+
+        // bob claims and submits
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(0)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.bounty_claim(id);
+        contract.bounty_submit(id, "sha256:work".to_string());
+
+        // alice approves
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(0)),
+        );
+        contract.bounty_approve(id);
+        assert_eq!(get_transfers_to(&accounts(1)), NEAR);
+        assert!(contract.bounty(id).is_none());
+        assert!(contract.bounties(None, None).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Claim is expired")]
+    fn test_submit_after_deadline() {
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(2 * NEAR)
+                .predecessor_account_id(accounts(0)),
+        );
+        let id = contract.bounty_post(
+            "Fix the bug".to_string(),
+            asset::Asset::Near(NEAR.into()),
+            U64(1_000),
+            None,
+        );
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(0)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.bounty_claim(id);
+
+        run_vm(vm.block_timestamp(1_001));
+        contract.bounty_submit(id, "sha256:work".to_string());
+    }
+}
+```
+*/
+
+use super::*;
+
+use asset::Asset;
+
+use near_sdk::{
+    collections::UnorderedMap,
+    ext_contract,
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+
+/// Gas for the `bounty_resolve_payout` callback.
+pub const GAS_FOR_RESOLVE_PAYOUT: Gas = Gas(10_000_000_000_000);
+
+raw_storage_key!(pub enum StorageKey in storage_key::BOUNTY {
+    Bounties,
+});
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq,
+)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum Status {
+    /// Waiting for the FT reward.
+    Funding,
+    Open,
+    Claimed,
+    Submitted,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Claim {
+    pub account_id: AccountId,
+    /// Block timestamp (nanoseconds) until which the work can be submitted.
+    pub deadline: U64,
+    /// Hash of the submitted work.
+    pub work_hash: Option<String>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Bounty {
+    pub poster: AccountId,
+    pub description: String,
+    pub reward: Asset,
+    /// Time (nanoseconds) a claimant has to submit.
+    pub claim_period: U64,
+    /// Can approve or reject besides the poster.
+    pub reviewer: Option<AccountId>,
+    pub status: Status,
+    pub claim: Option<Claim>,
+    /// Storage paid by the poster.
+    pub storage_deposit: U128,
+}
+impl Bounty {
+    #[inline]
+    pub fn is_claim_expired(&self) -> bool {
+        self.status == Status::Claimed
+            && self
+                .claim
+                .as_ref()
+                .is_some_and(|claim| env::block_timestamp() > claim.deadline.0)
+    }
+
+    #[inline]
+    pub fn assert_reviewer(&self) {
+        let predecessor_id = env::predecessor_account_id();
+        require!(
+            predecessor_id == self.poster || Some(&predecessor_id) == self.reviewer.as_ref(),
+            "Only the poster or the reviewer can review"
+        );
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BountyView {
+    pub id: U64,
+    #[serde(flatten)]
+    pub bounty: Bounty,
+}
+
+/// Logs a `bounty` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    events::emit(events::standards::BOUNTY, event, data);
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Bounties {
+    pub bounties: UnorderedMap<u64, Bounty>,
+    pub next_id: u64,
+}
+impl Default for Bounties {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Bounties {
+    pub fn new() -> Self {
+        Self {
+            bounties: UnorderedMap::new(StorageKey::Bounties),
+            next_id: 0,
+        }
+    }
+
+    #[inline]
+    pub fn expect(&self, id: u64) -> Bounty {
+        self.bounties
+            .get(&id)
+            .unwrap_or_else(|| env::panic_str("Bounty not found"))
+    }
+
+    /// Attached deposit pays the storage, and the reward if it's in NEAR.
+    pub fn post(
+        &mut self,
+        description: String,
+        reward: Asset,
+        claim_period: U64,
+        reviewer: Option<AccountId>,
+    ) -> u64 {
+        validate::validate!(description => validate::non_empty, validate::max_len(validate::MAX_DESCRIPTION_LENGTH));
+        let poster = env::predecessor_account_id();
+        let (status, near_reward) = match &reward {
+            Asset::Near(amount) => (Status::Open, amount.0),
+            Asset::Ft { .. } => (Status::Funding, 0),
+        };
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let initial_storage_usage = env::storage_usage();
+        let mut bounty = Bounty {
+            poster: poster.clone(),
+            description,
+            reward,
+            claim_period,
+            reviewer,
+            status,
+            claim: None,
+            storage_deposit: U128(0),
+        };
+        self.bounties.insert(&id, &bounty);
+        let storage_deposit = protocol::storage_cost(env::storage_usage() - initial_storage_usage);
+        // the deposit amount has the same size whatever it is
+        bounty.storage_deposit = U128(storage_deposit);
+        self.bounties.insert(&id, &bounty);
+
+        let required = storage_deposit + near_reward;
+        let attached = env::attached_deposit();
+        require!(
+            attached >= required,
+            format!(
+                "Must attach {} yoctoNEAR to cover the reward and storage",
+                required
+            )
+        );
+        if attached > required {
+            Promise::new(poster).transfer(attached - required);
+        }
+
+        emit(
+            "bounty_post",
+            serde_json::json!({ "id": U64(id), "bounty": bounty }),
+        );
+        id
+    }
+
+    /// Funds an FT bounty from `ft_on_transfer`, returns the unused amount.
+    pub fn fund(&mut self, id: u64, sender_id: &AccountId, amount: U128) -> U128 {
+        let mut bounty = self.expect(id);
+        require!(bounty.status == Status::Funding, "Bounty is already funded");
+        require!(&bounty.poster == sender_id, "Only the poster can fund");
+        let required = match &bounty.reward {
+            Asset::Ft { token_id, amount } => {
+                require!(
+                    token_id == &env::predecessor_account_id(),
+                    "Wrong reward token"
+                );
+                amount.0
+            }
+            Asset::Near(_) => env::panic_str("Bounty reward is in NEAR"),
+        };
+        require!(amount.0 >= required, "Not enough tokens for the reward");
+
+        bounty.status = Status::Open;
+        self.bounties.insert(&id, &bounty);
+        emit("bounty_fund", serde_json::json!({ "id": U64(id) }));
+        U128(amount.0 - required)
+    }
+
+    pub fn claim(&mut self, id: u64) {
+        let mut bounty = self.expect(id);
+        require!(
+            bounty.status == Status::Open || bounty.is_claim_expired(),
+            "Bounty is not open"
+        );
+        let account_id = env::predecessor_account_id();
+        let deadline = env::block_timestamp() + bounty.claim_period.0;
+        bounty.status = Status::Claimed;
+        bounty.claim = Some(Claim {
+            account_id: account_id.clone(),
+            deadline: U64(deadline),
+            work_hash: None,
+        });
+        self.bounties.insert(&id, &bounty);
+        emit(
+            "bounty_claim",
+            serde_json::json!({ "id": U64(id), "account_id": account_id, "deadline": U64(deadline) }),
+        );
+    }
+
+    pub fn submit(&mut self, id: u64, work_hash: String) {
+        validate::validate!(work_hash => validate::non_empty, validate::max_len(validate::MAX_URL_LENGTH));
+        let mut bounty = self.expect(id);
+        require!(bounty.status == Status::Claimed, "Bounty is not claimed");
+        require!(!bounty.is_claim_expired(), "Claim is expired");
+        let claim = bounty.claim.as_mut().unwrap();
+        require!(
+            claim.account_id == env::predecessor_account_id(),
+            "Only the claimant can submit"
+        );
+        claim.work_hash = Some(work_hash.clone());
+        bounty.status = Status::Submitted;
+        self.bounties.insert(&id, &bounty);
+        emit(
+            "bounty_submit",
+            serde_json::json!({ "id": U64(id), "work_hash": work_hash }),
+        );
+    }
+
+    /// Opens the bounty again for anyone to claim.
+    pub fn reject(&mut self, id: u64) {
+        let mut bounty = self.expect(id);
+        bounty.assert_reviewer();
+        require!(bounty.status == Status::Submitted, "Nothing is submitted");
+        bounty.status = Status::Open;
+        bounty.claim = None;
+        self.bounties.insert(&id, &bounty);
+        emit("bounty_reject", serde_json::json!({ "id": U64(id) }));
+    }
+
+    /// Removes the bounty and pays the reward to the claimant.
+    pub fn approve(&mut self, id: u64) -> Option<Promise> {
+        let bounty = self.expect(id);
+        bounty.assert_reviewer();
+        require!(bounty.status == Status::Submitted, "Nothing is submitted");
+        let claimant = bounty.claim.as_ref().unwrap().account_id.clone();
+
+        self.bounties.remove(&id);
+        emit(
+            "bounty_approve",
+            serde_json::json!({ "id": U64(id), "account_id": claimant }),
+        );
+        self.payout(id, bounty, claimant)
+    }
+
+    /// Removes an unclaimed (or forfeited) bounty and returns the reward to the poster.
+    pub fn cancel(&mut self, id: u64) -> Option<Promise> {
+        let bounty = self.expect(id);
+        require!(
+            bounty.poster == env::predecessor_account_id(),
+            "Only the poster can cancel"
+        );
+        require!(
+            matches!(bounty.status, Status::Funding | Status::Open) || bounty.is_claim_expired(),
+            "Bounty is claimed"
+        );
+
+        self.bounties.remove(&id);
+        emit("bounty_cancel", serde_json::json!({ "id": U64(id) }));
+        if bounty.status == Status::Funding {
+            Promise::new(bounty.poster.clone()).transfer(bounty.storage_deposit.0);
+            return None;
+        }
+        let poster = bounty.poster.clone();
+        self.payout(id, bounty, poster)
+    }
+
+    /// Sends the reward, then refunds the storage to the poster.
+    /// An FT reward is resolved in `bounty_resolve_payout`, which puts the bounty back on failure.
+    fn payout(&mut self, id: u64, bounty: Bounty, receiver_id: AccountId) -> Option<Promise> {
+        let transfer = bounty
+            .reward
+            .transfer(receiver_id, Some(format!("Bounty #{}", id)));
+        if !bounty.reward.is_ft() {
+            Promise::new(bounty.poster).transfer(bounty.storage_deposit.0);
+            return None;
+        }
+        Some(
+            transfer.then(
+                ext_bounty_resolver::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_PAYOUT)
+                    .bounty_resolve_payout(U64(id), bounty),
+            ),
+        )
+    }
+
+    /// Result of an FT payout.
+    pub fn resolve_payout(&mut self, id: u64, bounty: Bounty) -> bool {
+        let succeeded = matches!(env::promise_result(0), PromiseResult::Successful(_));
+        if succeeded {
+            Promise::new(bounty.poster).transfer(bounty.storage_deposit.0);
+        } else {
+            log!("Bounty #{} payout failed, it's restored", id);
+            self.bounties.insert(&id, &bounty);
+        }
+        succeeded
+    }
+
+    pub fn views(&self, from_index: Option<U64>, limit: Option<u64>) -> Vec<BountyView> {
+        self.bounties
+            .iter()
+            .skip(from_index.map_or(0, |from_index| from_index.0 as usize))
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .map(|(id, bounty)| BountyView {
+                id: U64(id),
+                bounty,
+            })
+            .collect()
+    }
+}
+
+#[ext_contract(ext_bounty_resolver)]
+pub trait BountyResolver {
+    fn bounty_resolve_payout(&mut self, id: U64, bounty: Bounty) -> bool;
+}
+
+#[macro_export]
+macro_rules! impl_bounty {
+    ($contract:ident, $bounties:ident $(, reputation = $reputation:ident)? $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_bounty!`.
+            pub const BOUNTY_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: Some("bounty"),
+                methods: &[
+                    $crate::manifest::Method::payable("bounty_post"),
+                    $crate::manifest::Method::call("ft_on_transfer"),
+                    $crate::manifest::Method::call("bounty_claim"),
+                    $crate::manifest::Method::call("bounty_submit"),
+                    $crate::manifest::Method::payable("bounty_approve").role("reviewer"),
+                    $crate::manifest::Method::payable("bounty_reject").role("reviewer"),
+                    $crate::manifest::Method::payable("bounty_cancel"),
+                    $crate::manifest::Method::private("bounty_resolve_payout"),
+                    $crate::manifest::Method::view("bounty"),
+                    $crate::manifest::Method::view("bounties"),
+                ],
+                storage: &[],
+            };
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Returns the bounty id.
+            #[payable]
+            pub fn bounty_post(
+                &mut self,
+                description: String,
+                reward: $crate::asset::Asset,
+                claim_period: U64,
+                reviewer: Option<AccountId>,
+            ) -> U64 {
+                #[cfg(feature = "decommission")]
+                $crate::decommission::require_active();
+                U64(self
+                    .$bounties
+                    .post(description, reward, claim_period, reviewer))
+            }
+
+            /// Funds the FT bounty with the id in `msg`.
+            pub fn ft_on_transfer(
+                &mut self,
+                sender_id: AccountId,
+                amount: U128,
+                msg: String,
+            ) -> PromiseOrValue<U128> {
+                #[cfg(feature = "decommission")]
+                $crate::decommission::require_active();
+                let id = msg
+                    .parse()
+                    .unwrap_or_else(|_| env::panic_str("msg must be a bounty id"));
+                PromiseOrValue::Value(self.$bounties.fund(id, &sender_id, amount))
+            }
+
+            pub fn bounty_claim(&mut self, id: U64) {
+                self.$bounties.claim(id.0)
+            }
+
+            pub fn bounty_submit(&mut self, id: U64, work_hash: String) {
+                self.$bounties.submit(id.0, work_hash)
+            }
+
+            #[payable]
+            pub fn bounty_approve(&mut self, id: U64) -> Option<Promise> {
+                near_sdk::assert_one_yocto();
+                $(
+                    if let Some(claim) = self.$bounties.expect(id.0).claim {
+                        self.$reputation.adjust(
+                            &claim.account_id,
+                            $crate::reputation::points::BOUNTY_COMPLETED,
+                            "Bounty completed",
+                        );
+                    }
+                )?
+                self.$bounties.approve(id.0)
+            }
+
+            #[payable]
+            pub fn bounty_reject(&mut self, id: U64) {
+                near_sdk::assert_one_yocto();
+                self.$bounties.reject(id.0)
+            }
+
+            #[payable]
+            pub fn bounty_cancel(&mut self, id: U64) -> Option<Promise> {
+                near_sdk::assert_one_yocto();
+                self.$bounties.cancel(id.0)
+            }
+
+            #[private]
+            pub fn bounty_resolve_payout(
+                &mut self,
+                id: U64,
+                bounty: $crate::bounty::Bounty,
+            ) -> bool {
+                self.$bounties.resolve_payout(id.0, bounty)
+            }
+
+            pub fn bounty(&self, id: U64) -> Option<$crate::bounty::Bounty> {
+                self.$bounties.bounties.get(&id.0)
+            }
+
+            pub fn bounties(
+                &self,
+                from_index: Option<U64>,
+                limit: Option<u64>,
+            ) -> Vec<$crate::bounty::BountyView> {
+                self.$bounties.views(from_index, limit)
+            }
+        }
+    };
+}
+pub use impl_bounty;
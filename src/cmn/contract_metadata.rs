@@ -0,0 +1,91 @@
+#![allow(dead_code)]
+/*!
+NEP-330 source metadata, for explorers and verification tooling.
+
+`impl_contract_metadata!` generates a `contract_source_metadata()` view with the version and the
+repository of the crate, read from Cargo at build time, and the implemented standards.
+
+# NOTES:
+  - NEP-330 itself is always listed, the other standards are given to the macro.
+  - The link is `repository` of `Cargo.toml` unless the macro is given a `link`. Empty values
+    are left out.
+
+# EXAMPLE:
+```
+# use cmn::*;
+// impl_contract_metadata!(Contract[, link = "<url>"][, standards = [<const of events::standards>, ..]]);
+impl_contract_metadata!(Contract, standards = [NEP141, NEP145, NEP148]);
+// {"version":"0.1.0","link":"https://github.com/..","standards":[{"standard":"nep330","version":"1.1.0"}, ..]}
+```
+*/
+
+use super::*;
+
+use near_sdk::serde::Serialize;
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ContractSourceMetadata {
+    pub version: Option<String>,
+    pub link: Option<String>,
+    pub standards: Vec<events::Standard>,
+}
+impl ContractSourceMetadata {
+    /// Empty `version`/`link` (E.g. an unset `repository`) are none.
+    pub fn new(version: &str, link: &str, standards: Vec<events::Standard>) -> Self {
+        let some = |value: &str| Some(value.to_string()).filter(|value| !value.is_empty());
+        Self {
+            version: some(version),
+            link: some(link),
+            standards,
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! impl_contract_metadata {
+        ($contract:ident $(, link = $link:expr)? $(, standards = [$($standard:ident),* $(,)?])? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_contract_metadata!`.
+                pub const CONTRACT_METADATA_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: None,
+                    methods: &[$crate::manifest::Method::view("contract_source_metadata")],
+                    storage: &[],
+                };
+            }
+            #[near_bindgen]
+            impl $contract {
+                /// It doesn't take `self`, so it also answers before initialization.
+                pub fn contract_source_metadata() -> $crate::contract_metadata::ContractSourceMetadata {
+                    #[allow(unused_variables)]
+                    let link: &str = env!("CARGO_PKG_REPOSITORY");
+                    $(let link: &str = $link;)?
+                    $crate::contract_metadata::ContractSourceMetadata::new(
+                        env!("CARGO_PKG_VERSION"),
+                        link,
+                        vec![
+                            $crate::events::standards::NEP330,
+                            $($($crate::events::standards::$standard,)*)?
+                        ],
+                    )
+                }
+            }
+        };
+    }
+pub use impl_contract_metadata;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_empty_values() {
+        let metadata = ContractSourceMetadata::new("0.1.0", "", vec![events::standards::NEP330]);
+        assert_eq!(metadata.version.as_deref(), Some("0.1.0"));
+        assert_eq!(metadata.link, None);
+        assert_eq!(
+            near_sdk::serde_json::to_string(&metadata).unwrap(),
+            r#"{"version":"0.1.0","link":null,"standards":[{"standard":"nep330","version":"1.1.0"}]}"#
+        );
+    }
+}
@@ -0,0 +1,350 @@
+#![cfg(feature = "decommission")]
+#![allow(dead_code)]
+/*!
+Decommission flow to shut a contract down.
+
+The owner starts it with `decommission(beneficiary_id)`, which pauses every [`features`] and
+opens a claim window for users to withdraw their assets and storage deposits. Once the window is
+over, the owner deletes the state in batches with `decommission_purge(keys)`, then anyone can
+`decommission_finish()` to delete the account, its remaining balance going to the beneficiary.
+
+# NOTES:
+  - The owner can cancel during the claim window, the features disabled before are kept disabled.
+  - The generated methods taking new deposits check [`require_active`]: `vault_deposit`,
+    `donate`, `sale_contribute`, `ft_sponsor_fund`, `bounty_post`, `keeper_register_task`,
+    `keeper_fund_task`, `names_register`, `names_renew`, `otc_make` and the `ft_on_transfer` of
+    bounty and otc.
+  - Every other generated method stays live for users to get their assets back, E.g.
+    `storage_deposit` (to register for a claim), `storage_withdraw`, `storage_unregister`,
+    `vault_withdraw`, `sale_claim`, `sale_refund`, `bounty_cancel`, `otc_cancel`, `otc_withdraw`
+    and `keeper_remove_task`. Gate the methods of the contract with [`require_active`].
+  - The runtime can't iterate the keys of a prefix, get them with the `view_state` RPC of the
+    prefix and pass at most [`MAX_PURGE_KEYS`] per call.
+  - The contract state, the feature flags and the decommission itself can't be purged,
+    `decommission_finish()` removes them right before deleting the account.
+  - The decommission lives under its own storage key, like the feature flags.
+  - Events are logged as `EVENT_JSON:` with the `decommission` standard.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+}
+
+const DAY: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        Self { owner_id }
+    }
+
+    pub fn set_greeting(&mut self, greeting: String) {
+        decommission::require_active();
+        env::storage_write(b"greeting", greeting.as_bytes());
+    }
+}
+
+impl_features!(Contract, owner = owner_id);
+// impl_decommission!(Contract[, owner = <AccountId field; default: the contract itself>][, claim_window = <nanoseconds; default: CLAIM_WINDOW>]);
+decommission::impl_decommission!(Contract, owner = owner_id, claim_window = 7 * DAY);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+        contract.set_greeting("Hello".to_string());
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.decommission(accounts(1));
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_decommission() {
+        let (mut vm, mut contract) = setup();
+        assert_eq!(
+            contract.disabled_features(),
+            features::Feature::ALL.to_vec()
+        );
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .block_timestamp(7 * DAY),
+        );
+        assert_eq!(
+            contract.decommission_purge(vec![b"greeting".to_vec().into()]),
+            1
+        );
+        assert!(!env::storage_has_key(b"greeting"));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(0)
+                .predecessor_account_id(accounts(2)),
+        );
+        contract.decommission_finish();
+        assert!(!env::state_exists());
+        assert!(get_created_receipts().into_iter().any(|receipt| receipt
+            .actions
+            .contains(&VmAction::DeleteAccount {
+                beneficiary_id: accounts(1)
+            })));
+    }
+
+    #[test]
+    fn test_cancel() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(vm.storage_usage(env::storage_usage()).block_timestamp(DAY));
+        contract.decommission_cancel();
+        assert!(contract.disabled_features().is_empty());
+        assert_eq!(contract.decommission_status(), None);
+        contract.set_greeting("Hello again".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "The claim window is still open")]
+    fn test_purge_during_claim_window() {
+        let (_, mut contract) = setup();
+        contract.decommission_purge(vec![b"greeting".to_vec().into()]);
+    }
+
+    #[test]
+    #[should_panic(expected = "The contract is decommissioned")]
+    fn test_require_active() {
+        let (_, mut contract) = setup();
+        contract.set_greeting("Hello again".to_string());
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::{
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+
+/// Storage key of the decommission.
+pub const DECOMMISSION_KEY: &[u8] = b"DECOMMISSION";
+
+/// Default time users have to withdraw before the state can be deleted (30 days).
+pub const CLAIM_WINDOW: u64 = 30 * 24 * 60 * 60 * 1_000_000_000;
+
+/// Keys deleted by one `decommission_purge`.
+pub const MAX_PURGE_KEYS: usize = 100;
+
+/// Keys only `decommission_finish` removes.
+pub const PROTECTED_KEYS: [&[u8]; 3] = [b"STATE", features::FEATURES_KEY, DECOMMISSION_KEY];
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Decommission {
+    /// Receives the remaining balance of the account.
+    pub beneficiary_id: AccountId,
+    /// Block timestamp (nanoseconds).
+    pub started_at: U64,
+    /// Block timestamp (nanoseconds) from which the state can be deleted.
+    pub claims_end_at: U64,
+    /// Number of keys deleted so far.
+    pub purged_keys: U64,
+    /// Features disabled before the decommission, kept on cancel.
+    #[serde(skip)]
+    pub disabled_before: u64,
+}
+impl Decommission {
+    #[inline]
+    pub fn load() -> Option<Self> {
+        env::storage_read(DECOMMISSION_KEY)
+            .map(|bytes| Self::try_from_slice(&bytes).unwrap_or_else(|_| env::abort()))
+    }
+
+    #[inline]
+    pub fn save(&self) {
+        env::storage_write(DECOMMISSION_KEY, &self.try_to_vec().unwrap());
+    }
+
+    #[inline]
+    pub fn is_claim_window_over(&self) -> bool {
+        env::block_timestamp() >= self.claims_end_at.0
+    }
+
+    /// Pauses every feature and opens the claim window.
+    pub fn start(beneficiary_id: AccountId, claim_window: u64) -> Self {
+        require!(
+            Self::load().is_none(),
+            "The contract is already decommissioned"
+        );
+        let mut flags = features::FeatureFlags::load();
+        let now = env::block_timestamp();
+        let this = Self {
+            beneficiary_id,
+            started_at: U64(now),
+            claims_end_at: U64(now + claim_window),
+            purged_keys: U64(0),
+            disabled_before: flags.0,
+        };
+        for feature in features::Feature::ALL {
+            flags.disable(feature);
+        }
+        flags.save();
+        this.save();
+        emit("decommission_start", serde_json::to_value(&this).unwrap());
+        this
+    }
+
+    /// Restores the features and removes the decommission, only during the claim window.
+    pub fn cancel(self) {
+        require!(
+            !self.is_claim_window_over(),
+            "The claim window is over, the decommission can't be cancelled"
+        );
+        features::FeatureFlags(self.disabled_before).save();
+        env::storage_remove(DECOMMISSION_KEY);
+        emit("decommission_cancel", serde_json::json!({}));
+    }
+
+    /// Deletes `keys` once the claim window is over, returns how many existed.
+    pub fn purge(&mut self, keys: &[Base64VecU8]) -> u32 {
+        require!(
+            self.is_claim_window_over(),
+            "The claim window is still open"
+        );
+        require!(
+            keys.len() <= MAX_PURGE_KEYS,
+            format!("Can't purge more than {} keys at once", MAX_PURGE_KEYS)
+        );
+        let mut purged = 0;
+        for key in keys {
+            require!(
+                !PROTECTED_KEYS.contains(&key.0.as_slice()),
+                "The key is removed by `decommission_finish`"
+            );
+            if env::storage_remove(&key.0) {
+                purged += 1;
+            }
+        }
+        self.purged_keys.0 += purged as u64;
+        self.save();
+        emit(
+            "decommission_purge",
+            serde_json::json!({ "purged_keys": purged }),
+        );
+        purged
+    }
+
+    /// Removes the last keys and deletes the account, once the claim window is over.
+    pub fn finish(self) -> Promise {
+        require!(
+            self.is_claim_window_over(),
+            "The claim window is still open"
+        );
+        for key in PROTECTED_KEYS {
+            env::storage_remove(key);
+        }
+        emit(
+            "decommission_finish",
+            serde_json::json!({ "beneficiary_id": self.beneficiary_id }),
+        );
+        Promise::new(env::current_account_id()).delete_account(self.beneficiary_id)
+    }
+}
+
+/// Panics once the contract is decommissioned.
+#[inline]
+pub fn require_active() {
+    require!(
+        !env::storage_has_key(DECOMMISSION_KEY),
+        "The contract is decommissioned"
+    );
+}
+
+/// Logs a `decommission` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    events::emit(events::standards::DECOMMISSION, event, data);
+}
+
+#[macro_export]
+macro_rules! impl_decommission {
+    ($contract:ident $(, owner = $owner:ident)? $(, claim_window = $claim_window:expr)? $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_decommission!`.
+            pub const DECOMMISSION_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: Some("decommission"),
+                methods: &[
+                    $crate::manifest::Method::payable("decommission").role("owner"),
+                    $crate::manifest::Method::payable("decommission_cancel").role("owner"),
+                    $crate::manifest::Method::payable("decommission_purge").role("owner"),
+                    $crate::manifest::Method::call("decommission_finish"),
+                    $crate::manifest::Method::view("decommission_status"),
+                ],
+                storage: &[],
+            };
+
+            fn assert_decommission_owner(&self) {
+                near_sdk::assert_one_yocto();
+                let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                    .cloned()
+                    .unwrap_or_else(env::current_account_id);
+                require!(
+                    env::predecessor_account_id() == owner_id,
+                    "Only the owner can decommission the contract"
+                );
+            }
+
+            fn load_decommission() -> $crate::decommission::Decommission {
+                $crate::decommission::Decommission::load()
+                    .unwrap_or_else(|| env::panic_str("The contract isn't decommissioned"))
+            }
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Pauses everything and opens the claim window, then the state can be deleted.
+            #[payable]
+            pub fn decommission(&mut self, beneficiary_id: AccountId) -> $crate::decommission::Decommission {
+                self.assert_decommission_owner();
+                #[allow(unused_variables)]
+                let claim_window = $crate::decommission::CLAIM_WINDOW;
+                $(let claim_window = $claim_window;)?
+                $crate::decommission::Decommission::start(beneficiary_id, claim_window)
+            }
+
+            #[payable]
+            pub fn decommission_cancel(&mut self) {
+                self.assert_decommission_owner();
+                Self::load_decommission().cancel()
+            }
+
+            /// Deletes the given keys, returns how many existed.
+            #[payable]
+            pub fn decommission_purge(&mut self, keys: Vec<Base64VecU8>) -> u32 {
+                self.assert_decommission_owner();
+                Self::load_decommission().purge(&keys)
+            }
+
+            /// Anyone can delete the account once the claim window is over.
+            /// It doesn't write the state back, it's deleted.
+            pub fn decommission_finish(&self) -> Promise {
+                Self::load_decommission().finish()
+            }
+
+            pub fn decommission_status(&self) -> Option<$crate::decommission::Decommission> {
+                $crate::decommission::Decommission::load()
+            }
+        }
+    };
+}
+pub use impl_decommission;
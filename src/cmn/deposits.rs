@@ -0,0 +1,374 @@
+#![cfg(feature = "deposits")]
+#![allow(dead_code)]
+/*!
+NEAR deposit vault with a balance per account.
+
+Accounts deposit NEAR once and the other components of the contract debit or credit their
+balance (E.g. a purchase, a bid and its refund when outbid) instead of attaching a deposit to
+every call and refunding it. Accounts withdraw their balance anytime.
+
+# NOTES:
+  - The vault doesn't pay interest, the balances are the deposits and credits less the debits
+    and withdrawals. `total` is their sum, the contract balance must always cover it.
+  - The depositor pays the storage of its balance from its first deposit, only the rest is
+    credited. It's kept when the balance is withdrawn, so the next deposits aren't charged.
+    The contract pays the storage of the balances it credits first.
+  - A failed withdrawal (E.g. the account was deleted) is credited back.
+  - Events are logged as `EVENT_JSON:` with the `near_vault` standard.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    vault: deposits::NearVault,
+}
+
+const PRICE: Balance = 1_000_000_000_000_000_000_000_000;
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            owner_id,
+            vault: deposits::NearVault::new(),
+        }
+    }
+
+    /// Pays the owner from the balance of the buyer.
+    pub fn buy(&mut self) {
+        self.vault.debit(&env::predecessor_account_id(), PRICE);
+        self.vault.credit(&self.owner_id, PRICE);
+    }
+}
+
+deposits::impl_near_vault!(Contract, vault);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    const STORAGE_COST: Balance = 10_000_000_000_000_000_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(2 * PRICE)
+                .predecessor_account_id(accounts(1)),
+        );
+        let balance = contract.vault_deposit();
+        assert!(balance.0 > 2 * PRICE - STORAGE_COST && balance.0 < 2 * PRICE);
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_deposit() {
+        let (mut vm, mut contract) = setup();
+        let balance = contract.vault_balance_of(accounts(1));
+
+        // the storage is charged once
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(PRICE));
+        assert_eq!(contract.vault_deposit().0, balance.0 + PRICE);
+        assert_eq!(contract.vault_total().0, balance.0 + PRICE);
+    }
+
+    #[test]
+    fn test_buy_and_withdraw() {
+        let (mut vm, mut contract) = setup();
+        let balance = contract.vault_balance_of(accounts(1));
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(0));
+        contract.buy();
+        assert_eq!(contract.vault_balance_of(accounts(1)).0, balance.0 - PRICE);
+        assert_eq!(contract.vault_balance_of(accounts(0)).0, PRICE);
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.vault_withdraw(None);
+        assert_eq!(get_transfers_to(&accounts(1)), balance.0 - PRICE);
+        assert_eq!(contract.vault_balance_of(accounts(1)).0, 0);
+        assert_eq!(contract.vault_total().0, PRICE);
+    }
+
+    #[test]
+    fn test_failed_withdraw() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.vault_withdraw(Some(U128(PRICE)));
+        let balance = contract.vault_balance_of(accounts(1));
+
+        run_vm_with_results(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(0)
+                .predecessor_account_id(env::current_account_id()),
+            vec![PromiseResult::Failed],
+        );
+        assert!(!contract.vault_resolve_withdraw(accounts(1), U128(PRICE)));
+        assert_eq!(contract.vault_balance_of(accounts(1)).0, balance.0 + PRICE);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough balance in the vault")]
+    fn test_buy_without_balance() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(vm.predecessor_account_id(accounts(2)));
+        contract.buy();
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::{collections::LookupMap, ext_contract, serde_json};
+
+raw_storage_key!(pub enum StorageKey in storage_key::DEPOSITS {
+    NearVaultBalances,
+});
+
+/// Gas for the `vault_resolve_withdraw` callback.
+pub const GAS_FOR_RESOLVE_WITHDRAW: Gas = Gas(5_000_000_000_000);
+
+/// Logs a `near_vault` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    events::emit(events::standards::NEAR_VAULT, event, data);
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct NearVault {
+    pub balances: LookupMap<AccountId, Balance>,
+    /// Sum of the balances.
+    pub total: Balance,
+}
+impl Default for NearVault {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl NearVault {
+    pub fn new() -> Self {
+        Self {
+            balances: LookupMap::new(StorageKey::NearVaultBalances),
+            total: 0,
+        }
+    }
+
+    #[inline]
+    pub fn balance_of(&self, account_id: &AccountId) -> Balance {
+        self.balances.get(account_id).unwrap_or(0)
+    }
+
+    /// Credits the attached deposit to the predecessor, less the storage of a new balance.
+    /// Returns the balance.
+    pub fn deposit(&mut self) -> Balance {
+        let account_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+        let balance = self.balance_of(&account_id);
+        // the balance has the same size whatever it is
+        self.balances.insert(&account_id, &balance);
+        let storage_cost =
+            protocol::storage_cost(env::storage_usage().saturating_sub(initial_storage_usage));
+        let attached = env::attached_deposit();
+        require!(
+            attached > storage_cost,
+            format!(
+                "Must attach more than {} yoctoNEAR to cover storage",
+                storage_cost
+            )
+        );
+        let amount = attached - storage_cost;
+        self.balances.insert(&account_id, &(balance + amount));
+        self.total += amount;
+
+        emit(
+            "vault_deposit",
+            serde_json::json!({ "account_id": account_id, "amount": U128(amount) }),
+        );
+        balance + amount
+    }
+
+    /// Adds `amount` to the balance of the account, E.g. a refund or the proceeds of a sale.
+    pub fn credit(&mut self, account_id: &AccountId, amount: Balance) {
+        self.balances
+            .insert(account_id, &(self.balance_of(account_id) + amount));
+        self.total += amount;
+    }
+
+    /// Takes `amount` from the balance of the account, E.g. a purchase or a bid.
+    pub fn debit(&mut self, account_id: &AccountId, amount: Balance) {
+        let balance = self.balance_of(account_id);
+        require!(balance >= amount, "Not enough balance in the vault");
+        self.balances.insert(account_id, &(balance - amount));
+        self.total -= amount;
+    }
+
+    /// Sends `amount` (all by default) of the balance of the predecessor, it's credited back
+    /// if the transfer fails.
+    pub fn withdraw(&mut self, amount: Option<Balance>) -> Promise {
+        let account_id = env::predecessor_account_id();
+        let amount = amount.unwrap_or_else(|| self.balance_of(&account_id));
+        require!(amount > 0, "Nothing to withdraw");
+        self.debit(&account_id, amount);
+
+        emit(
+            "vault_withdraw",
+            serde_json::json!({ "account_id": account_id, "amount": U128(amount) }),
+        );
+        Promise::new(account_id.clone()).transfer(amount).then(
+            ext_near_vault_resolver::ext(env::current_account_id())
+                .with_static_gas(GAS_FOR_RESOLVE_WITHDRAW)
+                .vault_resolve_withdraw(account_id, U128(amount)),
+        )
+    }
+
+    /// Result of a withdrawal.
+    pub fn resolve_withdraw(&mut self, account_id: AccountId, amount: U128) -> bool {
+        let succeeded = matches!(env::promise_result(0), PromiseResult::Successful(_));
+        if !succeeded {
+            log!(
+                "Withdrawal of {} yoctoNEAR to @{} failed, it's credited back",
+                amount.0,
+                account_id
+            );
+            self.credit(&account_id, amount.0);
+        }
+        succeeded
+    }
+}
+
+#[ext_contract(ext_near_vault_resolver)]
+pub trait NearVaultResolver {
+    fn vault_resolve_withdraw(&mut self, account_id: AccountId, amount: U128) -> bool;
+}
+
+#[macro_export]
+macro_rules! impl_near_vault {
+    ($contract:ident, $vault:ident) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_near_vault!`.
+            pub const NEAR_VAULT_MANIFEST: $crate::manifest::Component =
+                $crate::manifest::Component {
+                    feature: Some("deposits"),
+                    methods: &[
+                        $crate::manifest::Method::payable("vault_deposit"),
+                        $crate::manifest::Method::payable("vault_withdraw"),
+                        $crate::manifest::Method::private("vault_resolve_withdraw"),
+                        $crate::manifest::Method::view("vault_balance_of"),
+                        $crate::manifest::Method::view("vault_total"),
+                    ],
+                    storage: &[
+                        // 40 + 1 (prefix) + 4 + 64 (account id) + 16 (balance)
+                        $crate::manifest::StorageEstimate::new("vault_deposit", 125),
+                    ],
+                };
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Returns the balance of the predecessor.
+            #[payable]
+            pub fn vault_deposit(&mut self) -> U128 {
+                #[cfg(feature = "decommission")]
+                $crate::decommission::require_active();
+                U128(self.$vault.deposit())
+            }
+
+            /// Withdraws `amount`, all the balance by default.
+            #[payable]
+            pub fn vault_withdraw(&mut self, amount: Option<U128>) -> Promise {
+                near_sdk::assert_one_yocto();
+                self.$vault.withdraw(amount.map(|amount| amount.0))
+            }
+
+            #[private]
+            pub fn vault_resolve_withdraw(&mut self, account_id: AccountId, amount: U128) -> bool {
+                self.$vault.resolve_withdraw(account_id, amount)
+            }
+
+            pub fn vault_balance_of(&self, account_id: AccountId) -> U128 {
+                U128(self.$vault.balance_of(&account_id))
+            }
+
+            pub fn vault_total(&self) -> U128 {
+                U128(self.$vault.total)
+            }
+        }
+    };
+}
+pub use impl_near_vault;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+    fn deposited(vm: &mut VMContextBuilder) -> (NearVault, Balance) {
+        run_vm(&*vm);
+        let mut vault = NearVault::new();
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR),
+        );
+        let balance = vault.deposit();
+        assert!(balance < NEAR, "storage charged");
+        assert_eq!(vault.total, balance);
+        (vault, balance)
+    }
+
+    #[test]
+    fn check_withdraw() {
+        let mut vm = vm!(accounts(1));
+        let (mut vault, balance) = deposited(&mut vm);
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(0));
+        vault.withdraw(Some(balance / 2));
+        assert_eq!(vault.balance_of(&accounts(1)), balance - balance / 2);
+        assert_eq!(get_transfers_to(&accounts(1)), balance / 2);
+
+        run_vm(vm.storage_usage(env::storage_usage()));
+        vault.withdraw(None);
+        assert_eq!(vault.balance_of(&accounts(1)), 0);
+        assert_eq!(vault.total, 0);
+    }
+
+    #[test]
+    fn check_failed_withdraw_credited_back() {
+        let mut vm = vm!(accounts(1));
+        let (mut vault, balance) = deposited(&mut vm);
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(0));
+        vault.withdraw(None);
+        run_vm_with_results(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(accounts(0)),
+            vec![PromiseResult::Failed],
+        );
+        assert!(!vault.resolve_withdraw(accounts(1), U128(balance)));
+        assert_eq!(vault.balance_of(&accounts(1)), balance);
+        assert_eq!(vault.total, balance);
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough balance in the vault")]
+    fn check_overdraw() {
+        let mut vm = vm!(accounts(1));
+        let (mut vault, balance) = deposited(&mut vm);
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(0));
+        vault.withdraw(Some(balance + 1));
+    }
+}
@@ -0,0 +1,455 @@
+#![cfg(feature = "donation")]
+#![allow(dead_code)]
+/*!
+Donations split between recipients, with public attribution.
+
+`donate(message)` splits the attached deposit between the recipients by basis points and records
+the total of the donor with its last message, which the leaderboard views show.
+
+# NOTES:
+  - The splits of the recipients always total 100% (10000 bps), the rounding dust goes to the last.
+  - A recipient can only be listed once.
+  - The donor pays the storage of its record from the deposit, only the rest is donated.
+  - Messages are at most [`MAX_MESSAGE_LENGTH`] bytes, a donation without one keeps the previous.
+  - The leaderboard keeps the top [`LEADERBOARD_SIZE`] donors, every donor can be paged with `donors`.
+  - Events are logged as `EVENT_JSON:` with the `donation` standard.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    donations: donation::Donations,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId, recipients: Vec<donation::Split>) -> Self {
+        require_init!();
+        Self {
+            owner_id,
+            donations: donation::Donations::new(recipients),
+        }
+    }
+}
+
+// impl_donation!(Contract, donations[, owner = <AccountId field; default: the contract itself>]);
+donation::impl_donation!(Contract, donations, owner = owner_id);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let contract = Contract::new(
+            accounts(0),
+            vec![
+                donation::Split {
+                    account_id: accounts(1),
+                    bps: 7_000,
+                },
+                donation::Split {
+                    account_id: accounts(2),
+                    bps: 3_000,
+                },
+            ],
+        );
+        run_vm(vm.storage_usage(env::storage_usage()));
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_donate() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR)
+                .predecessor_account_id(accounts(3)),
+        );
+        contract.donate(Some(strings::BoundedString::new("For the trees")));
+        let donated = contract.donation_of(accounts(3)).unwrap().total.0;
+        assert!(donated < NEAR);
+        assert_eq!(get_transfers_to(&accounts(1)), donated * 7 / 10);
+        assert_eq!(get_transfers_to(&accounts(2)), donated - donated * 7 / 10);
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR * 2)
+                .predecessor_account_id(accounts(4)),
+        );
+        contract.donate(None);
+        let leaderboard = contract.donation_leaderboard();
+        assert_eq!(leaderboard[0].account_id, accounts(4));
+        assert_eq!(leaderboard[1].account_id, accounts(3));
+        assert_eq!(
+            leaderboard[1].record.message.as_deref(),
+            Some("For the trees")
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate account bob")]
+    fn test_set_recipients_duplicate() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.donation_set_recipients(vec![
+            donation::Split {
+                account_id: accounts(1),
+                bps: 5_000,
+            },
+            donation::Split {
+                account_id: accounts(1),
+                bps: 5_000,
+            },
+        ]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Splits must total 10000 bps")]
+    fn test_set_recipients_total() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.donation_set_recipients(vec![donation::Split {
+            account_id: accounts(1),
+            bps: 5_000,
+        }]);
+    }
+}
+```
+*/
+
+use super::*;
+
+use contract_core::bps;
+use near_sdk::{
+    collections::UnorderedMap,
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+use strings::BoundedString;
+
+pub const MAX_RECIPIENTS: usize = 10;
+pub const MAX_MESSAGE_LENGTH: usize = 140;
+pub const LEADERBOARD_SIZE: usize = 10;
+
+pub type Message = BoundedString<MAX_MESSAGE_LENGTH>;
+
+raw_storage_key!(pub enum StorageKey in storage_key::DONATION {
+    Donors,
+});
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Split {
+    pub account_id: AccountId,
+    pub bps: u32,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DonorRecord {
+    pub total: U128,
+    pub message: Option<Message>,
+    /// Block timestamp (nanoseconds).
+    pub last_donated_at: U64,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct DonorView {
+    pub account_id: AccountId,
+    #[serde(flatten)]
+    pub record: DonorRecord,
+}
+
+/// Logs a `donation` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    events::emit(events::standards::DONATION, event, data);
+}
+
+pub fn assert_valid_splits(splits: &[Split]) {
+    require!(
+        (1..=MAX_RECIPIENTS).contains(&splits.len()),
+        format!("Must have 1 to {} recipients", MAX_RECIPIENTS)
+    );
+    accounts::assert_unique(splits.iter().map(|split| &split.account_id));
+    let total_bps = splits
+        .iter()
+        .try_fold(0u32, |total, split| total.checked_add(split.bps));
+    require!(
+        total_bps == Some(bps::ONE_HUNDRED_PERCENT),
+        "Splits must total 10000 bps"
+    );
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Donations {
+    pub recipients: Vec<Split>,
+    pub donors: UnorderedMap<AccountId, DonorRecord>,
+    /// Top donors by total, highest first.
+    pub leaderboard: Vec<(AccountId, Balance)>,
+    pub total: Balance,
+}
+impl Donations {
+    pub fn new(recipients: Vec<Split>) -> Self {
+        assert_valid_splits(&recipients);
+        Self {
+            recipients,
+            donors: UnorderedMap::new(StorageKey::Donors),
+            leaderboard: vec![],
+            total: 0,
+        }
+    }
+
+    pub fn set_recipients(&mut self, recipients: Vec<Split>) {
+        assert_valid_splits(&recipients);
+        self.recipients = recipients;
+    }
+
+    /// Donates the attached deposit of the predecessor, less the storage of its record.
+    pub fn donate(&mut self, message: Option<Message>) {
+        let donor_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+        let mut record = self.donors.get(&donor_id).unwrap_or(DonorRecord {
+            total: U128(0),
+            message: None,
+            last_donated_at: U64(0),
+        });
+        if message.is_some() {
+            record.message = message;
+        }
+        record.last_donated_at = U64(env::block_timestamp());
+        // the total has the same size whatever it is
+        self.donors.insert(&donor_id, &record);
+        let storage_cost =
+            protocol::storage_cost(env::storage_usage().saturating_sub(initial_storage_usage));
+        let attached = env::attached_deposit();
+        require!(
+            attached > storage_cost,
+            format!(
+                "Must attach more than {} yoctoNEAR to cover storage",
+                storage_cost
+            )
+        );
+        let amount = attached - storage_cost;
+        record.total.0 += amount;
+        self.donors.insert(&donor_id, &record);
+        self.total += amount;
+        self.update_leaderboard(&donor_id, record.total.0);
+        self.pay(amount);
+
+        emit(
+            "donate",
+            serde_json::json!({
+                "donor_id": donor_id,
+                "amount": U128(amount),
+                "message": record.message,
+            }),
+        );
+    }
+
+    /// Sends the shares of `amount` to the recipients.
+    fn pay(&self, amount: Balance) {
+        let mut rest = amount;
+        let (last, others) = self.recipients.split_last().unwrap();
+        for split in others {
+            let share = bps::share(amount, split.bps);
+            rest -= share;
+            if share > 0 {
+                Promise::new(split.account_id.clone()).transfer(share);
+            }
+        }
+        if rest > 0 {
+            Promise::new(last.account_id.clone()).transfer(rest);
+        }
+    }
+
+    fn update_leaderboard(&mut self, donor_id: &AccountId, total: Balance) {
+        self.leaderboard
+            .retain(|(account_id, _)| account_id != donor_id);
+        let index = self
+            .leaderboard
+            .partition_point(|(_, other_total)| *other_total >= total);
+        if index < LEADERBOARD_SIZE {
+            self.leaderboard.insert(index, (donor_id.clone(), total));
+            self.leaderboard.truncate(LEADERBOARD_SIZE);
+        }
+    }
+
+    pub fn leaderboard(&self) -> Vec<DonorView> {
+        self.leaderboard
+            .iter()
+            .map(|(account_id, _)| DonorView {
+                account_id: account_id.clone(),
+                record: self.donors.get(account_id).unwrap(),
+            })
+            .collect()
+    }
+
+    pub fn views(&self, from_index: Option<U64>, limit: Option<u64>) -> Vec<DonorView> {
+        self.donors
+            .iter()
+            .skip(from_index.map_or(0, |from_index| from_index.0 as usize))
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .map(|(account_id, record)| DonorView { account_id, record })
+            .collect()
+    }
+}
+
+#[macro_export]
+macro_rules! impl_donation {
+    ($contract:ident, $donations:ident $(, owner = $owner:ident)? $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_donation!`.
+            pub const DONATION_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: Some("donation"),
+                methods: &[
+                    $crate::manifest::Method::payable("donate"),
+                    $crate::manifest::Method::payable("donation_set_recipients").role("owner"),
+                    $crate::manifest::Method::view("donation_recipients"),
+                    $crate::manifest::Method::view("donation_total"),
+                    $crate::manifest::Method::view("donation_of"),
+                    $crate::manifest::Method::view("donation_leaderboard"),
+                    $crate::manifest::Method::view("donors"),
+                ],
+                storage: &[
+                    // a record of a 64 bytes account with a 140 bytes message
+                    $crate::manifest::StorageEstimate::new("donate", 350),
+                ],
+            };
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Splits the attached deposit, less the storage of the donor record, between the recipients.
+            #[payable]
+            pub fn donate(&mut self, message: Option<$crate::donation::Message>) {
+                #[cfg(feature = "decommission")]
+                $crate::decommission::require_active();
+                self.$donations.donate(message)
+            }
+
+            #[payable]
+            pub fn donation_set_recipients(&mut self, recipients: Vec<$crate::donation::Split>) {
+                near_sdk::assert_one_yocto();
+                let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                    .cloned()
+                    .unwrap_or_else(env::current_account_id);
+                require!(
+                    env::predecessor_account_id() == owner_id,
+                    "Only the owner can set the recipients"
+                );
+                self.$donations.set_recipients(recipients)
+            }
+
+            pub fn donation_recipients(&self) -> Vec<$crate::donation::Split> {
+                self.$donations.recipients.clone()
+            }
+
+            pub fn donation_total(&self) -> U128 {
+                U128(self.$donations.total)
+            }
+
+            pub fn donation_of(&self, account_id: AccountId) -> Option<$crate::donation::DonorRecord> {
+                self.$donations.donors.get(&account_id)
+            }
+
+            /// Top donors by total, highest first.
+            pub fn donation_leaderboard(&self) -> Vec<$crate::donation::DonorView> {
+                self.$donations.leaderboard()
+            }
+
+            pub fn donors(
+                &self,
+                from_index: Option<U64>,
+                limit: Option<u64>,
+            ) -> Vec<$crate::donation::DonorView> {
+                self.$donations.views(from_index, limit)
+            }
+        }
+    };
+}
+pub use impl_donation;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+    fn donations() -> Donations {
+        Donations::new(vec![
+            Split {
+                account_id: accounts(0),
+                bps: 3_333,
+            },
+            Split {
+                account_id: accounts(1),
+                bps: 6_667,
+            },
+        ])
+    }
+
+    #[test]
+    fn check_donate_split() {
+        let mut vm = vm!(accounts(2));
+        run_vm(&vm);
+        let mut donations = donations();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR),
+        );
+        donations.donate(None);
+        let amount = donations.total;
+        assert!(amount < NEAR, "storage charged");
+        let share = bps::share(amount, 3_333);
+        assert_eq!(get_transfers_to(&accounts(0)), share);
+        // the last recipient gets the rounding
+        assert_eq!(get_transfers_to(&accounts(1)), amount - share);
+    }
+
+    #[test]
+    fn check_leaderboard() {
+        let mut vm = vm!(accounts(2));
+        run_vm(&vm);
+        let mut donations = donations();
+
+        for (account, deposit) in [(2, NEAR), (3, 2 * NEAR), (2, 2 * NEAR)] {
+            run_vm(
+                vm.storage_usage(env::storage_usage())
+                    .predecessor_account_id(accounts(account))
+                    .attached_deposit(deposit),
+            );
+            donations.donate(None);
+        }
+        let leaders: Vec<_> = donations
+            .leaderboard()
+            .into_iter()
+            .map(|view| view.account_id)
+            .collect();
+        assert_eq!(leaders, [accounts(2), accounts(3)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Splits must total 10000 bps")]
+    fn check_splits_total() {
+        Donations::new(vec![Split {
+            account_id: accounts(0),
+            bps: 9_999,
+        }]);
+    }
+}
@@ -0,0 +1,225 @@
+#![allow(dead_code)]
+/*!
+Buffer for NEP-297 events emitted by batch methods.
+
+Events pushed to an [`EventBuffer`] are coalesced by `(standard, version, event)` into array-form
+events (`"data": [..]`), and every log line is kept under the 16KB log limit by splitting the
+data into several events.
+
+# NOTES:
+  - Nothing is logged until [`EventBuffer::flush`] is called, so call it at the end of the method.
+  - Events are flushed in the order their kind was first pushed.
+
+# EXAMPLE:
+```
+# use cmn::*;
+#[near_bindgen]
+impl Contract {
+    pub fn ft_mint_many(&mut self, receivers: Vec<(AccountId, U128)>) {
+        let mut events = event_buffer::EventBuffer::new();
+        for (account_id, amount) in receivers.iter() {
+            self.ft.internal_deposit(account_id, amount.0);
+            events.ft_mint(ft::events::FtMint {
+                owner_id: account_id,
+                amount,
+                memo: None,
+            });
+        }
+        events.flush(); // EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_mint","data":[..]}
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::{serde::Serialize, serde_json};
+
+/// Max length of a single log line in bytes.
+pub const MAX_LOG_LENGTH: usize = 16 * 1024;
+
+const EVENT_JSON_PREFIX: &str = "EVENT_JSON:";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct EventKind {
+    standard: &'static str,
+    version: &'static str,
+    event: &'static str,
+}
+impl EventKind {
+    #[inline]
+    fn head(&self) -> String {
+        format!(
+            r#"{}{{"standard":"{}","version":"{}","event":"{}","data":["#,
+            EVENT_JSON_PREFIX, self.standard, self.version, self.event
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+#[must_use = "don't forget to `.flush()` the buffer"]
+pub struct EventBuffer {
+    /// Serialized data of each event, grouped by kind.
+    events: Vec<(EventKind, Vec<String>)>,
+}
+impl EventBuffer {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Adds the data of an event, E.g. `push("nep141", "1.0.0", "ft_mint", &data)`.
+    pub fn push<T: Serialize>(
+        &mut self,
+        standard: &'static str,
+        version: &'static str,
+        event: &'static str,
+        data: &T,
+    ) {
+        let kind = EventKind {
+            standard,
+            version,
+            event,
+        };
+        let data = serde_json::to_string(data).unwrap_or_else(|_| env::abort());
+        match self.events.iter_mut().find(|(k, _)| k == &kind) {
+            Some((_, datas)) => datas.push(data),
+            None => self.events.push((kind, vec![data])),
+        }
+    }
+
+    /// [`EventBuffer::push`] of a registered standard, E.g. `push_standard(events::standards::NEP141, "ft_mint", &data)`.
+    #[inline]
+    pub fn push_standard<T: Serialize>(
+        &mut self,
+        standard: events::Standard,
+        event: &'static str,
+        data: &T,
+    ) {
+        self.push(standard.standard, standard.version, event, data)
+    }
+
+    /// Log lines of the buffered events, each one at most [`MAX_LOG_LENGTH`] bytes.
+    pub fn logs(&self) -> Vec<String> {
+        let mut logs = vec![];
+        for (kind, datas) in self.events.iter() {
+            let head = kind.head();
+            // head + data + "]}"
+            require!(
+                datas
+                    .iter()
+                    .all(|data| head.len() + data.len() + 2 <= MAX_LOG_LENGTH),
+                format!("Event `{}` is too large for a log", kind.event)
+            );
+
+            let mut log = head.clone();
+            for data in datas {
+                let empty = log.len() == head.len();
+                if !empty && log.len() + 1 + data.len() + 2 > MAX_LOG_LENGTH {
+                    log.push_str("]}");
+                    logs.push(log);
+                    log = head.clone();
+                } else if !empty {
+                    log.push(',');
+                }
+                log.push_str(data);
+            }
+            log.push_str("]}");
+            logs.push(log);
+        }
+        logs
+    }
+
+    /// Logs the buffered events and empties the buffer.
+    pub fn flush(&mut self) {
+        for log in self.logs() {
+            env::log_str(&log);
+        }
+        self.events.clear();
+    }
+}
+
+#[cfg(feature = "ft")]
+impl EventBuffer {
+    #[inline]
+    pub fn ft_mint(&mut self, data: ft::events::FtMint) {
+        self.push_standard(events::standards::NEP141, "ft_mint", &data)
+    }
+    #[inline]
+    pub fn ft_transfer(&mut self, data: ft::events::FtTransfer) {
+        self.push_standard(events::standards::NEP141, "ft_transfer", &data)
+    }
+    #[inline]
+    pub fn ft_burn(&mut self, data: ft::events::FtBurn) {
+        self.push_standard(events::standards::NEP141, "ft_burn", &data)
+    }
+}
+
+#[cfg(feature = "nft")]
+impl EventBuffer {
+    #[inline]
+    pub fn nft_mint(&mut self, data: nft::events::NftMint) {
+        self.push_standard(events::standards::NEP171, "nft_mint", &data)
+    }
+    #[inline]
+    pub fn nft_transfer(&mut self, data: nft::events::NftTransfer) {
+        self.push_standard(events::standards::NEP171, "nft_transfer", &data)
+    }
+    #[inline]
+    pub fn nft_burn(&mut self, data: nft::events::NftBurn) {
+        self.push_standard(events::standards::NEP171, "nft_burn", &data)
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_coalesce() {
+        run_vm(vm!("alice.near"));
+
+        let mut events = EventBuffer::new();
+        events.push("nep141", "1.0.0", "ft_mint", &1);
+        events.push("nep141", "1.0.0", "ft_burn", &2);
+        events.push("nep141", "1.0.0", "ft_mint", &3);
+        events.flush();
+
+        logs![
+            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_mint","data":[1,3]}"#,
+            r#"EVENT_JSON:{"standard":"nep141","version":"1.0.0","event":"ft_burn","data":[2]}"#,
+        ]
+        .assert();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn check_chunks() {
+        run_vm(vm!("alice.near"));
+
+        let data = "a".repeat(1000);
+        let mut events = EventBuffer::new();
+        for _ in 0..100 {
+            events.push("nep171", "1.0.0", "nft_mint", &data);
+        }
+        let logs = events.logs();
+
+        assert!(logs.len() > 1);
+        assert!(logs.iter().all(|log| log.len() <= MAX_LOG_LENGTH));
+        let total: usize = logs
+            .iter()
+            .map(|log| {
+                let json = log.strip_prefix(EVENT_JSON_PREFIX).unwrap();
+                let value: serde_json::Value = serde_json::from_str(json).unwrap();
+                value["data"].as_array().unwrap().len()
+            })
+            .sum();
+        assert_eq!(total, 100);
+    }
+}
@@ -0,0 +1,148 @@
+#![allow(dead_code)]
+/*!
+Registry of the standards implemented by the modules, and the NEP-297 emitter they log through.
+
+Every module logs its events with a [`Standard`] of [`standards`], so a version is bumped in one
+place, and `impl_supported_standards!` lists the implemented ones in a `supported_standards()`
+view for wallets and indexers to feature-detect.
+
+# NOTES:
+  - NEPs without events (E.g. NEP-145 storage management) are listed too, they're only detected.
+  - The `nep141`/`nep171` events themselves are logged by near-contract-standards,
+    [`standards::NEP141`]/[`standards::NEP171`] match their versions.
+  - Events of the contract itself are structs implementing [`Event`](super::Event) with `event!`.
+
+# EXAMPLE:
+```
+# use cmn::*;
+events::emit(events::standards::BOUNTY, "bounty_post", near_sdk::serde_json::json!({ "id": "0" }));
+// EVENT_JSON:{"standard":"bounty","version":"1.0.0","event":"bounty_post","data":[{"id":"0"}]}
+
+// impl_supported_standards!(Contract, <const of events::standards>, ..);
+events::impl_supported_standards!(Contract, NEP141, NEP145, NEP148, NEP297, BOUNTY);
+// supported_standards() => [{"standard": "nep141", "version": "1.0.0"}, ..]
+```
+*/
+
+use super::*;
+
+use near_sdk::{serde::Serialize, serde_json};
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Standard {
+    pub standard: &'static str,
+    pub version: &'static str,
+}
+impl Standard {
+    #[inline]
+    pub const fn new(standard: &'static str, version: &'static str) -> Self {
+        Self { standard, version }
+    }
+}
+
+pub mod standards {
+    use super::Standard;
+
+    /// Fungible token core.
+    pub const NEP141: Standard = Standard::new("nep141", "1.0.0");
+    /// Storage management.
+    pub const NEP145: Standard = Standard::new("nep145", "1.0.0");
+    /// Fungible token metadata.
+    pub const NEP148: Standard = Standard::new("nep148", "1.0.0");
+    /// Non-fungible token core.
+    pub const NEP171: Standard = Standard::new("nep171", "1.0.0");
+    /// Non-fungible token metadata.
+    pub const NEP177: Standard = Standard::new("nep177", "1.0.0");
+    /// Non-fungible token approval management.
+    pub const NEP178: Standard = Standard::new("nep178", "1.0.0");
+    /// Non-fungible token enumeration.
+    pub const NEP181: Standard = Standard::new("nep181", "1.0.0");
+    /// Non-fungible token royalties and payouts.
+    pub const NEP199: Standard = Standard::new("nep199", "2.0.0");
+    /// Multi token.
+    pub const NEP245: Standard = Standard::new("nep245", "1.0.0");
+    /// Events.
+    pub const NEP297: Standard = Standard::new("nep297", "1.0.0");
+    /// Contract source metadata.
+    pub const NEP330: Standard = Standard::new("nep330", "1.1.0");
+
+    pub const ACCOUNT_MIGRATION: Standard = Standard::new("account_migration", "1.0.0");
+    pub const ATTEST: Standard = Standard::new("attest", "1.0.0");
+    pub const BOUNTY: Standard = Standard::new("bounty", "1.0.0");
+    pub const DECOMMISSION: Standard = Standard::new("decommission", "1.0.0");
+    pub const DONATION: Standard = Standard::new("donation", "1.0.0");
+    pub const FT_BREAKER: Standard = Standard::new("ft_breaker", "1.0.0");
+    pub const FT_BRIDGE: Standard = Standard::new("ft_bridge", "1.0.0");
+    pub const FT_FREEZE: Standard = Standard::new("ft_freeze", "1.0.0");
+    pub const FT_LOCKUP: Standard = Standard::new("ft_lockup", "1.0.0");
+    pub const FT_MAX_SUPPLY: Standard = Standard::new("ft_max_supply", "1.0.0");
+    pub const FT_REDEEM: Standard = Standard::new("ft_redeem", "1.0.0");
+    pub const FT_SALE: Standard = Standard::new("ft_sale", "1.0.0");
+    pub const INHERITANCE: Standard = Standard::new("inheritance", "1.0.0");
+    pub const INIT: Standard = Standard::new("init", "1.0.0");
+    pub const KEEPER: Standard = Standard::new("keeper", "1.0.0");
+    pub const MEMBERSHIP: Standard = Standard::new("membership", "1.0.0");
+    pub const NAMES: Standard = Standard::new("names", "1.0.0");
+    pub const NEAR_VAULT: Standard = Standard::new("near_vault", "1.0.0");
+    pub const NFT_CLAWBACK: Standard = Standard::new("nft_clawback", "1.0.0");
+    pub const OTC: Standard = Standard::new("otc", "1.0.0");
+    pub const OWNABLE: Standard = Standard::new("ownable", "1.0.0");
+    pub const REPUTATION: Standard = Standard::new("reputation", "1.0.0");
+    pub const TELEMETRY: Standard = Standard::new("telemetry", "1.0.0");
+}
+
+/// Logs a NEP-297 event of `standard` with `data` as its only item.
+pub fn emit(standard: Standard, event: &str, data: impl Serialize) {
+    // not `log!`, events are kept with the `strip-logs` feature
+    env::log_str(&format!(
+        "EVENT_JSON:{}",
+        serde_json::json!({
+            "standard": standard.standard,
+            "version": standard.version,
+            "event": event,
+            "data": [data],
+        })
+    ));
+}
+
+#[macro_export]
+macro_rules! impl_supported_standards {
+    ($contract:ident, $($standard:ident),+ $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_supported_standards!`.
+            pub const SUPPORTED_STANDARDS_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: None,
+                methods: &[$crate::manifest::Method::view("supported_standards")],
+                storage: &[],
+            };
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Standards and extensions implemented by the contract, with their versions.
+            pub fn supported_standards(&self) -> Vec<$crate::events::Standard> {
+                vec![$($crate::events::standards::$standard),+]
+            }
+        }
+    };
+}
+pub use impl_supported_standards;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_emit() {
+        run_vm(vm!("alice.near"));
+
+        emit(
+            standards::BOUNTY,
+            "bounty_post",
+            serde_json::json!({ "id": "0" }),
+        );
+        logs![r#"EVENT_JSON:{"data":[{"id":"0"}],"event":"bounty_post","standard":"bounty","version":"1.0.0"}"#]
+            .assert();
+    }
+}
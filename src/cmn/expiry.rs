@@ -0,0 +1,338 @@
+#![cfg(feature = "expiry")]
+#![allow(dead_code)]
+/*!
+Shared index of the entries that expire, E.g. orders or approvals, swept by keepers.
+
+Modules register an entry with its deadline, the index keeps them in time buckets so the
+expired ones are found without scanning the rest. Anyone can call `sweep_expired(limit)`, which
+pops up to `limit` expired entries and cleans each one up through its module, refunding its
+storage to whoever paid it.
+
+Registered by the modules:
+  - `OtcOrder`: orders posted with an `expires_at`, cancelled by the sweep. The tokens left and
+    the storage go back to the maker.
+  - `NftApproval`: approvals given with `nft_approve_with_expiry`, the sweep prunes the expired
+    approvals of the token and refunds their storage to the owner.
+  - `Other`: entries of the contract itself, E.g. listings or proposals, handled by the
+    `other` method given to `impl_expiry!`.
+
+# NOTES:
+  - The index is off until `expiry::enable()` is called, E.g. in the init. Entries registered
+    before aren't in it.
+  - The index entries are paid by the contract (~100 bytes each) and freed by the sweep.
+  - An entry is only swept once, whatever its module did in between (E.g. an order filled
+    before its deadline is already gone, and an approval renewed with a later expiry isn't
+    pruned). Register the new deadline again when it changes.
+  - Entries of a kind the macro isn't given a module for are dropped by the sweep.
+
+# EXAMPLE:
+Needs the `otc` feature too.
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    otc: otc::Otc,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new() -> Self {
+        require_init!();
+        expiry::enable();
+        Self { otc: otc::Otc::new() }
+    }
+}
+
+otc::impl_otc!(Contract, otc);
+// impl_expiry!(Contract[, otc = <otc::Otc field>][, nft = <nft::NonFungibleToken field>][, other = <fn(&mut self, &expiry::ExpiryEntry) method>]);
+expiry::impl_expiry!(Contract, otc = otc);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+    #[test]
+    fn test_sweep_expired() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new();
+
+        let (token_a, token_b): (AccountId, AccountId) =
+            ("a.token".parse().unwrap(), "b.token".parse().unwrap());
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(NEAR / 100));
+        for expires_at in [10, 20] {
+            contract.otc_make(
+                token_a.clone(),
+                U128(1_000),
+                token_b.clone(),
+                U128(500),
+                Some(U64(expires_at)),
+            );
+        }
+        assert_eq!(contract.expiry_len(), U64(2));
+        assert_eq!(contract.expiry_next_at(), Some(U64(10)));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .block_timestamp(10)
+                .attached_deposit(0)
+                .predecessor_account_id(accounts(1)),
+        );
+        assert_eq!(contract.sweep_expired(10), 1);
+        assert!(contract.otc_order(U64(0)).is_none());
+        assert!(contract.otc_order(U64(1)).is_some());
+        // the storage of the order goes back to the maker
+        assert!(get_transfers_to(&accounts(0)) > 0);
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Width of a time bucket, an hour in nanoseconds.
+pub const BUCKET_WIDTH: u64 = 60 * 60 * 1_000_000_000;
+
+raw_storage_key!(pub enum StorageKey in storage_key::EXPIRY {
+    Expiry,
+    ExpiryBuckets,
+});
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum ExpiryKind {
+    /// `key` is the order id.
+    OtcOrder,
+    /// `key` is the token id.
+    NftApproval,
+    /// Named by the contract.
+    Other(String),
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ExpiryEntry {
+    pub kind: ExpiryKind,
+    pub key: String,
+    /// Block timestamp (nanoseconds).
+    pub expires_at: U64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Index {
+    /// Entries by `expires_at / BUCKET_WIDTH`.
+    pub buckets: TreeMap<u64, Vec<ExpiryEntry>>,
+    pub len: u64,
+}
+impl Index {
+    #[inline]
+    fn key() -> Vec<u8> {
+        near_sdk::IntoStorageKey::into_storage_key(StorageKey::Expiry)
+    }
+
+    /// The index, none until [`enable`] is called.
+    pub fn load() -> Option<Self> {
+        env::storage_read(&Self::key())
+            .map(|bytes| Self::try_from_slice(&bytes).unwrap_or_else(|_| env::abort()))
+    }
+
+    #[inline]
+    pub fn save(&self) {
+        env::storage_write(&Self::key(), &self.try_to_vec().unwrap());
+    }
+
+    pub fn insert(&mut self, entry: ExpiryEntry) {
+        let bucket = entry.expires_at.0 / BUCKET_WIDTH;
+        let mut entries = self.buckets.get(&bucket).unwrap_or_default();
+        entries.push(entry);
+        self.buckets.insert(&bucket, &entries);
+        self.len += 1;
+    }
+
+    /// Deadline of the next entry to expire.
+    pub fn next_at(&self) -> Option<u64> {
+        let bucket = self.buckets.min()?;
+        self.buckets
+            .get(&bucket)?
+            .iter()
+            .map(|entry| entry.expires_at.0)
+            .min()
+    }
+
+    /// Removes up to `limit` entries expired at `now`, the earliest buckets first.
+    pub fn pop_expired(&mut self, now: u64, limit: usize) -> Vec<ExpiryEntry> {
+        let mut expired = vec![];
+        let mut bucket = self.buckets.min();
+        while let Some(current) = bucket {
+            if expired.len() >= limit || current > now / BUCKET_WIDTH {
+                break;
+            }
+            let (due, mut pending): (Vec<_>, Vec<_>) = self
+                .buckets
+                .get(&current)
+                .unwrap_or_default()
+                .into_iter()
+                .partition(|entry| entry.expires_at.0 <= now);
+            let taken = due.len().min(limit - expired.len());
+            let mut due = due.into_iter();
+            expired.extend(due.by_ref().take(taken));
+            pending.extend(due);
+            if pending.is_empty() {
+                self.buckets.remove(&current);
+            } else {
+                self.buckets.insert(&current, &pending);
+            }
+            bucket = self.buckets.higher(&current);
+        }
+        self.len -= expired.len() as u64;
+        expired
+    }
+}
+
+/// Turns the index on, entries are registered from then on.
+pub fn enable() {
+    if Index::load().is_none() {
+        Index {
+            buckets: TreeMap::new(StorageKey::ExpiryBuckets),
+            len: 0,
+        }
+        .save();
+    }
+}
+
+/// Registers an entry expiring at `expires_at` (block timestamp, nanoseconds), a no-op while
+/// the index is off.
+pub fn register(kind: ExpiryKind, key: String, expires_at: u64) {
+    if let Some(mut index) = Index::load() {
+        index.insert(ExpiryEntry {
+            kind,
+            key,
+            expires_at: U64(expires_at),
+        });
+        index.save();
+    }
+}
+
+/// Removes up to `limit` expired entries, for the module to clean up.
+pub fn pop_expired(limit: usize) -> Vec<ExpiryEntry> {
+    match Index::load() {
+        Some(mut index) => {
+            let expired = index.pop_expired(env::block_timestamp(), limit);
+            index.save();
+            expired
+        }
+        None => vec![],
+    }
+}
+
+#[macro_export]
+macro_rules! impl_expiry {
+    ($contract:ident $(, otc = $otc:ident)? $(, nft = $nft:ident)? $(, other = $other:ident)? $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_expiry!`.
+            pub const EXPIRY_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: Some("expiry"),
+                methods: &[
+                    $crate::manifest::Method::call("sweep_expired"),
+                    $crate::manifest::Method::view("expiry_len"),
+                    $crate::manifest::Method::view("expiry_next_at"),
+                ],
+                storage: &[],
+            };
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Cleans up to `limit` expired entries, returns how many were swept.
+            pub fn sweep_expired(&mut self, limit: u32) -> u32 {
+                let entries = $crate::expiry::pop_expired(limit as usize);
+                let swept = entries.len() as u32;
+                #[allow(unused_variables)]
+                for entry in entries {
+                    $(if entry.kind == $crate::expiry::ExpiryKind::OtcOrder {
+                        let id = entry.key.parse::<u64>().ok();
+                        if let Some(id) = id.filter(|id| {
+                            self.$otc.orders.get(id).is_some_and(|order| order.is_expired())
+                        }) {
+                            self.$otc.cancel(id);
+                        }
+                    })?
+                    $(if entry.kind == $crate::expiry::ExpiryKind::NftApproval {
+                        if let Some(owner_id) = self.$nft.token.owner_by_id.get(&entry.key) {
+                            let initial_storage_usage = env::storage_usage();
+                            self.$nft.prune_approvals(&entry.key);
+                            $crate::nft::refund_released_storage(initial_storage_usage, owner_id);
+                        }
+                    })?
+                    $(if let $crate::expiry::ExpiryKind::Other(_) = &entry.kind {
+                        self.$other(&entry);
+                    })?
+                }
+                swept
+            }
+
+            pub fn expiry_len(&self) -> U64 {
+                U64($crate::expiry::Index::load().map_or(0, |index| index.len))
+            }
+
+            /// Deadline of the next entry to expire.
+            pub fn expiry_next_at(&self) -> Option<U64> {
+                $crate::expiry::Index::load()
+                    .and_then(|index| index.next_at())
+                    .map(U64)
+            }
+        }
+    };
+}
+pub use impl_expiry;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    fn entry(key: &str, expires_at: u64) -> ExpiryEntry {
+        ExpiryEntry {
+            kind: ExpiryKind::Other("listing".into()),
+            key: key.into(),
+            expires_at: U64(expires_at),
+        }
+    }
+
+    #[test]
+    fn check_pop_expired() {
+        run_vm(vm!("alice.near"));
+
+        // off by default
+        register(ExpiryKind::OtcOrder, "0".into(), 1);
+        assert!(Index::load().is_none());
+
+        enable();
+        let mut index = Index::load().unwrap();
+        index.insert(entry("late", 3 * BUCKET_WIDTH));
+        index.insert(entry("b", BUCKET_WIDTH + 2));
+        index.insert(entry("a", 5));
+        index.insert(entry("c", BUCKET_WIDTH + 1));
+        assert_eq!(index.next_at(), Some(5));
+
+        assert_eq!(
+            index.pop_expired(BUCKET_WIDTH + 1, 10),
+            vec![entry("a", 5), entry("c", BUCKET_WIDTH + 1)]
+        );
+        // the rest of the bucket is kept
+        assert_eq!(index.next_at(), Some(BUCKET_WIDTH + 2));
+        assert_eq!(
+            index.pop_expired(u64::MAX, 1),
+            vec![entry("b", BUCKET_WIDTH + 2)]
+        );
+        assert_eq!(index.len, 1);
+    }
+}
@@ -0,0 +1,225 @@
+#![cfg(feature = "feature_flags")]
+#![allow(dead_code)]
+/*!
+Runtime feature flags.
+
+The generated methods of the impl macros check their [`Feature`] before doing anything,
+so the owner can switch off E.g. `nft_transfer_call` without a redeploy.
+
+# NOTES:
+  - Flags live under their own storage key, not in the contract struct, so the macros can
+    consult them without knowing the contract. Everything is enabled until disabled.
+  - Cargo features still decide what is compiled, these only switch compiled methods off.
+  - Behind the `feature_flags` feature, without it the generated methods don't read the flags.
+
+# EXAMPLE:
+```
+# use cmn::*;
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+}
+
+// impl_features!(Contract[, owner = <AccountId field; default: the contract itself>]);
+impl_features!(Contract, owner = owner_id);
+// disable_feature({"feature": "nft_transfer_call"}) => nft_transfer_call panics with "`nft_transfer_call` is disabled"
+```
+*/
+
+use super::*;
+
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Storage key of the flags.
+pub const FEATURES_KEY: &[u8] = b"FEATURES";
+
+#[repr(u8)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Feature {
+    FtTransfer,
+    FtTransferCall,
+    NftMint,
+    NftTransfer,
+    NftTransferCall,
+    NftApprove,
+    MtMint,
+    MtTransfer,
+    MtTransferCall,
+    MtApprove,
+}
+impl Feature {
+    pub const ALL: [Feature; 10] = [
+        Feature::FtTransfer,
+        Feature::FtTransferCall,
+        Feature::NftMint,
+        Feature::NftTransfer,
+        Feature::NftTransferCall,
+        Feature::NftApprove,
+        Feature::MtMint,
+        Feature::MtTransfer,
+        Feature::MtTransferCall,
+        Feature::MtApprove,
+    ];
+
+    #[inline]
+    pub const fn bit(self) -> u64 {
+        1 << self as u8
+    }
+
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Feature::FtTransfer => "ft_transfer",
+            Feature::FtTransferCall => "ft_transfer_call",
+            Feature::NftMint => "nft_mint",
+            Feature::NftTransfer => "nft_transfer",
+            Feature::NftTransferCall => "nft_transfer_call",
+            Feature::NftApprove => "nft_approve",
+            Feature::MtMint => "mt_mint",
+            Feature::MtTransfer => "mt_transfer",
+            Feature::MtTransferCall => "mt_transfer_call",
+            Feature::MtApprove => "mt_approve",
+        }
+    }
+}
+
+/// Bitset of the disabled features.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FeatureFlags(pub u64);
+impl FeatureFlags {
+    #[inline]
+    pub fn load() -> Self {
+        env::storage_read(FEATURES_KEY)
+            .map(|bytes| Self::try_from_slice(&bytes).unwrap_or_else(|_| env::abort()))
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn save(&self) {
+        env::storage_write(FEATURES_KEY, &self.try_to_vec().unwrap());
+    }
+
+    #[inline]
+    pub fn is_enabled(&self, feature: Feature) -> bool {
+        self.0 & feature.bit() == 0
+    }
+
+    #[inline]
+    pub fn enable(&mut self, feature: Feature) {
+        self.0 &= !feature.bit();
+    }
+
+    #[inline]
+    pub fn disable(&mut self, feature: Feature) {
+        self.0 |= feature.bit();
+    }
+
+    pub fn disabled(&self) -> Vec<Feature> {
+        Feature::ALL
+            .into_iter()
+            .filter(|feature| !self.is_enabled(*feature))
+            .collect()
+    }
+}
+
+/// Panics if `feature` is disabled.
+#[inline]
+pub fn require_enabled(feature: Feature) {
+    require!(
+        FeatureFlags::load().is_enabled(feature),
+        format!("`{}` is disabled", feature.as_str())
+    );
+}
+
+#[macro_export]
+macro_rules! impl_features {
+        ($contract:ident $(, owner = $owner:ident)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_features!`.
+                pub const FEATURES_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("feature_flags"),
+                    methods: &[
+                        $crate::manifest::Method::payable("enable_feature").role("owner"),
+                        $crate::manifest::Method::payable("disable_feature").role("owner"),
+                        $crate::manifest::Method::view("disabled_features"),
+                    ],
+                    storage: &[],
+                };
+
+                fn assert_features_owner(&self) {
+                    near_sdk::assert_one_yocto();
+                    let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                        .cloned()
+                        .unwrap_or_else(env::current_account_id);
+                    require!(
+                        env::predecessor_account_id() == owner_id,
+                        "Only the owner can change features"
+                    );
+                }
+            }
+            #[near_bindgen]
+            impl $contract {
+                #[payable]
+                pub fn enable_feature(&mut self, feature: $crate::features::Feature) {
+                    self.assert_features_owner();
+                    let mut flags = $crate::features::FeatureFlags::load();
+                    flags.enable(feature);
+                    flags.save();
+                    log!("Feature `{}` is enabled", feature.as_str());
+                }
+
+                #[payable]
+                pub fn disable_feature(&mut self, feature: $crate::features::Feature) {
+                    self.assert_features_owner();
+                    let mut flags = $crate::features::FeatureFlags::load();
+                    flags.disable(feature);
+                    flags.save();
+                    log!("Feature `{}` is disabled", feature.as_str());
+                }
+
+                pub fn disabled_features(&self) -> Vec<$crate::features::Feature> {
+                    $crate::features::FeatureFlags::load().disabled()
+                }
+            }
+        };
+    }
+pub use impl_features;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_flags() {
+        run_vm(vm!("alice.near"));
+
+        assert!(FeatureFlags::load().is_enabled(Feature::NftTransferCall));
+
+        let mut flags = FeatureFlags::load();
+        flags.disable(Feature::NftTransferCall);
+        flags.save();
+        assert_eq!(
+            FeatureFlags::load().disabled(),
+            vec![Feature::NftTransferCall]
+        );
+        require_enabled(Feature::NftTransfer);
+
+        flags.enable(Feature::NftTransferCall);
+        flags.save();
+        assert!(FeatureFlags::load().disabled().is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "`ft_transfer_call` is disabled")]
+    fn check_require_enabled() {
+        run_vm(vm!("alice.near"));
+
+        let mut flags = FeatureFlags::default();
+        flags.disable(Feature::FtTransferCall);
+        flags.save();
+        require_enabled(Feature::FtTransferCall);
+    }
+}
@@ -17,6 +17,24 @@ Fungible Token implementation with JSON serialization.
     attach more deposit than required.
   - To prevent the deployed contract from being modified or deleted, it should not have any access
     keys on its account.
+  - Minting should go through `FungibleToken::internal_mint`/`internal_deposit` so the optional
+    max supply is enforced. Changing the cap is proposed by the owner and applied after a timelock.
+  - With the `stats` feature transfers, mints and holders are counted in [`stats`](super::stats).
+  - With the `audit` feature every mutation checks that the ledger balances, see [`audit`].
+  - With the `breaker` feature the owner can set rules that pause the transfers on anomalies,
+    see `breaker`.
+  - With the `freeze` feature accounts can be frozen one by one, they can't send until unfrozen,
+    see `freeze`.
+  - With the `redeem` feature `ft_redeem` burns tokens for a single-use code, E.g. a gift card,
+    see `redeem`. `bridge`, `emissions` and `sponsor` have their features too.
+  - `ft_can_transfer` dry-runs the checks of `ft_transfer` (pause, registration, balance) without
+    mutating, so wallets can show why a transfer would fail.
+  - `impl_fungible_token_batch_views!` adds `ft_balances_of`/`storage_balances_of`, the balances of
+    up to [`MAX_BATCH_ACCOUNTS`] accounts (or the given `max_accounts`) in one view call.
+  - With `FungibleToken::with_lockups()` transfers can't move the locked part of a balance,
+    see [`lockup`].
+  - `FungibleToken::new_in` (or `InitArgs::namespace`) keeps the token keys under a prefix, so an
+    `nft::NonFungibleToken` can live in the same contract, see [`hybrid`](super::hybrid).
 
 # EXAMPLE:
 ```
@@ -31,7 +49,13 @@ pub struct Contract {
 
 const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 288 288'%3E%3Cg id='l' data-name='l'%3E%3Cpath d='M187.58,79.81l-30.1,44.69a3.2,3.2,0,0,0,4.75,4.2L191.86,103a1.2,1.2,0,0,1,2,.91v80.46a1.2,1.2,0,0,1-2.12.77L102.18,77.93A15.35,15.35,0,0,0,90.47,72.5H87.34A15.34,15.34,0,0,0,72,87.84V201.16A15.34,15.34,0,0,0,87.34,216.5h0a15.35,15.35,0,0,0,13.08-7.31l30.1-44.69a3.2,3.2,0,0,0-4.75-4.2L96.14,186a1.2,1.2,0,0,1-2-.91V104.61a1.2,1.2,0,0,1,2.12-.77l89.55,107.23a15.35,15.35,0,0,0,11.71,5.43h3.13A15.34,15.34,0,0,0,216,201.16V87.84A15.34,15.34,0,0,0,200.66,72.5h0A15.35,15.35,0,0,0,187.58,79.81Z'/%3E%3C/g%3E%3C/svg%3E";
 
+// impl_fungible_token_contract!(Contract, ft[, gas_for_ft_transfer_call = Gas(..), gas_for_resolve = Gas(..)]);
 ft::impl_fungible_token_contract!(Contract, ft);
+// impl_fungible_token_max_supply!(Contract, ft[, owner = <AccountId field; default: the contract itself>][, timelock = <nanoseconds; default: MAX_SUPPLY_TIMELOCK>]);
+ft::impl_fungible_token_max_supply!(Contract, ft);
+// impl_fungible_token_batch_views!(Contract, ft[, max_accounts = <usize; default: MAX_BATCH_ACCOUNTS>]);
+ft::impl_fungible_token_batch_views!(Contract, ft);
+events::impl_supported_standards!(Contract, NEP141, NEP145, NEP148, NEP297, FT_MAX_SUPPLY);
 
 #[near_bindgen]
 impl Contract {
@@ -54,6 +78,14 @@ impl Contract {
             ),
         }
     }
+
+    #[init]
+    pub fn new_with_args(args: ft::InitArgs) -> Self {
+        require_init!();
+        Self {
+            ft: ft::FungibleToken::from_args(args),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -61,7 +93,7 @@ mod tests {
     use super::test_utils::*;
     use super::*;
 
-    use ft::core::FungibleTokenCore;
+    use ft::{core::FungibleTokenCore, resolver::FungibleTokenResolver};
 
     const TOTAL_SUPPLY: Balance = 1_000_000_000_000_000;
 
@@ -83,6 +115,33 @@ mod tests {
         assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY);
     }
 
+    #[test]
+    fn test_balances_of() {
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let contract = Contract::new(accounts(1), TOTAL_SUPPLY.into());
+
+        run_vm(vm.is_view(true));
+        assert_eq!(
+            contract.ft_balances_of(vec![accounts(1), accounts(2)]),
+            vec![U128(TOTAL_SUPPLY), U128(0)]
+        );
+        let storage_balances = contract.storage_balances_of(vec![accounts(2), accounts(1)]);
+        assert!(storage_balances[0].is_none());
+        assert!(storage_balances[1].is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't have more than 100 accounts, got 101")]
+    fn test_balances_of_max() {
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let contract = Contract::new(accounts(1), TOTAL_SUPPLY.into());
+
+        run_vm(vm.is_view(true));
+        contract.ft_balances_of(vec![accounts(1); ft::MAX_BATCH_ACCOUNTS + 1]);
+    }
+
     #[test]
     #[should_panic(expected = "The contract is not initialized")]
     fn test_default() {
@@ -90,6 +149,71 @@ mod tests {
         _ = Contract::default();
     }
 
+    #[test]
+    fn test_storage_deposit_refund_to() {
+        let mut vm = get_vm(accounts(2));
+        run_vm(&vm);
+
+        let mut contract = Contract::new(accounts(2), TOTAL_SUPPLY.into());
+        let min_balance: Balance = contract.storage_balance_bounds().min.into();
+
+        // a relayer attached more than needed for alice
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(min_balance + 10)
+                .predecessor_account_id(accounts(0)),
+        );
+        contract.ft.storage_deposit(None, None, Some(accounts(3)));
+        assert_deposit_refunded(&accounts(3), 10);
+        assert!(contract.storage_balance_of(accounts(0)).is_some());
+    }
+
+    #[test]
+    fn test_migrate_account() {
+        let mut vm = get_vm(accounts(2));
+        run_vm(&vm);
+
+        let mut contract = Contract::new(accounts(2), TOTAL_SUPPLY.into());
+
+        run_vm(vm.storage_usage(env::storage_usage()));
+        assert_eq!(
+            contract.ft.migrate_account(&accounts(2), &accounts(1)),
+            TOTAL_SUPPLY
+        );
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, TOTAL_SUPPLY);
+        assert!(contract.storage_balance_of(accounts(2)).is_none());
+        assert_eq!(contract.ft.migrate_account(&accounts(2), &accounts(1)), 0);
+    }
+
+    #[test]
+    fn test_can_transfer() {
+        let mut vm = get_vm(accounts(2));
+        run_vm(&vm);
+
+        let mut contract = Contract::new(accounts(2), TOTAL_SUPPLY.into());
+
+        run_vm(vm.is_view(true));
+        let can_transfer = |contract: &Contract, amount: Balance| {
+            contract.ft_can_transfer(accounts(2), accounts(1), amount.into())
+        };
+        assert_eq!(
+            can_transfer(&contract, 1).reason,
+            Some(ft::TransferDenial::ReceiverNotRegistered)
+        );
+
+        run_vm(
+            vm.is_view(false)
+                .storage_usage(env::storage_usage())
+                .attached_deposit(contract.storage_balance_bounds().min.into()),
+        );
+        contract.storage_deposit(Some(accounts(1)), None);
+        assert!(can_transfer(&contract, TOTAL_SUPPLY).allowed);
+        assert_eq!(
+            can_transfer(&contract, TOTAL_SUPPLY + 1).reason,
+            Some(ft::TransferDenial::NotEnoughBalance)
+        );
+    }
+
     #[test]
     fn test_transfer() {
         let mut vm = get_vm(accounts(2));
@@ -125,14 +249,131 @@ mod tests {
         );
         assert_eq!(contract.ft_balance_of(accounts(1)).0, transfer_amount);
     }
+
+    #[test]
+    fn test_transfer_call_reentrancy() {
+        let mut vm = get_vm(accounts(2));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(2), TOTAL_SUPPLY.into());
+
+        for account_id in [accounts(1), accounts(3)] {
+            run_vm(
+                vm.storage_usage(env::storage_usage())
+                    .attached_deposit(contract.storage_balance_bounds().min.into())
+                    .predecessor_account_id(account_id),
+            );
+            contract.storage_deposit(None, None);
+        }
+
+        let mut probe = ReentrancyProbe::new(&vm);
+        probe.call(accounts(2), || {
+            contract.ft_transfer_call(accounts(1), 100.into(), None, "".to_string())
+        });
+        // the receiver passes the tokens on, then claims to have used none of them
+        probe.reenter(accounts(1), || {
+            contract.ft_transfer(accounts(3), 100.into(), None)
+        });
+        let used = probe.resolve(U128(100), || {
+            contract.ft_resolve_transfer(accounts(2), accounts(1), 100.into())
+        });
+
+        assert_eq!(used.0, 100);
+        assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 100);
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 0);
+        assert_eq!(contract.ft_balance_of(accounts(3)).0, 100);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY);
+    }
+
+    #[test]
+    #[should_panic(expected = "More gas is required")]
+    fn test_transfer_call_without_gas() {
+        let mut vm = get_vm(accounts(2));
+        run_vm(&vm);
+
+        let mut contract = Contract::new(accounts(2), TOTAL_SUPPLY.into());
+
+        run_vm(
+            vm.attached_deposit(1)
+                .prepaid_gas(ft::GAS_FOR_FT_TRANSFER_CALL)
+                .predecessor_account_id(accounts(2)),
+        );
+        contract.ft_transfer_call(accounts(1), 1.into(), None, "".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Max supply exceeded")]
+    fn test_mint_over_max_supply() {
+        run_vm(get_vm(accounts(2)));
+
+        let mut contract = Contract::new(accounts(2), TOTAL_SUPPLY.into());
+        contract.ft = contract.ft.with_max_supply(TOTAL_SUPPLY.into());
+
+        contract.ft.internal_mint(&accounts(2), 1.into(), None);
+    }
+
+    #[test]
+    fn test_max_supply_timelock() {
+        let mut vm = get_vm(accounts(2));
+        run_vm(&vm);
+
+        let mut contract = Contract::new(accounts(2), TOTAL_SUPPLY.into());
+        assert_eq!(contract.ft_max_supply(), None);
+
+        run_vm(
+            vm.attached_deposit(1)
+                .predecessor_account_id("current".parse().unwrap()),
+        );
+        contract.ft_propose_max_supply(Some((TOTAL_SUPPLY * 2).into()));
+
+        run_vm(vm.block_timestamp(ft::MAX_SUPPLY_TIMELOCK).attached_deposit(0));
+        contract.ft_apply_max_supply();
+        assert_eq!(contract.ft_max_supply(), Some((TOTAL_SUPPLY * 2).into()));
+        assert_eq!(contract.ft_pending_max_supply(), None);
+
+        contract.ft.internal_mint(&accounts(2), TOTAL_SUPPLY.into(), None);
+        assert_eq!(contract.ft_total_supply().0, TOTAL_SUPPLY * 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Max supply change is still timelocked")]
+    fn test_max_supply_before_timelock() {
+        let mut vm = get_vm("current".parse().unwrap());
+        run_vm(vm.attached_deposit(1));
+
+        let mut contract = Contract::new(accounts(2), TOTAL_SUPPLY.into());
+        contract.ft_propose_max_supply(Some(TOTAL_SUPPLY.into()));
+        contract.ft_apply_max_supply();
+    }
+
+    #[test]
+    fn test_new_with_args() {
+        let mut vm = get_vm(accounts(1));
+        run_vm(&vm);
+
+        let args: ft::InitArgs = near_sdk::serde_json::from_str(
+            r#"{ "total_supply": "1000", "name": "Example NEAR FT", "symbol": "EXAMPLE" }"#,
+        )
+        .unwrap();
+        let contract = Contract::new_with_args(args);
+
+        run_vm(vm.is_view(true));
+        assert_eq!(contract.ft_balance_of(accounts(1)).0, 1000);
+        assert_eq!(contract.ft.metadata.get().unwrap().decimals, 24);
+        assert_eq!(contract.ft_max_supply(), None);
+    }
 }
 ```
 */
 
 use super::*;
 
+use near_sdk::{
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+
 pub use near_contract_standards::fungible_token::{
-    self,
+    self, events,
     metadata::{self, FungibleTokenMetadata as Metadata, FT_METADATA_SPEC as METADATA_SPEC},
     FungibleToken as Token, *,
 };
@@ -145,37 +386,632 @@ mod for_rust_core {
         Token = 0,
         Metadata = 1,
     }
+    impl StorageKey {
+        /// The key under `namespace`, see `FungibleToken::new_in`.
+        pub fn in_namespace(self, namespace: &[u8]) -> Vec<u8> {
+            [namespace, &near_sdk::IntoStorageKey::into_storage_key(self)].concat()
+        }
+    }
 }
 pub use for_rust_core::*;
 
+pub mod audit;
+pub mod breaker;
+pub mod bridge;
+pub mod emissions;
+pub mod freeze;
+pub mod lockup;
+pub mod redeem;
+pub mod sale;
+pub mod sponsor;
+
+/// Gas attached to the `ft_resolve_transfer` callback.
+pub const GAS_FOR_RESOLVE: Gas = Gas(5_000_000_000_000);
+/// Gas `ft_transfer_call` keeps for itself and the callback, the rest goes to `ft_on_transfer`.
+pub const GAS_FOR_FT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000 + GAS_FOR_RESOLVE.0);
+
+/// Default cap of the accounts of `ft_balances_of`/`storage_balances_of`.
+pub const MAX_BATCH_ACCOUNTS: usize = 100;
+
+/// Default delay between proposing and applying a new max supply (1 day).
+pub const MAX_SUPPLY_TIMELOCK: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+/// Max supply change waiting for its timelock.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PendingMaxSupply {
+    /// `None` removes the cap.
+    pub max_supply: Option<U128>,
+    /// Block timestamp (nanoseconds) from which it can be applied.
+    pub unlocks_at: U64,
+}
+
+/// `ft_max_supply_changed` event, logged as `EVENT_JSON:`.
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FtMaxSupplyChanged {
+    pub old_max_supply: Option<U128>,
+    pub new_max_supply: Option<U128>,
+}
+impl FtMaxSupplyChanged {
+    pub fn emit(self) {
+        super::events::emit(
+            super::events::standards::FT_MAX_SUPPLY,
+            "ft_max_supply_changed",
+            self,
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum TransferDenial {
+    /// `ft_transfer` is disabled in the `features` flags.
+    Paused,
+    /// The sender is frozen, see `freeze`.
+    Frozen,
+    SameAccount,
+    ZeroAmount,
+    SenderNotRegistered,
+    NotEnoughBalance,
+    /// The balance is enough, its unlocked part isn't.
+    Locked,
+    ReceiverNotRegistered,
+}
+
+/// Result of `ft_can_transfer`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CanTransferResult {
+    pub allowed: bool,
+    pub reason: Option<TransferDenial>,
+}
+impl From<Option<TransferDenial>> for CanTransferResult {
+    #[inline]
+    fn from(reason: Option<TransferDenial>) -> Self {
+        Self {
+            allowed: reason.is_none(),
+            reason,
+        }
+    }
+}
+
+/// Init args as a single JSON object. Every field has a default,
+/// so new fields don't break existing deploy scripts.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde", default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InitArgs {
+    /// Defaults to the predecessor.
+    pub owner_id: Option<AccountId>,
+    pub total_supply: U128,
+    pub max_supply: Option<U128>,
+    pub spec: String,
+    pub name: String,
+    pub symbol: String,
+    pub icon: Option<String>,
+    pub reference: Option<String>,
+    pub reference_hash: Option<Base64VecU8>,
+    pub decimals: u8,
+    /// See `FungibleToken::with_lockups`.
+    pub lockups: bool,
+    /// Prefix of the token and metadata keys, see `FungibleToken::new_in`.
+    pub namespace: Option<String>,
+}
+impl Default for InitArgs {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            owner_id: None,
+            total_supply: U128(0),
+            max_supply: None,
+            spec: METADATA_SPEC.to_string(),
+            name: String::new(),
+            symbol: String::new(),
+            icon: None,
+            reference: None,
+            reference_hash: None,
+            decimals: 24,
+            lockups: false,
+            namespace: None,
+        }
+    }
+}
+impl InitArgs {
+    #[inline]
+    pub fn metadata(&self) -> Metadata {
+        Metadata {
+            spec: self.spec.clone(),
+            name: self.name.clone(),
+            symbol: self.symbol.clone(),
+            icon: self.icon.clone(),
+            reference: self.reference.clone(),
+            reference_hash: self.reference_hash.clone(),
+            decimals: self.decimals,
+        }
+    }
+}
+
+/// [`Metadata::assert_valid`] with bounded names and well-formed URIs.
+pub fn assert_valid_metadata(metadata: &Metadata) {
+    use validate::*;
+    metadata.assert_valid();
+    validate!(metadata.name => non_empty, max_len(MAX_NAME_LENGTH));
+    validate!(metadata.symbol => non_empty, max_len(MAX_NAME_LENGTH));
+    validate!(?metadata.icon => valid_data_uri, max_len(MAX_ICON_LENGTH));
+    validate!(?metadata.reference => valid_url, max_len(MAX_URL_LENGTH));
+}
+
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct FungibleToken {
     pub token: Token,
     pub metadata: LazyOption<Metadata>,
+    /// Cap of the total supply, checked on every mint.
+    pub max_supply: Option<Balance>,
+    pub pending_max_supply: Option<PendingMaxSupply>,
+    /// Locked balances, when lockups are enabled.
+    pub lockups: Option<collections::LookupMap<AccountId, lockup::Lockup>>,
 }
 impl FungibleToken {
+    #[inline]
     pub fn new(owner_id: AccountId, total_supply: U128, metadata: Metadata) -> Self {
-        metadata.assert_valid();
+        Self::new_in(&[], owner_id, total_supply, metadata)
+    }
+
+    /// Keeps the token and metadata keys under `namespace`, E.g. apart from the keys of an
+    /// `nft::NonFungibleToken` in the same contract, both start at `[0]`.
+    pub fn new_in(
+        namespace: &[u8],
+        owner_id: AccountId,
+        total_supply: U128,
+        metadata: Metadata,
+    ) -> Self {
+        assert_valid_metadata(&metadata);
         let mut this = Self {
-            token: Token::new(StorageKey::Token),
-            metadata: LazyOption::new(StorageKey::Metadata, Some(&metadata)),
+            token: Token::new(StorageKey::Token.in_namespace(namespace)),
+            metadata: LazyOption::new(
+                StorageKey::Metadata.in_namespace(namespace),
+                Some(&metadata),
+            ),
+            max_supply: None,
+            pending_max_supply: None,
+            lockups: None,
         };
         this.token.internal_register_account(&owner_id);
-        this.token.internal_deposit(&owner_id, total_supply.into());
+        this.internal_mint(
+            &owner_id,
+            total_supply,
+            Some("Initial tokens supply is minted"),
+        );
+        this
+    }
+
+    pub fn from_args(args: InitArgs) -> Self {
+        let metadata = args.metadata();
+        let owner_id = args.owner_id.unwrap_or_else(env::predecessor_account_id);
+        let namespace = args.namespace.unwrap_or_default();
+        let mut this = Self::new_in(namespace.as_bytes(), owner_id, args.total_supply, metadata);
+        if args.lockups {
+            this = this.with_lockups_in(namespace.as_bytes());
+        }
+        match args.max_supply {
+            Some(max_supply) => this.with_max_supply(max_supply),
+            None => this,
+        }
+    }
+
+    /// Caps the total supply from the start, without a timelock.
+    pub fn with_max_supply(mut self, max_supply: U128) -> Self {
+        require!(
+            self.token.total_supply <= max_supply.0,
+            "Max supply is less than the total supply"
+        );
+        self.max_supply = Some(max_supply.0);
+        self
+    }
+
+    /// Lets the owner lock balances, see [`lockup`].
+    #[inline]
+    pub fn with_lockups(self) -> Self {
+        self.with_lockups_in(&[])
+    }
+
+    /// [`Self::with_lockups`] of a token made with `new_in(namespace, ..)`.
+    pub fn with_lockups_in(mut self, namespace: &[u8]) -> Self {
+        self.lockups = Some(collections::LookupMap::new(
+            lockup::StorageKey::Lockups.in_namespace(namespace),
+        ));
+        self
+    }
+
+    #[inline]
+    pub fn lockup_of(&self, account_id: &AccountId) -> Option<lockup::Lockup> {
+        self.lockups
+            .as_ref()
+            .and_then(|lockups| lockups.get(account_id))
+    }
+
+    /// Locks the balance of a registered account, replacing its lockup.
+    pub fn set_lockup(&mut self, account_id: &AccountId, lockup: lockup::Lockup) {
+        lockup.assert_valid();
+        require!(
+            self.token.accounts.contains_key(account_id),
+            format!("The account {} is not registered", account_id)
+        );
+        let lockups = self
+            .lockups
+            .as_mut()
+            .unwrap_or_else(|| env::panic_str("Lockups are disabled"));
+        lockups.insert(account_id, &lockup);
+        lockup::emit(
+            "ft_lockup_set",
+            serde_json::json!({ "account_id": account_id, "lockup": lockup }),
+        );
+    }
+
+    /// Unlocks the balance of the account, returns its lockup.
+    pub fn remove_lockup(&mut self, account_id: &AccountId) -> Option<lockup::Lockup> {
+        let lockup = self
+            .lockups
+            .as_mut()
+            .and_then(|lockups| lockups.remove(account_id));
+        if lockup.is_some() {
+            lockup::emit(
+                "ft_lockup_remove",
+                serde_json::json!({ "account_id": account_id }),
+            );
+        }
+        lockup
+    }
+
+    /// Part of the balance of the account that's still locked.
+    #[inline]
+    pub fn locked_balance(&self, account_id: &AccountId) -> Balance {
+        self.lockup_of(account_id)
+            .map_or(0, |lockup| lockup.locked_at(env::block_timestamp()))
+    }
+
+    /// Part of the balance of the account that transfers can move.
+    #[inline]
+    pub fn transferable_balance(&self, account_id: &AccountId) -> Balance {
+        self.token
+            .accounts
+            .get(account_id)
+            .unwrap_or_default()
+            .saturating_sub(self.locked_balance(account_id))
+    }
+
+    /// Panics if `amount` is over the transferable balance, drops the lockup once it ended.
+    fn check_lockup(&mut self, sender_id: &AccountId, amount: Balance) {
+        let lockups = match self.lockups.as_mut() {
+            Some(lockups) => lockups,
+            None => return,
+        };
+        let locked = match lockups.get(sender_id) {
+            Some(lockup) => lockup.locked_at(env::block_timestamp()),
+            None => return,
+        };
+        if locked == 0 {
+            lockups.remove(sender_id);
+            return;
+        }
+        require!(
+            amount <= self.transferable_balance(sender_id),
+            "Not enough unlocked balance"
+        );
+    }
+
+    /// `internal_deposit` of the standard that respects the max supply.
+    /// Every path that creates tokens should go through it.
+    pub fn internal_deposit(&mut self, account_id: &AccountId, amount: Balance) {
+        if let Some(max_supply) = self.max_supply {
+            require!(
+                self.token
+                    .total_supply
+                    .checked_add(amount)
+                    .is_some_and(|total_supply| total_supply <= max_supply),
+                "Max supply exceeded"
+            );
+        }
+        self.track_holders(&[account_id], |ft| {
+            ft.token.internal_deposit(account_id, amount)
+        });
+    }
 
+    /// Why `ft_transfer` of `amount` from `sender_id` to `receiver_id` would fail now, without
+    /// transferring, in the order `ft_transfer` checks it. `None` if it would succeed.
+    pub fn can_transfer(
+        &self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+    ) -> Option<TransferDenial> {
+        let balance_of = |account_id| self.token.accounts.get(account_id);
+        #[cfg(feature = "feature_flags")]
+        let paused = !features::FeatureFlags::load().is_enabled(features::Feature::FtTransfer);
+        #[cfg(not(feature = "feature_flags"))]
+        let paused = false;
+        #[cfg(feature = "freeze")]
+        let frozen = freeze::is_frozen(sender_id);
+        #[cfg(not(feature = "freeze"))]
+        let frozen = false;
+        if paused {
+            Some(TransferDenial::Paused)
+        } else if frozen {
+            Some(TransferDenial::Frozen)
+        } else if sender_id == receiver_id {
+            Some(TransferDenial::SameAccount)
+        } else if amount == 0 {
+            Some(TransferDenial::ZeroAmount)
+        } else if balance_of(sender_id).is_none() {
+            Some(TransferDenial::SenderNotRegistered)
+        } else if balance_of(sender_id).unwrap() < amount {
+            Some(TransferDenial::NotEnoughBalance)
+        } else if self.transferable_balance(sender_id) < amount {
+            Some(TransferDenial::Locked)
+        } else if balance_of(receiver_id).is_none() {
+            Some(TransferDenial::ReceiverNotRegistered)
+        } else {
+            None
+        }
+    }
+
+    /// `ft_balance_of` of every account, in order, 0 for unregistered ones.
+    pub fn balances_of(&self, account_ids: &[AccountId], max_accounts: usize) -> Vec<U128> {
+        utils::accounts::assert_max_len(account_ids.len(), max_accounts);
+        account_ids
+            .iter()
+            .map(|account_id| U128(self.token.accounts.get(account_id).unwrap_or_default()))
+            .collect()
+    }
+
+    /// `storage_balance_of` of every account, in order, none for unregistered ones.
+    pub fn storage_balances_of(
+        &self,
+        account_ids: &[AccountId],
+        max_accounts: usize,
+    ) -> Vec<Option<StorageBalance>> {
+        utils::accounts::assert_max_len(account_ids.len(), max_accounts);
+        account_ids
+            .iter()
+            .map(|account_id| self.token.storage_balance_of(account_id.clone()))
+            .collect()
+    }
+
+    /// Whether the account has a non-zero balance.
+    #[inline]
+    pub fn is_holder(&self, account_id: &AccountId) -> bool {
+        self.token
+            .accounts
+            .get(account_id)
+            .is_some_and(|balance| balance > 0)
+    }
+
+    /// Runs `f` and records in [`stats`] the accounts that started or stopped holding tokens,
+    /// [`audit`] checks the ledger after it. With the `membership` feature, drops the cached
+    /// membership tiers of the accounts.
+    pub fn track_holders<R>(
+        &mut self,
+        account_ids: &[&AccountId],
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        // every mutation of the balances goes through here
+        #[cfg(feature = "membership")]
+        for account_id in account_ids {
+            membership::invalidate(account_id);
+        }
+        let f = |ft: &mut Self| audit::audited(ft, account_ids, f);
+        if !stats::ENABLED {
+            return f(self);
+        }
+        let were_holders: Vec<bool> = account_ids.iter().map(|id| self.is_holder(id)).collect();
+        let result = f(self);
+        for (account_id, was_holder) in account_ids.iter().zip(were_holders) {
+            stats::record_holder(was_holder, self.is_holder(account_id));
+        }
+        result
+    }
+
+    /// `internal_transfer` of the standard that checks the memo and records the transfer.
+    pub fn internal_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        amount: Balance,
+        memo: Option<String>,
+    ) {
+        if let Some(memo) = &memo {
+            strings::assert_max_bytes(memo, strings::MAX_MEMO_LENGTH);
+        }
+        #[cfg(feature = "freeze")]
+        freeze::assert_not_frozen(sender_id);
+        // The sender checks of the standard go first, so a locked sender without the balance
+        // fails like `can_transfer` tells.
+        require!(
+            sender_id != receiver_id,
+            "Sender and receiver should be different"
+        );
+        require!(amount > 0, "The amount should be a positive number");
+        require!(
+            self.token.internal_unwrap_balance_of(sender_id) >= amount,
+            "The account doesn't have enough balance"
+        );
+        self.check_lockup(sender_id, amount);
+        self.track_holders(&[sender_id, receiver_id], |ft| {
+            ft.token
+                .internal_transfer(sender_id, receiver_id, amount, memo)
+        });
+        stats::record(stats::Counter::Transfers);
+        #[cfg(feature = "breaker")]
+        breaker::record_transfer(self, amount);
+    }
+
+    /// Mints `amount` to a registered `account_id` and emits `ft_mint`.
+    pub fn internal_mint(&mut self, account_id: &AccountId, amount: U128, memo: Option<&str>) {
+        self.internal_deposit(account_id, amount.into());
+        stats::record(stats::Counter::Mints);
         events::FtMint {
-            owner_id: &owner_id,
-            amount: &total_supply,
-            memo: Some("Initial tokens supply is minted"),
+            owner_id: account_id,
+            amount: &amount,
+            memo,
         }
         .emit();
-        this
+    }
+
+    /// Moves the balance and the registration of `old_account_id` to `new_account_id`,
+    /// returns the moved balance. Does nothing when `old_account_id` isn't registered.
+    pub fn migrate_account(
+        &mut self,
+        old_account_id: &AccountId,
+        new_account_id: &AccountId,
+    ) -> Balance {
+        let balance = match self.token.accounts.get(old_account_id) {
+            Some(balance) => balance,
+            None => return 0,
+        };
+        let was_registered = self.token.accounts.contains_key(new_account_id);
+        if !was_registered {
+            self.token.internal_register_account(new_account_id);
+        }
+        // the lockup follows the balance, the transfer below would be locked otherwise
+        if let Some(lockups) = self.lockups.as_mut() {
+            if let Some(lockup) = lockups.remove(old_account_id) {
+                require!(
+                    !lockups.contains_key(new_account_id),
+                    "The new account already has a lockup"
+                );
+                lockups.insert(new_account_id, &lockup);
+            }
+        }
+        if balance > 0 {
+            self.internal_transfer(
+                old_account_id,
+                new_account_id,
+                balance,
+                Some("Account migration".to_string()),
+            );
+        }
+        self.token.accounts.remove(old_account_id);
+        if was_registered {
+            // the new account keeps its own registration, the released one is its refund
+            Promise::new(new_account_id.clone())
+                .transfer(self.token.storage_balance_bounds().min.0);
+        }
+        balance
+    }
+
+    /// `storage_deposit` of the standard that sends the unused deposit to `refund_to`,
+    /// the predecessor by default.
+    pub fn storage_deposit(
+        &mut self,
+        account_id: Option<AccountId>,
+        registration_only: Option<bool>,
+        refund_to: Option<AccountId>,
+    ) -> StorageBalance {
+        let refund_to = refund_to.unwrap_or_else(env::predecessor_account_id);
+        let amount = env::attached_deposit();
+        let account_id = account_id.unwrap_or_else(env::predecessor_account_id);
+        // the standard only takes the minimum balance, with or without `registration_only`
+        let _ = registration_only;
+        if self.token.accounts.contains_key(&account_id) {
+            log!("The account is already registered, refunding the deposit");
+            if amount > 0 {
+                Promise::new(refund_to).transfer(amount);
+            }
+        } else {
+            let min_balance = self.token.storage_balance_bounds().min.0;
+            require!(
+                amount >= min_balance,
+                "The attached deposit is less than the minimum storage balance"
+            );
+            self.token.internal_register_account(&account_id);
+            let refund = amount - min_balance;
+            if refund > 0 {
+                Promise::new(refund_to).transfer(refund);
+            }
+        }
+        self.token.storage_balance_of(account_id).unwrap()
+    }
+
+    /// Schedules a max supply change, applicable after `timelock` nanoseconds.
+    pub fn propose_max_supply(&mut self, max_supply: Option<U128>, timelock: u64) {
+        let unlocks_at = env::block_timestamp() + timelock;
+        self.pending_max_supply = Some(PendingMaxSupply {
+            max_supply,
+            unlocks_at: unlocks_at.into(),
+        });
+        log!("Max supply change is proposed, unlocks at {}", unlocks_at);
+    }
+
+    /// Applies the pending max supply change once its timelock has passed.
+    pub fn apply_max_supply(&mut self) {
+        let pending = self
+            .pending_max_supply
+            .take()
+            .unwrap_or_else(|| env::panic_str("No pending max supply"));
+        require!(
+            env::block_timestamp() >= pending.unlocks_at.0,
+            "Max supply change is still timelocked"
+        );
+        if let Some(max_supply) = pending.max_supply {
+            require!(
+                self.token.total_supply <= max_supply.0,
+                "Max supply is less than the total supply"
+            );
+        }
+
+        FtMaxSupplyChanged {
+            old_max_supply: self.max_supply.map(U128),
+            new_max_supply: pending.max_supply,
+        }
+        .emit();
+        self.max_supply = pending.max_supply.map(|max_supply| max_supply.0);
+    }
+
+    /// `ft_transfer_call` that fails fast when the prepaid gas can't cover
+    /// `gas_for_ft_transfer_call`, instead of leaving the receiver with nothing.
+    pub fn ft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        amount: U128,
+        memo: Option<String>,
+        msg: String,
+        gas_for_ft_transfer_call: Gas,
+        gas_for_resolve: Gas,
+    ) -> PromiseOrValue<U128> {
+        near_sdk::assert_one_yocto();
+        require!(
+            gas_for_resolve < gas_for_ft_transfer_call,
+            "gas_for_resolve must be less than gas_for_ft_transfer_call"
+        );
+        let prepaid_gas = env::prepaid_gas();
+        require!(
+            prepaid_gas > gas_for_ft_transfer_call,
+            format!(
+                "More gas is required: ft_transfer_call needs more than {} TGas, attached {} TGas",
+                gas_for_ft_transfer_call.0 / Gas::ONE_TERA.0,
+                prepaid_gas.0 / Gas::ONE_TERA.0
+            )
+        );
+
+        let sender_id = env::predecessor_account_id();
+        self.internal_transfer(&sender_id, &receiver_id, amount.into(), memo);
+
+        receiver::ext_ft_receiver::ext(receiver_id.clone())
+            .with_static_gas(prepaid_gas - gas_for_ft_transfer_call)
+            .ft_on_transfer(sender_id.clone(), amount, msg)
+            .then(
+                resolver::ext_ft_resolver::ext(env::current_account_id())
+                    .with_static_gas(gas_for_resolve)
+                    .ft_resolve_transfer(sender_id, receiver_id, amount),
+            )
+            .into()
     }
 }
 
 #[macro_export]
 macro_rules! impl_fungible_token_contract {
-        (@IMPL_CORE $contract:ident, $ft:ident) => {
+        (@IMPL_CORE $contract:ident, $ft:ident, $gas_for_ft_transfer_call:expr, $gas_for_resolve:expr) => {
             #[near_bindgen]
             impl $crate::ft::core::FungibleTokenCore for $contract {
                 #[payable]
@@ -185,7 +1021,11 @@ macro_rules! impl_fungible_token_contract {
                     amount: U128,
                     memo: Option<String>,
                 ) {
-                    self.$ft.token.ft_transfer(receiver_id, amount, memo)
+                    #[cfg(feature = "feature_flags")]
+                    $crate::features::require_enabled($crate::features::Feature::FtTransfer);
+                    near_sdk::assert_one_yocto();
+                    let sender_id = env::predecessor_account_id();
+                    self.$ft.internal_transfer(&sender_id, &receiver_id, amount.into(), memo)
                 }
 
                 #[payable]
@@ -196,7 +1036,16 @@ macro_rules! impl_fungible_token_contract {
                     memo: Option<String>,
                     msg: String,
                 ) -> PromiseOrValue<U128> {
-                    self.$ft.token.ft_transfer_call(receiver_id, amount, memo, msg)
+                    #[cfg(feature = "feature_flags")]
+                    $crate::features::require_enabled($crate::features::Feature::FtTransferCall);
+                    self.$ft.ft_transfer_call(
+                        receiver_id,
+                        amount,
+                        memo,
+                        msg,
+                        $gas_for_ft_transfer_call,
+                        $gas_for_resolve,
+                    )
                 }
 
                 fn ft_total_supply(&self) -> U128 {
@@ -218,7 +1067,9 @@ macro_rules! impl_fungible_token_contract {
                     amount: U128,
                 ) -> U128 {
                     let (used_amount, burned_amount) =
-                        self.$ft.token.internal_ft_resolve_transfer(&sender_id, receiver_id, amount);
+                        self.$ft.track_holders(&[&sender_id, &receiver_id.clone()], |ft| {
+                            ft.token.internal_ft_resolve_transfer(&sender_id, receiver_id, amount)
+                        });
                     if burned_amount > 0 {
                         self.on_tokens_burned(sender_id, burned_amount);
                     }
@@ -235,7 +1086,7 @@ macro_rules! impl_fungible_token_contract {
                     account_id: Option<AccountId>,
                     registration_only: Option<bool>,
                 ) -> StorageBalance {
-                    self.$ft.token.storage_deposit(account_id, registration_only)
+                    self.$ft.storage_deposit(account_id, registration_only, None)
                 }
 
                 #[payable]
@@ -245,8 +1096,13 @@ macro_rules! impl_fungible_token_contract {
 
                 #[payable]
                 fn storage_unregister(&mut self, force: Option<bool>) -> bool {
+                    let predecessor_id = env::predecessor_account_id();
                     #[allow(unused_variables)]
-                    if let Some((account_id, balance)) = self.$ft.token.internal_storage_unregister(force) {
+                    if let Some((account_id, balance)) = self.$ft.track_holders(&[&predecessor_id], |ft| {
+                        ft.token.internal_storage_unregister(force)
+                    }) {
+                        // a new registration starts unlocked
+                        self.$ft.remove_lockup(&account_id);
                         self.on_account_closed(account_id, balance);
                         true
                     } else {
@@ -264,7 +1120,43 @@ macro_rules! impl_fungible_token_contract {
             }
         };
         ($contract:ident, $ft:ident) => {
+            impl_fungible_token_contract!(
+                $contract,
+                $ft,
+                gas_for_ft_transfer_call = $crate::ft::GAS_FOR_FT_TRANSFER_CALL,
+                gas_for_resolve = $crate::ft::GAS_FOR_RESOLVE
+            );
+        };
+        (
+            $contract:ident,
+            $ft:ident,
+            gas_for_ft_transfer_call = $gas_for_ft_transfer_call:expr,
+            gas_for_resolve = $gas_for_resolve:expr $(,)?
+        ) => {
             impl $contract {
+                /// Manifest entries of the methods generated by `impl_fungible_token_contract!`.
+                pub const FT_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("ft"),
+                    methods: &[
+                        $crate::manifest::Method::payable("ft_transfer"),
+                        $crate::manifest::Method::payable("ft_transfer_call"),
+                        $crate::manifest::Method::view("ft_total_supply"),
+                        $crate::manifest::Method::view("ft_balance_of"),
+                        $crate::manifest::Method::private("ft_resolve_transfer"),
+                        $crate::manifest::Method::payable("storage_deposit"),
+                        $crate::manifest::Method::payable("storage_withdraw"),
+                        $crate::manifest::Method::payable("storage_unregister"),
+                        $crate::manifest::Method::view("storage_balance_bounds"),
+                        $crate::manifest::Method::view("storage_balance_of"),
+                        $crate::manifest::Method::view("ft_metadata"),
+                        $crate::manifest::Method::view("ft_can_transfer"),
+                    ],
+                    storage: &[
+                        // 40 (record) + 1 (prefix) + 4 + 64 (account id) + 16 (balance)
+                        $crate::manifest::StorageEstimate::new("storage_deposit", 125),
+                    ],
+                };
+
                 fn on_account_closed(&mut self, account_id: AccountId, balance: Balance) {
                     log!("Closed @{} with {}", account_id, balance);
                 }
@@ -273,9 +1165,21 @@ macro_rules! impl_fungible_token_contract {
                     log!("Account @{} burned {}", account_id, amount);
                 }
             }
-            impl_fungible_token_contract!(@IMPL_CORE $contract, $ft);
+            impl_fungible_token_contract!(@IMPL_CORE $contract, $ft, $gas_for_ft_transfer_call, $gas_for_resolve);
             impl_fungible_token_contract!(@IMPL_STORAGE $contract, $ft);
             #[near_bindgen]
+            impl $contract {
+                /// Whether `ft_transfer` would succeed now, and why not, E.g. for exchanges to pre-validate withdrawals.
+                pub fn ft_can_transfer(
+                    &self,
+                    sender_id: AccountId,
+                    receiver_id: AccountId,
+                    amount: U128,
+                ) -> $crate::ft::CanTransferResult {
+                    self.$ft.can_transfer(&sender_id, &receiver_id, amount.0).into()
+                }
+            }
+            #[near_bindgen]
             impl $crate::ft::metadata::FungibleTokenMetadataProvider for $contract {
                 fn ft_metadata(&self) -> $crate::ft::Metadata {
                     self.$ft.metadata.get().unwrap()
@@ -284,3 +1188,93 @@ macro_rules! impl_fungible_token_contract {
         };
     }
 pub use impl_fungible_token_contract;
+
+#[macro_export]
+macro_rules! impl_fungible_token_max_supply {
+        ($contract:ident, $ft:ident $(, owner = $owner:ident)? $(, timelock = $timelock:expr)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_fungible_token_max_supply!`.
+                pub const FT_MAX_SUPPLY_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("ft"),
+                    methods: &[
+                        $crate::manifest::Method::view("ft_max_supply"),
+                        $crate::manifest::Method::view("ft_pending_max_supply"),
+                        $crate::manifest::Method::payable("ft_propose_max_supply").role("owner"),
+                        $crate::manifest::Method::call("ft_apply_max_supply"),
+                    ],
+                    storage: &[],
+                };
+            }
+            #[near_bindgen]
+            impl $contract {
+                pub fn ft_max_supply(&self) -> Option<U128> {
+                    self.$ft.max_supply.map(U128)
+                }
+
+                pub fn ft_pending_max_supply(&self) -> Option<$crate::ft::PendingMaxSupply> {
+                    self.$ft.pending_max_supply.clone()
+                }
+
+                /// `None` removes the cap once applied.
+                #[payable]
+                pub fn ft_propose_max_supply(&mut self, max_supply: Option<U128>) {
+                    near_sdk::assert_one_yocto();
+                    let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                        .cloned()
+                        .unwrap_or_else(env::current_account_id);
+                    require!(
+                        env::predecessor_account_id() == owner_id,
+                        "Only the owner can propose a max supply"
+                    );
+                    #[allow(unused_variables)]
+                    let timelock = $crate::ft::MAX_SUPPLY_TIMELOCK;
+                    $(let timelock = $timelock;)?
+                    self.$ft.propose_max_supply(max_supply, timelock);
+                }
+
+                /// Anyone can apply the owner's proposal once the timelock has passed.
+                pub fn ft_apply_max_supply(&mut self) {
+                    self.$ft.apply_max_supply();
+                }
+            }
+        };
+    }
+pub use impl_fungible_token_max_supply;
+
+#[macro_export]
+macro_rules! impl_fungible_token_batch_views {
+        ($contract:ident, $ft:ident $(, max_accounts = $max_accounts:expr)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_fungible_token_batch_views!`.
+                pub const FT_BATCH_VIEWS_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("ft"),
+                    methods: &[
+                        $crate::manifest::Method::view("ft_balances_of"),
+                        $crate::manifest::Method::view("storage_balances_of"),
+                    ],
+                    storage: &[],
+                };
+
+                #[inline]
+                fn ft_batch_views_max_accounts() -> usize {
+                    #[allow(unused_variables)]
+                    let max_accounts = $crate::ft::MAX_BATCH_ACCOUNTS;
+                    $(let max_accounts = $max_accounts;)?
+                    max_accounts
+                }
+            }
+            #[near_bindgen]
+            impl $contract {
+                /// `ft_balance_of` of every account, in the order of `account_ids`.
+                pub fn ft_balances_of(&self, account_ids: Vec<AccountId>) -> Vec<U128> {
+                    self.$ft.balances_of(&account_ids, Self::ft_batch_views_max_accounts())
+                }
+
+                /// `storage_balance_of` of every account, in the order of `account_ids`.
+                pub fn storage_balances_of(&self, account_ids: Vec<AccountId>) -> Vec<Option<StorageBalance>> {
+                    self.$ft.storage_balances_of(&account_ids, Self::ft_batch_views_max_accounts())
+                }
+            }
+        };
+    }
+pub use impl_fungible_token_batch_views;
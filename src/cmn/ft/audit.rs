@@ -0,0 +1,191 @@
+/*!
+Double-entry audit of the FT ledger.
+
+With the `audit` feature every mutation of the balances checks that the balances it touched
+changed by what the total supply changed by, and keeps a running sum of all the balances that
+has to stay equal to the total supply. A divergence panics, so the mutation never lands.
+`impl_fungible_token_audit!` generates an `audit_check()` view, E.g. for a sanity check after a
+migration that wrote the state directly.
+
+# NOTES:
+  - Every mutation of the balances goes through `FungibleToken::track_holders`, which runs the
+    audit. Code writing `token.accounts`/`token.total_supply` directly isn't audited, that's what
+    `audit_check` catches.
+  - The running sum is one record under its own storage key, paid by the contract (~16 bytes).
+    It starts at the total supply of the first audited mutation, so enabling the feature on a
+    deployed contract assumes the ledger is balanced then.
+  - Without the `audit` feature nothing is checked and `audit_check` has no sum.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    ft: ft::FungibleToken,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId, total_supply: U128) -> Self {
+        require_init!();
+        Self {
+            ft: ft::FungibleToken::from_args(ft::InitArgs {
+                owner_id: Some(owner_id),
+                total_supply,
+                name: "Audited".to_string(),
+                symbol: "AUD".to_string(),
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+ft::impl_fungible_token_contract!(Contract, ft);
+// impl_fungible_token_audit!(Contract, ft);
+ft::audit::impl_fungible_token_audit!(Contract, ft);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn test_audit_check() {
+        let vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0), U128(1_000));
+
+        let report = contract.audit_check();
+        assert_eq!(report.total_supply, U128(1_000));
+        if !ft::audit::ENABLED {
+            assert_eq!(report.balanced, None);
+            return;
+        }
+        assert_eq!(report.balanced, Some(true));
+
+        // a migration that minted without a balance
+        contract.ft.token.total_supply += 500;
+        assert_eq!(contract.audit_check().balanced, Some(false));
+    }
+}
+```
+*/
+
+use super::super::*;
+use super::FungibleToken;
+
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Whether the ledger is audited, the `audit` feature.
+pub const ENABLED: bool = cfg!(feature = "audit");
+
+raw_storage_key!(pub enum StorageKey in storage_key::AUDIT {
+    Audit,
+});
+
+/// Result of `audit_check`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AuditReport {
+    pub total_supply: U128,
+    /// Running sum of all the balances, none without the `audit` feature.
+    pub balances: Option<U128>,
+    /// Whether the sum is the total supply, none without the `audit` feature.
+    pub balanced: Option<bool>,
+}
+
+#[inline]
+fn key() -> Vec<u8> {
+    near_sdk::IntoStorageKey::into_storage_key(StorageKey::Audit)
+}
+
+/// Running sum of all the balances, none before the first audited mutation.
+pub fn balances() -> Option<Balance> {
+    env::storage_read(&key()).map(|bytes| Balance::try_from_slice(&bytes).unwrap())
+}
+
+#[inline]
+fn set_balances(balances: Balance) {
+    env::storage_write(&key(), &balances.try_to_vec().unwrap());
+}
+
+fn balances_of(ft: &FungibleToken, account_ids: &[&AccountId]) -> Option<Balance> {
+    account_ids
+        .iter()
+        .enumerate()
+        // an account listed twice is counted once
+        .filter(|(index, account_id)| !account_ids[..*index].contains(account_id))
+        .try_fold(0u128, |sum, (_, account_id)| {
+            sum.checked_add(ft.token.accounts.get(account_id).unwrap_or_default())
+        })
+}
+
+/// Runs `f`, which only changes the balances of `account_ids`, and panics if the ledger
+/// doesn't balance after it.
+pub fn audited<R>(
+    ft: &mut FungibleToken,
+    account_ids: &[&AccountId],
+    f: impl FnOnce(&mut FungibleToken) -> R,
+) -> R {
+    if !ENABLED {
+        return f(ft);
+    }
+    let supply_before = ft.token.total_supply;
+    let sum_before = balances().unwrap_or(supply_before);
+    let touched_before = balances_of(ft, account_ids);
+    let result = f(ft);
+    let supply_after = ft.token.total_supply;
+    let touched_after = balances_of(ft, account_ids);
+
+    // sum_before - touched_before + touched_after, without going below 0
+    let sum_after = touched_before
+        .zip(touched_after)
+        .and_then(|(before, after)| sum_before.checked_add(after)?.checked_sub(before));
+    require!(
+        sum_after == Some(supply_after),
+        format!(
+            "Ledger audit failed: balances sum to {:?}, total supply is {}",
+            sum_after, supply_after
+        )
+    );
+    set_balances(supply_after);
+    result
+}
+
+/// Compares the running sum of the balances with the total supply.
+pub fn check(ft: &FungibleToken) -> AuditReport {
+    let total_supply = ft.token.total_supply;
+    let balances = balances().filter(|_| ENABLED);
+    AuditReport {
+        total_supply: U128(total_supply),
+        balances: balances.map(U128),
+        balanced: balances.map(|balances| balances == total_supply),
+    }
+}
+
+#[macro_export]
+macro_rules! impl_fungible_token_audit {
+    ($contract:ident, $ft:ident $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_fungible_token_audit!`.
+            pub const FT_AUDIT_MANIFEST: $crate::manifest::Component =
+                $crate::manifest::Component {
+                    feature: Some("ft"),
+                    methods: &[$crate::manifest::Method::view("audit_check")],
+                    storage: &[],
+                };
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Whether the balances sum to the total supply, with the `audit` feature.
+            pub fn audit_check(&self) -> $crate::ft::audit::AuditReport {
+                $crate::ft::audit::check(&self.$ft)
+            }
+        }
+    };
+}
+pub use impl_fungible_token_audit;
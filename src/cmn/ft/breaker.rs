@@ -0,0 +1,274 @@
+#![cfg(feature = "breaker")]
+/*!
+Circuit breaker of the FT, pausing transfers on anomalies.
+
+The owner sets rules, a tripped rule disables `ft_transfer`/`ft_transfer_call` in the
+[`features`](super::super::features) flags and logs a `breaker_tripped` alert, until the owner
+enables them again.
+
+Rules:
+  - `max_block_transfer_bps`: the transfers of one block move more than these basis points of
+    the total supply.
+  - `audit_mismatch`: the running sum of the balances of the [`audit`](super::audit) isn't the
+    total supply, checked by anyone with `breaker_check_audit` (E.g. a keeper bot).
+
+# NOTES:
+  - The rules and the volume of the current block are one record under their own storage key,
+    paid by the contract. Without rules a transfer only reads it, and without the `breaker`
+    feature it doesn't.
+  - The transfer that trips the breaker still goes through, a panic would revert the pause too.
+  - `audit_mismatch` needs the `audit` feature, without it the ledger is never unbalanced.
+  - Events are logged as `EVENT_JSON:` with the `ft_breaker` standard.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    ft: ft::FungibleToken,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId, total_supply: U128) -> Self {
+        require_init!();
+        Self {
+            owner_id: owner_id.clone(),
+            ft: ft::FungibleToken::from_args(ft::InitArgs {
+                owner_id: Some(owner_id),
+                total_supply,
+                name: "Guarded".to_string(),
+                symbol: "GRD".to_string(),
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+ft::impl_fungible_token_contract!(Contract, ft);
+// impl_fungible_token_breaker!(Contract, ft[, owner = <AccountId field; default: the contract itself>]);
+ft::breaker::impl_fungible_token_breaker!(Contract, ft, owner = owner_id);
+impl_features!(Contract, owner = owner_id);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    use ft::core::FungibleTokenCore;
+
+    #[test]
+    fn test_breaker_trips() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0), U128(1_000));
+        contract.ft.token.internal_register_account(&accounts(1));
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        // 10% of the supply per block
+        contract.breaker_set_rules(ft::breaker::BreakerRules {
+            max_block_transfer_bps: Some(1_000),
+            audit_mismatch: false,
+        });
+        contract.ft_transfer(accounts(1), U128(60), None);
+        assert!(contract.disabled_features().is_empty());
+        // 120 in the same block
+        contract.ft_transfer(accounts(1), U128(60), None);
+        assert_eq!(
+            contract.disabled_features(),
+            vec![features::Feature::FtTransfer, features::Feature::FtTransferCall]
+        );
+
+        // the owner resumes
+        contract.enable_feature(features::Feature::FtTransfer);
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .block_index(1),
+        );
+        contract.ft_transfer(accounts(1), U128(60), None);
+    }
+}
+```
+*/
+
+use super::super::*;
+use super::FungibleToken;
+
+use contract_core::bps;
+use near_sdk::serde::{Deserialize, Serialize};
+
+raw_storage_key!(pub enum StorageKey in storage_key::BREAKER {
+    Breaker,
+});
+
+#[derive(
+    BorshDeserialize,
+    BorshSerialize,
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BreakerRules {
+    /// Share of the total supply the transfers of one block can move.
+    pub max_block_transfer_bps: Option<u32>,
+    /// Trips `breaker_check_audit` when the ledger is unbalanced.
+    pub audit_mismatch: bool,
+}
+impl BreakerRules {
+    pub fn assert_valid(&self) {
+        if let Some(max_bps) = self.max_block_transfer_bps {
+            require!(
+                max_bps <= bps::ONE_HUNDRED_PERCENT,
+                "max_block_transfer_bps can't be over 10000"
+            );
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    pub rules: BreakerRules,
+    /// Block of `block_volume`.
+    pub block_height: u64,
+    /// Transferred in the block so far.
+    pub block_volume: Balance,
+}
+impl CircuitBreaker {
+    #[inline]
+    fn key() -> Vec<u8> {
+        near_sdk::IntoStorageKey::into_storage_key(StorageKey::Breaker)
+    }
+
+    pub fn load() -> Self {
+        env::storage_read(&Self::key())
+            .map(|bytes| Self::try_from_slice(&bytes).unwrap_or_else(|_| env::abort()))
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn save(&self) {
+        env::storage_write(&Self::key(), &self.try_to_vec().unwrap());
+    }
+
+    /// Replaces the rules, the volume of the current block is kept.
+    pub fn set_rules(rules: BreakerRules) {
+        rules.assert_valid();
+        let mut breaker = Self::load();
+        breaker.rules = rules;
+        breaker.save();
+    }
+}
+
+/// Disables the ft transfers and logs why.
+pub fn trip(rule: &str, detail: String) {
+    let mut flags = features::FeatureFlags::load();
+    flags.disable(features::Feature::FtTransfer);
+    flags.disable(features::Feature::FtTransferCall);
+    flags.save();
+    events::emit(
+        events::standards::FT_BREAKER,
+        "breaker_tripped",
+        near_sdk::serde_json::json!({ "rule": rule, "detail": detail }),
+    );
+}
+
+/// Adds a transfer to the volume of the block, trips `max_block_transfer_bps` over it.
+pub fn record_transfer(ft: &FungibleToken, amount: Balance) {
+    let mut breaker = CircuitBreaker::load();
+    let max_bps = match breaker.rules.max_block_transfer_bps {
+        Some(max_bps) => max_bps,
+        None => return,
+    };
+    let block_height = env::block_height();
+    if breaker.block_height != block_height {
+        breaker.block_height = block_height;
+        breaker.block_volume = 0;
+    }
+    breaker.block_volume = breaker.block_volume.saturating_add(amount);
+    breaker.save();
+
+    let max_volume = bps::share(ft.token.total_supply, max_bps);
+    if breaker.block_volume > max_volume {
+        trip(
+            "max_block_transfer_bps",
+            format!(
+                "{} transferred in block {}, over {}",
+                breaker.block_volume, block_height, max_volume
+            ),
+        );
+    }
+}
+
+/// Trips `audit_mismatch` if the ledger is unbalanced, returns whether it tripped.
+pub fn check_audit(ft: &FungibleToken) -> bool {
+    if !CircuitBreaker::load().rules.audit_mismatch {
+        return false;
+    }
+    let report = super::audit::check(ft);
+    if report.balanced != Some(false) {
+        return false;
+    }
+    trip(
+        "audit_mismatch",
+        format!(
+            "balances sum to {}, total supply is {}",
+            report.balances.unwrap_or(U128(0)).0,
+            report.total_supply.0
+        ),
+    );
+    true
+}
+
+#[macro_export]
+macro_rules! impl_fungible_token_breaker {
+        ($contract:ident, $ft:ident $(, owner = $owner:ident)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_fungible_token_breaker!`.
+                pub const FT_BREAKER_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("breaker"),
+                    methods: &[
+                        $crate::manifest::Method::payable("breaker_set_rules").role("owner"),
+                        $crate::manifest::Method::view("breaker_rules"),
+                        $crate::manifest::Method::call("breaker_check_audit"),
+                    ],
+                    storage: &[],
+                };
+            }
+            #[near_bindgen]
+            impl $contract {
+                #[payable]
+                pub fn breaker_set_rules(&mut self, rules: $crate::ft::breaker::BreakerRules) {
+                    near_sdk::assert_one_yocto();
+                    let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                        .cloned()
+                        .unwrap_or_else(env::current_account_id);
+                    require!(
+                        env::predecessor_account_id() == owner_id,
+                        "Only the owner can set the breaker rules"
+                    );
+                    $crate::ft::breaker::CircuitBreaker::set_rules(rules)
+                }
+
+                pub fn breaker_rules(&self) -> $crate::ft::breaker::BreakerRules {
+                    $crate::ft::breaker::CircuitBreaker::load().rules
+                }
+
+                /// Anyone can trip `audit_mismatch`, returns whether it tripped.
+                pub fn breaker_check_audit(&mut self) -> bool {
+                    $crate::ft::breaker::check_audit(&self.$ft)
+                }
+            }
+        };
+    }
+pub use impl_fungible_token_breaker;
@@ -0,0 +1,294 @@
+#![cfg(feature = "bridge")]
+/*!
+The NEAR half of a lock/mint bridge.
+
+`ft_burn_to` burns tokens of the caller and logs where to release them on the other chain,
+`ft_mint_from` mints tokens locked on the other chain, once per proof id.
+
+# NOTES:
+  - Only minters added by the owner (E.g. the relayer or the light client) can mint.
+  - The minter pays the storage of the used proof id, and the registration of a new receiver.
+  - The max supply of the token is checked on mint like any other mint.
+  - Events are logged as `EVENT_JSON:` with the `ft_bridge` standard, along with the
+    `ft_burn`/`ft_mint` of nep141.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    ft: ft::FungibleToken,
+    bridge: ft::bridge::Bridge,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            owner_id: owner_id.clone(),
+            ft: ft::FungibleToken::from_args(ft::InitArgs {
+                owner_id: Some(owner_id),
+                total_supply: U128(1_000),
+                name: "Bridged".to_string(),
+                symbol: "BRG".to_string(),
+                ..Default::default()
+            }),
+            bridge: ft::bridge::Bridge::new(),
+        }
+    }
+}
+
+ft::impl_fungible_token_contract!(Contract, ft);
+// impl_fungible_token_bridge!(Contract, ft, bridge[, owner = <AccountId field; default: the contract itself>]);
+ft::bridge::impl_fungible_token_bridge!(Contract, ft, bridge, owner = owner_id);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.ft_bridge_add_minter(accounts(1));
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_burn_and_mint() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.ft_burn_to("ethereum".to_string(), "0xabc".to_string(), U128(400));
+        assert_eq!(contract.ft.token.total_supply, 600);
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR / 100)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.ft_mint_from("0x01".to_string(), accounts(2), U128(100));
+        assert_eq!(contract.ft.token.internal_unwrap_balance_of(&accounts(2)), 100);
+        assert!(contract.ft_bridge_is_proof_used("0x01".to_string()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Proof is already used")]
+    fn test_mint_replay() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR / 100)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.ft_mint_from("0x01".to_string(), accounts(0), U128(100));
+        contract.ft_mint_from("0x01".to_string(), accounts(0), U128(100));
+    }
+}
+```
+*/
+
+use super::super::*;
+use super::{events, FungibleToken};
+
+use near_sdk::{
+    collections::{LookupSet, UnorderedSet},
+    serde_json,
+};
+
+pub const MAX_CHAIN_ID_LENGTH: usize = 32;
+pub const MAX_EXTERNAL_ADDRESS_LENGTH: usize = 128;
+pub const MAX_PROOF_ID_LENGTH: usize = 128;
+
+raw_storage_key!(pub enum StorageKey in storage_key::BRIDGE {
+    BridgeMinters,
+    BridgeProofs,
+});
+
+/// Logs an `ft_bridge` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    super::super::events::emit(super::super::events::standards::FT_BRIDGE, event, data);
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Bridge {
+    pub minters: UnorderedSet<AccountId>,
+    /// Proof ids already minted from.
+    pub used_proofs: LookupSet<String>,
+}
+impl Default for Bridge {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Bridge {
+    pub fn new() -> Self {
+        Self {
+            minters: UnorderedSet::new(StorageKey::BridgeMinters),
+            used_proofs: LookupSet::new(StorageKey::BridgeProofs),
+        }
+    }
+
+    /// Burns `amount` of the predecessor to be released to `external_address` on `chain_id`.
+    pub fn burn_to(
+        &mut self,
+        ft: &mut FungibleToken,
+        chain_id: String,
+        external_address: String,
+        amount: U128,
+    ) {
+        use validate::*;
+        validate!(chain_id => non_empty, max_len(MAX_CHAIN_ID_LENGTH));
+        validate!(external_address => non_empty, max_len(MAX_EXTERNAL_ADDRESS_LENGTH));
+        require!(amount.0 > 0, "The amount should be a positive number");
+
+        let sender_id = env::predecessor_account_id();
+        #[cfg(feature = "freeze")]
+        super::freeze::assert_not_frozen(&sender_id);
+        ft.track_holders(&[&sender_id], |ft| {
+            ft.token.internal_withdraw(&sender_id, amount.0)
+        });
+        let memo = format!("Bridged to {}", chain_id);
+        events::FtBurn {
+            owner_id: &sender_id,
+            amount: &amount,
+            memo: Some(&memo),
+        }
+        .emit();
+        emit(
+            "ft_burn_to",
+            serde_json::json!({
+                "sender_id": sender_id,
+                "chain_id": chain_id,
+                "external_address": external_address,
+                "amount": amount,
+            }),
+        );
+    }
+
+    /// Mints `amount` to `receiver_id` for a proof of tokens locked on the other chain.
+    pub fn mint_from(
+        &mut self,
+        ft: &mut FungibleToken,
+        proof_id: String,
+        receiver_id: AccountId,
+        amount: U128,
+    ) {
+        let minter_id = env::predecessor_account_id();
+        require!(self.minters.contains(&minter_id), "Only minters can mint");
+        validate::validate!(proof_id => validate::non_empty, validate::max_len(MAX_PROOF_ID_LENGTH));
+
+        let initial_storage_usage = env::storage_usage();
+        require!(self.used_proofs.insert(&proof_id), "Proof is already used");
+        if !ft.token.accounts.contains_key(&receiver_id) {
+            ft.token.internal_register_account(&receiver_id);
+        }
+        ft.internal_mint(
+            &receiver_id,
+            amount,
+            Some(&format!("Bridged by {}", proof_id)),
+        );
+
+        let storage_cost = protocol::storage_cost(env::storage_usage() - initial_storage_usage);
+        let attached = env::attached_deposit();
+        require!(
+            attached >= storage_cost,
+            format!("Must attach {} yoctoNEAR to cover storage", storage_cost)
+        );
+        if attached > storage_cost {
+            Promise::new(minter_id.clone()).transfer(attached - storage_cost);
+        }
+
+        emit(
+            "ft_mint_from",
+            serde_json::json!({
+                "minter_id": minter_id,
+                "proof_id": proof_id,
+                "receiver_id": receiver_id,
+                "amount": amount,
+            }),
+        );
+    }
+}
+
+#[macro_export]
+macro_rules! impl_fungible_token_bridge {
+        ($contract:ident, $ft:ident, $bridge:ident $(, owner = $owner:ident)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_fungible_token_bridge!`.
+                pub const FT_BRIDGE_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("bridge"),
+                    methods: &[
+                        $crate::manifest::Method::payable("ft_burn_to"),
+                        $crate::manifest::Method::payable("ft_mint_from").role("minter"),
+                        $crate::manifest::Method::payable("ft_bridge_add_minter").role("owner"),
+                        $crate::manifest::Method::payable("ft_bridge_remove_minter").role("owner"),
+                        $crate::manifest::Method::view("ft_bridge_minters"),
+                        $crate::manifest::Method::view("ft_bridge_is_proof_used"),
+                    ],
+                    storage: &[
+                        // a 128 bytes proof id and the registration of a 64 bytes account
+                        $crate::manifest::StorageEstimate::new("ft_mint_from", 400),
+                    ],
+                };
+
+                fn assert_bridge_owner(&self) {
+                    near_sdk::assert_one_yocto();
+                    let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                        .cloned()
+                        .unwrap_or_else(env::current_account_id);
+                    require!(
+                        env::predecessor_account_id() == owner_id,
+                        "Only the owner can manage minters"
+                    );
+                }
+            }
+            #[near_bindgen]
+            impl $contract {
+                #[payable]
+                pub fn ft_burn_to(&mut self, chain_id: String, external_address: String, amount: U128) {
+                    near_sdk::assert_one_yocto();
+                    self.$bridge.burn_to(&mut self.$ft, chain_id, external_address, amount)
+                }
+
+                /// Requires the storage deposit of the proof id and of the receiver registration.
+                #[payable]
+                pub fn ft_mint_from(&mut self, proof_id: String, receiver_id: AccountId, amount: U128) {
+                    self.$bridge.mint_from(&mut self.$ft, proof_id, receiver_id, amount)
+                }
+
+                #[payable]
+                pub fn ft_bridge_add_minter(&mut self, minter_id: AccountId) {
+                    self.assert_bridge_owner();
+                    self.$bridge.minters.insert(&minter_id);
+                }
+
+                #[payable]
+                pub fn ft_bridge_remove_minter(&mut self, minter_id: AccountId) {
+                    self.assert_bridge_owner();
+                    self.$bridge.minters.remove(&minter_id);
+                }
+
+                pub fn ft_bridge_minters(&self) -> Vec<AccountId> {
+                    self.$bridge.minters.to_vec()
+                }
+
+                pub fn ft_bridge_is_proof_used(&self, proof_id: String) -> bool {
+                    self.$bridge.used_proofs.contains(&proof_id)
+                }
+            }
+        };
+    }
+pub use impl_fungible_token_bridge;
@@ -0,0 +1,284 @@
+#![cfg(feature = "emissions")]
+/*!
+Scheduled emissions of the FT, E.g. staking rewards or a treasury allowance.
+
+Every epoch mints `amount` to the target account, the amount decaying by `decay_bps` from one
+epoch to the next. Nothing runs by itself: anyone (E.g. a keeper bot) calls `emissions_poke()`,
+which mints every epoch that has ended since the last poke.
+
+# NOTES:
+  - Missed epochs are caught up at most [`MAX_CATCH_UP_EPOCHS`] per poke, poke again for more.
+  - The schedule ends after `epochs` epochs, or once the decayed amount rounds down to 0.
+  - Emissions are mints, so they stop at the max supply of the token (the poke panics).
+  - The target has to be registered in the token.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    ft: ft::FungibleToken,
+    emissions: ft::emissions::Emissions,
+}
+
+const DAY: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            owner_id: owner_id.clone(),
+            ft: ft::FungibleToken::from_args(ft::InitArgs {
+                owner_id: Some(owner_id.clone()),
+                total_supply: U128(0),
+                name: "Emitted".to_string(),
+                symbol: "EMT".to_string(),
+                ..Default::default()
+            }),
+            // 1000 tokens to the owner (E.g. a treasury) every day, 10% less each day
+            emissions: ft::emissions::Emissions::new(ft::emissions::Schedule {
+                target_id: owner_id,
+                starts_at: U64(env::block_timestamp()),
+                epoch_duration: U64(DAY),
+                amount: U128(1_000),
+                decay_bps: 1_000,
+                epochs: None,
+            }),
+        }
+    }
+}
+
+ft::impl_fungible_token_contract!(Contract, ft);
+// impl_fungible_token_emissions!(Contract, ft, emissions[, owner = <AccountId field; default: the contract itself>]);
+ft::emissions::impl_fungible_token_emissions!(Contract, ft, emissions, owner = owner_id);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    use ft::core::FungibleTokenCore;
+
+    #[test]
+    fn test_poke() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .block_timestamp(DAY - 1)
+                .predecessor_account_id(accounts(1)),
+        );
+        assert_eq!(contract.emissions_poke(), U128(0));
+
+        // 3 epochs ended, caught up at once
+        run_vm(vm.storage_usage(env::storage_usage()).block_timestamp(3 * DAY));
+        assert_eq!(contract.emissions_poke(), U128(1_000 + 900 + 810));
+        assert_eq!(contract.ft_total_supply(), U128(2_710));
+
+        let upcoming = contract.emissions_upcoming(Some(2));
+        assert_eq!(upcoming[0].amount, U128(729));
+        assert_eq!(upcoming[1].ends_at, U64(5 * DAY));
+    }
+}
+```
+*/
+
+use super::super::*;
+use super::FungibleToken;
+
+use contract_core::bps;
+use near_sdk::serde::{Deserialize, Serialize};
+
+pub const MAX_CATCH_UP_EPOCHS: u64 = 100;
+pub const MAX_UPCOMING_EPOCHS: u64 = 100;
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Schedule {
+    /// Receiver of the emissions.
+    pub target_id: AccountId,
+    /// Block timestamp (nanoseconds) of the start of the first epoch.
+    pub starts_at: U64,
+    /// Nanoseconds.
+    pub epoch_duration: U64,
+    /// Tokens of the first epoch.
+    pub amount: U128,
+    /// Decrease of the amount from one epoch to the next, in basis points.
+    pub decay_bps: u32,
+    /// Number of epochs, unlimited by default.
+    pub epochs: Option<u64>,
+}
+impl Schedule {
+    pub fn assert_valid(&self) {
+        require!(self.epoch_duration.0 > 0, "Epoch duration must be positive");
+        require!(
+            self.decay_bps <= bps::ONE_HUNDRED_PERCENT,
+            "decay_bps can't be over 10000"
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Epoch {
+    pub index: U64,
+    /// Block timestamp (nanoseconds) from which it can be emitted.
+    pub ends_at: U64,
+    pub amount: U128,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Emissions {
+    pub schedule: Schedule,
+    /// Index of the next epoch to emit.
+    pub next_epoch: u64,
+    /// Amount of the next epoch.
+    pub next_amount: Balance,
+    /// Tokens emitted so far.
+    pub emitted: Balance,
+}
+impl Emissions {
+    pub fn new(schedule: Schedule) -> Self {
+        schedule.assert_valid();
+        Self {
+            next_amount: schedule.amount.0,
+            schedule,
+            next_epoch: 0,
+            emitted: 0,
+        }
+    }
+
+    /// Replaces the schedule from now on, its first epoch is emitted next.
+    pub fn set_schedule(&mut self, schedule: Schedule) {
+        schedule.assert_valid();
+        self.next_amount = schedule.amount.0;
+        self.schedule = schedule;
+        self.next_epoch = 0;
+    }
+
+    #[inline]
+    fn decayed(&self, amount: Balance) -> Balance {
+        amount - bps::share(amount, self.schedule.decay_bps)
+    }
+
+    #[inline]
+    pub fn is_over(&self) -> bool {
+        self.next_amount == 0
+            || self
+                .schedule
+                .epochs
+                .is_some_and(|epochs| self.next_epoch >= epochs)
+    }
+
+    /// Block timestamp (nanoseconds) at which the epoch ends.
+    #[inline]
+    pub fn epoch_end(&self, index: u64) -> u64 {
+        self.schedule.starts_at.0 + (index + 1) * self.schedule.epoch_duration.0
+    }
+
+    /// Mints the ended epochs to the target, returns the minted amount.
+    pub fn poke(&mut self, ft: &mut FungibleToken) -> Balance {
+        let now = env::block_timestamp();
+        let mut amount = 0;
+        for _ in 0..MAX_CATCH_UP_EPOCHS {
+            if self.is_over() || now < self.epoch_end(self.next_epoch) {
+                break;
+            }
+            amount += self.next_amount;
+            self.next_amount = self.decayed(self.next_amount);
+            self.next_epoch += 1;
+        }
+        if amount > 0 {
+            self.emitted += amount;
+            ft.internal_mint(
+                &self.schedule.target_id,
+                U128(amount),
+                Some(&format!("Emissions until epoch {}", self.next_epoch)),
+            );
+        }
+        amount
+    }
+
+    /// The next `limit` epochs to emit.
+    pub fn upcoming(&self, limit: u64) -> Vec<Epoch> {
+        let mut epochs = vec![];
+        let mut index = self.next_epoch;
+        let mut amount = self.next_amount;
+        while (epochs.len() as u64) < limit.min(MAX_UPCOMING_EPOCHS)
+            && amount > 0
+            && self.schedule.epochs.map_or(true, |epochs| index < epochs)
+        {
+            epochs.push(Epoch {
+                index: U64(index),
+                ends_at: U64(self.epoch_end(index)),
+                amount: U128(amount),
+            });
+            amount = self.decayed(amount);
+            index += 1;
+        }
+        epochs
+    }
+}
+
+#[macro_export]
+macro_rules! impl_fungible_token_emissions {
+        ($contract:ident, $ft:ident, $emissions:ident $(, owner = $owner:ident)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_fungible_token_emissions!`.
+                pub const FT_EMISSIONS_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("emissions"),
+                    methods: &[
+                        $crate::manifest::Method::call("emissions_poke"),
+                        $crate::manifest::Method::payable("emissions_set_schedule").role("owner"),
+                        $crate::manifest::Method::view("emissions_schedule"),
+                        $crate::manifest::Method::view("emissions_emitted"),
+                        $crate::manifest::Method::view("emissions_upcoming"),
+                    ],
+                    storage: &[],
+                };
+            }
+            #[near_bindgen]
+            impl $contract {
+                /// Anyone can mint the ended epochs to the target, returns the minted amount.
+                pub fn emissions_poke(&mut self) -> U128 {
+                    U128(self.$emissions.poke(&mut self.$ft))
+                }
+
+                #[payable]
+                pub fn emissions_set_schedule(&mut self, schedule: $crate::ft::emissions::Schedule) {
+                    near_sdk::assert_one_yocto();
+                    let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                        .cloned()
+                        .unwrap_or_else(env::current_account_id);
+                    require!(
+                        env::predecessor_account_id() == owner_id,
+                        "Only the owner can set the schedule"
+                    );
+                    self.$emissions.set_schedule(schedule)
+                }
+
+                pub fn emissions_schedule(&self) -> $crate::ft::emissions::Schedule {
+                    self.$emissions.schedule.clone()
+                }
+
+                pub fn emissions_emitted(&self) -> U128 {
+                    U128(self.$emissions.emitted)
+                }
+
+                /// The next epochs to emit, at most `MAX_UPCOMING_EPOCHS`.
+                pub fn emissions_upcoming(&self, limit: Option<u64>) -> Vec<$crate::ft::emissions::Epoch> {
+                    self.$emissions
+                        .upcoming(limit.unwrap_or($crate::ft::emissions::MAX_UPCOMING_EPOCHS))
+                }
+            }
+        };
+    }
+pub use impl_fungible_token_emissions;
@@ -0,0 +1,280 @@
+#![cfg(feature = "freeze")]
+/*!
+Freezing of FT accounts, E.g. to contain a compromised account during an incident.
+
+A frozen account can't send: `ft_transfer`, `ft_transfer_call`, `ft_burn_to` and the migration
+of the account fail, while the rest of the contract keeps working. Unlike the global pause of
+the `features` flags, only the frozen accounts are stopped.
+
+# NOTES:
+  - The owner and the freezers it adds (E.g. a security team account) freeze and unfreeze.
+  - A frozen account can still receive tokens, and `ft_resolve_transfer` still refunds it.
+  - The contract pays the storage of the frozen accounts and of the freezers.
+  - `ft_can_transfer` gives `frozen` for a frozen sender.
+  - Behind the `freeze` feature, without it the transfers don't look up the frozen set.
+  - Events are logged as `EVENT_JSON:` with the `ft_freeze` standard.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    ft: ft::FungibleToken,
+    freeze: ft::freeze::Freeze,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId, total_supply: U128) -> Self {
+        require_init!();
+        Self {
+            owner_id: owner_id.clone(),
+            ft: ft::FungibleToken::from_args(ft::InitArgs {
+                owner_id: Some(owner_id),
+                total_supply,
+                name: "Frozen".to_string(),
+                symbol: "FRZ".to_string(),
+                ..Default::default()
+            }),
+            freeze: ft::freeze::Freeze::new(),
+        }
+    }
+}
+
+ft::impl_fungible_token_contract!(Contract, ft);
+// impl_fungible_token_freeze!(Contract, freeze[, owner = <AccountId field; default: the contract itself>]);
+ft::freeze::impl_fungible_token_freeze!(Contract, freeze, owner = owner_id);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    use ft::core::FungibleTokenCore;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0), U128(1_000));
+        contract.ft.token.internal_register_account(&accounts(1));
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.ft_freeze_add_freezer(accounts(2));
+        (vm, contract)
+    }
+
+    #[test]
+    #[should_panic(expected = "The account alice is frozen")]
+    fn test_freeze() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(vm.predecessor_account_id(accounts(2)));
+        contract.ft_freeze(accounts(0), Some("Leaked key".to_string()));
+        assert!(contract.ft_is_frozen(accounts(0)));
+        assert_eq!(
+            contract.ft_can_transfer(accounts(0), accounts(1), U128(1)).reason,
+            Some(ft::TransferDenial::Frozen)
+        );
+
+        run_vm(vm.predecessor_account_id(accounts(0)));
+        contract.ft_transfer(accounts(1), U128(1), None);
+    }
+
+    #[test]
+    fn test_unfreeze() {
+        let (_, mut contract) = setup();
+
+        contract.ft_freeze(accounts(0), None);
+        contract.ft_unfreeze(accounts(0));
+        contract.ft_transfer(accounts(1), U128(1), None);
+        assert_eq!(contract.ft_balance_of(accounts(1)), U128(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner or a freezer can freeze accounts")]
+    fn test_freeze_role() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(vm.predecessor_account_id(accounts(1)));
+        contract.ft_freeze(accounts(0), None);
+    }
+}
+```
+*/
+
+use super::super::*;
+
+use near_sdk::{
+    collections::{LookupSet, UnorderedSet},
+    serde_json,
+};
+
+raw_storage_key!(pub enum StorageKey in storage_key::FREEZE {
+    Freezers,
+    Frozen,
+});
+
+pub const MAX_REASON_LENGTH: usize = 256;
+
+/// Logs an `ft_freeze` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    events::emit(events::standards::FT_FREEZE, event, data);
+}
+
+/// The frozen accounts, under their own key so transfers check them without the [`Freeze`].
+#[inline]
+fn frozen() -> LookupSet<AccountId> {
+    LookupSet::new(StorageKey::Frozen)
+}
+
+#[inline]
+pub fn is_frozen(account_id: &AccountId) -> bool {
+    frozen().contains(account_id)
+}
+
+pub fn assert_not_frozen(account_id: &AccountId) {
+    require!(
+        !is_frozen(account_id),
+        format!("The account {} is frozen", account_id)
+    );
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Freeze {
+    /// Can freeze and unfreeze, along with the owner.
+    pub freezers: UnorderedSet<AccountId>,
+}
+impl Default for Freeze {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Freeze {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            freezers: UnorderedSet::new(StorageKey::Freezers),
+        }
+    }
+
+    /// Freezes the account on behalf of the predecessor, returns false if it already was.
+    pub fn freeze(&mut self, account_id: &AccountId, reason: Option<String>) -> bool {
+        if let Some(reason) = reason.as_ref() {
+            strings::assert_max_bytes(reason, MAX_REASON_LENGTH);
+        }
+        let frozen = frozen().insert(account_id);
+        if frozen {
+            emit(
+                "freeze",
+                serde_json::json!({
+                    "account_id": account_id,
+                    "by": env::predecessor_account_id(),
+                    "reason": reason,
+                }),
+            );
+        }
+        frozen
+    }
+
+    /// Unfreezes the account on behalf of the predecessor, returns false if it wasn't frozen.
+    pub fn unfreeze(&mut self, account_id: &AccountId) -> bool {
+        let unfrozen = frozen().remove(account_id);
+        if unfrozen {
+            emit(
+                "unfreeze",
+                serde_json::json!({
+                    "account_id": account_id,
+                    "by": env::predecessor_account_id(),
+                }),
+            );
+        }
+        unfrozen
+    }
+}
+
+#[macro_export]
+macro_rules! impl_fungible_token_freeze {
+        ($contract:ident, $freeze:ident $(, owner = $owner:ident)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_fungible_token_freeze!`.
+                pub const FT_FREEZE_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("freeze"),
+                    methods: &[
+                        $crate::manifest::Method::payable("ft_freeze").role("freezer"),
+                        $crate::manifest::Method::payable("ft_unfreeze").role("freezer"),
+                        $crate::manifest::Method::view("ft_is_frozen"),
+                        $crate::manifest::Method::payable("ft_freeze_add_freezer").role("owner"),
+                        $crate::manifest::Method::payable("ft_freeze_remove_freezer").role("owner"),
+                        $crate::manifest::Method::view("ft_freezers"),
+                    ],
+                    storage: &[],
+                };
+
+                fn ft_freeze_owner_id(&self) -> AccountId {
+                    None::<&AccountId>$(.or(Some(&self.$owner)))?
+                        .cloned()
+                        .unwrap_or_else(env::current_account_id)
+                }
+
+                fn assert_freeze_owner(&self) {
+                    near_sdk::assert_one_yocto();
+                    require!(
+                        env::predecessor_account_id() == self.ft_freeze_owner_id(),
+                        "Only the owner can manage freezers"
+                    );
+                }
+
+                fn assert_freezer(&self) {
+                    near_sdk::assert_one_yocto();
+                    let predecessor_id = env::predecessor_account_id();
+                    require!(
+                        predecessor_id == self.ft_freeze_owner_id()
+                            || self.$freeze.freezers.contains(&predecessor_id),
+                        "Only the owner or a freezer can freeze accounts"
+                    );
+                }
+            }
+            #[near_bindgen]
+            impl $contract {
+                /// Returns false if the account already was frozen.
+                #[payable]
+                pub fn ft_freeze(&mut self, account_id: AccountId, reason: Option<String>) -> bool {
+                    self.assert_freezer();
+                    self.$freeze.freeze(&account_id, reason)
+                }
+
+                /// Returns false if the account wasn't frozen.
+                #[payable]
+                pub fn ft_unfreeze(&mut self, account_id: AccountId) -> bool {
+                    self.assert_freezer();
+                    self.$freeze.unfreeze(&account_id)
+                }
+
+                pub fn ft_is_frozen(&self, account_id: AccountId) -> bool {
+                    $crate::ft::freeze::is_frozen(&account_id)
+                }
+
+                #[payable]
+                pub fn ft_freeze_add_freezer(&mut self, freezer_id: AccountId) {
+                    self.assert_freeze_owner();
+                    self.$freeze.freezers.insert(&freezer_id);
+                }
+
+                #[payable]
+                pub fn ft_freeze_remove_freezer(&mut self, freezer_id: AccountId) {
+                    self.assert_freeze_owner();
+                    self.$freeze.freezers.remove(&freezer_id);
+                }
+
+                pub fn ft_freezers(&self) -> Vec<AccountId> {
+                    self.$freeze.freezers.to_vec()
+                }
+            }
+        };
+    }
+pub use impl_fungible_token_freeze;
@@ -0,0 +1,339 @@
+/*!
+Lockups of FT balances, E.g. for investor or team allocations.
+
+The owner locks an amount of an account with a schedule: nothing unlocks before the cliff, then
+the amount unlocks linearly from the start to the end. `ft_transfer`/`ft_transfer_call` only move
+the balance over the locked remainder, whatever contract the account deals with.
+
+# NOTES:
+  - Lockups are off by default, enable them with `FungibleToken::with_lockups()`
+    (or `InitArgs::lockups`). A token of `new_in(namespace, ..)` keeps them under its namespace
+    with `with_lockups_in(namespace)`.
+  - One lockup per account, setting another replaces it. The locked amount isn't taken from the
+    balance, so the tokens can be sent before or after the lockup is set.
+  - The contract pays the storage of the lockups, they're set by the owner.
+    A lockup is removed by the first transfer after its end.
+  - `ft_resolve_transfer` can still take back unused tokens from a locked receiver,
+    the standard returns them to the sender.
+  - Events are logged as `EVENT_JSON:` with the `ft_lockup` standard.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    ft: ft::FungibleToken,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId, total_supply: U128) -> Self {
+        require_init!();
+        Self {
+            owner_id: owner_id.clone(),
+            ft: ft::FungibleToken::from_args(ft::InitArgs {
+                owner_id: Some(owner_id),
+                total_supply,
+                name: "Locked".to_string(),
+                symbol: "LCK".to_string(),
+                lockups: true,
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+ft::impl_fungible_token_contract!(Contract, ft);
+// impl_fungible_token_lockup!(Contract, ft[, owner = <AccountId field; default: the contract itself>]);
+ft::lockup::impl_fungible_token_lockup!(Contract, ft, owner = owner_id);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    use ft::core::FungibleTokenCore;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0), U128(1_000));
+
+        for account_id in [accounts(1), accounts(2)] {
+            run_vm(
+                vm.storage_usage(env::storage_usage())
+                    .attached_deposit(contract.storage_balance_bounds().min.0),
+            );
+            contract.storage_deposit(Some(account_id), None);
+        }
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.ft_transfer(accounts(1), U128(1_000), None);
+        // cliff at 10, everything unlocked at 100
+        contract.ft_lockup_set(
+            accounts(1),
+            ft::lockup::Lockup {
+                amount: U128(1_000),
+                starts_at: U64(0),
+                cliff_at: U64(10),
+                ends_at: U64(100),
+            },
+        );
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_lockup() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(vm.block_timestamp(9).is_view(true));
+        assert_eq!(contract.ft_transferable_balance_of(accounts(1)), U128(0));
+        assert_eq!(
+            contract
+                .ft_can_transfer(accounts(1), accounts(2), U128(1))
+                .reason,
+            Some(ft::TransferDenial::Locked)
+        );
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .block_timestamp(50)
+                .is_view(false)
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(1)),
+        );
+        assert_eq!(contract.ft_lockup_of(accounts(1)).unwrap().locked, U128(500));
+        contract.ft_transfer(accounts(2), U128(500), None);
+        assert_eq!(contract.ft_balance_of(accounts(2)), U128(500));
+
+        // removed once it ended
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .block_timestamp(100),
+        );
+        contract.ft_transfer(accounts(2), U128(500), None);
+        assert!(contract.ft_lockup_of(accounts(1)).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Not enough unlocked balance")]
+    fn test_lockup_transfer_locked() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .block_timestamp(50)
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.ft_transfer(accounts(2), U128(501), None);
+    }
+}
+```
+*/
+
+use super::super::*;
+
+use near_sdk::{
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+
+raw_storage_key!(pub enum StorageKey in storage_key::LOCKUP {
+    Lockups,
+});
+impl StorageKey {
+    /// The key under `namespace`, see `FungibleToken::new_in`.
+    pub fn in_namespace(self, namespace: &[u8]) -> Vec<u8> {
+        [namespace, &near_sdk::IntoStorageKey::into_storage_key(self)].concat()
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Lockup {
+    /// Locked at the start.
+    pub amount: U128,
+    /// Block timestamp (nanoseconds) from which the amount unlocks linearly.
+    pub starts_at: U64,
+    /// Block timestamp (nanoseconds) before which nothing is unlocked.
+    pub cliff_at: U64,
+    /// Block timestamp (nanoseconds) from which everything is unlocked.
+    pub ends_at: U64,
+}
+impl Lockup {
+    pub fn assert_valid(&self) {
+        require!(self.amount.0 > 0, "Lockup amount must be positive");
+        require!(
+            self.starts_at.0 <= self.cliff_at.0 && self.cliff_at.0 <= self.ends_at.0,
+            "Lockup must start before its cliff, and the cliff before its end"
+        );
+    }
+
+    /// Unlocked at `timestamp`, rounded down.
+    pub fn unlocked_at(&self, timestamp: u64) -> Balance {
+        let amount = self.amount.0;
+        if timestamp < self.cliff_at.0 {
+            return 0;
+        }
+        if timestamp >= self.ends_at.0 {
+            return amount;
+        }
+        let elapsed = Balance::from(timestamp - self.starts_at.0);
+        let duration = Balance::from(self.ends_at.0 - self.starts_at.0);
+        // `amount * elapsed / duration` without overflowing
+        amount / duration * elapsed + amount % duration * elapsed / duration
+    }
+
+    #[inline]
+    pub fn locked_at(&self, timestamp: u64) -> Balance {
+        self.amount.0 - self.unlocked_at(timestamp)
+    }
+
+    #[inline]
+    pub fn view(&self) -> LockupView {
+        let now = env::block_timestamp();
+        LockupView {
+            lockup: self.clone(),
+            locked: U128(self.locked_at(now)),
+        }
+    }
+}
+
+/// Result of `ft_lockup_of`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct LockupView {
+    #[serde(flatten)]
+    pub lockup: Lockup,
+    /// Still locked now.
+    pub locked: U128,
+}
+
+/// Logs an `ft_lockup` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    super::super::events::emit(super::super::events::standards::FT_LOCKUP, event, data);
+}
+
+#[macro_export]
+macro_rules! impl_fungible_token_lockup {
+        ($contract:ident, $ft:ident $(, owner = $owner:ident)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_fungible_token_lockup!`.
+                pub const FT_LOCKUP_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("ft"),
+                    methods: &[
+                        $crate::manifest::Method::payable("ft_lockup_set").role("owner"),
+                        $crate::manifest::Method::payable("ft_lockup_remove").role("owner"),
+                        $crate::manifest::Method::view("ft_lockup_of"),
+                        $crate::manifest::Method::view("ft_transferable_balance_of"),
+                    ],
+                    storage: &[],
+                };
+
+                fn assert_lockup_owner(&self) {
+                    near_sdk::assert_one_yocto();
+                    let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                        .cloned()
+                        .unwrap_or_else(env::current_account_id);
+                    require!(
+                        env::predecessor_account_id() == owner_id,
+                        "Only the owner can manage lockups"
+                    );
+                }
+            }
+            #[near_bindgen]
+            impl $contract {
+                #[payable]
+                pub fn ft_lockup_set(&mut self, account_id: AccountId, lockup: $crate::ft::lockup::Lockup) {
+                    self.assert_lockup_owner();
+                    self.$ft.set_lockup(&account_id, lockup)
+                }
+
+                /// Unlocks everything that's still locked.
+                #[payable]
+                pub fn ft_lockup_remove(&mut self, account_id: AccountId) -> Option<$crate::ft::lockup::Lockup> {
+                    self.assert_lockup_owner();
+                    self.$ft.remove_lockup(&account_id)
+                }
+
+                pub fn ft_lockup_of(&self, account_id: AccountId) -> Option<$crate::ft::lockup::LockupView> {
+                    self.$ft.lockup_of(&account_id).map(|lockup| lockup.view())
+                }
+
+                /// Balance over the locked remainder, what `ft_transfer` can move now.
+                pub fn ft_transferable_balance_of(&self, account_id: AccountId) -> U128 {
+                    U128(self.$ft.transferable_balance(&account_id))
+                }
+            }
+        };
+    }
+pub use impl_fungible_token_lockup;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::super::test_utils::*;
+    use super::super::{FungibleToken, InitArgs};
+    use super::*;
+
+    fn token(namespace: &str) -> FungibleToken {
+        FungibleToken::from_args(InitArgs {
+            owner_id: Some(accounts(0)),
+            total_supply: U128(1_000),
+            name: "Locked".to_string(),
+            symbol: "LCK".to_string(),
+            lockups: true,
+            namespace: Some(namespace.to_string()),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn check_namespaced_lockups() {
+        run_vm(vm!(accounts(0)));
+
+        let mut ft = token("a");
+        ft.set_lockup(
+            &accounts(0),
+            Lockup {
+                amount: U128(100),
+                starts_at: U64(0),
+                cliff_at: U64(10),
+                ends_at: U64(20),
+            },
+        );
+        let key = |prefix: Vec<u8>| [prefix, accounts(0).try_to_vec().unwrap()].concat();
+        assert!(env::storage_has_key(&key(
+            StorageKey::Lockups.in_namespace(b"a")
+        )));
+        assert!(!env::storage_has_key(&key(
+            near_sdk::IntoStorageKey::into_storage_key(StorageKey::Lockups)
+        )));
+    }
+
+    #[test]
+    #[should_panic(expected = "The account doesn't have enough balance")]
+    fn check_locked_overdraw() {
+        run_vm(vm!(accounts(0)));
+
+        let mut ft = token("a");
+        ft.set_lockup(
+            &accounts(0),
+            Lockup {
+                amount: U128(1_000),
+                starts_at: U64(0),
+                cliff_at: U64(10),
+                ends_at: U64(20),
+            },
+        );
+        assert_eq!(
+            ft.can_transfer(&accounts(0), &accounts(1), 1_001),
+            Some(super::super::TransferDenial::NotEnoughBalance)
+        );
+        ft.internal_transfer(&accounts(0), &accounts(1), 1_001, None);
+    }
+}
@@ -0,0 +1,228 @@
+#![cfg(feature = "redeem")]
+/*!
+Burn-to-redeem of the FT, E.g. for gift cards or loyalty points.
+
+`ft_redeem(amount, redeem_code_hash)` burns `amount` of the caller and logs a redemption with the
+hash, an off-chain service watching the events then delivers the voucher of the code.
+A code is redeemed once.
+
+# NOTES:
+  - `redeem_code_hash` is the sha256 of the code (32 bytes, base64), the code itself stays
+    off-chain until it's delivered.
+  - The redeemer pays the storage of the used code hash, the rest of the deposit is refunded.
+  - With the `freeze` feature a frozen account can't redeem, see `freeze`.
+  - Events are logged as `EVENT_JSON:` with the `ft_redeem` standard, along with the `ft_burn`
+    of nep141.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    ft: ft::FungibleToken,
+    redemptions: ft::redeem::Redemptions,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId, total_supply: U128) -> Self {
+        require_init!();
+        Self {
+            ft: ft::FungibleToken::from_args(ft::InitArgs {
+                owner_id: Some(owner_id),
+                total_supply,
+                name: "Points".to_string(),
+                symbol: "PTS".to_string(),
+                ..Default::default()
+            }),
+            redemptions: ft::redeem::Redemptions::new(),
+        }
+    }
+}
+
+ft::impl_fungible_token_contract!(Contract, ft);
+// impl_fungible_token_redeem!(Contract, ft, redemptions);
+ft::redeem::impl_fungible_token_redeem!(Contract, ft, redemptions);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    use ft::core::FungibleTokenCore;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+    fn code_hash(code: &str) -> Base64VecU8 {
+        env::sha256(code.as_bytes()).into()
+    }
+
+    #[test]
+    fn test_redeem() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0), U128(1_000));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR / 100),
+        );
+        contract.ft_redeem(U128(400), code_hash("GIFT-1"));
+        assert_eq!(contract.ft_total_supply(), U128(600));
+        assert!(contract.ft_is_redeem_code_used(code_hash("GIFT-1")));
+        assert!(!contract.ft_is_redeem_code_used(code_hash("GIFT-2")));
+        assert!(get_logs().last().unwrap().contains(r#""event":"ft_redeem""#));
+    }
+
+    #[test]
+    #[should_panic(expected = "Redeem code is already used")]
+    fn test_redeem_twice() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0), U128(1_000));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR / 100),
+        );
+        contract.ft_redeem(U128(1), code_hash("GIFT-1"));
+        contract.ft_redeem(U128(1), code_hash("GIFT-1"));
+    }
+}
+```
+*/
+
+use super::super::*;
+use super::{events, FungibleToken};
+
+use near_sdk::{collections::LookupSet, serde_json, CryptoHash};
+
+raw_storage_key!(pub enum StorageKey in storage_key::REDEEM {
+    RedeemedCodes,
+});
+
+/// Logs an `ft_redeem` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    super::super::events::emit(super::super::events::standards::FT_REDEEM, event, data);
+}
+
+/// The 32 bytes of a code hash.
+pub fn code_hash(redeem_code_hash: &Base64VecU8) -> CryptoHash {
+    redeem_code_hash
+        .0
+        .as_slice()
+        .try_into()
+        .unwrap_or_else(|_| env::panic_str("redeem_code_hash must be 32 bytes"))
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Redemptions {
+    /// Hashes of the redeemed codes.
+    pub used_codes: LookupSet<CryptoHash>,
+}
+impl Default for Redemptions {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Redemptions {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            used_codes: LookupSet::new(StorageKey::RedeemedCodes),
+        }
+    }
+
+    #[inline]
+    pub fn is_used(&self, redeem_code_hash: &Base64VecU8) -> bool {
+        self.used_codes.contains(&code_hash(redeem_code_hash))
+    }
+
+    /// Burns `amount` of the predecessor for the code, the attached deposit pays the storage of
+    /// the code hash.
+    pub fn redeem(&mut self, ft: &mut FungibleToken, amount: U128, redeem_code_hash: Base64VecU8) {
+        require!(
+            redeem_code_hash.0.len() == 32,
+            "redeem_code_hash must be 32 bytes"
+        );
+        require!(amount.0 > 0, "The amount should be a positive number");
+        let account_id = env::predecessor_account_id();
+        #[cfg(feature = "freeze")]
+        super::freeze::assert_not_frozen(&account_id);
+
+        let initial_storage_usage = env::storage_usage();
+        require!(
+            self.used_codes.insert(&code_hash(&redeem_code_hash)),
+            "Redeem code is already used"
+        );
+        ft.track_holders(&[&account_id], |ft| {
+            ft.token.internal_withdraw(&account_id, amount.0)
+        });
+
+        let storage_cost =
+            protocol::storage_cost(env::storage_usage().saturating_sub(initial_storage_usage));
+        let attached = env::attached_deposit();
+        require!(
+            attached >= storage_cost,
+            format!("Must attach {} yoctoNEAR to cover storage", storage_cost)
+        );
+        if attached > storage_cost {
+            Promise::new(account_id.clone()).transfer(attached - storage_cost);
+        }
+
+        events::FtBurn {
+            owner_id: &account_id,
+            amount: &amount,
+            memo: Some("Redeemed"),
+        }
+        .emit();
+        emit(
+            "ft_redeem",
+            serde_json::json!({
+                "account_id": account_id,
+                "amount": amount,
+                "redeem_code_hash": redeem_code_hash,
+            }),
+        );
+    }
+}
+
+#[macro_export]
+macro_rules! impl_fungible_token_redeem {
+    ($contract:ident, $ft:ident, $redemptions:ident $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_fungible_token_redeem!`.
+            pub const FT_REDEEM_MANIFEST: $crate::manifest::Component =
+                $crate::manifest::Component {
+                    feature: Some("redeem"),
+                    methods: &[
+                        $crate::manifest::Method::payable("ft_redeem"),
+                        $crate::manifest::Method::view("ft_is_redeem_code_used"),
+                    ],
+                    storage: &[
+                        // 40 (record) + 1 (prefix) + 32 (code hash)
+                        $crate::manifest::StorageEstimate::new("ft_redeem", 73),
+                    ],
+                };
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// `redeem_code_hash` is the sha256 of the code. Requires the storage deposit.
+            #[payable]
+            pub fn ft_redeem(&mut self, amount: U128, redeem_code_hash: Base64VecU8) {
+                self.$redemptions
+                    .redeem(&mut self.$ft, amount, redeem_code_hash)
+            }
+
+            pub fn ft_is_redeem_code_used(&self, redeem_code_hash: Base64VecU8) -> bool {
+                self.$redemptions.is_used(&redeem_code_hash)
+            }
+        }
+    };
+}
+pub use impl_fungible_token_redeem;
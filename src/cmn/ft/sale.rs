@@ -0,0 +1,715 @@
+#![cfg(feature = "sale")]
+/*!
+Token sale (crowdfunding) of the FT for NEAR.
+
+Contributions are taken during a window up to a hard cap, priced by tiers of the raised amount.
+Once the window ends or the hard cap is reached anyone can finalize the sale: if the soft cap is
+met the raised NEAR goes to the owner and contributors claim their tokens, otherwise contributors
+get their NEAR back.
+
+# NOTES:
+  - Tiers are ordered by `raised_cap`, the last one being the hard cap. A contribution across
+    tiers is priced by each of them for its part. Over the hard cap, the rest is refunded.
+  - Tokens are minted on claim, within the max supply of the token. Contributors have to be
+    registered in the token (`storage_deposit`) to claim.
+  - With a `vesting`, claimed tokens are locked through [`lockup`](super::lockup) from the end of
+    the sale, the token needs `lockups` enabled. An account still locked by another lockup can't
+    claim until it ends.
+  - The first contribution of an account pays the storage of its record from the deposit,
+    it's given back with the claim or the refund.
+  - Events are logged as `EVENT_JSON:` with the `ft_sale` standard.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    ft: ft::FungibleToken,
+    sale: ft::sale::TokenSale,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId, sale: ft::sale::SaleConfig) -> Self {
+        require_init!();
+        Self {
+            owner_id: owner_id.clone(),
+            ft: ft::FungibleToken::from_args(ft::InitArgs {
+                owner_id: Some(owner_id),
+                total_supply: U128(0),
+                name: "Sold".to_string(),
+                symbol: "SLD".to_string(),
+                ..Default::default()
+            }),
+            sale: ft::sale::TokenSale::new(sale),
+        }
+    }
+}
+
+ft::impl_fungible_token_contract!(Contract, ft);
+// impl_fungible_token_sale!(Contract, ft, sale[, owner = <AccountId field; default: the contract itself>]);
+ft::sale::impl_fungible_token_sale!(Contract, ft, sale, owner = owner_id);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+    const END: u64 = 100;
+
+    fn setup(soft_cap: Balance) -> (VMContextBuilder, Contract) {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let contract = Contract::new(
+            accounts(0),
+            ft::sale::SaleConfig {
+                starts_at: U64(0),
+                ends_at: U64(END),
+                soft_cap: U128(soft_cap),
+                tiers: vec![
+                    ft::sale::Tier {
+                        raised_cap: U128(10 * NEAR),
+                        tokens_per_near: U128(200),
+                    },
+                    ft::sale::Tier {
+                        raised_cap: U128(20 * NEAR),
+                        tokens_per_near: U128(100),
+                    },
+                ],
+                vesting: None,
+            },
+        );
+        (vm, contract)
+    }
+
+    fn contribute(
+        vm: &mut VMContextBuilder,
+        contract: &mut Contract,
+        account_id: AccountId,
+        amount: Balance,
+    ) {
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(amount)
+                .predecessor_account_id(account_id),
+        );
+        contract.sale_contribute();
+    }
+
+    #[test]
+    fn test_sale_claim() {
+        let (mut vm, mut contract) = setup(5 * NEAR);
+
+        contribute(&mut vm, &mut contract, accounts(1), 8 * NEAR);
+        // 2 NEAR at the first tier, the rest at the second
+        contribute(&mut vm, &mut contract, accounts(2), 5 * NEAR);
+        let contribution = contract.sale_contribution_of(accounts(2)).unwrap();
+        assert!(contribution.tokens.0 < 2 * 200 + 3 * 100);
+        assert!(contribution.tokens.0 >= 2 * 200 + 2 * 100);
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(contract.storage_balance_bounds().min.0)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.storage_deposit(None, None);
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(0)
+                .block_timestamp(END),
+        );
+        contract.sale_finalize();
+        assert_eq!(get_transfers_to(&accounts(0)), contract.sale_info().raised.0);
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.sale_claim();
+        assert!(contract.ft.token.internal_unwrap_balance_of(&accounts(1)) > 1_500);
+        assert!(contract.sale_contribution_of(accounts(1)).is_none());
+    }
+
+    #[test]
+    fn test_sale_refund() {
+        let (mut vm, mut contract) = setup(15 * NEAR);
+
+        contribute(&mut vm, &mut contract, accounts(1), 8 * NEAR);
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(0)
+                .block_timestamp(END),
+        );
+        contract.sale_finalize();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.sale_refund();
+        // the contribution and its storage
+        assert_eq!(get_transfers_to(&accounts(1)), 8 * NEAR);
+    }
+
+    #[test]
+    fn test_sale_ended() {
+        let (mut vm, contract) = setup(5 * NEAR);
+
+        // `sale_contribute` panics with "The sale has ended" from here on
+        run_vm(vm.block_timestamp(END).is_view(true));
+        assert_eq!(contract.sale_info().status, ft::sale::Status::Ended);
+    }
+}
+```
+*/
+
+use super::super::*;
+use super::{lockup::Lockup, FungibleToken};
+
+use near_sdk::{
+    collections::LookupMap,
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+
+pub const MAX_TIERS: usize = 10;
+/// 1 NEAR in yoctoNEAR.
+pub const ONE_NEAR: Balance = 10u128.pow(24);
+
+raw_storage_key!(pub enum StorageKey in storage_key::SALE {
+    SaleContributions,
+});
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Tier {
+    /// Raised yoctoNEAR up to which the price applies.
+    pub raised_cap: U128,
+    /// Tokens (in the smallest unit) for 1 NEAR.
+    pub tokens_per_near: U128,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Vesting {
+    /// Block timestamp (nanoseconds) before which nothing is unlocked.
+    pub cliff_at: U64,
+    /// Block timestamp (nanoseconds) from which everything is unlocked.
+    pub ends_at: U64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SaleConfig {
+    /// Block timestamp (nanoseconds).
+    pub starts_at: U64,
+    /// Block timestamp (nanoseconds).
+    pub ends_at: U64,
+    /// yoctoNEAR to raise for the sale to succeed.
+    pub soft_cap: U128,
+    /// By ascending `raised_cap`, the last one is the hard cap.
+    pub tiers: Vec<Tier>,
+    /// Lockup of the claimed tokens, none by default.
+    #[serde(default)]
+    pub vesting: Option<Vesting>,
+}
+impl SaleConfig {
+    pub fn assert_valid(&self) {
+        require!(
+            self.starts_at.0 < self.ends_at.0,
+            "The sale must start before it ends"
+        );
+        require!(
+            (1..=MAX_TIERS).contains(&self.tiers.len()),
+            format!("Must have 1 to {} tiers", MAX_TIERS)
+        );
+        require!(
+            self.tiers
+                .windows(2)
+                .all(|tiers| tiers[0].raised_cap.0 < tiers[1].raised_cap.0),
+            "Tiers must be by ascending raised_cap"
+        );
+        require!(
+            self.soft_cap.0 <= self.hard_cap(),
+            "Soft cap can't be over the hard cap"
+        );
+        if let Some(lockup) = self.lockup(U128(1)) {
+            lockup.assert_valid();
+        }
+    }
+
+    /// Lockup of `tokens` claimed, unlocking linearly from the end of the sale.
+    pub fn lockup(&self, tokens: U128) -> Option<Lockup> {
+        self.vesting.as_ref().map(|vesting| Lockup {
+            amount: tokens,
+            starts_at: self.ends_at,
+            cliff_at: vesting.cliff_at,
+            ends_at: vesting.ends_at,
+        })
+    }
+
+    #[inline]
+    pub fn hard_cap(&self) -> Balance {
+        self.tiers.last().map_or(0, |tier| tier.raised_cap.0)
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Contribution {
+    /// yoctoNEAR contributed.
+    pub amount: U128,
+    /// Tokens bought.
+    pub tokens: U128,
+    /// Storage paid by the contributor.
+    pub storage_deposit: U128,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum Status {
+    Pending,
+    Open,
+    /// Ended or reached the hard cap, waiting for `sale_finalize`.
+    Ended,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SaleInfo {
+    #[serde(flatten)]
+    pub config: SaleConfig,
+    pub status: Status,
+    pub raised: U128,
+    pub sold: U128,
+}
+
+/// Logs an `ft_sale` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    super::super::events::emit(super::super::events::standards::FT_SALE, event, data);
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct TokenSale {
+    pub config: SaleConfig,
+    pub contributions: LookupMap<AccountId, Contribution>,
+    /// yoctoNEAR raised.
+    pub raised: Balance,
+    /// Tokens bought.
+    pub sold: Balance,
+    /// Whether the sale succeeded, once it's finalized.
+    pub succeeded: Option<bool>,
+}
+impl TokenSale {
+    pub fn new(config: SaleConfig) -> Self {
+        config.assert_valid();
+        Self {
+            config,
+            contributions: LookupMap::new(StorageKey::SaleContributions),
+            raised: 0,
+            sold: 0,
+            succeeded: None,
+        }
+    }
+
+    pub fn status(&self) -> Status {
+        match self.succeeded {
+            Some(true) => Status::Succeeded,
+            Some(false) => Status::Failed,
+            None if env::block_timestamp() < self.config.starts_at.0 => Status::Pending,
+            None if env::block_timestamp() >= self.config.ends_at.0
+                || self.raised >= self.config.hard_cap() =>
+            {
+                Status::Ended
+            }
+            None => Status::Open,
+        }
+    }
+
+    /// Tokens for `amount` yoctoNEAR contributed after `raised`, priced by the tiers.
+    pub fn tokens_for(&self, mut raised: Balance, amount: Balance) -> Balance {
+        let mut tokens = 0;
+        let mut left = amount;
+        for tier in &self.config.tiers {
+            if left == 0 {
+                break;
+            }
+            if raised >= tier.raised_cap.0 {
+                continue;
+            }
+            let part = left.min(tier.raised_cap.0 - raised);
+            tokens += u256::mul_div(part, tier.tokens_per_near.0, ONE_NEAR);
+            raised += part;
+            left -= part;
+        }
+        tokens
+    }
+
+    /// Contributes the attached deposit of the predecessor, less the storage of its first
+    /// contribution. The part over the hard cap is refunded.
+    pub fn contribute(&mut self) {
+        match self.status() {
+            Status::Pending => env::panic_str("The sale hasn't started"),
+            Status::Open => (),
+            _ => env::panic_str("The sale has ended"),
+        }
+        let account_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+        let mut contribution = self.contributions.get(&account_id).unwrap_or(Contribution {
+            amount: U128(0),
+            tokens: U128(0),
+            storage_deposit: U128(0),
+        });
+        // the amounts have the same size whatever they are
+        self.contributions.insert(&account_id, &contribution);
+        let storage_cost = protocol::storage_cost(env::storage_usage() - initial_storage_usage);
+
+        let attached = env::attached_deposit();
+        require!(
+            attached > storage_cost,
+            format!(
+                "Must attach more than {} yoctoNEAR to cover storage",
+                storage_cost
+            )
+        );
+        let amount = (attached - storage_cost).min(self.config.hard_cap() - self.raised);
+        let refund = attached - storage_cost - amount;
+        let tokens = self.tokens_for(self.raised, amount);
+        require!(tokens > 0, "The contribution is too small");
+
+        contribution.amount.0 += amount;
+        contribution.tokens.0 += tokens;
+        contribution.storage_deposit.0 += storage_cost;
+        self.contributions.insert(&account_id, &contribution);
+        self.raised += amount;
+        self.sold += tokens;
+        if refund > 0 {
+            Promise::new(account_id.clone()).transfer(refund);
+        }
+
+        emit(
+            "sale_contribute",
+            serde_json::json!({
+                "account_id": account_id,
+                "amount": U128(amount),
+                "tokens": U128(tokens),
+            }),
+        );
+    }
+
+    /// Ends the sale, the raised NEAR goes to `owner_id` if the soft cap is met.
+    pub fn finalize(&mut self, owner_id: AccountId) {
+        require!(
+            self.status() == Status::Ended,
+            "The sale can't be finalized"
+        );
+        let succeeded = self.raised >= self.config.soft_cap.0;
+        self.succeeded = Some(succeeded);
+        if succeeded && self.raised > 0 {
+            Promise::new(owner_id).transfer(self.raised);
+        }
+
+        emit(
+            "sale_finalize",
+            serde_json::json!({
+                "succeeded": succeeded,
+                "raised": U128(self.raised),
+                "sold": U128(self.sold),
+            }),
+        );
+    }
+
+    #[inline]
+    fn take_contribution(&mut self, account_id: &AccountId) -> Contribution {
+        self.contributions
+            .remove(account_id)
+            .unwrap_or_else(|| env::panic_str("Nothing contributed"))
+    }
+
+    /// Mints the tokens bought by the predecessor, locked by the vesting if any, and gives back
+    /// its storage.
+    pub fn claim(&mut self, ft: &mut FungibleToken) {
+        require!(
+            self.status() == Status::Succeeded,
+            "The sale didn't succeed"
+        );
+        let account_id = env::predecessor_account_id();
+        require!(
+            ft.token.accounts.contains_key(&account_id),
+            "The account must be registered in the token to claim"
+        );
+        let contribution = self.take_contribution(&account_id);
+        ft.internal_mint(&account_id, contribution.tokens, Some("Token sale"));
+        if let Some(lockup) = self.config.lockup(contribution.tokens) {
+            require!(
+                ft.locked_balance(&account_id) == 0,
+                "The account is locked by another lockup"
+            );
+            ft.set_lockup(&account_id, lockup);
+        }
+        Promise::new(account_id.clone()).transfer(contribution.storage_deposit.0);
+
+        emit(
+            "sale_claim",
+            serde_json::json!({ "account_id": account_id, "tokens": contribution.tokens }),
+        );
+    }
+
+    /// Gives back the contribution of the predecessor and its storage.
+    pub fn refund(&mut self) {
+        require!(self.status() == Status::Failed, "The sale didn't fail");
+        let account_id = env::predecessor_account_id();
+        let contribution = self.take_contribution(&account_id);
+        Promise::new(account_id.clone())
+            .transfer(contribution.amount.0 + contribution.storage_deposit.0);
+
+        emit(
+            "sale_refund",
+            serde_json::json!({ "account_id": account_id, "amount": contribution.amount }),
+        );
+    }
+
+    pub fn info(&self) -> SaleInfo {
+        SaleInfo {
+            config: self.config.clone(),
+            status: self.status(),
+            raised: U128(self.raised),
+            sold: U128(self.sold),
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! impl_fungible_token_sale {
+        ($contract:ident, $ft:ident, $sale:ident $(, owner = $owner:ident)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_fungible_token_sale!`.
+                pub const FT_SALE_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("sale"),
+                    methods: &[
+                        $crate::manifest::Method::payable("sale_contribute"),
+                        $crate::manifest::Method::call("sale_finalize"),
+                        $crate::manifest::Method::call("sale_claim"),
+                        $crate::manifest::Method::call("sale_refund"),
+                        $crate::manifest::Method::view("sale_info"),
+                        $crate::manifest::Method::view("sale_contribution_of"),
+                    ],
+                    storage: &[
+                        // a contribution of a 64 bytes account
+                        $crate::manifest::StorageEstimate::new("sale_contribute", 200),
+                    ],
+                };
+            }
+            #[near_bindgen]
+            impl $contract {
+                /// Contributes the attached deposit, less the storage of the first contribution.
+                #[payable]
+                pub fn sale_contribute(&mut self) {
+                    #[cfg(feature = "decommission")]
+                    $crate::decommission::require_active();
+                    self.$sale.contribute()
+                }
+
+                /// Anyone can finalize once the sale ended or reached the hard cap.
+                pub fn sale_finalize(&mut self) {
+                    let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                        .cloned()
+                        .unwrap_or_else(env::current_account_id);
+                    self.$sale.finalize(owner_id)
+                }
+
+                pub fn sale_claim(&mut self) {
+                    self.$sale.claim(&mut self.$ft)
+                }
+
+                pub fn sale_refund(&mut self) {
+                    self.$sale.refund()
+                }
+
+                pub fn sale_info(&self) -> $crate::ft::sale::SaleInfo {
+                    self.$sale.info()
+                }
+
+                pub fn sale_contribution_of(
+                    &self,
+                    account_id: AccountId,
+                ) -> Option<$crate::ft::sale::Contribution> {
+                    self.$sale.contributions.get(&account_id)
+                }
+            }
+        };
+    }
+pub use impl_fungible_token_sale;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::super::test_utils::*;
+    use super::*;
+
+    /// 1 to 2 NEAR raised at 100 tokens per NEAR, then up to 4 NEAR at 50.
+    fn sale() -> TokenSale {
+        TokenSale::new(SaleConfig {
+            starts_at: U64(10),
+            ends_at: U64(20),
+            soft_cap: U128(ONE_NEAR),
+            tiers: vec![
+                Tier {
+                    raised_cap: U128(2 * ONE_NEAR),
+                    tokens_per_near: U128(100),
+                },
+                Tier {
+                    raised_cap: U128(4 * ONE_NEAR),
+                    tokens_per_near: U128(50),
+                },
+            ],
+            vesting: None,
+        })
+    }
+
+    fn contribute(
+        vm: &mut VMContextBuilder,
+        sale: &mut TokenSale,
+        account: usize,
+        amount: Balance,
+    ) {
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .block_timestamp(10)
+                .predecessor_account_id(accounts(account))
+                .attached_deposit(amount),
+        );
+        sale.contribute();
+    }
+
+    #[test]
+    fn check_contribute_over_hard_cap() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut sale = sale();
+
+        contribute(&mut vm, &mut sale, 1, 5 * ONE_NEAR);
+        let contribution = sale.contributions.get(&accounts(1)).unwrap();
+        assert_eq!(contribution.amount, U128(4 * ONE_NEAR));
+        assert_eq!(contribution.tokens, U128(2 * 100 + 2 * 50));
+        assert_eq!(
+            get_transfers_to(&accounts(1)),
+            ONE_NEAR - contribution.storage_deposit.0
+        );
+        assert_eq!(sale.status(), Status::Ended);
+    }
+
+    #[test]
+    fn check_claim() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut ft = FungibleToken::from_args(super::super::InitArgs {
+            owner_id: Some(accounts(0)),
+            name: "Sold".to_string(),
+            symbol: "SLD".to_string(),
+            ..Default::default()
+        });
+        let mut sale = sale();
+        contribute(&mut vm, &mut sale, 1, 3 * ONE_NEAR);
+        let contribution = sale.contributions.get(&accounts(1)).unwrap();
+        // the storage comes out of the last tier
+        assert_eq!(contribution.tokens, U128(2 * 100 + 49));
+
+        run_vm(vm.storage_usage(env::storage_usage()).block_timestamp(20));
+        sale.finalize(accounts(0));
+        assert_eq!(sale.status(), Status::Succeeded);
+        assert_eq!(get_transfers_to(&accounts(0)), contribution.amount.0);
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(accounts(1)),
+        );
+        ft.token.internal_register_account(&accounts(1));
+        sale.claim(&mut ft);
+        assert_eq!(ft.token.internal_unwrap_balance_of(&accounts(1)), 249);
+        assert!(sale.contributions.get(&accounts(1)).is_none());
+    }
+
+    #[test]
+    fn check_claim_vested() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut ft = FungibleToken::from_args(super::super::InitArgs {
+            owner_id: Some(accounts(0)),
+            name: "Sold".to_string(),
+            symbol: "SLD".to_string(),
+            lockups: true,
+            ..Default::default()
+        });
+        let mut sale = sale();
+        sale.config.vesting = Some(Vesting {
+            cliff_at: U64(30),
+            ends_at: U64(40),
+        });
+        contribute(&mut vm, &mut sale, 1, 3 * ONE_NEAR);
+
+        run_vm(vm.storage_usage(env::storage_usage()).block_timestamp(20));
+        sale.finalize(accounts(0));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(accounts(1)),
+        );
+        ft.token.internal_register_account(&accounts(1));
+        sale.claim(&mut ft);
+        assert_eq!(
+            ft.lockup_of(&accounts(1)),
+            Some(Lockup {
+                amount: U128(249),
+                starts_at: U64(20),
+                cliff_at: U64(30),
+                ends_at: U64(40),
+            })
+        );
+        assert_eq!(ft.transferable_balance(&accounts(1)), 0);
+    }
+
+    #[test]
+    fn check_refund() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut sale = sale();
+        contribute(&mut vm, &mut sale, 1, ONE_NEAR / 2);
+        let contribution = sale.contributions.get(&accounts(1)).unwrap();
+
+        run_vm(vm.storage_usage(env::storage_usage()).block_timestamp(20));
+        sale.finalize(accounts(0));
+        assert_eq!(sale.status(), Status::Failed);
+        assert_eq!(get_transfers_to(&accounts(0)), 0);
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(accounts(1)),
+        );
+        sale.refund();
+        assert_eq!(get_transfers_to(&accounts(1)), ONE_NEAR / 2);
+        assert_eq!(
+            contribution.amount.0 + contribution.storage_deposit.0,
+            ONE_NEAR / 2
+        );
+    }
+
+    #[test]
+    fn check_ended() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let sale = sale();
+
+        // `contribute` panics with "The sale has ended" from here on
+        run_vm(vm.block_timestamp(20));
+        assert_eq!(sale.status(), Status::Ended);
+    }
+}
@@ -0,0 +1,329 @@
+#![cfg(feature = "sponsor")]
+/*!
+Storage registration paid by the contract, for onboarding users that have no NEAR yet.
+
+The owner funds a pool, then `ft_sponsor_registration` registers the first N accounts that ask
+for themselves, and allowlisted accounts at anyone's request, paying their storage from the pool.
+
+# NOTES:
+  - An account is sponsored at most once, even after it unregisters.
+  - `storage_unregister` still refunds the registration to the account, so every slot and
+    allowlist entry costs the pool up to one registration.
+  - The pool is a part of the contract balance, only the owner can fund and withdraw it.
+  - `ft_sponsor_import_accounts` allowlists a batch of accounts at once. The allowlist can't be
+    enumerated, see [`allowlist`](super::super::allowlist) for one that can be exported.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    ft: ft::FungibleToken,
+    sponsorship: ft::sponsor::Sponsorship,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            owner_id: owner_id.clone(),
+            ft: ft::FungibleToken::from_args(ft::InitArgs {
+                owner_id: Some(owner_id),
+                total_supply: U128(1_000),
+                name: "Sponsored".to_string(),
+                symbol: "SPN".to_string(),
+                ..Default::default()
+            }),
+            sponsorship: ft::sponsor::Sponsorship::new(),
+        }
+    }
+}
+
+ft::impl_fungible_token_contract!(Contract, ft);
+// impl_fungible_token_sponsor!(Contract, ft, sponsorship[, owner = <AccountId field; default: the contract itself>]);
+ft::sponsor::impl_fungible_token_sponsor!(Contract, ft, sponsorship, owner = owner_id);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(NEAR));
+        contract.ft_sponsor_fund();
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.ft_sponsor_set_slots(1);
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_sponsor_registration() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.ft_sponsor_registration(accounts(1));
+        assert_eq!(contract.ft.token.internal_unwrap_balance_of(&accounts(1)), 0);
+        assert_eq!(contract.ft_sponsor_slots(), 0);
+        assert!(contract.ft_sponsor_pool().0 < NEAR);
+
+        // allowlisted accounts don't need a slot, anyone can register them
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR / 100)
+                .predecessor_account_id(accounts(0)),
+        );
+        contract.ft_sponsor_allow(accounts(2));
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(0)
+                .predecessor_account_id(accounts(3)),
+        );
+        contract.ft_sponsor_registration(accounts(2));
+        assert!(contract.ft_sponsor_is_sponsored(accounts(2)));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR / 100)
+                .predecessor_account_id(accounts(0)),
+        );
+        assert_eq!(
+            contract.ft_sponsor_import_accounts(vec![accounts(2), accounts(4), accounts(5)]),
+            2
+        );
+        assert!(contract.ft_sponsor_is_allowed(accounts(5)));
+    }
+
+    #[test]
+    #[should_panic(expected = "The account is not eligible for sponsored storage")]
+    fn test_sponsor_other_account() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.ft_sponsor_registration(accounts(2));
+    }
+}
+```
+*/
+
+use super::super::*;
+use super::FungibleToken;
+
+use near_sdk::collections::LookupSet;
+
+raw_storage_key!(pub enum StorageKey in storage_key::SPONSOR {
+    SponsorAllowlist,
+    Sponsored,
+});
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Sponsorship {
+    /// Balance left to pay registrations with.
+    pub pool: Balance,
+    /// Registrations left for accounts that aren't allowlisted.
+    pub slots: u32,
+    pub allowlist: LookupSet<AccountId>,
+    /// Accounts already sponsored once.
+    pub sponsored: LookupSet<AccountId>,
+}
+impl Default for Sponsorship {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Sponsorship {
+    pub fn new() -> Self {
+        Self {
+            pool: 0,
+            slots: 0,
+            allowlist: LookupSet::new(StorageKey::SponsorAllowlist),
+            sponsored: LookupSet::new(StorageKey::Sponsored),
+        }
+    }
+
+    /// Registers `account_id` in `ft` paying its storage from the pool.
+    /// Without the allowlist only the predecessor can be registered, taking a slot.
+    pub fn sponsor_registration(&mut self, ft: &mut FungibleToken, account_id: AccountId) {
+        require!(
+            !ft.token.accounts.contains_key(&account_id),
+            "The account is already registered"
+        );
+        require!(
+            !self.sponsored.contains(&account_id),
+            "The account was already sponsored"
+        );
+        if !self.allowlist.contains(&account_id) {
+            require!(
+                account_id == env::predecessor_account_id() && self.slots > 0,
+                "The account is not eligible for sponsored storage"
+            );
+            self.slots -= 1;
+        }
+
+        let initial_storage_usage = env::storage_usage();
+        self.sponsored.insert(&account_id);
+        ft.token.internal_register_account(&account_id);
+        let storage_cost = protocol::storage_cost(env::storage_usage() - initial_storage_usage);
+        require!(
+            self.pool >= storage_cost,
+            "Sponsorship pool is too low to pay the storage"
+        );
+        self.pool -= storage_cost;
+
+        log!("Sponsored the registration of @{}", account_id);
+    }
+
+    /// Takes `amount` out of the pool, the caller sends it.
+    pub fn withdraw(&mut self, amount: Balance) {
+        require!(amount <= self.pool, "Not enough balance in the pool");
+        self.pool -= amount;
+    }
+}
+
+#[macro_export]
+macro_rules! impl_fungible_token_sponsor {
+        ($contract:ident, $ft:ident, $sponsorship:ident $(, owner = $owner:ident)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_fungible_token_sponsor!`.
+                pub const FT_SPONSOR_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("sponsor"),
+                    methods: &[
+                        $crate::manifest::Method::call("ft_sponsor_registration"),
+                        $crate::manifest::Method::payable("ft_sponsor_fund").role("owner"),
+                        $crate::manifest::Method::payable("ft_sponsor_withdraw").role("owner"),
+                        $crate::manifest::Method::payable("ft_sponsor_set_slots").role("owner"),
+                        $crate::manifest::Method::payable("ft_sponsor_allow").role("owner"),
+                        $crate::manifest::Method::payable("ft_sponsor_import_accounts").role("owner"),
+                        $crate::manifest::Method::payable("ft_sponsor_disallow").role("owner"),
+                        $crate::manifest::Method::view("ft_sponsor_pool"),
+                        $crate::manifest::Method::view("ft_sponsor_slots"),
+                        $crate::manifest::Method::view("ft_sponsor_is_allowed"),
+                        $crate::manifest::Method::view("ft_sponsor_is_sponsored"),
+                    ],
+                    storage: &[
+                        // allowlist entry of a 64 bytes account, paid by the owner
+                        $crate::manifest::StorageEstimate::new("ft_sponsor_allow", 110),
+                        // per account
+                        $crate::manifest::StorageEstimate::new("ft_sponsor_import_accounts", 110),
+                    ],
+                };
+
+                fn sponsor_owner_id(&self) -> AccountId {
+                    None::<&AccountId>$(.or(Some(&self.$owner)))?
+                        .cloned()
+                        .unwrap_or_else(env::current_account_id)
+                }
+
+                fn assert_sponsor_owner(&self) {
+                    require!(
+                        env::predecessor_account_id() == self.sponsor_owner_id(),
+                        "Only the owner can manage the sponsorship"
+                    );
+                }
+            }
+            #[near_bindgen]
+            impl $contract {
+                /// Registers `account_id` with the storage paid by the contract,
+                /// the predecessor itself or an allowlisted account.
+                pub fn ft_sponsor_registration(&mut self, account_id: AccountId) {
+                    self.$sponsorship.sponsor_registration(&mut self.$ft, account_id)
+                }
+
+                /// Adds the attached deposit to the pool.
+                #[payable]
+                pub fn ft_sponsor_fund(&mut self) {
+                    #[cfg(feature = "decommission")]
+                    $crate::decommission::require_active();
+                    self.assert_sponsor_owner();
+                    self.$sponsorship.pool += env::attached_deposit();
+                }
+
+                #[payable]
+                pub fn ft_sponsor_withdraw(&mut self, amount: U128) -> Promise {
+                    near_sdk::assert_one_yocto();
+                    self.assert_sponsor_owner();
+                    self.$sponsorship.withdraw(amount.0);
+                    Promise::new(self.sponsor_owner_id()).transfer(amount.0)
+                }
+
+                /// Registrations left for the first accounts that ask for themselves.
+                #[payable]
+                pub fn ft_sponsor_set_slots(&mut self, slots: u32) {
+                    near_sdk::assert_one_yocto();
+                    self.assert_sponsor_owner();
+                    self.$sponsorship.slots = slots;
+                }
+
+                /// Requires the storage deposit of the allowlist entry.
+                #[payable]
+                pub fn ft_sponsor_allow(&mut self, account_id: AccountId) {
+                    self.assert_sponsor_owner();
+                    let initial_storage_usage = env::storage_usage();
+                    self.$sponsorship.allowlist.insert(&account_id);
+                    $crate::refund_deposit_to(
+                        env::storage_usage() - initial_storage_usage,
+                        None,
+                    );
+                }
+
+                /// [`ft_sponsor_allow`] for a batch of up to `MAX_ALLOWLIST_BATCH` accounts.
+                /// Returns how many weren't allowlisted yet.
+                #[payable]
+                pub fn ft_sponsor_import_accounts(&mut self, account_ids: Vec<AccountId>) -> u32 {
+                    self.assert_sponsor_owner();
+                    $crate::accounts::assert_max_len(account_ids.len(), $crate::allowlist::MAX_ALLOWLIST_BATCH);
+                    let initial_storage_usage = env::storage_usage();
+                    let imported = account_ids
+                        .iter()
+                        .filter(|account_id| self.$sponsorship.allowlist.insert(account_id))
+                        .count() as u32;
+                    $crate::refund_deposit_to(
+                        env::storage_usage() - initial_storage_usage,
+                        None,
+                    );
+                    imported
+                }
+
+                #[payable]
+                pub fn ft_sponsor_disallow(&mut self, account_id: AccountId) {
+                    near_sdk::assert_one_yocto();
+                    self.assert_sponsor_owner();
+                    self.$sponsorship.allowlist.remove(&account_id);
+                }
+
+                pub fn ft_sponsor_pool(&self) -> U128 {
+                    self.$sponsorship.pool.into()
+                }
+
+                pub fn ft_sponsor_slots(&self) -> u32 {
+                    self.$sponsorship.slots
+                }
+
+                pub fn ft_sponsor_is_allowed(&self, account_id: AccountId) -> bool {
+                    self.$sponsorship.allowlist.contains(&account_id)
+                }
+
+                pub fn ft_sponsor_is_sponsored(&self, account_id: AccountId) -> bool {
+                    self.$sponsorship.sponsored.contains(&account_id)
+                }
+            }
+        };
+    }
+pub use impl_fungible_token_sponsor;
@@ -0,0 +1,98 @@
+#![allow(dead_code)]
+/*!
+Health check view for monitoring.
+
+`impl_health!` generates a `health()` view that works the same on every contract built from
+this template, even before it has been initialized.
+
+# EXAMPLE:
+```
+# use cmn::*;
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    paused: bool,
+}
+
+// impl_health!(Contract[, owner = <AccountId or Option<AccountId> field>][, paused = <bool field>]);
+impl_health!(Contract, owner = owner_id, paused = paused);
+// {"version":"0.1.0","initialized":true,"paused":false,"owner":"alice.near","storage_usage":"219"}
+```
+*/
+
+use super::*;
+
+use near_sdk::serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Health {
+    /// Crate version of the deployed code.
+    pub version: String,
+    pub initialized: bool,
+    pub paused: bool,
+    pub owner: Option<AccountId>,
+    pub storage_usage: U64,
+}
+impl Default for Health {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Health {
+    /// Health of the current contract without any contract state.
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            initialized: env::state_exists(),
+            paused: false,
+            owner: None,
+            storage_usage: env::storage_usage().into(),
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! impl_health {
+        (@FIELD $health:ident, $state:ident, owner, $field:ident) => {
+            $health.owner = $state.$field.clone().into();
+        };
+        (@FIELD $health:ident, $state:ident, paused, $field:ident) => {
+            $health.paused = $state.$field;
+        };
+        (@IMPL $contract:ident, $health:ident, $fill:block) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_health!`.
+                pub const HEALTH_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: None,
+                    methods: &[$crate::manifest::Method::view("health")],
+                    storage: &[],
+                };
+            }
+            #[near_bindgen]
+            impl $contract {
+                /// It doesn't take `self`, so it also answers before initialization.
+                pub fn health() -> $crate::health::Health {
+                    #[allow(unused_mut)]
+                    let mut $health = $crate::health::Health::new();
+                    $fill
+                    $health
+                }
+            }
+        };
+        ($contract:ident) => {
+            impl_health!(@IMPL $contract, health, {});
+        };
+        ($contract:ident, $($key:ident = $field:ident),+ $(,)?) => {
+            impl_health!(@IMPL $contract, health, {
+                if let Some(state) = env::state_read::<$contract>() {
+                    $(impl_health!(@FIELD health, state, $key, $field);)+
+                }
+            });
+        };
+    }
+pub use impl_health;
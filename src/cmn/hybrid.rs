@@ -0,0 +1,251 @@
+#![cfg(all(feature = "ft", feature = "nft"))]
+#![allow(dead_code)]
+/*!
+One contract with a fungible token as rewards and a non fungible token as membership.
+
+`impl_hybrid_rewards!` generates `is_member(account_id)`, whether the account holds a token of
+the `nft`, and `reward_members(account_ids, amount)` for the owner, which mints `amount` of the
+`ft` to every member.
+
+# NOTES:
+  - `ft::FungibleToken` and `nft::NonFungibleToken` both write under the key `[0]`, the `ft` has
+    to be built with `FungibleToken::new_in` (or `ft::InitArgs::namespace`), E.g. under
+    [`FT_NAMESPACE`]. `init_pipeline!` rejects them otherwise.
+  - The ft and nft impl macros generate disjoint methods (`ft_*`/`storage_*` and `nft_*`), both
+    can be used on the same contract. They call themselves by name, so outside of the crate root
+    they have to be imported (`use ft::impl_fungible_token_contract;`) rather than called by path.
+  - Rewarded members must be registered in the `ft` (`storage_deposit`), the rewards are logged
+    as `ft_mint` events with the memo [`REWARD_MEMO`].
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    ft: ft::FungibleToken,
+    nft: nft::NonFungibleToken,
+}
+
+init_pipeline!(Contract, InitArgs, owner = owner_id, {
+    ft: ft::FungibleToken,
+    nft: nft::NonFungibleToken,
+});
+ft::impl_fungible_token_contract!(Contract, ft);
+nft::impl_non_fungible_token_contract!(Contract, nft);
+// impl_hybrid_rewards!(Contract, ft, nft[, owner = <AccountId field; default: the contract itself>]);
+hybrid::impl_hybrid_rewards!(Contract, ft, nft, owner = owner_id);
+// new({"args": {"ft": {"namespace": "ft", "name": "Points", ..}, "nft": {"name": "Members", ..}}})
+```
+*/
+
+use super::*;
+
+/// Namespace of the `ft` keys in a hybrid contract, see `FungibleToken::new_in`.
+pub const FT_NAMESPACE: &str = "ft";
+/// Memo of the `ft_mint` events of the rewards.
+pub const REWARD_MEMO: &str = "Membership reward";
+/// Max number of members rewarded by one `reward_members` call.
+pub const MAX_REWARDED_MEMBERS: usize = 100;
+
+/// Whether `account_id` holds a token of `nft`.
+#[inline]
+pub fn is_member(nft: &nft::NonFungibleToken, account_id: &AccountId) -> bool {
+    nft.supply_for_owner(account_id) > 0
+}
+
+/// Mints `amount` of `ft` to every account, panics if one isn't a member of `nft`.
+pub fn reward_members(
+    ft: &mut ft::FungibleToken,
+    nft: &nft::NonFungibleToken,
+    account_ids: &[AccountId],
+    amount: U128,
+) {
+    require!(
+        account_ids.len() <= MAX_REWARDED_MEMBERS,
+        format!(
+            "Can't reward more than {} members, got {}",
+            MAX_REWARDED_MEMBERS,
+            account_ids.len()
+        )
+    );
+    for account_id in account_ids {
+        require!(
+            is_member(nft, account_id),
+            format!("@{} is not a member", account_id)
+        );
+        ft.internal_mint(account_id, amount, Some(REWARD_MEMO));
+    }
+}
+
+#[macro_export]
+macro_rules! impl_hybrid_rewards {
+    ($contract:ident, $ft:ident, $nft:ident $(, owner = $owner:ident)? $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_hybrid_rewards!`.
+            pub const HYBRID_REWARDS_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: None,
+                methods: &[
+                    $crate::manifest::Method::view("is_member"),
+                    $crate::manifest::Method::payable("reward_members").role("owner"),
+                ],
+                storage: &[],
+            };
+        }
+        #[near_bindgen]
+        impl $contract {
+            pub fn is_member(&self, account_id: AccountId) -> bool {
+                $crate::hybrid::is_member(&self.$nft, &account_id)
+            }
+
+            #[payable]
+            pub fn reward_members(&mut self, account_ids: Vec<AccountId>, amount: U128) {
+                near_sdk::assert_one_yocto();
+                let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                    .cloned()
+                    .unwrap_or_else(env::current_account_id);
+                require!(
+                    env::predecessor_account_id() == owner_id,
+                    "Only the owner can reward members"
+                );
+                $crate::hybrid::reward_members(&mut self.$ft, &self.$nft, &account_ids, amount);
+            }
+        }
+    };
+}
+pub use impl_hybrid_rewards;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    use ft::core::FungibleTokenCore;
+    use ft::metadata::FungibleTokenMetadataProvider;
+    use nft::metadata::NonFungibleTokenMetadataProvider;
+    // the impl macros call themselves by name, outside the crate root they have to be imported
+    use ft::impl_fungible_token_contract;
+    use init::init_pipeline;
+    use nft::impl_non_fungible_token_contract;
+
+    #[near_bindgen]
+    #[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+    pub struct Contract {
+        owner_id: AccountId,
+        ft: ft::FungibleToken,
+        nft: nft::NonFungibleToken,
+    }
+
+    init_pipeline!(Contract, InitArgs, owner = owner_id, {
+        ft: ft::FungibleToken,
+        nft: nft::NonFungibleToken,
+    });
+    impl_fungible_token_contract!(Contract, ft);
+    impl_non_fungible_token_contract!(Contract, nft);
+    impl_hybrid_rewards!(Contract, ft, nft, owner = owner_id);
+
+    const MINT_STORAGE_COST: Balance = 100_000_000_000_000_000_000_000;
+
+    fn init_args(namespace: Option<&str>) -> InitArgs {
+        InitArgs {
+            owner_id: Some(accounts(0)),
+            ft: ft::InitArgs {
+                total_supply: U128(1_000),
+                name: "Points".to_string(),
+                symbol: "PTS".to_string(),
+                namespace: namespace.map(str::to_string),
+                ..Default::default()
+            },
+            nft: nft::InitArgs {
+                name: "Members".to_string(),
+                symbol: "MBR".to_string(),
+                ..Default::default()
+            },
+        }
+    }
+
+    fn join(vm: &mut VMContextBuilder, contract: &mut Contract, account_id: AccountId) {
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST),
+        );
+        contract.nft_mint(
+            account_id.to_string(),
+            account_id.clone(),
+            nft::TokenMetadata {
+                title: Some("Member".into()),
+                description: None,
+                media: None,
+                media_hash: None,
+                copies: None,
+                issued_at: None,
+                expires_at: None,
+                starts_at: None,
+                updated_at: None,
+                extra: None,
+                reference: None,
+                reference_hash: None,
+            },
+        );
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(contract.storage_balance_bounds().min.into()),
+        );
+        contract.storage_deposit(Some(account_id), None);
+    }
+
+    #[test]
+    fn check_reward_members() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(init_args(Some(FT_NAMESPACE)));
+
+        join(&mut vm, &mut contract, accounts(1));
+        assert!(contract.is_member(accounts(1)));
+        assert!(!contract.is_member(accounts(2)));
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.reward_members(vec![accounts(1)], U128(10));
+        assert_eq!(contract.ft_balance_of(accounts(1)), U128(10));
+        assert_eq!(contract.ft_total_supply(), U128(1_010));
+        assert!(get_logs().last().unwrap().contains(REWARD_MEMO));
+
+        // neither token overwrote the other
+        assert_eq!(contract.ft_metadata().symbol, "PTS");
+        assert_eq!(contract.nft_metadata().symbol, "MBR");
+        assert_eq!(contract.ft_balance_of(accounts(0)), U128(1_000));
+    }
+
+    #[test]
+    #[should_panic(expected = "@charlie is not a member")]
+    fn check_reward_non_member() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(init_args(Some(FT_NAMESPACE)));
+
+        run_vm(vm.attached_deposit(1));
+        contract.reward_members(vec![accounts(2)], U128(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can reward members")]
+    fn check_reward_owner() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(init_args(Some(FT_NAMESPACE)));
+
+        join(&mut vm, &mut contract, accounts(1));
+        run_vm(vm.attached_deposit(1).predecessor_account_id(accounts(1)));
+        contract.reward_members(vec![accounts(1)], U128(10));
+    }
+
+    #[test]
+    #[should_panic(expected = "Storage key [0] of `nft` overlaps [0] of `ft`")]
+    fn check_without_namespace() {
+        run_vm(vm!(accounts(0)));
+        Contract::new(init_args(None));
+    }
+}
@@ -0,0 +1,316 @@
+#![cfg(feature = "inbox")]
+#![allow(dead_code)]
+/*!
+Inboxes of on-chain notifications, E.g. an offer received, an auction outbid or a proposal
+created.
+
+Modules push small typed notifications to the bounded inbox of an account, the account reads
+them by page with `inbox_of` and clears them with `inbox_clear`.
+
+# NOTES:
+  - The sender pays the storage of a notification, [`Inbox::push`] returns the cost to take
+    from the attached deposit and [`Inbox::push_paid`] takes it. The storage is refunded to the
+    sender when the notification is cleared.
+  - An inbox keeps the last [`MAX_INBOX_LENGTH`] notifications, a push to a full inbox drops
+    (and refunds) the oldest one.
+  - The storage of the inbox record itself (~100 bytes per account) is paid by the contract.
+  - Ids are per inbox and increasing, `inbox_clear` clears up to one, E.g. the last one read.
+
+# EXAMPLE:
+```
+# use cmn::*;
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn make_offer(&mut self, token_id: nft::TokenId, price: U128) {
+        let owner_id = self.nft.token.owner_by_id.get(&token_id).expect("Token not found");
+        // ..
+        self.inbox.push_paid(
+            &owner_id,
+            inbox::NotificationKind::OfferReceived,
+            Some(format!(r#"{{"token_id":"{}","price":"{}"}}"#, token_id, price.0)),
+            env::attached_deposit() - price.0,
+        );
+    }
+}
+
+// impl_inbox!(Contract, inbox);
+inbox::impl_inbox!(Contract, inbox);
+// inbox_of({"account_id": "alice.near", "from_index": 0, "limit": 10}) => [{"id": "3", "kind": "offer_received", ..}]
+// inbox_clear({"up_to": "3"}) => 3 cleared, their storage refunded to the senders
+```
+*/
+
+use super::*;
+
+use near_sdk::serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Notifications kept per account, the oldest are dropped.
+pub const MAX_INBOX_LENGTH: usize = 50;
+/// Bytes of the payload of a notification.
+pub const MAX_PAYLOAD_LENGTH: usize = 256;
+
+raw_storage_key!(pub enum StorageKey in storage_key::INBOX {
+    Inbox,
+});
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum NotificationKind {
+    OfferReceived,
+    AuctionOutbid,
+    ProposalCreated,
+    /// Anything else, named by the module.
+    Other(String),
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Notification {
+    pub id: U64,
+    pub kind: NotificationKind,
+    /// Paid the storage, refunded on clear.
+    pub sender_id: AccountId,
+    /// E.g. a JSON object with the token or the proposal.
+    pub payload: Option<String>,
+    /// Block timestamp (nanoseconds).
+    pub created_at: U64,
+}
+impl Notification {
+    /// Storage cost of the notification in the inbox.
+    #[inline]
+    pub fn storage_cost(&self) -> Balance {
+        protocol::storage_cost(self.try_to_vec().unwrap().len() as u64)
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct InboxRecord {
+    /// Id of the next notification.
+    pub next_id: u64,
+    /// From the oldest.
+    pub notifications: Vec<Notification>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Inbox {
+    pub records: collections::LookupMap<AccountId, InboxRecord>,
+}
+impl Default for Inbox {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Inbox {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            records: collections::LookupMap::new(StorageKey::Inbox),
+        }
+    }
+
+    /// Pushes a notification from the predecessor to `receiver_id`, returns the storage cost
+    /// the sender owes.
+    pub fn push(
+        &mut self,
+        receiver_id: &AccountId,
+        kind: NotificationKind,
+        payload: Option<String>,
+    ) -> Balance {
+        if let Some(payload) = payload.as_ref() {
+            require!(
+                payload.len() <= MAX_PAYLOAD_LENGTH,
+                format!("Payload can't be longer than {} bytes", MAX_PAYLOAD_LENGTH)
+            );
+        }
+        let mut record = self.records.get(receiver_id).unwrap_or_default();
+        let notification = Notification {
+            id: U64(record.next_id),
+            kind,
+            sender_id: env::predecessor_account_id(),
+            payload,
+            created_at: U64(env::block_timestamp()),
+        };
+        let cost = notification.storage_cost();
+        record.next_id += 1;
+        record.notifications.push(notification);
+        let dropped = record.notifications.len().saturating_sub(MAX_INBOX_LENGTH);
+        refund_senders(record.notifications.drain(..dropped));
+        self.records.insert(receiver_id, &record);
+        cost
+    }
+
+    /// [`Inbox::push`] paid from `deposit`, the rest is refunded to the predecessor.
+    pub fn push_paid(
+        &mut self,
+        receiver_id: &AccountId,
+        kind: NotificationKind,
+        payload: Option<String>,
+        deposit: Balance,
+    ) {
+        let cost = self.push(receiver_id, kind, payload);
+        require!(
+            cost <= deposit,
+            format!("Must attach {} yoctoNEAR to cover storage", cost)
+        );
+        let refund = deposit - cost;
+        if refund > 1 {
+            Promise::new(env::predecessor_account_id()).transfer(refund);
+        }
+    }
+
+    /// Page of the notifications of the account, from the oldest.
+    pub fn notifications(
+        &self,
+        account_id: &AccountId,
+        from_index: Option<U64>,
+        limit: Option<u64>,
+    ) -> Vec<Notification> {
+        self.records
+            .get(account_id)
+            .map(|record| record.notifications)
+            .unwrap_or_default()
+            .into_iter()
+            .skip(from_index.map_or(0, |index| index.0 as usize))
+            .take(limit.map_or(MAX_INBOX_LENGTH, |limit| limit as usize))
+            .collect()
+    }
+
+    #[inline]
+    pub fn len(&self, account_id: &AccountId) -> u64 {
+        self.records
+            .get(account_id)
+            .map_or(0, |record| record.notifications.len() as u64)
+    }
+
+    /// Clears the notifications of the account up to the id (all without one), refunding their
+    /// senders. Returns how many were cleared.
+    pub fn clear(&mut self, account_id: &AccountId, up_to: Option<u64>) -> u32 {
+        let mut record = match self.records.get(account_id) {
+            Some(record) => record,
+            None => return 0,
+        };
+        let cleared = record
+            .notifications
+            .iter()
+            .take_while(|notification| up_to.map_or(true, |up_to| notification.id.0 <= up_to))
+            .count();
+        refund_senders(record.notifications.drain(..cleared));
+        self.records.insert(account_id, &record);
+        cleared as u32
+    }
+}
+
+/// Refunds the storage of the notifications to their senders, one transfer per sender.
+fn refund_senders(notifications: impl Iterator<Item = Notification>) {
+    let mut refunds: HashMap<AccountId, Balance> = HashMap::new();
+    for notification in notifications {
+        let cost = notification.storage_cost();
+        *refunds.entry(notification.sender_id).or_default() += cost;
+    }
+    for (sender_id, refund) in refunds {
+        Promise::new(sender_id).transfer(refund);
+    }
+}
+
+#[macro_export]
+macro_rules! impl_inbox {
+    ($contract:ident, $inbox:ident $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_inbox!`.
+            pub const INBOX_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: Some("inbox"),
+                methods: &[
+                    $crate::manifest::Method::view("inbox_of"),
+                    $crate::manifest::Method::view("inbox_len"),
+                    $crate::manifest::Method::payable("inbox_clear"),
+                ],
+                storage: &[],
+            };
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Page of the notifications of the account, from the oldest.
+            pub fn inbox_of(
+                &self,
+                account_id: AccountId,
+                from_index: Option<U64>,
+                limit: Option<u64>,
+            ) -> Vec<$crate::inbox::Notification> {
+                self.$inbox.notifications(&account_id, from_index, limit)
+            }
+
+            pub fn inbox_len(&self, account_id: AccountId) -> U64 {
+                self.$inbox.len(&account_id).into()
+            }
+
+            /// Clears the notifications of the predecessor up to `up_to` (all by default),
+            /// their storage goes back to the senders.
+            #[payable]
+            pub fn inbox_clear(&mut self, up_to: Option<U64>) -> u32 {
+                near_sdk::assert_one_yocto();
+                self.$inbox
+                    .clear(&env::predecessor_account_id(), up_to.map(|up_to| up_to.0))
+            }
+        }
+    };
+}
+pub use impl_inbox;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_push_clear() {
+        run_vm(vm!("alice.near"));
+
+        let mut inbox = Inbox::new();
+        let bob: AccountId = "bob.near".parse().unwrap();
+        let cost = inbox.push(&bob, NotificationKind::OfferReceived, None);
+        assert!(cost > 0);
+        inbox.push(
+            &bob,
+            NotificationKind::Other("mention".into()),
+            Some("{}".into()),
+        );
+        assert_eq!(inbox.len(&bob), 2);
+        assert_eq!(
+            inbox.notifications(&bob, Some(U64(1)), None)[0].kind,
+            NotificationKind::Other("mention".into())
+        );
+
+        assert_eq!(inbox.clear(&bob, Some(0)), 1);
+        assert_eq!(inbox.notifications(&bob, None, None)[0].id, U64(1));
+        assert_eq!(get_transfers_to(&"alice.near".parse().unwrap()), cost);
+    }
+
+    #[test]
+    fn check_bounded() {
+        run_vm(vm!("alice.near"));
+
+        let mut inbox = Inbox::new();
+        let bob: AccountId = "bob.near".parse().unwrap();
+        for _ in 0..MAX_INBOX_LENGTH + 2 {
+            inbox.push(&bob, NotificationKind::AuctionOutbid, None);
+        }
+        let notifications = inbox.notifications(&bob, None, None);
+        assert_eq!(notifications.len(), MAX_INBOX_LENGTH);
+        assert_eq!(notifications[0].id, U64(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Payload can't be longer than 256 bytes")]
+    fn check_payload_length() {
+        run_vm(vm!("alice.near"));
+
+        Inbox::new().push(
+            &"bob.near".parse().unwrap(),
+            NotificationKind::ProposalCreated,
+            Some("a".repeat(MAX_PAYLOAD_LENGTH + 1)),
+        );
+    }
+}
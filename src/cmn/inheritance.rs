@@ -0,0 +1,401 @@
+#![cfg(feature = "inheritance")]
+#![allow(dead_code)]
+/*!
+Inheritance of the assets held by the contract, a dead-man switch.
+
+The owner designates beneficiaries of NEAR or FT held by the contract and an inactivity period,
+and proves being alive with `inheritance_ping()`. Once the owner has been inactive for the period,
+anyone can trigger `claim_inheritance()` to send every bequest to its beneficiary.
+
+# NOTES:
+  - Only the methods of this module count as activity, call `Inheritance::ping` from the other
+    owner methods to count them too.
+  - FT bequests are sent with `ft_transfer` from the contract, so beneficiaries must be registered
+    in the token. A failed transfer puts the bequest back, `claim_inheritance()` can retry it.
+  - At most [`MAX_BEQUESTS`] bequests, they're read on every call with the contract state.
+  - Events are logged as `EVENT_JSON:` with the `inheritance` standard.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    inheritance: inheritance::Inheritance,
+}
+
+const DAY: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            owner_id,
+            inheritance: inheritance::Inheritance::new(365 * DAY),
+        }
+    }
+}
+
+// impl_inheritance!(Contract, inheritance[, owner = <AccountId field; default: the contract itself>]);
+inheritance::impl_inheritance!(Contract, inheritance, owner = owner_id);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.inheritance_set(
+            vec![inheritance::Bequest {
+                beneficiary_id: accounts(1),
+                asset: asset::Asset::Near(U128(NEAR)),
+            }],
+            U64(DAY),
+        );
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_claim_inheritance() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(0)
+                .block_timestamp(DAY)
+                .predecessor_account_id(accounts(2)),
+        );
+        contract.claim_inheritance();
+        assert!(contract.inheritance().bequests.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "The owner is still active")]
+    fn test_claim_after_ping() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(0)
+                .block_timestamp(DAY / 2),
+        );
+        contract.inheritance_ping();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .block_timestamp(DAY)
+                .predecessor_account_id(accounts(2)),
+        );
+        contract.claim_inheritance();
+    }
+
+    #[test]
+    #[should_panic(expected = "The owner is still active")]
+    fn test_claim_never() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(vm.storage_usage(env::storage_usage()).block_timestamp(DAY));
+        contract.inheritance_set(contract.inheritance().bequests, U64(u64::MAX));
+        assert_eq!(contract.inheritance_claimable_at(), U64(u64::MAX));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(0)
+                .block_timestamp(2 * DAY)
+                .predecessor_account_id(accounts(2)),
+        );
+        contract.claim_inheritance();
+    }
+}
+```
+*/
+
+use super::*;
+
+use asset::Asset;
+
+use near_sdk::{
+    ext_contract,
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+
+pub const MAX_BEQUESTS: usize = 16;
+
+/// Gas for the `inheritance_resolve_transfer` callback.
+pub const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(10_000_000_000_000);
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Bequest {
+    pub beneficiary_id: AccountId,
+    pub asset: Asset,
+}
+
+/// Logs an `inheritance` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    events::emit(events::standards::INHERITANCE, event, data);
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Inheritance {
+    pub bequests: Vec<Bequest>,
+    /// Time (nanoseconds) without activity of the owner after which bequests can be claimed.
+    pub inactivity_period: U64,
+    /// Block timestamp (nanoseconds) of the last activity of the owner.
+    pub last_activity: U64,
+}
+impl Inheritance {
+    pub fn new(inactivity_period: u64) -> Self {
+        require!(inactivity_period > 0, "Inactivity period must be positive");
+        Self {
+            bequests: vec![],
+            inactivity_period: U64(inactivity_period),
+            last_activity: U64(env::block_timestamp()),
+        }
+    }
+
+    /// Records an activity of the owner.
+    #[inline]
+    pub fn ping(&mut self) {
+        self.last_activity = U64(env::block_timestamp());
+    }
+
+    /// Block timestamp (nanoseconds) from which bequests can be claimed.
+    /// Saturates, a huge period means never.
+    #[inline]
+    pub fn claimable_at(&self) -> u64 {
+        self.last_activity
+            .0
+            .saturating_add(self.inactivity_period.0)
+    }
+
+    /// Replaces the bequests and the inactivity period, as an activity of the owner.
+    pub fn set(&mut self, bequests: Vec<Bequest>, inactivity_period: u64) {
+        require!(
+            bequests.len() <= MAX_BEQUESTS,
+            format!("Can't have more than {} bequests", MAX_BEQUESTS)
+        );
+        require!(inactivity_period > 0, "Inactivity period must be positive");
+        self.bequests = bequests;
+        self.inactivity_period = U64(inactivity_period);
+        self.ping();
+        emit(
+            "inheritance_set",
+            serde_json::json!({
+                "bequests": self.bequests,
+                "inactivity_period": self.inactivity_period,
+            }),
+        );
+    }
+
+    /// Sends every bequest once the owner has been inactive for the period.
+    /// FT transfers are resolved in `inheritance_resolve_transfer`.
+    pub fn claim(&mut self) {
+        require!(
+            env::block_timestamp() >= self.claimable_at(),
+            "The owner is still active"
+        );
+        require!(!self.bequests.is_empty(), "Nothing to claim");
+
+        for bequest in std::mem::take(&mut self.bequests) {
+            emit("inheritance_claim", serde_json::json!(bequest));
+            let transfer = bequest.asset.transfer(
+                bequest.beneficiary_id.clone(),
+                Some("Inheritance".to_string()),
+            );
+            if bequest.asset.is_ft() {
+                transfer.then(
+                    ext_inheritance_resolver::ext(env::current_account_id())
+                        .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                        .inheritance_resolve_transfer(bequest),
+                );
+            }
+        }
+    }
+
+    /// Result of an FT bequest transfer.
+    pub fn resolve_transfer(&mut self, bequest: Bequest) -> bool {
+        let succeeded = matches!(env::promise_result(0), PromiseResult::Successful(_));
+        if !succeeded {
+            log!(
+                "Bequest to @{} failed, it's restored",
+                bequest.beneficiary_id
+            );
+            self.bequests.push(bequest);
+        }
+        succeeded
+    }
+}
+
+#[ext_contract(ext_inheritance_resolver)]
+pub trait InheritanceResolver {
+    fn inheritance_resolve_transfer(&mut self, bequest: Bequest) -> bool;
+}
+
+#[macro_export]
+macro_rules! impl_inheritance {
+    ($contract:ident, $inheritance:ident $(, owner = $owner:ident)? $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_inheritance!`.
+            pub const INHERITANCE_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: Some("inheritance"),
+                methods: &[
+                    $crate::manifest::Method::call("inheritance_ping").role("owner"),
+                    $crate::manifest::Method::payable("inheritance_set").role("owner"),
+                    $crate::manifest::Method::call("claim_inheritance"),
+                    $crate::manifest::Method::private("inheritance_resolve_transfer"),
+                    $crate::manifest::Method::view("inheritance"),
+                    $crate::manifest::Method::view("inheritance_claimable_at"),
+                ],
+                storage: &[],
+            };
+
+            fn assert_inheritance_owner(&self) {
+                let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                    .cloned()
+                    .unwrap_or_else(env::current_account_id);
+                require!(
+                    env::predecessor_account_id() == owner_id,
+                    "Only the owner can manage the inheritance"
+                );
+            }
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Heartbeat of the owner.
+            pub fn inheritance_ping(&mut self) {
+                self.assert_inheritance_owner();
+                self.$inheritance.ping();
+            }
+
+            /// Replaces the bequests and the inactivity period (nanoseconds).
+            #[payable]
+            pub fn inheritance_set(
+                &mut self,
+                bequests: Vec<$crate::inheritance::Bequest>,
+                inactivity_period: U64,
+            ) {
+                near_sdk::assert_one_yocto();
+                self.assert_inheritance_owner();
+                self.$inheritance.set(bequests, inactivity_period.0);
+            }
+
+            /// Anyone can trigger it once the owner has been inactive for the period.
+            pub fn claim_inheritance(&mut self) {
+                self.$inheritance.claim();
+            }
+
+            #[private]
+            pub fn inheritance_resolve_transfer(
+                &mut self,
+                bequest: $crate::inheritance::Bequest,
+            ) -> bool {
+                self.$inheritance.resolve_transfer(bequest)
+            }
+
+            pub fn inheritance(&self) -> $crate::inheritance::Inheritance {
+                self.$inheritance.clone()
+            }
+
+            pub fn inheritance_claimable_at(&self) -> U64 {
+                U64(self.$inheritance.claimable_at())
+            }
+        }
+    };
+}
+pub use impl_inheritance;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    const DAY: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+    fn ft_bequest() -> Bequest {
+        Bequest {
+            beneficiary_id: accounts(2),
+            asset: Asset::Ft {
+                token_id: "a.token".parse().unwrap(),
+                amount: U128(100),
+            },
+        }
+    }
+
+    fn inheritance() -> Inheritance {
+        let mut inheritance = Inheritance::new(DAY);
+        inheritance.set(
+            vec![
+                Bequest {
+                    beneficiary_id: accounts(1),
+                    asset: Asset::Near(U128(1_000)),
+                },
+                ft_bequest(),
+            ],
+            DAY,
+        );
+        inheritance
+    }
+
+    #[test]
+    fn check_claim() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut inheritance = inheritance();
+
+        run_vm(vm.block_timestamp(DAY).predecessor_account_id(accounts(3)));
+        inheritance.claim();
+        assert!(inheritance.bequests.is_empty());
+        assert_eq!(get_transfers_to(&accounts(1)), 1_000);
+        let receipts = get_created_receipts();
+        assert!(receipts
+            .iter()
+            .any(|receipt| receipt.receiver_id.as_str() == "a.token"));
+    }
+
+    #[test]
+    fn check_failed_ft_bequest_restored() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut inheritance = inheritance();
+
+        run_vm(vm.block_timestamp(DAY));
+        inheritance.claim();
+        run_vm_with_results(&vm, vec![PromiseResult::Failed]);
+        assert!(!inheritance.resolve_transfer(ft_bequest()));
+        assert_eq!(inheritance.bequests, [ft_bequest()]);
+    }
+
+    #[test]
+    fn check_claimable_at_saturates() {
+        let mut vm = vm!(accounts(0));
+        run_vm(vm.block_timestamp(DAY));
+        let inheritance = Inheritance::new(u64::MAX);
+
+        assert_eq!(inheritance.claimable_at(), u64::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "The owner is still active")]
+    fn check_claim_active() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut inheritance = inheritance();
+
+        run_vm(vm.block_timestamp(DAY - 1));
+        inheritance.claim();
+    }
+}
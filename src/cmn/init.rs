@@ -0,0 +1,368 @@
+#![allow(dead_code)]
+/*!
+Init pipeline of contracts assembled from several components.
+
+`init_pipeline!` generates the init args of the contract, one JSON object with the owner and the
+args of every component, and an `#[init] new(args)` that checks the components fit together
+before building them:
+  - owner consistency: a component given an owner must be given the contract owner.
+  - storage keys: no component writes under a prefix of the keys of another one.
+
+It then logs a single `init` event with the owner and the components.
+
+# NOTES:
+  - Every field of the contract is a component (or the owner field), a component is a type
+    implementing [`Component`]: `ft::FungibleToken`, `nft::NonFungibleToken`,
+    `allowlist::Allowlist`, `inbox::Inbox` and `otc::Otc`, the last two with their features.
+  - `ft::FungibleToken` and `nft::NonFungibleToken` both write under the key `[0]` (their
+    `BorshStorageKey` enums write the variant index), the pipeline rejects them together unless
+    the `ft` args have a `namespace`, see [`hybrid`](super::hybrid).
+  - Events are logged as `EVENT_JSON:` with the `init` standard.
+
+# EXAMPLE:
+Needs the `ft` and `inbox` features.
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    ft: ft::FungibleToken,
+    allowlist: allowlist::Allowlist,
+    inbox: inbox::Inbox,
+}
+
+// init_pipeline!(Contract, <init args struct to generate>[, owner = <AccountId field>], { <field>: <Component type>, .. });
+init_pipeline!(Contract, InitArgs, owner = owner_id, {
+    ft: ft::FungibleToken,
+    allowlist: allowlist::Allowlist,
+    inbox: inbox::Inbox,
+});
+ft::impl_fungible_token_contract!(Contract, ft);
+// new({"args": {"owner_id": "owner.near", "ft": {"total_supply": "1000", ..}, "allowlist": ["alice.near"]}})
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    use ft::core::FungibleTokenCore;
+
+    #[test]
+    fn test_init_pipeline() {
+        run_vm(vm!(accounts(0)));
+        let contract = Contract::new(InitArgs {
+            owner_id: Some(accounts(1)),
+            ft: ft::InitArgs {
+                total_supply: U128(1_000),
+                name: "Composed".to_string(),
+                symbol: "CMP".to_string(),
+                ..Default::default()
+            },
+            allowlist: vec![accounts(2)],
+            ..Default::default()
+        });
+        assert_eq!(contract.owner_id, accounts(1));
+        // the owner is passed down
+        assert_eq!(contract.ft_balance_of(accounts(1)), U128(1_000));
+        assert!(contract.allowlist.contains(&accounts(2)));
+        assert!(get_logs()
+            .last()
+            .unwrap()
+            .contains(r#""components":["ft","allowlist","inbox"]"#));
+    }
+
+    #[test]
+    #[should_panic(expected = "Owner of `ft` must be the contract owner")]
+    fn test_init_pipeline_owner() {
+        run_vm(vm!(accounts(0)));
+        Contract::new(InitArgs {
+            owner_id: Some(accounts(1)),
+            ft: ft::InitArgs {
+                owner_id: Some(accounts(2)),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::serde::{de::DeserializeOwned, Serialize};
+
+/// A part of a contract built by `init_pipeline!`.
+pub trait Component: Sized {
+    /// Args of the component in the init args.
+    type Args: Serialize + DeserializeOwned + Default;
+
+    /// Prefixes of every storage key the component writes, with these args.
+    fn storage_keys(args: &Self::Args) -> Vec<Vec<u8>>;
+
+    /// Owner given in the args, if any.
+    #[inline]
+    fn owner_of(_args: &Self::Args) -> Option<&AccountId> {
+        None
+    }
+
+    fn init(owner_id: &AccountId, args: Self::Args) -> Self;
+}
+
+/// Checks of `init_pipeline!`, see the module docs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pipeline {
+    pub owner_id: AccountId,
+    /// Storage key prefixes with the component writing them.
+    pub storage_keys: Vec<(Vec<u8>, &'static str)>,
+    pub components: Vec<&'static str>,
+}
+impl Pipeline {
+    #[inline]
+    pub fn new(owner_id: AccountId) -> Self {
+        Self {
+            owner_id,
+            storage_keys: vec![],
+            components: vec![],
+        }
+    }
+
+    /// Adds the component `name`, panics if it doesn't fit with the previous ones.
+    pub fn check<C: Component>(&mut self, name: &'static str, args: &C::Args) {
+        if let Some(owner_id) = C::owner_of(args) {
+            require!(
+                owner_id == &self.owner_id,
+                format!("Owner of `{}` must be the contract owner", name)
+            );
+        }
+        for key in C::storage_keys(args) {
+            let overlapped = self
+                .storage_keys
+                .iter()
+                .find(|(other, _)| other.starts_with(&key) || key.starts_with(other));
+            if let Some((other, other_name)) = overlapped {
+                require!(
+                    false,
+                    format!(
+                        "Storage key {:?} of `{}` overlaps {:?} of `{}`",
+                        key, name, other, other_name
+                    )
+                );
+            }
+            self.storage_keys.push((key, name));
+        }
+        self.components.push(name);
+    }
+
+    /// Logs the `init` event.
+    pub fn emit(&self) {
+        events::emit(
+            events::standards::INIT,
+            "init",
+            near_sdk::serde_json::json!({
+                "owner_id": self.owner_id,
+                "components": self.components,
+            }),
+        );
+    }
+}
+
+#[inline]
+fn key(key: impl near_sdk::IntoStorageKey) -> Vec<u8> {
+    key.into_storage_key()
+}
+
+#[cfg(feature = "ft")]
+impl Component for ft::FungibleToken {
+    type Args = ft::InitArgs;
+
+    fn storage_keys(args: &Self::Args) -> Vec<Vec<u8>> {
+        let namespace = args.namespace.as_deref().unwrap_or_default().as_bytes();
+        let mut keys = vec![
+            ft::StorageKey::Token.in_namespace(namespace),
+            ft::StorageKey::Metadata.in_namespace(namespace),
+        ];
+        if args.lockups {
+            keys.push(ft::lockup::StorageKey::Lockups.in_namespace(namespace));
+        }
+        keys
+    }
+
+    #[inline]
+    fn owner_of(args: &Self::Args) -> Option<&AccountId> {
+        args.owner_id.as_ref()
+    }
+
+    fn init(owner_id: &AccountId, args: Self::Args) -> Self {
+        Self::from_args(ft::InitArgs {
+            owner_id: Some(owner_id.clone()),
+            ..args
+        })
+    }
+}
+
+#[cfg(feature = "nft")]
+impl Component for nft::NonFungibleToken {
+    type Args = nft::InitArgs;
+
+    fn storage_keys(args: &Self::Args) -> Vec<Vec<u8>> {
+        use nft::StorageKey::*;
+        let mut keys = vec![
+            key(TokenMetadata),
+            key(Token),
+            key(Metadata),
+            key(Enumeration),
+            key(Approval),
+            key(Operators),
+            key(TokenExtras),
+            key(OwnerCounts),
+            key(Provenance),
+            key(ApprovalExpiries),
+        ];
+        if args.attributes {
+            keys.push(key(nft::attributes::StorageKey::Attributes));
+            keys.push(key(nft::attributes::StorageKey::AttributeTokens));
+        }
+        keys
+    }
+
+    #[inline]
+    fn owner_of(args: &Self::Args) -> Option<&AccountId> {
+        args.owner_id.as_ref()
+    }
+
+    fn init(owner_id: &AccountId, args: Self::Args) -> Self {
+        Self::from_args(nft::InitArgs {
+            owner_id: Some(owner_id.clone()),
+            ..args
+        })
+    }
+}
+
+/// The args are the first accounts, at most `MAX_ALLOWLIST_BATCH`, paid by the contract.
+impl Component for allowlist::Allowlist {
+    type Args = Vec<AccountId>;
+
+    #[inline]
+    fn storage_keys(_args: &Self::Args) -> Vec<Vec<u8>> {
+        vec![key(allowlist::StorageKey::Allowlist)]
+    }
+
+    fn init(_owner_id: &AccountId, args: Self::Args) -> Self {
+        let mut allowlist = Self::default();
+        allowlist.import(&args, allowlist::MAX_ALLOWLIST_BATCH);
+        allowlist
+    }
+}
+
+#[cfg(feature = "inbox")]
+impl Component for inbox::Inbox {
+    type Args = ();
+
+    #[inline]
+    fn storage_keys(_args: &Self::Args) -> Vec<Vec<u8>> {
+        vec![key(inbox::StorageKey::Inbox)]
+    }
+
+    #[inline]
+    fn init(_owner_id: &AccountId, _args: Self::Args) -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "otc")]
+impl Component for otc::Otc {
+    type Args = ();
+
+    #[inline]
+    fn storage_keys(_args: &Self::Args) -> Vec<Vec<u8>> {
+        vec![
+            key(otc::StorageKey::OtcOrders),
+            key(otc::StorageKey::OtcUnclaimed),
+        ]
+    }
+
+    #[inline]
+    fn init(_owner_id: &AccountId, _args: Self::Args) -> Self {
+        Self::new()
+    }
+}
+
+#[macro_export]
+macro_rules! init_pipeline {
+    ($contract:ident, $args:ident $(, owner = $owner:ident)?, { $($field:ident: $component:ty),+ $(,)? } $(,)?) => {
+        /// Init args of the contract, every field has a default.
+        #[derive(near_sdk::serde::Serialize, near_sdk::serde::Deserialize, Default)]
+        #[serde(crate = "near_sdk::serde", default)]
+        pub struct $args {
+            /// Defaults to the predecessor.
+            pub owner_id: Option<AccountId>,
+            $(pub $field: <$component as $crate::init::Component>::Args,)+
+        }
+
+        #[near_bindgen]
+        impl $contract {
+            #[init]
+            pub fn new(args: $args) -> Self {
+                require_init!();
+                let owner_id = args.owner_id.unwrap_or_else(env::predecessor_account_id);
+                let mut pipeline = $crate::init::Pipeline::new(owner_id.clone());
+                $(pipeline.check::<$component>(stringify!($field), &args.$field);)+
+                let this = Self {
+                    $($owner: owner_id.clone(),)?
+                    $($field: <$component as $crate::init::Component>::init(&owner_id, args.$field),)+
+                };
+                pipeline.emit();
+                this
+            }
+        }
+    };
+}
+pub use init_pipeline;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "Storage key [32] of `b` overlaps [32] of `a`")]
+    fn check_storage_keys() {
+        run_vm(vm!("alice.near"));
+
+        let mut pipeline = Pipeline::new("alice.near".parse().unwrap());
+        pipeline.check::<allowlist::Allowlist>("a", &vec![]);
+        pipeline.check::<allowlist::Allowlist>("b", &vec![]);
+    }
+
+    #[cfg(all(feature = "ft", feature = "nft"))]
+    #[test]
+    #[should_panic(expected = "Storage key [0] of `nft` overlaps [0] of `ft`")]
+    fn check_ft_nft_keys() {
+        run_vm(vm!("alice.near"));
+
+        let mut pipeline = Pipeline::new("alice.near".parse().unwrap());
+        pipeline.check::<ft::FungibleToken>("ft", &Default::default());
+        pipeline.check::<nft::NonFungibleToken>("nft", &Default::default());
+    }
+
+    #[cfg(all(feature = "ft", feature = "nft"))]
+    #[test]
+    fn check_ft_namespace() {
+        run_vm(vm!("alice.near"));
+
+        let mut pipeline = Pipeline::new("alice.near".parse().unwrap());
+        let ft_args = ft::InitArgs {
+            namespace: Some("ft".to_string()),
+            lockups: true,
+            ..Default::default()
+        };
+        pipeline.check::<ft::FungibleToken>("ft", &ft_args);
+        pipeline.check::<nft::NonFungibleToken>("nft", &Default::default());
+        assert_eq!(pipeline.storage_keys[0], (b"ft\0".to_vec(), "ft"));
+        assert_eq!(pipeline.storage_keys[2], (b"ft\x1d".to_vec(), "ft"));
+    }
+}
@@ -0,0 +1,400 @@
+#![cfg(feature = "keeper")]
+#![allow(dead_code)]
+/*!
+Registry of recurring tasks run by keepers, E.g. `emissions_poke` every day.
+
+A task is a method of the contract itself called at most once per `interval`. Anyone can call
+`execute_due_tasks(limit)`, which calls every due task and pays the caller the `reward` of each
+from the budget of the task.
+
+# NOTES:
+  - Tasks are called by the contract itself, so their methods can be `#[private]`.
+  - A task only runs while its budget covers its reward, anyone can top it up with `keeper_fund_task`.
+  - Due tasks which don't fit in the prepaid gas are left for the next call.
+  - The owner registers and removes tasks, the budget of a removed task goes back to the owner.
+  - Events are logged as `EVENT_JSON:` with the `keeper` standard.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    keeper: keeper::Keeper,
+    ticks: u64,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            owner_id,
+            keeper: keeper::Keeper::new(),
+            ticks: 0,
+        }
+    }
+
+    #[private]
+    pub fn tick(&mut self) {
+        self.ticks += 1;
+    }
+}
+
+// impl_keeper!(Contract, keeper[, owner = <AccountId field; default: the contract itself>]);
+keeper::impl_keeper!(Contract, keeper, owner = owner_id);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+    const HOUR: u64 = 60 * 60 * 1_000_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(NEAR));
+        contract.keeper_register_task(
+            "tick".to_string(),
+            keeper::TaskArgs {
+                method_name: "tick".to_string(),
+                args: None,
+                interval: U64(HOUR),
+                gas: U64(10_000_000_000_000),
+                reward: U128(NEAR / 100),
+            },
+        );
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_execute_due_tasks() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(0)
+                .block_timestamp(HOUR)
+                .predecessor_account_id(accounts(1)),
+        );
+        assert_eq!(contract.execute_due_tasks(None), vec!["tick".to_string()]);
+        assert_eq!(get_transfers_to(&accounts(1)), NEAR / 100);
+
+        // not due again before an hour
+        run_vm(vm.storage_usage(env::storage_usage()).block_timestamp(2 * HOUR - 1));
+        assert!(contract.execute_due_tasks(None).is_empty());
+        assert_eq!(get_transfers_to(&accounts(1)), 0);
+    }
+
+    #[test]
+    fn test_remove_task() {
+        let (mut vm, mut contract) = setup();
+
+        let budget = contract.keeper_task("tick".to_string()).unwrap().budget.0;
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.keeper_remove_task("tick".to_string());
+        assert!(get_transfers_to(&accounts(0)) > budget);
+        assert!(contract.keeper_tasks(None, None).is_empty());
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::{
+    collections::UnorderedMap,
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+
+pub const MAX_TASKS: u64 = 32;
+pub const MAX_TASK_NAME_LENGTH: usize = 64;
+/// Gas kept for `execute_due_tasks` itself.
+pub const GAS_FOR_EXECUTE: Gas = Gas(10_000_000_000_000);
+
+raw_storage_key!(pub enum StorageKey in storage_key::KEEPER {
+    Tasks,
+});
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TaskArgs {
+    /// Method of the contract itself.
+    pub method_name: String,
+    /// Arguments of the method, none by default.
+    pub args: Option<Base64VecU8>,
+    /// Minimum nanoseconds between two runs.
+    pub interval: U64,
+    /// Gas attached to the call.
+    pub gas: U64,
+    /// yoctoNEAR paid to the keeper per run.
+    pub reward: U128,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Task {
+    pub method_name: String,
+    pub args: Base64VecU8,
+    pub interval: U64,
+    pub gas: U64,
+    pub reward: U128,
+    /// yoctoNEAR left to pay the rewards.
+    pub budget: U128,
+    /// Block timestamp (nanoseconds), 0 before the first run.
+    pub last_run_at: U64,
+}
+impl Task {
+    #[inline]
+    pub fn is_due(&self, now: u64) -> bool {
+        now >= self.last_run_at.0 + self.interval.0 && self.budget.0 >= self.reward.0
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TaskView {
+    pub name: String,
+    #[serde(flatten)]
+    pub task: Task,
+}
+
+/// Logs a `keeper` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    events::emit(events::standards::KEEPER, event, data);
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Keeper {
+    pub tasks: UnorderedMap<String, Task>,
+}
+impl Default for Keeper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Keeper {
+    pub fn new() -> Self {
+        Self {
+            tasks: UnorderedMap::new(StorageKey::Tasks),
+        }
+    }
+
+    /// Registers or replaces the task, the attached deposit less the storage funds its budget.
+    pub fn register(&mut self, name: String, args: TaskArgs) {
+        require!(
+            !name.is_empty() && name.len() <= MAX_TASK_NAME_LENGTH,
+            format!("Task name must be 1 to {} bytes", MAX_TASK_NAME_LENGTH)
+        );
+        require!(args.interval.0 > 0, "Interval must be positive");
+        let previous = self.tasks.get(&name);
+        let previous_budget = previous.as_ref().map_or(0, |previous| previous.budget.0);
+        require!(
+            previous.is_some() || self.tasks.len() < MAX_TASKS,
+            format!("Can't have more than {} tasks", MAX_TASKS)
+        );
+        let initial_storage_usage = env::storage_usage();
+        let mut task = Task {
+            method_name: args.method_name,
+            args: args.args.unwrap_or_else(|| Base64VecU8(b"{}".to_vec())),
+            interval: args.interval,
+            gas: args.gas,
+            reward: args.reward,
+            budget: U128(0),
+            last_run_at: previous.map_or(U64(0), |previous| previous.last_run_at),
+        };
+        // the budget has the same size whatever it is
+        self.tasks.insert(&name, &task);
+        let storage_cost =
+            protocol::storage_cost(env::storage_usage().saturating_sub(initial_storage_usage));
+        let attached = env::attached_deposit();
+        require!(
+            attached >= storage_cost,
+            format!("Must attach {} yoctoNEAR to cover storage", storage_cost)
+        );
+        task.budget = U128(previous_budget + attached - storage_cost);
+        self.tasks.insert(&name, &task);
+
+        emit(
+            "task_register",
+            serde_json::json!({ "name": name, "method_name": task.method_name }),
+        );
+    }
+
+    /// Adds the attached deposit to the budget of the task.
+    pub fn fund(&mut self, name: &String) -> Balance {
+        let mut task = self.tasks.get(name).expect("Task not found");
+        task.budget.0 += env::attached_deposit();
+        self.tasks.insert(name, &task);
+        task.budget.0
+    }
+
+    /// Removes the task, returns its budget and storage to be refunded.
+    pub fn remove(&mut self, name: &String) -> Balance {
+        let initial_storage_usage = env::storage_usage();
+        let task = self.tasks.remove(name).expect("Task not found");
+        let storage_refund =
+            protocol::storage_cost(initial_storage_usage.saturating_sub(env::storage_usage()));
+
+        emit("task_remove", serde_json::json!({ "name": name }));
+        task.budget.0 + storage_refund
+    }
+
+    /// Calls at most `limit` due tasks, returns their names and the total reward.
+    pub fn execute_due(&mut self, limit: usize) -> (Vec<String>, Balance) {
+        let now = env::block_timestamp();
+        let mut gas_left = env::prepaid_gas() - env::used_gas();
+        gas_left = Gas(gas_left.0.saturating_sub(GAS_FOR_EXECUTE.0));
+        let due: Vec<_> = self
+            .tasks
+            .iter()
+            .filter(|(_, task)| task.is_due(now))
+            .take(limit)
+            .collect();
+        let mut executed = vec![];
+        let mut reward = 0;
+        for (name, mut task) in due {
+            if task.gas.0 > gas_left.0 {
+                continue;
+            }
+            gas_left = Gas(gas_left.0 - task.gas.0);
+            task.last_run_at = U64(now);
+            task.budget.0 -= task.reward.0;
+            reward += task.reward.0;
+            self.tasks.insert(&name, &task);
+            Promise::new(env::current_account_id()).function_call(
+                task.method_name.clone(),
+                task.args.0.clone(),
+                0,
+                Gas(task.gas.0),
+            );
+            executed.push(name);
+        }
+        if !executed.is_empty() {
+            emit(
+                "task_execute",
+                serde_json::json!({
+                    "keeper_id": env::predecessor_account_id(),
+                    "names": executed,
+                    "reward": U128(reward),
+                }),
+            );
+        }
+        (executed, reward)
+    }
+
+    pub fn views(&self, from_index: Option<U64>, limit: Option<u64>) -> Vec<TaskView> {
+        self.tasks
+            .iter()
+            .skip(from_index.map_or(0, |from_index| from_index.0 as usize))
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .map(|(name, task)| TaskView { name, task })
+            .collect()
+    }
+}
+
+#[macro_export]
+macro_rules! impl_keeper {
+    ($contract:ident, $keeper:ident $(, owner = $owner:ident)? $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_keeper!`.
+            pub const KEEPER_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: Some("keeper"),
+                methods: &[
+                    $crate::manifest::Method::payable("keeper_register_task").role("owner"),
+                    $crate::manifest::Method::payable("keeper_fund_task"),
+                    $crate::manifest::Method::payable("keeper_remove_task").role("owner"),
+                    $crate::manifest::Method::call("execute_due_tasks"),
+                    $crate::manifest::Method::view("keeper_task"),
+                    $crate::manifest::Method::view("keeper_tasks"),
+                    $crate::manifest::Method::view("keeper_due_tasks"),
+                ],
+                storage: &[
+                    // a task of 64 bytes name and method name with 512 bytes of arguments
+                    $crate::manifest::StorageEstimate::new("keeper_register_task", 800),
+                ],
+            };
+
+            fn assert_keeper_owner(&self) {
+                let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                    .cloned()
+                    .unwrap_or_else(env::current_account_id);
+                require!(
+                    env::predecessor_account_id() == owner_id,
+                    "Only the owner can manage the tasks"
+                );
+            }
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Registers or replaces the task, the attached deposit less the storage funds its budget.
+            #[payable]
+            pub fn keeper_register_task(&mut self, name: String, task: $crate::keeper::TaskArgs) {
+                #[cfg(feature = "decommission")]
+                $crate::decommission::require_active();
+                self.assert_keeper_owner();
+                self.$keeper.register(name, task)
+            }
+
+            /// Anyone can add the attached deposit to the budget of the task, returns the budget.
+            #[payable]
+            pub fn keeper_fund_task(&mut self, name: String) -> U128 {
+                #[cfg(feature = "decommission")]
+                $crate::decommission::require_active();
+                U128(self.$keeper.fund(&name))
+            }
+
+            /// Removes the task, its budget and storage go back to the owner.
+            #[payable]
+            pub fn keeper_remove_task(&mut self, name: String) {
+                near_sdk::assert_one_yocto();
+                self.assert_keeper_owner();
+                let refund = self.$keeper.remove(&name);
+                Promise::new(env::predecessor_account_id()).transfer(refund + 1);
+            }
+
+            /// Anyone can call at most `limit` due tasks and be paid their rewards, returns their names.
+            pub fn execute_due_tasks(&mut self, limit: Option<u64>) -> Vec<String> {
+                let (executed, reward) = self
+                    .$keeper
+                    .execute_due(limit.map_or(usize::MAX, |limit| limit as usize));
+                if reward > 0 {
+                    Promise::new(env::predecessor_account_id()).transfer(reward);
+                }
+                executed
+            }
+
+            pub fn keeper_task(&self, name: String) -> Option<$crate::keeper::Task> {
+                self.$keeper.tasks.get(&name)
+            }
+
+            pub fn keeper_tasks(
+                &self,
+                from_index: Option<U64>,
+                limit: Option<u64>,
+            ) -> Vec<$crate::keeper::TaskView> {
+                self.$keeper.views(from_index, limit)
+            }
+
+            /// Names of the tasks which are due now.
+            pub fn keeper_due_tasks(&self) -> Vec<String> {
+                let now = env::block_timestamp();
+                self.$keeper
+                    .tasks
+                    .iter()
+                    .filter(|(_, task)| task.is_due(now))
+                    .map(|(name, _)| name)
+                    .collect()
+            }
+        }
+    };
+}
+pub use impl_keeper;
@@ -0,0 +1,255 @@
+#![allow(dead_code)]
+/*!
+Leveled, structured logs.
+
+`debug!`, `info!` and `warn!` log `key=value` pairs (logfmt) when their level is at or above the
+level stored in the contract, so the owner can silence the debug logs of a mainnet contract
+without deploying a different build.
+
+# NOTES:
+  - The level lives under its own storage key like the `features` flags,
+    [`Level::Info`] until it's set. Every enabled log reads it.
+  - Values are formatted with `Display`, and quoted when they're empty or have spaces, quotes
+    or `=`.
+  - Like `log!`, they're compiled out of release builds with the `strip-logs` feature.
+    Events don't go through them.
+
+# EXAMPLE:
+```
+# use cmn::*;
+// impl_log_level!(Contract[, owner = <AccountId field; default: the contract itself>]);
+impl_log_level!(Contract, owner = owner_id);
+// set_log_level({"level": "warn"}) => debug! and info! don't log anymore
+
+// <debug!|info!|warn!>(<message>[, <key> = <value>, ..]);
+info!("Token minted", token_id = token_id, owner_id = owner_id);
+// level=info msg="Token minted" token_id=0 owner_id=alice.near
+```
+*/
+
+use super::*;
+
+use near_sdk::serde::{Deserialize, Serialize};
+use std::fmt::{Display, Write};
+
+/// Storage key of the level.
+pub const LOG_LEVEL_KEY: &[u8] = b"LOG_LEVEL";
+
+#[repr(u8)]
+#[derive(
+    BorshDeserialize,
+    BorshSerialize,
+    Serialize,
+    Deserialize,
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub enum Level {
+    Debug,
+    #[default]
+    Info,
+    Warn,
+}
+impl Level {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Level::Debug => "debug",
+            Level::Info => "info",
+            Level::Warn => "warn",
+        }
+    }
+
+    /// The stored level, [`Level::Info`] by default.
+    #[inline]
+    pub fn load() -> Self {
+        env::storage_read(LOG_LEVEL_KEY)
+            .map(|bytes| Self::try_from_slice(&bytes).unwrap_or_else(|_| env::abort()))
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn save(&self) {
+        env::storage_write(LOG_LEVEL_KEY, &self.try_to_vec().unwrap());
+    }
+
+    /// Back to the default level.
+    #[inline]
+    pub fn clear() {
+        env::storage_remove(LOG_LEVEL_KEY);
+    }
+}
+
+/// Whether logs of `level` are logged.
+#[inline]
+pub fn enabled(level: Level) -> bool {
+    level >= Level::load()
+}
+
+/// Quotes and escapes `value` unless it's one plain word, so a value can't forge fields or lines.
+fn write_value(line: &mut String, value: &str) {
+    if value.is_empty()
+        || value.contains(|c: char| c.is_whitespace() || c.is_control() || c == '"' || c == '=')
+    {
+        write!(line, "{:?}", value).unwrap();
+    } else {
+        line.push_str(value);
+    }
+}
+
+/// `level=<level> msg=<message> <key>=<value> ..`
+pub fn format(level: Level, message: &dyn Display, fields: &[(&str, &dyn Display)]) -> String {
+    let mut line = format!("level={} msg=", level.as_str());
+    write_value(&mut line, &message.to_string());
+    for (key, value) in fields {
+        write!(line, " {}=", key).unwrap();
+        write_value(&mut line, &value.to_string());
+    }
+    line
+}
+
+#[macro_export]
+macro_rules! structured_log {
+    ($level:ident, $message:expr $(, $key:ident = $value:expr)* $(,)?) => {{
+        #[cfg(not(all(feature = "strip-logs", not(debug_assertions))))]
+        if $crate::logger::enabled($crate::logger::Level::$level) {
+            near_sdk::env::log_str(&$crate::logger::format(
+                $crate::logger::Level::$level,
+                &$message,
+                &[$((stringify!($key), &$value)),*],
+            ));
+        }
+        // never formatted, keeps the arguments used
+        #[cfg(all(feature = "strip-logs", not(debug_assertions)))]
+        if false {
+            let _ = (&$message, $(&$value),*);
+        }
+    }};
+}
+pub use structured_log;
+
+/// Logs at [`Level::Debug`], see [`logger`](self).
+#[macro_export]
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        $crate::logger::structured_log!(Debug, $($arg)*)
+    };
+}
+pub use debug;
+
+/// Logs at [`Level::Info`], see [`logger`](self).
+#[macro_export]
+macro_rules! info {
+    ($($arg:tt)*) => {
+        $crate::logger::structured_log!(Info, $($arg)*)
+    };
+}
+pub use info;
+
+/// Logs at [`Level::Warn`], see [`logger`](self).
+#[macro_export]
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        $crate::logger::structured_log!(Warn, $($arg)*)
+    };
+}
+// only at the crate root, a `pub use warn;` would be ambiguous with the `#[warn]` attribute
+
+#[macro_export]
+macro_rules! impl_log_level {
+        ($contract:ident $(, owner = $owner:ident)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_log_level!`.
+                pub const LOG_LEVEL_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: None,
+                    methods: &[
+                        $crate::manifest::Method::payable("set_log_level").role("owner"),
+                        $crate::manifest::Method::view("log_level"),
+                    ],
+                    storage: &[],
+                };
+            }
+            #[near_bindgen]
+            impl $contract {
+                /// `None` goes back to the default level.
+                #[payable]
+                pub fn set_log_level(&mut self, level: Option<$crate::logger::Level>) {
+                    near_sdk::assert_one_yocto();
+                    let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                        .cloned()
+                        .unwrap_or_else(env::current_account_id);
+                    require!(
+                        env::predecessor_account_id() == owner_id,
+                        "Only the owner can set the log level"
+                    );
+                    match level {
+                        Some(level) => level.save(),
+                        None => $crate::logger::Level::clear(),
+                    }
+                }
+
+                pub fn log_level(&self) -> $crate::logger::Level {
+                    $crate::logger::Level::load()
+                }
+            }
+        };
+    }
+pub use impl_log_level;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_format() {
+        assert_eq!(
+            format(
+                Level::Info,
+                &"Token minted",
+                &[("token_id", &0), ("memo", &""), ("owner_id", &"alice.near")]
+            ),
+            r#"level=info msg="Token minted" token_id=0 memo="" owner_id=alice.near"#
+        );
+        assert_eq!(
+            format(Level::Warn, &"a=\"b\"", &[]),
+            r#"level=warn msg="a=\"b\"""#
+        );
+        // a value can't start another line or field
+        assert_eq!(
+            format(
+                Level::Info,
+                &"ok",
+                &[("memo", &"x\nlevel=error"), ("to", &"bob\tmallory\u{7}")]
+            ),
+            r#"level=info msg=ok memo="x\nlevel=error" to="bob\tmallory\u{7}""#
+        );
+    }
+
+    #[test]
+    fn check_levels() {
+        run_vm(vm!("alice.near"));
+
+        let amount = 5;
+        debug!("Skipped");
+        info!("Transferred", amount = amount);
+        let mut logs = logs!["level=info msg=Transferred amount=5"];
+        logs.assert();
+
+        Level::Warn.save();
+        info!("Skipped");
+        warn!("Low balance", amount = amount);
+        logs.push("level=warn msg=\"Low balance\" amount=5");
+        logs.assert();
+
+        Level::clear();
+        assert_eq!(Level::load(), Level::Info);
+    }
+}
@@ -0,0 +1,270 @@
+#![allow(dead_code)]
+/*!
+Deployment manifest for deploy scripts.
+
+The impl macros export a [`Component`] for the methods they generate
+(E.g. `Contract::FT_MANIFEST`, `Contract::NFT_MANIFEST`), so the contract only lists its own methods.
+
+# NOTES:
+  - `make manifest` prints the manifest of `Contract::manifest()` to `./build/manifest.json`.
+  - Storage estimates are worst cases (64 chars account ids) at the current storage byte cost.
+  - `impl_method_acl!` exposes the methods of the manifest as a `method_acl()` view.
+
+# EXAMPLE:
+```
+# use cmn::*;
+impl Contract {
+    pub fn manifest() -> manifest::Manifest {
+        manifest::Manifest::new()
+            .init("new", near_sdk::serde_json::json!({ "owner_id": "", "total_supply": "0" }))
+            .component(Self::FT_MANIFEST)
+            .method(manifest::Method::view("get_solution"))
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::serde::Serialize;
+use near_sdk::serde_json::{self, Value};
+
+/// Storage staking cost per byte (1E19 yoctoNEAR).
+pub const STORAGE_BYTE_COST: Balance = 10_000_000_000_000_000_000;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde", rename_all = "lowercase")]
+pub enum MethodKind {
+    View,
+    Call,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Method {
+    pub name: &'static str,
+    pub kind: MethodKind,
+    pub payable: bool,
+    pub private: bool,
+    /// Role the predecessor needs, E.g. `"owner"`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<&'static str>,
+}
+impl Method {
+    #[inline]
+    pub const fn view(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: MethodKind::View,
+            payable: false,
+            private: false,
+            role: None,
+        }
+    }
+    #[inline]
+    pub const fn call(name: &'static str) -> Self {
+        Self {
+            name,
+            kind: MethodKind::Call,
+            payable: false,
+            private: false,
+            role: None,
+        }
+    }
+    #[inline]
+    pub const fn payable(name: &'static str) -> Self {
+        Self {
+            payable: true,
+            ..Self::call(name)
+        }
+    }
+    #[inline]
+    pub const fn private(name: &'static str) -> Self {
+        Self {
+            private: true,
+            ..Self::call(name)
+        }
+    }
+    /// Only callable by the accounts of `role`, E.g. `Method::payable("set_x").role("owner")`.
+    #[inline]
+    pub const fn role(self, role: &'static str) -> Self {
+        Self {
+            role: Some(role),
+            ..self
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageEstimate {
+    pub name: &'static str,
+    pub bytes: u64,
+    pub deposit: U128,
+}
+impl StorageEstimate {
+    #[inline]
+    pub const fn new(name: &'static str, bytes: u64) -> Self {
+        Self {
+            name,
+            bytes,
+            deposit: U128(bytes as Balance * STORAGE_BYTE_COST),
+        }
+    }
+}
+
+/// Manifest entries of an impl macro.
+#[derive(Debug, Clone, Copy)]
+pub struct Component {
+    /// Cargo feature the component requires.
+    pub feature: Option<&'static str>,
+    pub methods: &'static [Method],
+    pub storage: &'static [StorageEstimate],
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Init {
+    pub method: &'static str,
+    pub args: Value,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MethodRole {
+    pub method: &'static str,
+    pub role: &'static str,
+}
+
+/// Methods by how they can be called, E.g. to scope a function call access key.
+#[derive(Serialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MethodAcl {
+    pub view_methods: Vec<&'static str>,
+    /// Change methods without a deposit, the only ones a function call access key can call.
+    pub change_methods: Vec<&'static str>,
+    /// Change methods that need a deposit, at least 1 yoctoNEAR, so a full access key.
+    pub payable_methods: Vec<&'static str>,
+    /// Callbacks only the contract itself can call.
+    pub private_methods: Vec<&'static str>,
+    pub roles: Vec<MethodRole>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Manifest {
+    pub name: &'static str,
+    pub version: &'static str,
+    pub init: Option<Init>,
+    pub features: Vec<&'static str>,
+    pub methods: Vec<Method>,
+    pub storage: Vec<StorageEstimate>,
+}
+impl Default for Manifest {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Manifest {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            name: env!("CARGO_PKG_NAME"),
+            version: env!("CARGO_PKG_VERSION"),
+            init: None,
+            features: vec![],
+            methods: vec![],
+            storage: vec![],
+        }
+    }
+    /// Init method with an args template.
+    #[inline]
+    pub fn init(mut self, method: &'static str, args: Value) -> Self {
+        self.init = Some(Init { method, args });
+        self
+    }
+    #[inline]
+    pub fn feature(mut self, feature: &'static str) -> Self {
+        if !self.features.contains(&feature) {
+            self.features.push(feature);
+        }
+        self
+    }
+    #[inline]
+    pub fn method(mut self, method: Method) -> Self {
+        self.methods.push(method);
+        self
+    }
+    #[inline]
+    pub fn storage(mut self, estimate: StorageEstimate) -> Self {
+        self.storage.push(estimate);
+        self
+    }
+    /// Adds everything an impl macro generated.
+    pub fn component(mut self, component: Component) -> Self {
+        if let Some(feature) = component.feature {
+            self = self.feature(feature);
+        }
+        self.methods.extend_from_slice(component.methods);
+        self.storage.extend_from_slice(component.storage);
+        self
+    }
+    /// Methods grouped by how they can be called.
+    pub fn acl(&self) -> MethodAcl {
+        let mut acl = MethodAcl::default();
+        for method in self.methods.iter() {
+            match method {
+                Method { private: true, .. } => acl.private_methods.push(method.name),
+                Method { payable: true, .. } => acl.payable_methods.push(method.name),
+                Method {
+                    kind: MethodKind::View,
+                    ..
+                } => acl.view_methods.push(method.name),
+                Method { .. } => acl.change_methods.push(method.name),
+            }
+            if let Some(role) = method.role {
+                acl.roles.push(MethodRole {
+                    method: method.name,
+                    role,
+                });
+            }
+        }
+        acl
+    }
+    #[inline]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("Invalid manifest")
+    }
+}
+
+/// Generates the `method_acl()` view from `Contract::manifest()`.
+///
+/// # Example
+/// ```
+/// # use cmn::*;
+/// impl_method_acl!(Contract);
+/// // method_acl() => {"view_methods": [..], "change_methods": [..], "payable_methods": [..], "private_methods": [..], "roles": [{"method": "set_x", "role": "owner"}]}
+/// ```
+#[macro_export]
+macro_rules! impl_method_acl {
+    ($contract:ident) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_method_acl!`.
+            pub const METHOD_ACL_MANIFEST: $crate::manifest::Component =
+                $crate::manifest::Component {
+                    feature: None,
+                    methods: &[$crate::manifest::Method::view("method_acl")],
+                    storage: &[],
+                };
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Methods by how they can be called and the roles they require, for wallets to scope access keys.
+            pub fn method_acl() -> $crate::manifest::MethodAcl {
+                Self::manifest().acl()
+            }
+        }
+    };
+}
+pub use impl_method_acl;
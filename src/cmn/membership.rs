@@ -0,0 +1,471 @@
+#![cfg(feature = "membership")]
+#![allow(dead_code)]
+/*!
+Membership tiers derived from the holdings of an account, E.g. silver for 100 tokens and gold
+for 1000 or for a token of the `gold-` NFT series.
+
+A [`Tier`] qualifies by an FT balance, by a number of NFTs (of a series: the token ids starting
+with a prefix), or by either of them. Tiers are ordered from the lowest, an account is in the
+last tier it qualifies for. `membership_tier_of(account_id)` views it, other modules gate on it
+with [`Membership::require_tier`] and discount their fees with [`Membership::discount_bps`],
+E.g. `MintFee::charge_with_discount` of `nft::fee`.
+
+# NOTES:
+  - The holdings are read through [`Holdings`], implemented by `ft::FungibleToken` and
+    `nft::NonFungibleToken`: `impl_membership!(.., ft = <field>, nft = <field>)` reads either
+    or both.
+  - `membership_refresh` caches the tier of an account, the caller pays its storage. Every
+    transfer, mint or burn drops the cache of its accounts through [`invalidate`], and changing
+    the tiers drops all of it, so a cached tier is never stale.
+  - Behind the `membership` feature, the tokens only pay the [`invalidate`] of their transfers
+    with it.
+  - A series is counted by iterating the tokens of the owner, keep series tiers for collections
+    where an owner holds a few tokens.
+  - The tiers are set by the owner, events are logged as `EVENT_JSON:` with the `membership`
+    standard.
+
+# EXAMPLE:
+Needs the `ft` feature too.
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    ft: ft::FungibleToken,
+    membership: membership::Membership,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId, total_supply: U128) -> Self {
+        require_init!();
+        Self {
+            owner_id: owner_id.clone(),
+            ft: ft::FungibleToken::from_args(ft::InitArgs {
+                owner_id: Some(owner_id),
+                total_supply,
+                name: "Points".to_string(),
+                symbol: "PTS".to_string(),
+                ..Default::default()
+            }),
+            membership: membership::Membership::new(vec![
+                membership::Tier::ft("silver", U128(100), 500),
+                membership::Tier::ft("gold", U128(500), 1_000),
+            ]),
+        }
+    }
+
+    /// Only for gold members.
+    pub fn lounge(&self) -> String {
+        self.require_membership_tier(&env::predecessor_account_id(), "gold");
+        "Welcome".to_string()
+    }
+}
+
+ft::impl_fungible_token_contract!(Contract, ft);
+// impl_membership!(Contract, membership[, ft = <ft::FungibleToken field>][, nft = <nft::NonFungibleToken field>][, owner = <AccountId field; default: the contract itself>]);
+membership::impl_membership!(Contract, membership, ft = ft, owner = owner_id);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    use ft::core::FungibleTokenCore;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0), U128(1_000));
+        contract.ft.token.internal_register_account(&accounts(1));
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        (vm, contract)
+    }
+
+    fn tier_name(tier: Option<membership::Tier>) -> Option<String> {
+        tier.map(|tier| tier.name)
+    }
+
+    #[test]
+    fn test_tier_of() {
+        let (_, mut contract) = setup();
+
+        assert_eq!(tier_name(contract.membership_tier_of(accounts(0))), Some("gold".into()));
+        assert_eq!(contract.membership_tier_of(accounts(1)), None);
+        assert_eq!(contract.membership_discount_bps(accounts(1)), 0);
+
+        contract.ft_transfer(accounts(1), U128(200), None);
+        assert_eq!(tier_name(contract.membership_tier_of(accounts(1))), Some("silver".into()));
+        assert_eq!(contract.membership_discount_bps(accounts(1)), 500);
+    }
+
+    #[test]
+    fn test_refresh() {
+        let (mut vm, mut contract) = setup();
+
+        contract.ft_transfer(accounts(1), U128(200), None);
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR / 100),
+        );
+        contract.membership_refresh(accounts(1));
+        assert_eq!(tier_name(contract.membership_tier_of(accounts(1))), Some("silver".into()));
+
+        // the transfer drops the cached tier
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.ft_transfer(accounts(1), U128(400), None);
+        assert_eq!(tier_name(contract.membership_tier_of(accounts(1))), Some("gold".into()));
+        assert_eq!(tier_name(contract.membership_tier_of(accounts(0))), Some("silver".into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "@bob needs the gold tier")]
+    fn test_require_tier() {
+        let (mut vm, mut contract) = setup();
+
+        assert_eq!(contract.lounge(), "Welcome");
+        contract.ft_transfer(accounts(1), U128(200), None);
+        run_vm(vm.predecessor_account_id(accounts(1)));
+        contract.lounge();
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::{
+    collections::LookupMap,
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+
+pub const MAX_TIERS: usize = 10;
+pub const MAX_TIER_NAME_LENGTH: usize = 32;
+/// 100%.
+pub const MAX_DISCOUNT_BPS: u32 = contract_core::bps::ONE_HUNDRED_PERCENT;
+
+raw_storage_key!(pub enum StorageKey in storage_key::MEMBERSHIP {
+    TierCache,
+});
+
+/// Logs a `membership` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    events::emit(events::standards::MEMBERSHIP, event, data);
+}
+
+/// What an account holds, from the tokens of the contract.
+pub trait Holdings {
+    fn ft_balance(&self, _account_id: &AccountId) -> Balance {
+        0
+    }
+
+    /// Number of NFTs of the account, only the token ids starting with `series` if any.
+    fn nft_tokens(&self, _account_id: &AccountId, _series: Option<&str>) -> u64 {
+        0
+    }
+}
+
+#[cfg(feature = "ft")]
+impl Holdings for ft::FungibleToken {
+    #[inline]
+    fn ft_balance(&self, account_id: &AccountId) -> Balance {
+        self.token.accounts.get(account_id).unwrap_or_default()
+    }
+}
+
+#[cfg(feature = "nft")]
+impl Holdings for nft::NonFungibleToken {
+    fn nft_tokens(&self, account_id: &AccountId, series: Option<&str>) -> u64 {
+        let series = match series {
+            Some(series) => series,
+            None => return self.supply_for_owner(account_id),
+        };
+        self.token
+            .tokens_per_owner
+            .as_ref()
+            .and_then(|tokens_per_owner| tokens_per_owner.get(account_id))
+            .map_or(0, |token_ids| {
+                token_ids
+                    .iter()
+                    .filter(|token_id| token_id.starts_with(series))
+                    .count() as u64
+            })
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Tier {
+    pub name: String,
+    /// FT balance that qualifies, none to not qualify by the FT.
+    pub min_ft_balance: Option<U128>,
+    /// Number of NFTs that qualifies, none to not qualify by the NFT.
+    pub min_nft_tokens: Option<u64>,
+    /// Only counts the NFTs whose token id starts with it, E.g. `gold-`.
+    pub nft_series: Option<String>,
+    /// Off the fees of the members.
+    pub discount_bps: u32,
+}
+impl Tier {
+    /// Qualifies by the FT balance only.
+    pub fn ft(name: &str, min_ft_balance: U128, discount_bps: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            min_ft_balance: Some(min_ft_balance),
+            min_nft_tokens: None,
+            nft_series: None,
+            discount_bps,
+        }
+    }
+
+    /// Qualifies by the NFTs of the series only, of any series if `None`.
+    pub fn nft(
+        name: &str,
+        min_nft_tokens: u64,
+        nft_series: Option<&str>,
+        discount_bps: u32,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            min_ft_balance: None,
+            min_nft_tokens: Some(min_nft_tokens),
+            nft_series: nft_series.map(str::to_string),
+            discount_bps,
+        }
+    }
+
+    pub fn assert_valid(&self) {
+        require!(
+            !self.name.is_empty() && self.name.len() <= MAX_TIER_NAME_LENGTH,
+            format!("Tier name must be 1 to {} bytes", MAX_TIER_NAME_LENGTH)
+        );
+        require!(
+            self.min_ft_balance.is_some() || self.min_nft_tokens.is_some(),
+            format!("Tier {} has no threshold", self.name)
+        );
+        require!(
+            self.discount_bps <= MAX_DISCOUNT_BPS,
+            "Discount can't be over 10000 bps"
+        );
+    }
+
+    /// Whether the holdings of the account reach one of the thresholds.
+    pub fn qualifies(&self, account_id: &AccountId, holdings: &[&dyn Holdings]) -> bool {
+        let by_ft = self.min_ft_balance.is_some_and(|min| {
+            holdings
+                .iter()
+                .map(|holdings| holdings.ft_balance(account_id))
+                .fold(0, Balance::saturating_add)
+                >= min.0
+        });
+        by_ft
+            || self.min_nft_tokens.is_some_and(|min| {
+                holdings
+                    .iter()
+                    .map(|holdings| holdings.nft_tokens(account_id, self.nft_series.as_deref()))
+                    .sum::<u64>()
+                    >= min
+            })
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub(crate) struct CachedTier {
+    /// [`Membership::version`] of the tiers it was computed with.
+    version: u32,
+    tier: Option<u8>,
+}
+
+/// The cached tiers, under their own key so transfers drop them without the [`Membership`].
+#[inline]
+fn cache() -> LookupMap<AccountId, CachedTier> {
+    LookupMap::new(StorageKey::TierCache)
+}
+
+/// Drops the cached tier of the account, its holdings changed.
+#[inline]
+pub fn invalidate(account_id: &AccountId) {
+    cache().remove(account_id);
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Membership {
+    /// From the lowest.
+    pub tiers: Vec<Tier>,
+    /// Bumped by every change of the tiers, older cached tiers are ignored.
+    pub version: u32,
+}
+impl Membership {
+    pub fn new(tiers: Vec<Tier>) -> Self {
+        let mut this = Self {
+            tiers: vec![],
+            version: 0,
+        };
+        this.set_tiers(tiers);
+        this
+    }
+
+    pub fn set_tiers(&mut self, tiers: Vec<Tier>) {
+        require!(
+            tiers.len() <= MAX_TIERS,
+            format!("Can't have more than {} tiers", MAX_TIERS)
+        );
+        for (i, tier) in tiers.iter().enumerate() {
+            tier.assert_valid();
+            require!(
+                tiers[..i].iter().all(|other| other.name != tier.name),
+                format!("Tier {} is defined twice", tier.name)
+            );
+        }
+        self.tiers = tiers;
+        self.version = self.version.wrapping_add(1);
+        emit(
+            "set_tiers",
+            serde_json::json!({ "tiers": self.tiers, "version": self.version }),
+        );
+    }
+
+    /// Index of the tier of the account, from its holdings.
+    pub fn compute(&self, account_id: &AccountId, holdings: &[&dyn Holdings]) -> Option<u8> {
+        self.tiers
+            .iter()
+            .rposition(|tier| tier.qualifies(account_id, holdings))
+            .map(|i| i as u8)
+    }
+
+    /// Index of the tier of the account, cached or computed.
+    pub fn tier_index(&self, account_id: &AccountId, holdings: &[&dyn Holdings]) -> Option<u8> {
+        match cache().get(account_id) {
+            Some(cached) if cached.version == self.version => cached.tier,
+            _ => self.compute(account_id, holdings),
+        }
+    }
+
+    #[inline]
+    pub fn tier_of(&self, account_id: &AccountId, holdings: &[&dyn Holdings]) -> Option<&Tier> {
+        self.tier_index(account_id, holdings)
+            .map(|i| &self.tiers[i as usize])
+    }
+
+    /// Computes the tier of the account and caches it until its holdings or the tiers change.
+    pub fn refresh(&self, account_id: &AccountId, holdings: &[&dyn Holdings]) -> Option<&Tier> {
+        let tier = self.compute(account_id, holdings);
+        cache().insert(
+            account_id,
+            &CachedTier {
+                version: self.version,
+                tier,
+            },
+        );
+        tier.map(|i| &self.tiers[i as usize])
+    }
+
+    /// Discount of the tier of the account, 0 without a tier.
+    #[inline]
+    pub fn discount_bps(&self, account_id: &AccountId, holdings: &[&dyn Holdings]) -> u32 {
+        self.tier_of(account_id, holdings)
+            .map_or(0, |tier| tier.discount_bps)
+    }
+
+    /// Requires the account to be in the tier `name` or a higher one.
+    pub fn require_tier(&self, account_id: &AccountId, holdings: &[&dyn Holdings], name: &str) {
+        let required = self
+            .tiers
+            .iter()
+            .position(|tier| tier.name == name)
+            .unwrap_or_else(|| env::panic_str(&format!("Unknown tier {}", name)));
+        require!(
+            self.tier_index(account_id, holdings)
+                .is_some_and(|i| i as usize >= required),
+            format!("@{} needs the {} tier", account_id, name)
+        );
+    }
+}
+
+#[macro_export]
+macro_rules! impl_membership {
+    ($contract:ident, $membership:ident $(, ft = $ft:ident)? $(, nft = $nft:ident)? $(, owner = $owner:ident)? $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_membership!`.
+            pub const MEMBERSHIP_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: Some("membership"),
+                methods: &[
+                    $crate::manifest::Method::view("membership_tiers"),
+                    $crate::manifest::Method::view("membership_tier_of"),
+                    $crate::manifest::Method::view("membership_discount_bps"),
+                    $crate::manifest::Method::payable("membership_refresh"),
+                    $crate::manifest::Method::payable("membership_set_tiers").role("owner"),
+                ],
+                storage: &[
+                    // 40 (record) + 1 (prefix) + 68 (account id) + 6 (cached tier)
+                    $crate::manifest::StorageEstimate::new("membership_refresh", 115),
+                ],
+            };
+
+            fn membership_holdings(&self) -> Vec<&dyn $crate::membership::Holdings> {
+                vec![$(&self.$ft as &dyn $crate::membership::Holdings,)? $(&self.$nft as &dyn $crate::membership::Holdings,)?]
+            }
+
+            /// Access guard, requires the account to be in the tier `name` or a higher one.
+            fn require_membership_tier(&self, account_id: &AccountId, name: &str) {
+                self.$membership
+                    .require_tier(account_id, &self.membership_holdings(), name)
+            }
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// From the lowest.
+            pub fn membership_tiers(&self) -> Vec<$crate::membership::Tier> {
+                self.$membership.tiers.clone()
+            }
+
+            pub fn membership_tier_of(&self, account_id: AccountId) -> Option<$crate::membership::Tier> {
+                self.$membership
+                    .tier_of(&account_id, &self.membership_holdings())
+                    .cloned()
+            }
+
+            pub fn membership_discount_bps(&self, account_id: AccountId) -> u32 {
+                self.$membership
+                    .discount_bps(&account_id, &self.membership_holdings())
+            }
+
+            /// Caches the tier of the account, requires the storage deposit.
+            #[payable]
+            pub fn membership_refresh(&mut self, account_id: AccountId) -> Option<$crate::membership::Tier> {
+                let initial_storage_usage = env::storage_usage();
+                let tier = self
+                    .$membership
+                    .refresh(&account_id, &self.membership_holdings())
+                    .cloned();
+                $crate::refund_deposit_to(
+                    env::storage_usage().saturating_sub(initial_storage_usage),
+                    None,
+                );
+                tier
+            }
+
+            /// From the lowest, drops the cached tiers.
+            #[payable]
+            pub fn membership_set_tiers(&mut self, tiers: Vec<$crate::membership::Tier>) {
+                near_sdk::assert_one_yocto();
+                let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                    .cloned()
+                    .unwrap_or_else(env::current_account_id);
+                require!(
+                    env::predecessor_account_id() == owner_id,
+                    "Only the owner can set the tiers"
+                );
+                self.$membership.set_tiers(tiers);
+            }
+        }
+    };
+}
+pub use impl_membership;
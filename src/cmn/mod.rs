@@ -1,3 +1,5 @@
+#![allow(unused_imports, clippy::test_attr_in_doctest)]
+
 pub use near_sdk::{
     borsh::{self, BorshDeserialize, BorshSerialize},
     BorshStorageKey, PanicOnDefault,
@@ -6,7 +8,7 @@ pub use near_sdk::{
     collections::{self, LazyOption, LegacyTreeMap, TreeMap},
     store::*,
 };
-pub use near_sdk::{env, log, near_bindgen, require};
+pub use near_sdk::{env, near_bindgen, require};
 pub use near_sdk::{
     json_types::*, AccountId, Balance, Gas, Promise, PromiseError, PromiseOrValue, PromiseResult,
 };
@@ -21,6 +23,44 @@ pub use uint::hex::{FromHex, FromHexError, ToHex};
 mod utils;
 pub use utils::*;
 
+pub mod account_migration;
+pub mod asset;
+pub mod attest;
+pub mod batch;
+pub mod bounty;
+pub mod contract_metadata;
+pub mod decommission;
+pub mod deposits;
+pub mod donation;
+pub mod event_buffer;
+pub mod events;
+pub mod expiry;
+pub mod features;
 pub mod ft;
+pub mod health;
+pub mod hybrid;
+pub mod inbox;
+pub mod inheritance;
+pub mod init;
+pub mod keeper;
+pub mod logger;
+pub mod manifest;
+pub mod membership;
+pub mod mt;
+pub mod multiview;
+pub mod names;
 pub mod nft;
+pub mod otc;
+pub mod ownable;
+pub mod protocol;
+pub mod reputation;
+pub mod royalty;
+pub mod schema;
+pub mod signed_config;
+pub mod state_export;
+pub mod state_schema;
+pub mod stats;
+pub mod telemetry;
 pub mod test_utils;
+pub mod validate;
+pub mod xcc;
@@ -0,0 +1,1324 @@
+#![cfg(feature = "mt")]
+#![allow(dead_code)]
+/*!
+Multi Token (NEP-245) implementation with JSON serialization.
+
+One contract issues fungible tokens (E.g. `gold`, minted by the thousands) and non fungible
+ones (E.g. `sword-1`, minted once) side by side, every token id has its own supply and
+balances.
+
+# NOTES:
+  - near-contract-standards has no NEP-245, [`MultiToken`] implements it here along with the
+    [`core`], [`approval`], [`enumeration`] and [`metadata`] traits the macro implements.
+  - The owner mints with `mt_mint(token_id, receiver_id, amount, token_metadata)`, the metadata
+    is set by the first mint of a token id. The minter pays the measured storage and gets the
+    rest of the deposit back.
+  - The contract pays the storage of the balances transfers create, like NEP-171 transfers.
+  - Batches take up to [`MAX_BATCH_LENGTH`] token ids.
+  - Approvals are per owner of a token and for an amount, which transfers by the approved
+    account use up. `mt_is_approved` takes the `owner_id` of the approvals for it. Approvals
+    are charged and refunded by the measured storage, like NFT approvals.
+  - `Token::owner_id` is only set by `mt_tokens_for_owner`, a token id can have many owners.
+  - `mt_resolve_transfer` refunds the unused amounts, it doesn't restore the approvals used by
+    the transfer.
+  - Events are logged as `EVENT_JSON:` with the `nep245` standard.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    mt: mt::MultiToken,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            mt: mt::MultiToken::from_args(mt::InitArgs {
+                owner_id: Some(owner_id),
+                name: "Game Items".to_string(),
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+// impl_multi_token_contract!(Contract, mt);
+mt::impl_multi_token_contract!(Contract, mt);
+events::impl_supported_standards!(Contract, NEP245, NEP297);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    use mt::{
+        approval::MultiTokenApproval,
+        core::{MultiTokenCore, MultiTokenResolver},
+        enumeration::MultiTokenEnumeration,
+    };
+
+    const MINT_STORAGE_COST: Balance = 100_000_000_000_000_000_000_000;
+
+    fn token_metadata(title: &str) -> mt::TokenMetadata {
+        mt::TokenMetadata {
+            title: Some(title.into()),
+            ..Default::default()
+        }
+    }
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST),
+        );
+        contract.mt_mint("gold".into(), accounts(0), U128(1_000), Some(token_metadata("Gold")));
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST),
+        );
+        contract.mt_mint("sword-1".into(), accounts(0), U128(1), Some(token_metadata("Sword")));
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_mint() {
+        let (_, contract) = setup();
+
+        assert_eq!(contract.mt_supply("gold".into()), Some(U128(1_000)));
+        assert_eq!(contract.mt_supply("silver".into()), None);
+        assert_eq!(
+            contract.mt_batch_balance_of(accounts(0), vec!["gold".into(), "sword-1".into()]),
+            vec![U128(1_000), U128(1)]
+        );
+        assert_eq!(contract.mt_tokens(None, None).len(), 2);
+    }
+
+    #[test]
+    fn test_batch_transfer() {
+        let (_, mut contract) = setup();
+
+        contract.mt_batch_transfer(
+            accounts(1),
+            vec!["gold".into(), "sword-1".into()],
+            vec![U128(100), U128(1)],
+            None,
+            None,
+        );
+        assert_eq!(contract.mt_balance_of(accounts(0), "gold".into()), U128(900));
+        assert_eq!(contract.mt_balance_of(accounts(1), "gold".into()), U128(100));
+        assert_eq!(contract.mt_balance_of(accounts(0), "sword-1".into()), U128(0));
+        assert_eq!(
+            contract.mt_tokens_for_owner(accounts(0), None, None),
+            vec![mt::Token {
+                token_id: "gold".into(),
+                owner_id: Some(accounts(0)),
+            }]
+        );
+        assert_eq!(contract.mt_tokens_for_owner(accounts(1), None, None).len(), 2);
+        // both transfers in one log
+        assert_eq!(get_logs().len(), 1);
+        assert!(get_logs()[0].contains(r#""event":"mt_transfer""#));
+    }
+
+    #[test]
+    fn test_approved_transfer() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST),
+        );
+        contract.mt_approve(vec!["gold".into()], vec![U128(50)], accounts(2), None);
+        assert!(contract.mt_is_approved(
+            accounts(0),
+            vec!["gold".into()],
+            accounts(2),
+            vec![U128(50)],
+            None
+        ));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(2)),
+        );
+        contract.mt_transfer(accounts(1), "gold".into(), U128(30), Some((accounts(0), 0)), None);
+        assert_eq!(contract.mt_balance_of(accounts(1), "gold".into()), U128(30));
+        assert!(!contract.mt_is_approved(
+            accounts(0),
+            vec!["gold".into()],
+            accounts(2),
+            vec![U128(30)],
+            None
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "Approved amount is exceeded")]
+    fn test_approved_transfer_over_amount() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST),
+        );
+        contract.mt_approve(vec!["gold".into()], vec![U128(50)], accounts(2), None);
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(2)),
+        );
+        contract.mt_transfer(accounts(1), "gold".into(), U128(51), Some((accounts(0), 0)), None);
+    }
+
+    #[test]
+    fn test_resolve_transfer() {
+        let (mut vm, mut contract) = setup();
+
+        contract.mt_transfer(accounts(1), "gold".into(), U128(100), None, None);
+        run_vm_with_results(
+            vm.predecessor_account_id(accounts(0)),
+            vec![PromiseResult::Successful(b"[\"40\"]".to_vec())],
+        );
+        let used = contract.mt_resolve_transfer(
+            vec![accounts(0)],
+            accounts(1),
+            vec!["gold".into()],
+            vec![U128(100)],
+        );
+        assert_eq!(used, vec![U128(60)]);
+        assert_eq!(contract.mt_balance_of(accounts(0), "gold".into()), U128(940));
+        assert_eq!(contract.mt_balance_of(accounts(1), "gold".into()), U128(60));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can mint")]
+    fn test_mint_owner() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.attached_deposit(MINT_STORAGE_COST)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.mt_mint("gold".into(), accounts(1), U128(1), None);
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::{
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+use std::collections::HashMap;
+
+raw_storage_key!(pub enum StorageKey in storage_key::MT {
+    Metadata,
+    TokenMetadata,
+    Supplies,
+    Balances,
+    TokensPerOwner,
+    TokensPerOwnerInner,
+    Approvals,
+});
+
+pub type TokenId = String;
+
+pub const METADATA_SPEC: &str = "mt-1.0.0";
+/// Max number of token ids of a batch.
+pub const MAX_BATCH_LENGTH: usize = 50;
+
+/// Gas attached to the `mt_resolve_transfer` callback.
+pub const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(10_000_000_000_000);
+/// Gas `mt_transfer_call` keeps for itself and the callback, the rest goes to `mt_on_transfer`.
+pub const GAS_FOR_MT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER.0);
+/// Gas attached to `mt_on_approve`.
+pub const GAS_FOR_MT_APPROVE: Gas = Gas(10_000_000_000_000);
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Token {
+    pub token_id: TokenId,
+    pub owner_id: Option<AccountId>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Metadata {
+    pub spec: String,
+    pub name: String,
+    pub icon: Option<String>,
+    pub base_uri: Option<String>,
+    pub reference: Option<String>,
+    pub reference_hash: Option<Base64VecU8>,
+}
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenMetadata {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub media: Option<String>,
+    pub media_hash: Option<Base64VecU8>,
+    /// Unix epoch in milliseconds or RFC3339.
+    pub issued_at: Option<String>,
+    pub expires_at: Option<String>,
+    pub starts_at: Option<String>,
+    pub updated_at: Option<String>,
+    pub extra: Option<String>,
+    pub reference: Option<String>,
+    pub reference_hash: Option<Base64VecU8>,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Approval {
+    pub approval_id: u64,
+    /// Left to transfer.
+    pub amount: U128,
+}
+
+/// Init args as a single JSON object. Every field has a default,
+/// so new fields don't break existing deploy scripts.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(crate = "near_sdk::serde", default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InitArgs {
+    /// Defaults to the predecessor, mints the tokens.
+    pub owner_id: Option<AccountId>,
+    /// Defaults to [`METADATA_SPEC`].
+    pub spec: Option<String>,
+    pub name: String,
+    pub icon: Option<String>,
+    pub base_uri: Option<String>,
+    pub reference: Option<String>,
+    pub reference_hash: Option<Base64VecU8>,
+}
+impl InitArgs {
+    #[inline]
+    pub fn metadata(&self) -> Metadata {
+        Metadata {
+            spec: self
+                .spec
+                .clone()
+                .unwrap_or_else(|| METADATA_SPEC.to_string()),
+            name: self.name.clone(),
+            icon: self.icon.clone(),
+            base_uri: self.base_uri.clone(),
+            reference: self.reference.clone(),
+            reference_hash: self.reference_hash.clone(),
+        }
+    }
+}
+
+/// Bounded names and well-formed URIs.
+pub fn assert_valid_metadata(metadata: &Metadata) {
+    use validate::*;
+    validate!(metadata.name => non_empty, max_len(MAX_NAME_LENGTH));
+    validate!(?metadata.icon => valid_data_uri, max_len(MAX_ICON_LENGTH));
+    validate!(?metadata.base_uri => valid_url, max_len(MAX_URL_LENGTH));
+    validate!(?metadata.reference => valid_url, max_len(MAX_URL_LENGTH));
+    require!(
+        metadata.reference.is_some() == metadata.reference_hash.is_some(),
+        "reference and reference_hash must be set together"
+    );
+}
+
+pub fn assert_valid_token_metadata(metadata: &TokenMetadata) {
+    use validate::*;
+    validate!(?metadata.title => max_len(MAX_NAME_LENGTH));
+    validate!(?metadata.description => max_len(MAX_DESCRIPTION_LENGTH));
+    validate!(?metadata.media => non_empty, max_len(MAX_URL_LENGTH));
+    validate!(?metadata.reference => non_empty, max_len(MAX_URL_LENGTH));
+    validate!(?metadata.extra => max_len(MAX_DESCRIPTION_LENGTH));
+}
+
+/// NEP-245 events.
+pub mod events {
+    use super::*;
+
+    pub fn mt_mint(
+        owner_id: &AccountId,
+        token_ids: &[TokenId],
+        amounts: &[U128],
+        memo: Option<&str>,
+    ) {
+        super::super::events::emit(
+            super::super::events::standards::NEP245,
+            "mt_mint",
+            serde_json::json!({
+                "owner_id": owner_id,
+                "token_ids": token_ids,
+                "amounts": amounts,
+                "memo": memo,
+            }),
+        );
+    }
+
+    /// A transfer of each token, coalesced into one log.
+    pub fn mt_transfer(
+        authorized_ids: &[Option<AccountId>],
+        old_owner_ids: &[AccountId],
+        new_owner_id: &AccountId,
+        token_ids: &[TokenId],
+        amounts: &[U128],
+        memo: Option<&str>,
+    ) {
+        let mut events = event_buffer::EventBuffer::new();
+        for (i, token_id) in token_ids.iter().enumerate() {
+            events.push_standard(
+                super::super::events::standards::NEP245,
+                "mt_transfer",
+                &serde_json::json!({
+                    "authorized_id": authorized_ids[i],
+                    "old_owner_id": old_owner_ids[i],
+                    "new_owner_id": new_owner_id,
+                    "token_ids": [token_id],
+                    "amounts": [amounts[i]],
+                    "memo": memo,
+                }),
+            );
+        }
+        events.flush();
+    }
+}
+
+/// `mt_transfer`/`mt_transfer_call` and their batches, `mt_token`, the balances and supplies.
+pub mod core {
+    use super::*;
+
+    pub trait MultiTokenCore {
+        fn mt_transfer(
+            &mut self,
+            receiver_id: AccountId,
+            token_id: TokenId,
+            amount: U128,
+            approval: Option<(AccountId, u64)>,
+            memo: Option<String>,
+        );
+
+        fn mt_batch_transfer(
+            &mut self,
+            receiver_id: AccountId,
+            token_ids: Vec<TokenId>,
+            amounts: Vec<U128>,
+            approvals: Option<Vec<Option<(AccountId, u64)>>>,
+            memo: Option<String>,
+        );
+
+        /// Returns the amounts used by the receiver.
+        fn mt_transfer_call(
+            &mut self,
+            receiver_id: AccountId,
+            token_id: TokenId,
+            amount: U128,
+            approval: Option<(AccountId, u64)>,
+            memo: Option<String>,
+            msg: String,
+        ) -> PromiseOrValue<Vec<U128>>;
+
+        /// Returns the amounts used by the receiver.
+        fn mt_batch_transfer_call(
+            &mut self,
+            receiver_id: AccountId,
+            token_ids: Vec<TokenId>,
+            amounts: Vec<U128>,
+            approvals: Option<Vec<Option<(AccountId, u64)>>>,
+            memo: Option<String>,
+            msg: String,
+        ) -> PromiseOrValue<Vec<U128>>;
+
+        fn mt_token(&self, token_ids: Vec<TokenId>) -> Vec<Option<Token>>;
+
+        fn mt_balance_of(&self, account_id: AccountId, token_id: TokenId) -> U128;
+
+        fn mt_batch_balance_of(&self, account_id: AccountId, token_ids: Vec<TokenId>) -> Vec<U128>;
+
+        fn mt_supply(&self, token_id: TokenId) -> Option<U128>;
+
+        fn mt_batch_supply(&self, token_ids: Vec<TokenId>) -> Vec<Option<U128>>;
+    }
+
+    pub trait MultiTokenResolver {
+        /// Refunds the unused amounts to the previous owners, returns the used ones.
+        fn mt_resolve_transfer(
+            &mut self,
+            previous_owner_ids: Vec<AccountId>,
+            receiver_id: AccountId,
+            token_ids: Vec<TokenId>,
+            amounts: Vec<U128>,
+        ) -> Vec<U128>;
+    }
+}
+
+/// `mt_approve` and `mt_revoke` of amounts of tokens.
+pub mod approval {
+    use super::*;
+
+    pub trait MultiTokenApproval {
+        fn mt_approve(
+            &mut self,
+            token_ids: Vec<TokenId>,
+            amounts: Vec<U128>,
+            account_id: AccountId,
+            msg: Option<String>,
+        ) -> Option<Promise>;
+
+        fn mt_revoke(&mut self, token_ids: Vec<TokenId>, account_id: AccountId);
+
+        fn mt_revoke_all(&mut self, token_ids: Vec<TokenId>);
+
+        /// Whether `approved_account_id` can transfer `amounts` of the tokens of `owner_id`.
+        fn mt_is_approved(
+            &self,
+            owner_id: AccountId,
+            token_ids: Vec<TokenId>,
+            approved_account_id: AccountId,
+            amounts: Vec<U128>,
+            approval_ids: Option<Vec<u64>>,
+        ) -> bool;
+    }
+}
+
+/// `mt_tokens` and `mt_tokens_for_owner`.
+pub mod enumeration {
+    use super::*;
+
+    pub trait MultiTokenEnumeration {
+        fn mt_tokens(&self, from_index: Option<U128>, limit: Option<u32>) -> Vec<Token>;
+
+        fn mt_tokens_for_owner(
+            &self,
+            account_id: AccountId,
+            from_index: Option<U128>,
+            limit: Option<u32>,
+        ) -> Vec<Token>;
+    }
+}
+
+/// Metadata of the contract and of the tokens.
+pub mod metadata {
+    use super::*;
+
+    pub trait MultiTokenMetadataProvider {
+        fn mt_metadata_contract(&self) -> Metadata;
+
+        fn mt_metadata_token_by_token_id(
+            &self,
+            token_ids: Vec<TokenId>,
+        ) -> Vec<Option<TokenMetadata>>;
+    }
+}
+
+// near-sdk generates the `ext_*` structs of the receiver interfaces from these.
+#[near_sdk::ext_contract(ext_mt_receiver)]
+pub trait MtReceiver {
+    fn mt_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_ids: Vec<AccountId>,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>>;
+}
+
+#[near_sdk::ext_contract(ext_mt_resolver)]
+pub trait MtResolver {
+    fn mt_resolve_transfer(
+        &mut self,
+        previous_owner_ids: Vec<AccountId>,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+    ) -> Vec<U128>;
+}
+
+#[near_sdk::ext_contract(ext_mt_approval_receiver)]
+pub trait MtApprovalReceiver {
+    fn mt_on_approve(
+        &mut self,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        owner_id: AccountId,
+        approval_ids: Vec<u64>,
+        msg: String,
+    );
+}
+
+fn assert_batch(token_ids: &[TokenId], amounts: &[U128]) {
+    require!(
+        !token_ids.is_empty() && token_ids.len() <= MAX_BATCH_LENGTH,
+        format!(
+            "Batches have 1 to {} tokens, got {}",
+            MAX_BATCH_LENGTH,
+            token_ids.len()
+        )
+    );
+    require!(
+        token_ids.len() == amounts.len(),
+        "token_ids and amounts must have the same length"
+    );
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MultiToken {
+    /// Mints the tokens.
+    pub owner_id: AccountId,
+    pub metadata: LazyOption<Metadata>,
+    /// Set by the first mint of a token.
+    pub token_metadata: collections::LookupMap<TokenId, TokenMetadata>,
+    /// Every token id, with its supply.
+    pub supplies: collections::UnorderedMap<TokenId, Balance>,
+    pub balances: collections::LookupMap<(TokenId, AccountId), Balance>,
+    /// Token ids with a balance, per owner.
+    pub tokens_per_owner: collections::LookupMap<AccountId, collections::UnorderedSet<TokenId>>,
+    /// By approved account, per token and owner.
+    pub approvals: collections::LookupMap<(TokenId, AccountId), HashMap<AccountId, Approval>>,
+    pub next_approval_id: u64,
+}
+impl MultiToken {
+    pub fn new(owner_id: AccountId, metadata: Metadata) -> Self {
+        assert_valid_metadata(&metadata);
+        Self {
+            owner_id,
+            metadata: LazyOption::new(StorageKey::Metadata, Some(&metadata)),
+            token_metadata: collections::LookupMap::new(StorageKey::TokenMetadata),
+            supplies: collections::UnorderedMap::new(StorageKey::Supplies),
+            balances: collections::LookupMap::new(StorageKey::Balances),
+            tokens_per_owner: collections::LookupMap::new(StorageKey::TokensPerOwner),
+            approvals: collections::LookupMap::new(StorageKey::Approvals),
+            next_approval_id: 0,
+        }
+    }
+
+    #[inline]
+    pub fn from_args(args: InitArgs) -> Self {
+        Self::new(
+            args.owner_id
+                .clone()
+                .unwrap_or_else(env::predecessor_account_id),
+            args.metadata(),
+        )
+    }
+
+    #[inline]
+    pub fn balance_of(&self, account_id: &AccountId, token_id: &TokenId) -> Balance {
+        self.balances
+            .get(&(token_id.clone(), account_id.clone()))
+            .unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn supply(&self, token_id: &TokenId) -> Option<Balance> {
+        self.supplies.get(token_id)
+    }
+
+    pub fn internal_deposit(
+        &mut self,
+        token_id: &TokenId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) {
+        let balance = self.balance_of(account_id, token_id);
+        let new_balance = balance
+            .checked_add(amount)
+            .unwrap_or_else(|| env::panic_str("Balance overflow"));
+        self.balances
+            .insert(&(token_id.clone(), account_id.clone()), &new_balance);
+        if balance == 0 {
+            let mut token_ids = self.tokens_of(account_id);
+            token_ids.insert(token_id);
+            self.tokens_per_owner.insert(account_id, &token_ids);
+        }
+    }
+
+    pub fn internal_withdraw(
+        &mut self,
+        token_id: &TokenId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) {
+        let balance = self.balance_of(account_id, token_id);
+        let new_balance = balance
+            .checked_sub(amount)
+            .unwrap_or_else(|| env::panic_str("The account doesn't have enough balance"));
+        let key = (token_id.clone(), account_id.clone());
+        if new_balance > 0 {
+            self.balances.insert(&key, &new_balance);
+            return;
+        }
+        self.balances.remove(&key);
+        let mut token_ids = self.tokens_of(account_id);
+        token_ids.remove(token_id);
+        if token_ids.is_empty() {
+            self.tokens_per_owner.remove(account_id);
+        } else {
+            self.tokens_per_owner.insert(account_id, &token_ids);
+        }
+    }
+
+    /// Token ids of the owner, an empty set under its own prefix if none.
+    fn tokens_of(&self, account_id: &AccountId) -> collections::UnorderedSet<TokenId> {
+        self.tokens_per_owner.get(account_id).unwrap_or_else(|| {
+            let mut prefix = vec![StorageKey::TokensPerOwnerInner as u8];
+            prefix.extend(env::sha256_array(account_id.as_bytes()));
+            collections::UnorderedSet::new(prefix)
+        })
+    }
+
+    /// Mints `amount` of `token_id` to `receiver_id`, `token_metadata` is required by the first
+    /// mint of the token and ignored after. Charges the measured storage from the deposit.
+    pub fn internal_mint(
+        &mut self,
+        token_id: TokenId,
+        receiver_id: AccountId,
+        amount: U128,
+        token_metadata: Option<TokenMetadata>,
+    ) {
+        require!(amount.0 > 0, "The amount should be a positive number");
+        let initial_storage_usage = env::storage_usage();
+        let supply = match self.supplies.get(&token_id) {
+            Some(supply) => supply,
+            None => {
+                strings::assert_max_bytes(&token_id, strings::MAX_MEMO_LENGTH);
+                let token_metadata = token_metadata.unwrap_or_else(|| {
+                    env::panic_str("token_metadata is required to mint a new token")
+                });
+                assert_valid_token_metadata(&token_metadata);
+                self.token_metadata.insert(&token_id, &token_metadata);
+                0
+            }
+        };
+        let supply = supply
+            .checked_add(amount.0)
+            .unwrap_or_else(|| env::panic_str("Supply overflow"));
+        self.supplies.insert(&token_id, &supply);
+        self.internal_deposit(&token_id, &receiver_id, amount.0);
+        refund_deposit_to(env::storage_usage() - initial_storage_usage, None);
+
+        events::mt_mint(&receiver_id, &[token_id], &[amount], None);
+    }
+
+    /// Uses `amount` of the approval of `account_id` for the token of `owner_id`.
+    fn use_approval(
+        &mut self,
+        token_id: &TokenId,
+        owner_id: &AccountId,
+        account_id: &AccountId,
+        approval_id: u64,
+        amount: Balance,
+    ) {
+        let key = (token_id.clone(), owner_id.clone());
+        let mut approvals = self.approvals.get(&key).unwrap_or_default();
+        let approval = approvals
+            .get_mut(account_id)
+            .filter(|approval| approval.approval_id == approval_id)
+            .unwrap_or_else(|| env::panic_str("Sender is not approved"));
+        require!(amount <= approval.amount.0, "Approved amount is exceeded");
+        approval.amount.0 -= amount;
+        if approval.amount.0 == 0 {
+            approvals.remove(account_id);
+        }
+        if approvals.is_empty() {
+            self.approvals.remove(&key);
+        } else {
+            self.approvals.insert(&key, &approvals);
+        }
+    }
+
+    /// Moves the tokens from their owners (the sender, or the owners of the approvals) to
+    /// `receiver_id`, returns the previous owners.
+    pub fn internal_batch_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        token_ids: &[TokenId],
+        amounts: &[U128],
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+    ) -> Vec<AccountId> {
+        assert_batch(token_ids, amounts);
+        if let Some(approvals) = approvals.as_ref() {
+            require!(
+                approvals.len() == token_ids.len(),
+                "token_ids and approvals must have the same length"
+            );
+        }
+        if let Some(memo) = memo.as_ref() {
+            strings::assert_max_bytes(memo, strings::MAX_MEMO_LENGTH);
+        }
+
+        let mut authorized_ids = Vec::with_capacity(token_ids.len());
+        let mut old_owner_ids = Vec::with_capacity(token_ids.len());
+        for (i, (token_id, amount)) in token_ids.iter().zip(amounts).enumerate() {
+            require!(amount.0 > 0, "The amount should be a positive number");
+            let approval = approvals
+                .as_ref()
+                .and_then(|approvals| approvals[i].clone());
+            let owner_id = match approval {
+                Some((owner_id, approval_id)) => {
+                    self.use_approval(token_id, &owner_id, sender_id, approval_id, amount.0);
+                    authorized_ids.push(Some(sender_id.clone()));
+                    owner_id
+                }
+                None => {
+                    authorized_ids.push(None);
+                    sender_id.clone()
+                }
+            };
+            require!(
+                &owner_id != receiver_id,
+                "The sender and the receiver should be different"
+            );
+            self.internal_withdraw(token_id, &owner_id, amount.0);
+            self.internal_deposit(token_id, receiver_id, amount.0);
+            old_owner_ids.push(owner_id);
+        }
+
+        events::mt_transfer(
+            &authorized_ids,
+            &old_owner_ids,
+            receiver_id,
+            token_ids,
+            amounts,
+            memo.as_deref(),
+        );
+        old_owner_ids
+    }
+
+    /// Transfers the tokens and calls `mt_on_transfer` of the receiver, then
+    /// `mt_resolve_transfer` refunds the unused amounts.
+    pub fn mt_batch_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        approvals: Option<Vec<Option<(AccountId, u64)>>>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>> {
+        near_sdk::assert_one_yocto();
+        let prepaid_gas = env::prepaid_gas();
+        require!(
+            prepaid_gas > GAS_FOR_MT_TRANSFER_CALL,
+            format!(
+                "More gas is required: mt_transfer_call needs more than {} TGas, attached {} TGas",
+                GAS_FOR_MT_TRANSFER_CALL.0 / Gas::ONE_TERA.0,
+                prepaid_gas.0 / Gas::ONE_TERA.0
+            )
+        );
+        let sender_id = env::predecessor_account_id();
+        let previous_owner_ids = self.internal_batch_transfer(
+            &sender_id,
+            &receiver_id,
+            &token_ids,
+            &amounts,
+            approvals,
+            memo,
+        );
+
+        ext_mt_receiver::ext(receiver_id.clone())
+            .with_static_gas(prepaid_gas - GAS_FOR_MT_TRANSFER_CALL)
+            .mt_on_transfer(
+                sender_id,
+                previous_owner_ids.clone(),
+                token_ids.clone(),
+                amounts.clone(),
+                msg,
+            )
+            .then(
+                ext_mt_resolver::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                    .mt_resolve_transfer(previous_owner_ids, receiver_id, token_ids, amounts),
+            )
+            .into()
+    }
+
+    /// Refunds the unused amounts still held by the receiver, all of them if `mt_on_transfer`
+    /// failed or returned garbage. Returns the used amounts.
+    pub fn mt_resolve_transfer(
+        &mut self,
+        previous_owner_ids: Vec<AccountId>,
+        receiver_id: AccountId,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+    ) -> Vec<U128> {
+        let unused_amounts = match env::promise_result(0) {
+            PromiseResult::Successful(value) => serde_json::from_slice::<Vec<U128>>(&value)
+                .ok()
+                .filter(|unused| unused.len() == amounts.len())
+                .unwrap_or_else(|| amounts.clone()),
+            _ => amounts.clone(),
+        };
+
+        let mut refunded = (vec![], vec![], vec![]);
+        let used_amounts = amounts
+            .iter()
+            .zip(unused_amounts)
+            .enumerate()
+            .map(|(i, (amount, unused))| {
+                let unused = unused.0.min(amount.0);
+                let refund = unused.min(self.balance_of(&receiver_id, &token_ids[i]));
+                if refund > 0 {
+                    self.internal_withdraw(&token_ids[i], &receiver_id, refund);
+                    self.internal_deposit(&token_ids[i], &previous_owner_ids[i], refund);
+                    refunded.0.push(previous_owner_ids[i].clone());
+                    refunded.1.push(token_ids[i].clone());
+                    refunded.2.push(U128(refund));
+                }
+                U128(amount.0 - refund)
+            })
+            .collect();
+
+        // one refund per token, from the receiver back to its previous owner
+        let (old_owner_ids, refunded_token_ids, refunded_amounts) = refunded;
+        for i in 0..old_owner_ids.len() {
+            events::mt_transfer(
+                &[None],
+                std::slice::from_ref(&receiver_id),
+                &old_owner_ids[i],
+                &refunded_token_ids[i..=i],
+                &refunded_amounts[i..=i],
+                Some("refund"),
+            );
+        }
+        used_amounts
+    }
+
+    /// Approves `account_id` for `amounts` of the tokens of the predecessor, replacing its
+    /// previous approvals of them. Charges the measured storage.
+    pub fn mt_approve(
+        &mut self,
+        token_ids: Vec<TokenId>,
+        amounts: Vec<U128>,
+        account_id: AccountId,
+        msg: Option<String>,
+    ) -> Option<Promise> {
+        require!(
+            env::attached_deposit() >= 1,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        assert_batch(&token_ids, &amounts);
+        let owner_id = env::predecessor_account_id();
+        require!(owner_id != account_id, "Owner can't approve itself");
+
+        let initial_storage_usage = env::storage_usage();
+        let approval_ids: Vec<u64> = token_ids
+            .iter()
+            .zip(&amounts)
+            .map(|(token_id, amount)| {
+                require!(
+                    self.balance_of(&owner_id, token_id) >= amount.0,
+                    "The account doesn't have enough balance"
+                );
+                let key = (token_id.clone(), owner_id.clone());
+                let mut approvals = self.approvals.get(&key).unwrap_or_default();
+                let approval_id = self.next_approval_id;
+                self.next_approval_id += 1;
+                approvals.insert(
+                    account_id.clone(),
+                    Approval {
+                        approval_id,
+                        amount: *amount,
+                    },
+                );
+                self.approvals.insert(&key, &approvals);
+                approval_id
+            })
+            .collect();
+        refund_deposit_to(
+            env::storage_usage().saturating_sub(initial_storage_usage),
+            None,
+        );
+
+        msg.map(|msg| {
+            ext_mt_approval_receiver::ext(account_id)
+                .with_static_gas(GAS_FOR_MT_APPROVE)
+                .mt_on_approve(token_ids, amounts, owner_id, approval_ids, msg)
+        })
+    }
+
+    /// Revokes the approvals of `account_id` or of everyone, the released storage goes to the
+    /// owner, the predecessor.
+    pub fn mt_revoke(&mut self, token_ids: Vec<TokenId>, account_id: Option<AccountId>) {
+        near_sdk::assert_one_yocto();
+        require!(
+            !token_ids.is_empty() && token_ids.len() <= MAX_BATCH_LENGTH,
+            format!("Batches have 1 to {} tokens", MAX_BATCH_LENGTH)
+        );
+        let owner_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+        for token_id in token_ids {
+            let key = (token_id, owner_id.clone());
+            let mut approvals = match (self.approvals.get(&key), account_id.as_ref()) {
+                (Some(approvals), Some(_)) => approvals,
+                _ => {
+                    self.approvals.remove(&key);
+                    continue;
+                }
+            };
+            approvals.remove(account_id.as_ref().unwrap());
+            if approvals.is_empty() {
+                self.approvals.remove(&key);
+            } else {
+                self.approvals.insert(&key, &approvals);
+            }
+        }
+        let released = initial_storage_usage.saturating_sub(env::storage_usage());
+        if released > 0 {
+            Promise::new(owner_id).transfer(protocol::storage_cost(released));
+        }
+    }
+
+    pub fn mt_is_approved(
+        &self,
+        owner_id: &AccountId,
+        token_ids: &[TokenId],
+        approved_account_id: &AccountId,
+        amounts: &[U128],
+        approval_ids: Option<&[u64]>,
+    ) -> bool {
+        assert_batch(token_ids, amounts);
+        token_ids
+            .iter()
+            .zip(amounts)
+            .enumerate()
+            .all(|(i, (token_id, amount))| {
+                self.approvals
+                    .get(&(token_id.clone(), owner_id.clone()))
+                    .and_then(|approvals| approvals.get(approved_account_id).cloned())
+                    .is_some_and(|approval| {
+                        approval.amount.0 >= amount.0
+                            && approval_ids
+                                .map_or(true, |ids| ids.get(i) == Some(&approval.approval_id))
+                    })
+            })
+    }
+
+    pub fn tokens(&self, from_index: Option<U128>, limit: Option<u32>) -> Vec<Token> {
+        let from_index = from_index.map_or(0, |from_index| from_index.0 as usize);
+        self.supplies
+            .keys()
+            .skip(from_index)
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .map(|token_id| Token {
+                token_id,
+                owner_id: None,
+            })
+            .collect()
+    }
+
+    pub fn tokens_for_owner(
+        &self,
+        account_id: &AccountId,
+        from_index: Option<U128>,
+        limit: Option<u32>,
+    ) -> Vec<Token> {
+        let from_index = from_index.map_or(0, |from_index| from_index.0 as usize);
+        self.tokens_per_owner
+            .get(account_id)
+            .map_or(vec![], |token_ids| {
+                token_ids
+                    .iter()
+                    .skip(from_index)
+                    .take(limit.map_or(usize::MAX, |limit| limit as usize))
+                    .map(|token_id| Token {
+                        token_id,
+                        owner_id: Some(account_id.clone()),
+                    })
+                    .collect()
+            })
+    }
+}
+
+#[macro_export]
+macro_rules! impl_multi_token_contract {
+        (@IMPL_CORE $contract:ident, $mt:ident) => {
+            #[near_bindgen]
+            impl $crate::mt::core::MultiTokenCore for $contract {
+                #[payable]
+                fn mt_transfer(
+                    &mut self,
+                    receiver_id: AccountId,
+                    token_id: $crate::mt::TokenId,
+                    amount: U128,
+                    approval: Option<(AccountId, u64)>,
+                    memo: Option<String>,
+                ) {
+                    #[cfg(feature = "feature_flags")]
+                    $crate::features::require_enabled($crate::features::Feature::MtTransfer);
+                    near_sdk::assert_one_yocto();
+                    self.$mt.internal_batch_transfer(
+                        &env::predecessor_account_id(),
+                        &receiver_id,
+                        &[token_id],
+                        &[amount],
+                        approval.map(|approval| vec![Some(approval)]),
+                        memo,
+                    );
+                }
+
+                #[payable]
+                fn mt_batch_transfer(
+                    &mut self,
+                    receiver_id: AccountId,
+                    token_ids: Vec<$crate::mt::TokenId>,
+                    amounts: Vec<U128>,
+                    approvals: Option<Vec<Option<(AccountId, u64)>>>,
+                    memo: Option<String>,
+                ) {
+                    #[cfg(feature = "feature_flags")]
+                    $crate::features::require_enabled($crate::features::Feature::MtTransfer);
+                    near_sdk::assert_one_yocto();
+                    self.$mt.internal_batch_transfer(
+                        &env::predecessor_account_id(),
+                        &receiver_id,
+                        &token_ids,
+                        &amounts,
+                        approvals,
+                        memo,
+                    );
+                }
+
+                #[payable]
+                fn mt_transfer_call(
+                    &mut self,
+                    receiver_id: AccountId,
+                    token_id: $crate::mt::TokenId,
+                    amount: U128,
+                    approval: Option<(AccountId, u64)>,
+                    memo: Option<String>,
+                    msg: String,
+                ) -> PromiseOrValue<Vec<U128>> {
+                    #[cfg(feature = "feature_flags")]
+                    $crate::features::require_enabled($crate::features::Feature::MtTransferCall);
+                    self.$mt.mt_batch_transfer_call(
+                        receiver_id,
+                        vec![token_id],
+                        vec![amount],
+                        approval.map(|approval| vec![Some(approval)]),
+                        memo,
+                        msg,
+                    )
+                }
+
+                #[payable]
+                fn mt_batch_transfer_call(
+                    &mut self,
+                    receiver_id: AccountId,
+                    token_ids: Vec<$crate::mt::TokenId>,
+                    amounts: Vec<U128>,
+                    approvals: Option<Vec<Option<(AccountId, u64)>>>,
+                    memo: Option<String>,
+                    msg: String,
+                ) -> PromiseOrValue<Vec<U128>> {
+                    #[cfg(feature = "feature_flags")]
+                    $crate::features::require_enabled($crate::features::Feature::MtTransferCall);
+                    self.$mt.mt_batch_transfer_call(receiver_id, token_ids, amounts, approvals, memo, msg)
+                }
+
+                fn mt_token(&self, token_ids: Vec<$crate::mt::TokenId>) -> Vec<Option<$crate::mt::Token>> {
+                    token_ids
+                        .into_iter()
+                        .map(|token_id| {
+                            self.$mt.supply(&token_id).map(|_| $crate::mt::Token {
+                                token_id,
+                                owner_id: None,
+                            })
+                        })
+                        .collect()
+                }
+
+                fn mt_balance_of(&self, account_id: AccountId, token_id: $crate::mt::TokenId) -> U128 {
+                    U128(self.$mt.balance_of(&account_id, &token_id))
+                }
+
+                fn mt_batch_balance_of(
+                    &self,
+                    account_id: AccountId,
+                    token_ids: Vec<$crate::mt::TokenId>,
+                ) -> Vec<U128> {
+                    token_ids
+                        .iter()
+                        .map(|token_id| U128(self.$mt.balance_of(&account_id, token_id)))
+                        .collect()
+                }
+
+                fn mt_supply(&self, token_id: $crate::mt::TokenId) -> Option<U128> {
+                    self.$mt.supply(&token_id).map(U128)
+                }
+
+                fn mt_batch_supply(&self, token_ids: Vec<$crate::mt::TokenId>) -> Vec<Option<U128>> {
+                    token_ids
+                        .iter()
+                        .map(|token_id| self.$mt.supply(token_id).map(U128))
+                        .collect()
+                }
+            }
+
+            #[near_bindgen]
+            impl $crate::mt::core::MultiTokenResolver for $contract {
+                #[private]
+                fn mt_resolve_transfer(
+                    &mut self,
+                    previous_owner_ids: Vec<AccountId>,
+                    receiver_id: AccountId,
+                    token_ids: Vec<$crate::mt::TokenId>,
+                    amounts: Vec<U128>,
+                ) -> Vec<U128> {
+                    self.$mt
+                        .mt_resolve_transfer(previous_owner_ids, receiver_id, token_ids, amounts)
+                }
+            }
+        };
+        (@IMPL_APPROVAL $contract:ident, $mt:ident) => {
+            #[near_bindgen]
+            impl $crate::mt::approval::MultiTokenApproval for $contract {
+                #[payable]
+                fn mt_approve(
+                    &mut self,
+                    token_ids: Vec<$crate::mt::TokenId>,
+                    amounts: Vec<U128>,
+                    account_id: AccountId,
+                    msg: Option<String>,
+                ) -> Option<Promise> {
+                    #[cfg(feature = "feature_flags")]
+                    $crate::features::require_enabled($crate::features::Feature::MtApprove);
+                    self.$mt.mt_approve(token_ids, amounts, account_id, msg)
+                }
+
+                #[payable]
+                fn mt_revoke(&mut self, token_ids: Vec<$crate::mt::TokenId>, account_id: AccountId) {
+                    self.$mt.mt_revoke(token_ids, Some(account_id))
+                }
+
+                #[payable]
+                fn mt_revoke_all(&mut self, token_ids: Vec<$crate::mt::TokenId>) {
+                    self.$mt.mt_revoke(token_ids, None)
+                }
+
+                fn mt_is_approved(
+                    &self,
+                    owner_id: AccountId,
+                    token_ids: Vec<$crate::mt::TokenId>,
+                    approved_account_id: AccountId,
+                    amounts: Vec<U128>,
+                    approval_ids: Option<Vec<u64>>,
+                ) -> bool {
+                    self.$mt.mt_is_approved(
+                        &owner_id,
+                        &token_ids,
+                        &approved_account_id,
+                        &amounts,
+                        approval_ids.as_deref(),
+                    )
+                }
+            }
+        };
+        (@IMPL_ENUMERATION $contract:ident, $mt:ident) => {
+            #[near_bindgen]
+            impl $crate::mt::enumeration::MultiTokenEnumeration for $contract {
+                fn mt_tokens(&self, from_index: Option<U128>, limit: Option<u32>) -> Vec<$crate::mt::Token> {
+                    self.$mt.tokens(from_index, limit)
+                }
+
+                fn mt_tokens_for_owner(
+                    &self,
+                    account_id: AccountId,
+                    from_index: Option<U128>,
+                    limit: Option<u32>,
+                ) -> Vec<$crate::mt::Token> {
+                    self.$mt.tokens_for_owner(&account_id, from_index, limit)
+                }
+            }
+        };
+        ($contract:ident, $mt:ident $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_multi_token_contract!`.
+                pub const MT_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("mt"),
+                    methods: &[
+                        $crate::manifest::Method::payable("mt_mint").role("owner"),
+                        $crate::manifest::Method::payable("mt_transfer"),
+                        $crate::manifest::Method::payable("mt_batch_transfer"),
+                        $crate::manifest::Method::payable("mt_transfer_call"),
+                        $crate::manifest::Method::payable("mt_batch_transfer_call"),
+                        $crate::manifest::Method::view("mt_token"),
+                        $crate::manifest::Method::view("mt_balance_of"),
+                        $crate::manifest::Method::view("mt_batch_balance_of"),
+                        $crate::manifest::Method::view("mt_supply"),
+                        $crate::manifest::Method::view("mt_batch_supply"),
+                        $crate::manifest::Method::private("mt_resolve_transfer"),
+                        $crate::manifest::Method::payable("mt_approve"),
+                        $crate::manifest::Method::payable("mt_revoke"),
+                        $crate::manifest::Method::payable("mt_revoke_all"),
+                        $crate::manifest::Method::view("mt_is_approved"),
+                        $crate::manifest::Method::view("mt_tokens"),
+                        $crate::manifest::Method::view("mt_tokens_for_owner"),
+                        $crate::manifest::Method::view("mt_metadata_contract"),
+                        $crate::manifest::Method::view("mt_metadata_token_by_token_id"),
+                    ],
+                    storage: &[
+                        // 4 + 64 (account id) + 8 (approval id) + 16 (amount)
+                        $crate::manifest::StorageEstimate::new("mt_approve", 92),
+                    ],
+                };
+            }
+            #[near_bindgen]
+            impl $contract {
+                /// `token_metadata` is required by the first mint of a token id.
+                #[payable]
+                pub fn mt_mint(
+                    &mut self,
+                    token_id: $crate::mt::TokenId,
+                    receiver_id: AccountId,
+                    amount: U128,
+                    token_metadata: Option<$crate::mt::TokenMetadata>,
+                ) {
+                    #[cfg(feature = "feature_flags")]
+                    $crate::features::require_enabled($crate::features::Feature::MtMint);
+                    require!(
+                        env::predecessor_account_id() == self.$mt.owner_id,
+                        "Only the owner can mint"
+                    );
+                    self.$mt.internal_mint(token_id, receiver_id, amount, token_metadata)
+                }
+            }
+            impl_multi_token_contract!(@IMPL_CORE $contract, $mt);
+            impl_multi_token_contract!(@IMPL_APPROVAL $contract, $mt);
+            impl_multi_token_contract!(@IMPL_ENUMERATION $contract, $mt);
+            #[near_bindgen]
+            impl $crate::mt::metadata::MultiTokenMetadataProvider for $contract {
+                fn mt_metadata_contract(&self) -> $crate::mt::Metadata {
+                    self.$mt.metadata.get().unwrap()
+                }
+
+                fn mt_metadata_token_by_token_id(
+                    &self,
+                    token_ids: Vec<$crate::mt::TokenId>,
+                ) -> Vec<Option<$crate::mt::TokenMetadata>> {
+                    token_ids
+                        .iter()
+                        .map(|token_id| self.$mt.token_metadata.get(token_id))
+                        .collect()
+                }
+            }
+        };
+    }
+pub use impl_multi_token_contract;
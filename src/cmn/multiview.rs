@@ -0,0 +1,178 @@
+#![allow(dead_code)]
+/*!
+Many views in one request.
+
+`impl_multiview!` generates a `multiview(calls)` view which dispatches every call to one of the
+registered view methods, so a frontend fetches E.g. balance, metadata and allowance in one RPC round-trip.
+
+# NOTES:
+  - Only the registered methods can be called, with the same JSON args they take on their own.
+  - Results are in the order of the calls, one failing call fails the whole request.
+  - At most [`MAX_VIEW_CALLS`] calls per request.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    balances: LookupMap<AccountId, Balance>,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        let mut balances = LookupMap::new(b"b");
+        balances.insert(owner_id.clone(), 100);
+        Self { owner_id, balances }
+    }
+
+    pub fn get_owner(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    pub fn get_balance(&self, account_id: AccountId) -> U128 {
+        self.balances.get(&account_id).copied().unwrap_or(0).into()
+    }
+}
+
+// impl_multiview!(Contract, <view method>(<arg>: <type>, ..), ..);
+multiview::impl_multiview!(Contract, get_owner(), get_balance(account_id: AccountId));
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+    use near_sdk::serde_json::{self, json};
+
+    #[test]
+    fn test_multiview() {
+        run_vm(vm!(accounts(0)));
+        let contract = Contract::new(accounts(0));
+
+        let calls = serde_json::from_value(json!([
+            { "method": "get_owner" },
+            { "method": "get_balance", "args": { "account_id": "alice" } },
+            { "method": "get_balance", "args": { "account_id": "bob" } },
+        ]))
+        .unwrap();
+        assert_eq!(
+            contract.multiview(calls),
+            vec![json!("alice"), json!("100"), json!("0")]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Unknown view method `new`")]
+    fn test_multiview_unknown() {
+        run_vm(vm!(accounts(0)));
+        let contract = Contract::new(accounts(0));
+
+        contract.multiview(vec![multiview::ViewCall {
+            method: "new".to_string(),
+            args: json!({ "owner_id": "bob" }),
+        }]);
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::{
+    serde::{de::DeserializeOwned, Deserialize, Serialize},
+    serde_json,
+};
+
+pub const MAX_VIEW_CALLS: usize = 32;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ViewCall {
+    pub method: String,
+    /// JSON args of the method, can be omitted when it takes none.
+    #[serde(default)]
+    pub args: serde_json::Value,
+}
+impl ViewCall {
+    /// Deserializes the args, panics with the method name if they don't match.
+    pub fn args_as<T: DeserializeOwned>(&self) -> T {
+        let args = match &self.args {
+            serde_json::Value::Null => serde_json::Value::Object(Default::default()),
+            args => args.clone(),
+        };
+        match serde_json::from_value(args) {
+            Ok(args) => args,
+            Err(e) => {
+                require!(false, format!("Invalid args of `{}`: {}", self.method, e));
+                unreachable!()
+            }
+        }
+    }
+}
+
+#[inline]
+pub fn unknown_method(method: &str) -> serde_json::Value {
+    require!(false, format!("Unknown view method `{}`", method));
+    unreachable!()
+}
+
+#[inline]
+pub fn assert_max_calls(calls: usize) {
+    require!(
+        calls <= MAX_VIEW_CALLS,
+        format!("Can't make more than {} view calls", MAX_VIEW_CALLS)
+    );
+}
+
+#[inline]
+pub fn to_value<T: Serialize>(result: &T) -> serde_json::Value {
+    serde_json::to_value(result).unwrap_or_else(|e| env::panic_str(&e.to_string()))
+}
+
+#[macro_export]
+macro_rules! impl_multiview {
+        ($contract:ident, $($method:ident($($arg:ident: $ty:ty),* $(,)?)),+ $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_multiview!`.
+                pub const MULTIVIEW_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: None,
+                    methods: &[$crate::manifest::Method::view("multiview")],
+                    storage: &[],
+                };
+            }
+            #[near_bindgen]
+            impl $contract {
+                /// Results of the view calls, in order.
+                pub fn multiview(
+                    &self,
+                    calls: Vec<$crate::multiview::ViewCall>,
+                ) -> Vec<near_sdk::serde_json::Value> {
+                    $crate::multiview::assert_max_calls(calls.len());
+                    calls
+                        .iter()
+                        .map(|call| match call.method.as_str() {
+                            $(stringify!($method) => {
+                                #[derive(near_sdk::serde::Deserialize)]
+                                #[serde(crate = "near_sdk::serde")]
+                                struct Args {
+                                    $($arg: $ty,)*
+                                }
+                                #[allow(unused_variables)]
+                                let Args { $($arg,)* } = call.args_as::<Args>();
+                                $crate::multiview::to_value(&self.$method($($arg),*))
+                            })+
+                            method => $crate::multiview::unknown_method(method),
+                        })
+                        .collect()
+                }
+            }
+        };
+    }
+pub use impl_multiview;
@@ -0,0 +1,427 @@
+#![cfg(feature = "names")]
+#![allow(dead_code)]
+/*!
+Name/handle registry with a registration fee and expiry.
+
+A handle resolves to an account until it expires. The owner can renew it during the grace period
+that follows the expiry, after that anyone can register it again.
+
+# NOTES:
+  - Handles are 2 to 32 bytes of `a-z`, `0-9`, `-` and `_`.
+  - `names_register` and `names_renew` take `fee_per_period` per period, the registration also
+    takes the measured storage of the record, like the other components do. Extra deposit is refunded.
+  - The storage is refunded to the previous owner when an expired handle is registered again.
+  - Fees stay on the contract account.
+  - Events are logged as `EVENT_JSON:` with the `names` standard.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    names: names::Names,
+}
+
+const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new() -> Self {
+        require_init!();
+        Self {
+            names: names::Names::new(names::Config {
+                fee_per_period: U128(NEAR / 10),
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+names::impl_names!(Contract, names);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    fn get_vm(predecessor: AccountId) -> VMContextBuilder {
+        vm!(predecessor)
+            .current_account_id("current".parse().unwrap())
+            .clone()
+    }
+
+    #[test]
+    fn test_register_and_expire() {
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new();
+        let config = contract.names_config();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR)
+                .predecessor_account_id(accounts(0)),
+        );
+        contract.names_register("alice".to_string(), None);
+        assert_eq!(contract.names_resolve("alice".to_string()), Some(accounts(0)));
+
+        // expired, but still in the grace period
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .block_timestamp(config.period.0 + 1)
+                .predecessor_account_id(accounts(1)),
+        );
+        assert_eq!(contract.names_resolve("alice".to_string()), None);
+
+        // after the grace period, bob can take it
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .block_timestamp(config.period.0 + config.grace_period.0 + 1)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.names_register("alice".to_string(), None);
+        assert_eq!(contract.names_resolve("alice".to_string()), Some(accounts(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Name is taken")]
+    fn test_register_taken() {
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR)
+                .predecessor_account_id(accounts(0)),
+        );
+        contract.names_register("alice".to_string(), None);
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.names_register("alice".to_string(), None);
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::{
+    collections::UnorderedMap,
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+
+/// A year in nanoseconds.
+pub const YEAR: u64 = 365 * 24 * 60 * 60 * 1_000_000_000;
+/// 30 days in nanoseconds.
+pub const GRACE_PERIOD: u64 = 30 * 24 * 60 * 60 * 1_000_000_000;
+
+pub const MIN_NAME_LENGTH: usize = 2;
+pub const MAX_NAME_LENGTH: usize = 32;
+
+raw_storage_key!(pub enum StorageKey in storage_key::NAMES {
+    Names,
+});
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Config {
+    pub fee_per_period: U128,
+    /// Registration period in nanoseconds.
+    pub period: U64,
+    /// Time (nanoseconds) after the expiry during which only the owner can renew.
+    pub grace_period: U64,
+}
+impl Default for Config {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            fee_per_period: U128(0),
+            period: U64(YEAR),
+            grace_period: U64(GRACE_PERIOD),
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NameRecord {
+    pub owner_id: AccountId,
+    /// The account the name resolves to.
+    pub target_id: AccountId,
+    /// Block timestamp (nanoseconds).
+    pub expires_at: U64,
+    /// Storage paid by the owner.
+    pub storage_deposit: U128,
+}
+impl NameRecord {
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        env::block_timestamp() >= self.expires_at.0
+    }
+}
+
+/// Logs a `names` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    events::emit(events::standards::NAMES, event, data);
+}
+
+pub fn assert_valid_name(name: &str) {
+    require!(
+        (MIN_NAME_LENGTH..=MAX_NAME_LENGTH).contains(&name.len()),
+        format!(
+            "Name must be {} to {} bytes",
+            MIN_NAME_LENGTH, MAX_NAME_LENGTH
+        )
+    );
+    require!(
+        name.bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_'),
+        "Name can only have a-z, 0-9, - and _"
+    );
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Names {
+    pub names: UnorderedMap<String, NameRecord>,
+    pub config: Config,
+}
+impl Names {
+    pub fn new(config: Config) -> Self {
+        Self {
+            names: UnorderedMap::new(StorageKey::Names),
+            config,
+        }
+    }
+
+    #[inline]
+    pub fn expect(&self, name: &String) -> NameRecord {
+        self.names
+            .get(name)
+            .unwrap_or_else(|| env::panic_str("Name not found"))
+    }
+
+    #[inline]
+    fn expect_owned(&self, name: &String) -> NameRecord {
+        let record = self.expect(name);
+        require!(
+            record.owner_id == env::predecessor_account_id(),
+            "Only the owner of the name can do this"
+        );
+        record
+    }
+
+    /// Registers a free or abandoned name for the predecessor.
+    pub fn register(&mut self, name: String, target_id: Option<AccountId>) {
+        assert_valid_name(&name);
+        let owner_id = env::predecessor_account_id();
+        let now = env::block_timestamp();
+
+        let previous = self.names.get(&name);
+        if let Some(previous) = &previous {
+            require!(
+                now >= previous.expires_at.0 + self.config.grace_period.0,
+                "Name is taken"
+            );
+        }
+
+        let initial_storage_usage = env::storage_usage();
+        self.names.remove(&name);
+        let mut record = NameRecord {
+            owner_id: owner_id.clone(),
+            target_id: target_id.unwrap_or_else(|| owner_id.clone()),
+            expires_at: U64(now + self.config.period.0),
+            storage_deposit: U128(0),
+        };
+        self.names.insert(&name, &record);
+        let storage_deposit =
+            protocol::storage_cost(env::storage_usage().saturating_sub(initial_storage_usage));
+        // the deposit amount has the same size whatever it is
+        record.storage_deposit = U128(storage_deposit);
+        self.names.insert(&name, &record);
+
+        if let Some(previous) = previous {
+            Promise::new(previous.owner_id).transfer(previous.storage_deposit.0);
+        }
+        charge(self.config.fee_per_period.0 + storage_deposit);
+
+        emit(
+            "names_register",
+            serde_json::json!({ "name": name, "record": record }),
+        );
+    }
+
+    /// Extends the name by a period, the owner can still renew during the grace period.
+    pub fn renew(&mut self, name: String) {
+        let mut record = self.expect_owned(&name);
+        let now = env::block_timestamp();
+        require!(
+            now < record.expires_at.0 + self.config.grace_period.0,
+            "Grace period is over"
+        );
+        record.expires_at = U64(record.expires_at.0.max(now) + self.config.period.0);
+        self.names.insert(&name, &record);
+        charge(self.config.fee_per_period.0);
+
+        emit(
+            "names_renew",
+            serde_json::json!({ "name": name, "expires_at": record.expires_at }),
+        );
+    }
+
+    pub fn transfer(&mut self, name: String, new_owner_id: AccountId) {
+        let mut record = self.expect_owned(&name);
+        require!(!record.is_expired(), "Name is expired");
+        // the new owner gets the storage deposit with the name
+        record.owner_id = new_owner_id.clone();
+        record.target_id = new_owner_id.clone();
+        self.names.insert(&name, &record);
+
+        emit(
+            "names_transfer",
+            serde_json::json!({ "name": name, "new_owner_id": new_owner_id }),
+        );
+    }
+
+    /// Transfers `names` of `old_account_id` to `new_account_id`, also during their grace period.
+    /// Names that resolved to `old_account_id` resolve to `new_account_id`.
+    pub fn migrate_account(
+        &mut self,
+        old_account_id: &AccountId,
+        new_account_id: &AccountId,
+        names: &[String],
+    ) {
+        let now = env::block_timestamp();
+        for name in names {
+            let mut record = self.expect(name);
+            require!(
+                &record.owner_id == old_account_id,
+                "Only the owner of the name can do this"
+            );
+            require!(
+                now < record.expires_at.0 + self.config.grace_period.0,
+                "Grace period is over"
+            );
+            record.owner_id = new_account_id.clone();
+            if &record.target_id == old_account_id {
+                record.target_id = new_account_id.clone();
+            }
+            self.names.insert(name, &record);
+
+            emit(
+                "names_transfer",
+                serde_json::json!({ "name": name, "new_owner_id": new_account_id }),
+            );
+        }
+    }
+
+    pub fn set_target(&mut self, name: String, target_id: AccountId) {
+        let mut record = self.expect_owned(&name);
+        require!(!record.is_expired(), "Name is expired");
+        record.target_id = target_id.clone();
+        self.names.insert(&name, &record);
+
+        emit(
+            "names_set_target",
+            serde_json::json!({ "name": name, "target_id": target_id }),
+        );
+    }
+
+    /// The target of the name, `None` once it's expired.
+    pub fn resolve(&self, name: &String) -> Option<AccountId> {
+        self.names
+            .get(name)
+            .filter(|record| !record.is_expired())
+            .map(|record| record.target_id)
+    }
+}
+
+/// Requires `amount` from the attached deposit and refunds the rest.
+fn charge(amount: Balance) {
+    let attached = env::attached_deposit();
+    require!(
+        attached >= amount,
+        format!(
+            "Must attach {} yoctoNEAR to cover the fee and storage",
+            amount
+        )
+    );
+    if attached > amount {
+        Promise::new(env::predecessor_account_id()).transfer(attached - amount);
+    }
+}
+
+#[macro_export]
+macro_rules! impl_names {
+    ($contract:ident, $names:ident) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_names!`.
+            pub const NAMES_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: Some("names"),
+                methods: &[
+                    $crate::manifest::Method::payable("names_register"),
+                    $crate::manifest::Method::payable("names_renew"),
+                    $crate::manifest::Method::payable("names_transfer"),
+                    $crate::manifest::Method::payable("names_set_target"),
+                    $crate::manifest::Method::view("names_resolve"),
+                    $crate::manifest::Method::view("names_record"),
+                    $crate::manifest::Method::view("names_config"),
+                ],
+                storage: &[
+                    // a 32 bytes name with 64 bytes owner and target
+                    $crate::manifest::StorageEstimate::new("names_register", 400),
+                ],
+            };
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// `target_id` defaults to the predecessor.
+            #[payable]
+            pub fn names_register(&mut self, name: String, target_id: Option<AccountId>) {
+                #[cfg(feature = "decommission")]
+                $crate::decommission::require_active();
+                self.$names.register(name, target_id)
+            }
+
+            #[payable]
+            pub fn names_renew(&mut self, name: String) {
+                #[cfg(feature = "decommission")]
+                $crate::decommission::require_active();
+                self.$names.renew(name)
+            }
+
+            #[payable]
+            pub fn names_transfer(&mut self, name: String, new_owner_id: AccountId) {
+                near_sdk::assert_one_yocto();
+                self.$names.transfer(name, new_owner_id)
+            }
+
+            #[payable]
+            pub fn names_set_target(&mut self, name: String, target_id: AccountId) {
+                near_sdk::assert_one_yocto();
+                self.$names.set_target(name, target_id)
+            }
+
+            pub fn names_resolve(&self, name: String) -> Option<AccountId> {
+                self.$names.resolve(&name)
+            }
+
+            pub fn names_record(&self, name: String) -> Option<$crate::names::NameRecord> {
+                self.$names.names.get(&name)
+            }
+
+            pub fn names_config(&self) -> $crate::names::Config {
+                self.$names.config.clone()
+            }
+        }
+    };
+}
+pub use impl_names;
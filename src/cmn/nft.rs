@@ -15,6 +15,42 @@ Non-Fungible Token implementation with JSON serialization.
     If the storage decreases, the contract will issue a refund for the cost of the released storage.
     The unused tokens from the attached deposit are also refunded, so it's safe to
     attach more deposit than required.
+  - Approvals are charged and refunded by the measured storage, not by a per-account estimate,
+    so `nft_revoke`/`nft_revoke_all` give back everything `nft_approve` took, including the
+    approvals map of the token itself.
+  - `nft_approve_all` approves an operator for every token of the owner (like ERC-721
+    `setApprovalForAll`). Operators transfer without an `approval_id`, and transfers still
+    clear the per-token approvals.
+  - `nft_approve_with_expiry` approves until a block timestamp, transfers of the approved account
+    fail after it and `nft_is_approved` is false. Expired approvals are dropped by the next
+    approval of the token or by anyone with `nft_prune_approvals` (or the `expiry` sweep with its feature),
+    `nft_approvals` lists the live ones with their expiry.
+  - `impl_non_fungible_token_detailed!` adds `nft_tokens_detailed`, the tokens of an owner with
+    their metadata, royalty split and approvals, and the operators of the owner, in one view.
+    `fields` leaves out the parts a frontend doesn't need.
+  - `NonFungibleToken::with_hash_verification()` makes `nft_mint` require a well-formed
+    (32 bytes, base64) `media_hash`/`reference_hash` whenever `media`/`reference` is set.
+  - `NonFungibleToken::with_lazy_metadata()` keeps `description`, `extra` and `reference` of minted
+    tokens under their own key, so reads of the rest of the metadata (E.g. `nft_token_uri` of a
+    token with media) don't load them. Views of whole tokens join them back.
+  - `nft_approve_all`/`nft_revoke_all_operator` and the wrapper methods that charge or release
+    storage take a `refund_to` (the predecessor by default), E.g. the relayer of a meta transaction.
+  - `nft_supply_for_owner` reads a counter per owner. Owners of a contract deployed before the
+    counters fall back to their token set, `NonFungibleToken::migrate_owner_counts` stores theirs.
+  - `nft_token_uri` resolves `media` (or `reference`) against `base_uri` of the contract metadata.
+    `ipfs://` URIs are kept unless the macro is given an `ipfs_gateway`.
+  - `NonFungibleToken::with_provenance()` keeps the last [`MAX_PROVENANCE_LENGTH`] owners of each
+    token (`nft_provenance`). It's off by default, the contract pays the storage of the history
+    of transfers, only the first entry is charged on mint.
+  - `NonFungibleToken::with_attributes()` indexes the tokens by the attributes in the `extra` of
+    their metadata, for `nft_tokens_by_attribute`, see [`attributes`]. The minter pays the index.
+  - With the `mint_fee` feature, `impl_non_fungible_token_contract!(.., mint_fee = <field>)`
+    charges a platform fee on `nft_mint` on top of the storage, see `fee`.
+  - With the `payout` feature, `impl_non_fungible_token_contract!(.., payouts = <field>)` adds the
+    NEP-199 `nft_payout` and `nft_transfer_payout` for marketplaces, paying the royalties of a
+    `royalty::Registry`, see `payout`.
+  - With the `clawback` feature a regulator can be allowed to force transfers of any token, until
+    the capability is renounced for good, see `clawback`.
   - To prevent the deployed contract from being modified or deleted, it should not have any access
     keys on its account.
 
@@ -31,7 +67,11 @@ pub struct Contract {
 
 const DATA_IMAGE_SVG_NEAR_ICON: &str = "data:image/svg+xml,%3Csvg xmlns='http://www.w3.org/2000/svg' viewBox='0 0 288 288'%3E%3Cg id='l' data-name='l'%3E%3Cpath d='M187.58,79.81l-30.1,44.69a3.2,3.2,0,0,0,4.75,4.2L191.86,103a1.2,1.2,0,0,1,2,.91v80.46a1.2,1.2,0,0,1-2.12.77L102.18,77.93A15.35,15.35,0,0,0,90.47,72.5H87.34A15.34,15.34,0,0,0,72,87.84V201.16A15.34,15.34,0,0,0,87.34,216.5h0a15.35,15.35,0,0,0,13.08-7.31l30.1-44.69a3.2,3.2,0,0,0-4.75-4.2L96.14,186a1.2,1.2,0,0,1-2-.91V104.61a1.2,1.2,0,0,1,2.12-.77l89.55,107.23a15.35,15.35,0,0,0,11.71,5.43h3.13A15.34,15.34,0,0,0,216,201.16V87.84A15.34,15.34,0,0,0,200.66,72.5h0A15.35,15.35,0,0,0,187.58,79.81Z'/%3E%3C/g%3E%3C/svg%3E";
 
+// impl_non_fungible_token_contract!(Contract, nft[, ipfs_gateway = "https://ipfs.io/ipfs/"][, mint_fee = <nft::fee::MintFee field>][, payouts = <nft::Payouts field>]);
 nft::impl_non_fungible_token_contract!(Contract, nft);
+// impl_non_fungible_token_detailed!(Contract, nft[, royalties = <royalty::Registry field>]);
+nft::impl_non_fungible_token_detailed!(Contract, nft);
+events::impl_supported_standards!(Contract, NEP171, NEP177, NEP178, NEP181, NEP297);
 
 #[near_bindgen]
 impl Contract {
@@ -53,6 +93,14 @@ impl Contract {
             ),
         }
     }
+
+    #[init]
+    pub fn new_with_args(args: nft::InitArgs) -> Self {
+        require_init!();
+        Self {
+            nft: nft::NonFungibleToken::from_args(args),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -60,10 +108,15 @@ mod tests {
     use super::test_utils::*;
     use super::*;
 
-    use nft::{approval::NonFungibleTokenApproval, core::NonFungibleTokenCore};
+    use nft::{
+        approval::NonFungibleTokenApproval,
+        core::{NonFungibleTokenCore, NonFungibleTokenResolver},
+        enumeration::NonFungibleTokenEnumeration,
+    };
     use std::collections::HashMap;
 
-    const MINT_STORAGE_COST: u128 = 5870000000000000000000;
+    const MINT_STORAGE_COST: u128 = 6450000000000000000000;
+    const APPROVE_STORAGE_COST: u128 = 1000000000000000000000;
 
     fn get_vm(predecessor: AccountId) -> VMContextBuilder {
         vm!(predecessor)
@@ -126,6 +179,32 @@ mod tests {
         assert_eq!(token.approved_account_ids.unwrap(), HashMap::new());
     }
 
+    #[test]
+    fn test_mint_lazy_metadata() {
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let Contract { nft } = Contract::new(accounts(0));
+        let mut contract = Contract {
+            nft: nft.with_lazy_metadata(),
+        };
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST * 2)
+                .predecessor_account_id(accounts(0)),
+        );
+        let token_id = "0".to_string();
+        let token = contract.nft_mint(token_id.clone(), accounts(0), sample_token_metadata());
+        assert_eq!(token.metadata.unwrap(), sample_token_metadata());
+
+        let stored = contract.nft.token.token_metadata_by_id.as_ref().unwrap();
+        assert_eq!(stored.get(&token_id).unwrap().description, None);
+        assert_eq!(
+            contract.nft_token(token_id).unwrap().metadata.unwrap(),
+            sample_token_metadata()
+        );
+    }
+
     #[test]
     fn test_transfer() {
         let mut vm = get_vm(accounts(0));
@@ -163,6 +242,115 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_provenance() {
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let Contract { nft } = Contract::new(accounts(0));
+        let mut contract = Contract {
+            nft: nft.with_provenance(),
+        };
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST * 2)
+                .predecessor_account_id(accounts(0)),
+        );
+        let token_id = "0".to_string();
+        contract.nft_mint(token_id.clone(), accounts(0), sample_token_metadata());
+
+        for (i, receiver_id) in [accounts(1), accounts(2)].into_iter().enumerate() {
+            run_vm(
+                vm.storage_usage(env::storage_usage())
+                    .attached_deposit(1)
+                    .block_timestamp(i as u64 + 1)
+                    .predecessor_account_id(accounts(i)),
+            );
+            contract.nft_transfer(receiver_id, token_id.clone(), None, None);
+        }
+
+        let owners: Vec<_> = contract
+            .nft_provenance(token_id.clone(), None, None)
+            .into_iter()
+            .map(|entry| (entry.owner_id, entry.timestamp.0))
+            .collect();
+        assert_eq!(
+            owners,
+            vec![(accounts(0), 0), (accounts(1), 1), (accounts(2), 2)]
+        );
+        let page = contract.nft_provenance(token_id, Some(U128(1)), Some(1));
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].owner_id, accounts(1));
+        assert_eq!(page[0].price, None);
+    }
+
+    #[test]
+    fn test_transfer_call_reentrancy() {
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST)
+                .predecessor_account_id(accounts(0)),
+        );
+        let token_id = "0".to_string();
+        contract.nft_mint(token_id.clone(), accounts(0), sample_token_metadata());
+
+        let mut probe = ReentrancyProbe::new(&vm);
+        probe.call(accounts(0), || {
+            contract.nft_transfer_call(accounts(1), token_id.clone(), None, None, "".to_string())
+        });
+        // the receiver passes the token on, then asks for it back
+        probe.reenter(accounts(1), || {
+            contract.nft_transfer(accounts(3), token_id.clone(), None, None)
+        });
+        let transferred = probe.resolve(true, || {
+            contract.nft_resolve_transfer(accounts(0), accounts(1), token_id.clone(), None)
+        });
+
+        assert!(transferred);
+        assert_eq!(contract.nft_token(token_id).unwrap().owner_id, accounts(3));
+        assert_eq!(contract.nft_supply_for_owner(accounts(0)), U128(0));
+        assert_eq!(contract.nft_supply_for_owner(accounts(1)), U128(0));
+        assert_eq!(contract.nft_supply_for_owner(accounts(3)), U128(1));
+    }
+
+    #[test]
+    fn test_supply_for_owner() {
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST)
+                .predecessor_account_id(accounts(0)),
+        );
+        contract.nft_mint("0".to_string(), accounts(0), sample_token_metadata());
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST),
+        );
+        contract.nft_mint("1".to_string(), accounts(0), sample_token_metadata());
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.nft_transfer(accounts(1), "0".to_string(), None, None);
+        assert_eq!(contract.nft_supply_for_owner(accounts(0)), U128(1));
+        assert_eq!(contract.nft_supply_for_owner(accounts(1)), U128(1));
+        assert_eq!(contract.nft_supply_for_owner(accounts(2)), U128(0));
+
+        // deployed before the counters
+        contract.nft.owner_counts.remove(&accounts(1));
+        assert_eq!(contract.nft_supply_for_owner(accounts(1)), U128(1));
+        assert_eq!(
+            contract.nft.migrate_owner_counts(&[accounts(1), accounts(2)]),
+            1
+        );
+        assert_eq!(contract.nft.owner_counts.try_get(&accounts(1)), Some(1));
+    }
+
     #[test]
     fn test_approve() {
         let mut vm = get_vm(accounts(0));
@@ -180,7 +368,7 @@ mod tests {
         // alice approves bob
         run_vm(
             vm.storage_usage(env::storage_usage())
-                .attached_deposit(150000000000000000000)
+                .attached_deposit(APPROVE_STORAGE_COST)
                 .predecessor_account_id(accounts(0)),
         );
         contract.nft_approve(token_id.clone(), accounts(1), None);
@@ -194,6 +382,70 @@ mod tests {
         assert!(contract.nft_is_approved(token_id, accounts(1), Some(1)));
     }
 
+    fn approve_with_expiry(vm: &mut VMContextBuilder, contract: &mut Contract, expires_at: u64) {
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST)
+                .predecessor_account_id(accounts(0)),
+        );
+        contract.nft_mint("0".to_string(), accounts(0), sample_token_metadata());
+
+        // alice approves bob until `expires_at`
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(2 * APPROVE_STORAGE_COST),
+        );
+        contract.nft_approve_with_expiry("0".to_string(), accounts(1), U64(expires_at), None);
+    }
+
+    #[test]
+    fn test_approve_with_expiry() {
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+        approve_with_expiry(&mut vm, &mut contract, 100);
+        let token_id = "0".to_string();
+
+        run_vm(vm.block_timestamp(99).is_view(true));
+        assert!(contract.nft_is_approved(token_id.clone(), accounts(1), Some(1)));
+        assert_eq!(
+            contract.nft_approvals(token_id.clone())[0].expires_at,
+            Some(U64(100))
+        );
+
+        run_vm(vm.block_timestamp(100));
+        assert!(!contract.nft_is_approved(token_id.clone(), accounts(1), Some(1)));
+        assert!(contract.nft_approvals(token_id.clone()).is_empty());
+
+        // anyone drops it, alice gets the storage back
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .is_view(false)
+                .attached_deposit(0)
+                .predecessor_account_id(accounts(2)),
+        );
+        assert_eq!(contract.nft_prune_approvals(token_id.clone()), 1);
+        assert!(get_transfers_to(&accounts(0)) > 0);
+        assert!(contract.nft.approval_expiries.get(&token_id).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Approval expired")]
+    fn test_transfer_expired_approval() {
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+        approve_with_expiry(&mut vm, &mut contract, 100);
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .block_timestamp(100)
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.nft_transfer(accounts(1), "0".to_string(), Some(1), None);
+    }
+
     #[test]
     fn test_revoke() {
         let mut vm = get_vm(accounts(0));
@@ -211,7 +463,7 @@ mod tests {
         // alice approves bob
         run_vm(
             vm.storage_usage(env::storage_usage())
-                .attached_deposit(150000000000000000000)
+                .attached_deposit(APPROVE_STORAGE_COST)
                 .predecessor_account_id(accounts(0)),
         );
         contract.nft_approve(token_id.clone(), accounts(1), None);
@@ -249,7 +501,7 @@ mod tests {
         // alice approves bob
         run_vm(
             vm.storage_usage(env::storage_usage())
-                .attached_deposit(150000000000000000000)
+                .attached_deposit(APPROVE_STORAGE_COST)
                 .predecessor_account_id(accounts(0)),
         );
         contract.nft_approve(token_id.clone(), accounts(1), None);
@@ -269,14 +521,224 @@ mod tests {
         );
         assert!(!contract.nft_is_approved(token_id, accounts(1), Some(1)));
     }
+
+    #[test]
+    fn test_revoke_all_refund() {
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST)
+                .predecessor_account_id(accounts(0)),
+        );
+        let token_id = "0".to_string();
+        contract.nft_mint(token_id.clone(), accounts(0), sample_token_metadata());
+
+        // alice approves a lot of accounts
+        let mut spenders = vec![];
+        for i in 0..50 {
+            let spender: AccountId = format!("spender-{}.near", i).parse().unwrap();
+            run_vm(
+                vm.storage_usage(env::storage_usage())
+                    .attached_deposit(APPROVE_STORAGE_COST)
+                    .predecessor_account_id(accounts(0)),
+            );
+            contract.nft_approve(token_id.clone(), spender.clone(), None);
+            spenders.push(spender);
+        }
+
+        // alice revokes all of them
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(0)),
+        );
+        let storage_before_revoke = env::storage_usage();
+        contract.nft_revoke_all(token_id);
+        let released = storage_before_revoke - env::storage_usage();
+
+        // more than the per-account estimate of the standard, the map itself is refunded too
+        let estimated: u64 = spenders.iter().map(nft::bytes_for_approved_account_id).sum();
+        assert!(released > estimated);
+        assert_eq!(
+            get_transfers_to(&accounts(0)),
+            released as u128 * env::storage_byte_cost()
+        );
+    }
+
+    #[test]
+    fn test_tokens_detailed() {
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+        approve_with_expiry(&mut vm, &mut contract, 100);
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(APPROVE_STORAGE_COST),
+        );
+        contract.nft_approve_all(accounts(2), None);
+
+        run_vm(vm.is_view(true));
+        let detailed = contract.nft_tokens_detailed(accounts(0), None, None, None);
+        assert_eq!(detailed.operators, Some(vec![accounts(2)]));
+        let token = &detailed.tokens[0];
+        assert_eq!(token.metadata, Some(sample_token_metadata()));
+        assert_eq!(token.approvals.as_ref().unwrap()[0].account_id, accounts(1));
+        // no royalty registry
+        assert_eq!(token.royalty, None);
+
+        let fields = nft::DetailFields {
+            metadata: false,
+            operators: false,
+            ..Default::default()
+        };
+        let detailed = contract.nft_tokens_detailed(accounts(0), None, None, Some(fields));
+        assert_eq!(detailed.operators, None);
+        assert_eq!(detailed.tokens[0].metadata, None);
+        assert!(detailed.tokens[0].approvals.is_some());
+    }
+
+    #[test]
+    fn test_operator_transfer() {
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST)
+                .predecessor_account_id(accounts(0)),
+        );
+        let token_id = "0".to_string();
+        contract.nft_mint(token_id.clone(), accounts(0), sample_token_metadata());
+
+        // alice approves bob for all of her tokens
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(APPROVE_STORAGE_COST)
+                .predecessor_account_id(accounts(0)),
+        );
+        // a relayer attached the deposit
+        contract.nft_approve_all(accounts(1), Some(accounts(3)));
+        assert!(get_transfers_to(&accounts(0)) == 0);
+        assert!(get_transfers_to(&accounts(3)) > 0);
+        assert!(contract.nft_is_approved_operator(accounts(0), accounts(1)));
+
+        // bob transfers alice's token to charlie
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.nft_transfer(accounts(2), token_id.clone(), None, None);
+        assert_eq!(
+            contract.nft_token(token_id).unwrap().owner_id,
+            accounts(2)
+        );
+
+        // alice revokes bob
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(0)),
+        );
+        contract.nft_revoke_all_operator(accounts(1), None);
+        assert!(!contract.nft_is_approved_operator(accounts(0), accounts(1)));
+    }
+
+    #[test]
+    fn test_token_uri() {
+        assert_eq!(
+            nft::token_uri(Some("https://example.com/"), "/1.png", None),
+            "https://example.com/1.png"
+        );
+        assert_eq!(
+            nft::token_uri(Some("https://example.com"), "ipfs://Qm1", None),
+            "ipfs://Qm1"
+        );
+        assert_eq!(
+            nft::token_uri(Some("ipfs://Qm0"), "1.png", Some("https://ipfs.io/ipfs/")),
+            "https://ipfs.io/ipfs/Qm0/1.png"
+        );
+
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST * 2)
+                .predecessor_account_id(accounts(0)),
+        );
+        let token_id = "0".to_string();
+        contract.nft_mint(
+            token_id.clone(),
+            accounts(0),
+            nft::TokenMetadata {
+                media: Some("ipfs://Qm1".into()),
+                ..sample_token_metadata()
+            },
+        );
+        assert_eq!(
+            contract.nft_token_uri(token_id),
+            Some("ipfs://Qm1".to_string())
+        );
+        assert_eq!(contract.nft_token_uri("1".to_string()), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "media_hash must be 32 bytes")]
+    fn test_mint_with_invalid_media_hash() {
+        let mut vm = get_vm(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+        contract.nft = contract.nft.with_hash_verification();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST * 2)
+                .predecessor_account_id(accounts(0)),
+        );
+        contract.nft_mint(
+            "0".to_string(),
+            accounts(0),
+            nft::TokenMetadata {
+                media: Some("ipfs://Qm1".into()),
+                media_hash: Some(vec![0; 31].into()),
+                ..sample_token_metadata()
+            },
+        );
+    }
+
+    #[test]
+    fn test_new_with_args() {
+        let mut vm = get_vm(accounts(1));
+        run_vm(&vm);
+
+        let args: nft::InitArgs = near_sdk::serde_json::from_str(
+            r#"{ "name": "Example NEAR NFT", "symbol": "EXAMPLE" }"#,
+        )
+        .unwrap();
+        let contract = Contract::new_with_args(args);
+
+        run_vm(vm.is_view(true));
+        assert_eq!(contract.nft.metadata.get().unwrap().spec, nft::METADATA_SPEC);
+        assert_eq!(contract.nft.token.owner_id, accounts(1));
+    }
 }
 ```
 */
 
 use super::*;
 
+use near_sdk::serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
 pub use near_contract_standards::non_fungible_token::{
-    self,
+    self, events,
     metadata::{
         self, NFTContractMetadata as Metadata, TokenMetadata, NFT_METADATA_SPEC as METADATA_SPEC,
     },
@@ -293,18 +755,280 @@ mod for_rust_core {
         Metadata = 3,
         Enumeration = 4,
         Approval = 5,
+        Operators = 6,
+        TokenExtras = 7,
+        OwnerCounts = 8,
+        Provenance = 9,
+        ApprovalExpiries = 10,
     }
 }
 pub use for_rust_core::*;
 
+pub mod attributes;
+pub mod clawback;
+pub mod fee;
+pub mod payout;
+
+#[cfg(feature = "payout")]
+pub use payout::{Payout, Payouts};
+
+/// Gas attached to the `nft_resolve_transfer` callback.
+pub const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(5_000_000_000_000);
+/// Gas `nft_transfer_call` keeps for itself and the callback, the rest goes to `nft_on_transfer`.
+pub const GAS_FOR_NFT_TRANSFER_CALL: Gas = Gas(25_000_000_000_000 + GAS_FOR_RESOLVE_TRANSFER.0);
+
+fn assert_valid_hash(field: &str, uri: &Option<String>, hash: &Option<Base64VecU8>) {
+    match (uri, hash) {
+        (Some(_), None) => env::panic_str(&format!("{}_hash is required", field)),
+        (_, Some(hash)) => require!(
+            hash.0.len() == 32,
+            format!("{}_hash must be 32 bytes", field)
+        ),
+        (None, None) => {}
+    }
+}
+
+/// Resolves a `media`/`reference` value of token metadata to a full URI.
+///
+/// Absolute URIs are kept as is and relative ones are joined to `base_uri`.
+/// With `ipfs_gateway` (E.g. `"https://ipfs.io/ipfs/"`), `ipfs://` URIs are rewritten to the gateway.
+pub fn token_uri(base_uri: Option<&str>, path: &str, ipfs_gateway: Option<&str>) -> String {
+    let uri = match base_uri {
+        Some(base_uri) if !path.contains("://") => format!(
+            "{}/{}",
+            base_uri.trim_end_matches('/'),
+            path.trim_start_matches('/')
+        ),
+        _ => path.to_string(),
+    };
+    match (ipfs_gateway, uri.strip_prefix("ipfs://")) {
+        (Some(gateway), Some(cid)) => format!(
+            "{}/{}",
+            gateway.trim_end_matches('/'),
+            cid.trim_start_matches("ipfs/")
+        ),
+        _ => uri,
+    }
+}
+
+// near-contract-standards keeps these private.
+#[near_sdk::ext_contract(ext_nft_receiver)]
+pub trait NftReceiver {
+    fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: TokenId,
+        msg: String,
+    ) -> PromiseOrValue<bool>;
+}
+
+#[near_sdk::ext_contract(ext_nft_resolver)]
+pub trait NftResolver {
+    fn nft_resolve_transfer(
+        &mut self,
+        previous_owner_id: AccountId,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approved_account_ids: Option<HashMap<AccountId, u64>>,
+    ) -> bool;
+}
+
+/// Init args as a single JSON object. Every field has a default,
+/// so new fields don't break existing deploy scripts.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(crate = "near_sdk::serde", default)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InitArgs {
+    /// Defaults to the predecessor.
+    pub owner_id: Option<AccountId>,
+    /// Defaults to [`METADATA_SPEC`].
+    pub spec: Option<String>,
+    pub name: String,
+    pub symbol: String,
+    pub icon: Option<String>,
+    pub base_uri: Option<String>,
+    pub reference: Option<String>,
+    pub reference_hash: Option<Base64VecU8>,
+    /// See `NonFungibleToken::with_hash_verification`.
+    pub verify_hashes: bool,
+    /// See `NonFungibleToken::with_provenance`.
+    pub provenance: bool,
+    /// See `NonFungibleToken::with_attributes`.
+    pub attributes: bool,
+}
+impl InitArgs {
+    #[inline]
+    pub fn metadata(&self) -> Metadata {
+        Metadata {
+            spec: self
+                .spec
+                .clone()
+                .unwrap_or_else(|| METADATA_SPEC.to_string()),
+            name: self.name.clone(),
+            symbol: self.symbol.clone(),
+            icon: self.icon.clone(),
+            base_uri: self.base_uri.clone(),
+            reference: self.reference.clone(),
+            reference_hash: self.reference_hash.clone(),
+        }
+    }
+}
+
+/// [`Metadata::assert_valid`] with bounded names and well-formed URIs.
+pub fn assert_valid_metadata(metadata: &Metadata) {
+    use validate::*;
+    metadata.assert_valid();
+    validate!(metadata.name => non_empty, max_len(MAX_NAME_LENGTH));
+    validate!(metadata.symbol => non_empty, max_len(MAX_NAME_LENGTH));
+    validate!(?metadata.icon => valid_data_uri, max_len(MAX_ICON_LENGTH));
+    validate!(?metadata.base_uri => valid_url, max_len(MAX_URL_LENGTH));
+    validate!(?metadata.reference => valid_url, max_len(MAX_URL_LENGTH));
+}
+
+/// Bounds the strings of token metadata, `media` and `reference` can be relative to `base_uri`.
+pub fn assert_valid_token_metadata(metadata: &TokenMetadata) {
+    use validate::*;
+    validate!(?metadata.title => max_len(MAX_NAME_LENGTH));
+    validate!(?metadata.description => max_len(MAX_DESCRIPTION_LENGTH));
+    validate!(?metadata.media => non_empty, max_len(MAX_URL_LENGTH));
+    validate!(?metadata.reference => non_empty, max_len(MAX_URL_LENGTH));
+    validate!(?metadata.extra => max_len(MAX_DESCRIPTION_LENGTH));
+}
+
+/// Entries kept per token, the oldest are dropped.
+pub const MAX_PROVENANCE_LENGTH: usize = 20;
+
+/// An owner of a token, see `NonFungibleToken::with_provenance`.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Provenance {
+    pub owner_id: AccountId,
+    /// Paid by the owner, none for mints and plain transfers.
+    pub price: Option<U128>,
+    /// Block timestamp (nanoseconds) the owner got the token.
+    pub timestamp: U64,
+}
+
+/// Expiry of an approval, only for the `approval_id` it was given with.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApprovalExpiry {
+    pub approval_id: u64,
+    /// Block timestamp (nanoseconds) from which the approval is expired.
+    pub expires_at: u64,
+}
+
+/// An approval of a token, see `nft_approvals`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ApprovalView {
+    pub account_id: AccountId,
+    pub approval_id: u64,
+    /// Block timestamp (nanoseconds), none for approvals that don't expire.
+    pub expires_at: Option<U64>,
+}
+
+/// Default and max number of tokens of `nft_tokens_detailed`.
+pub const MAX_DETAILED_TOKENS: usize = 50;
+
+/// Parts of the tokens in `nft_tokens_detailed`, all of them by default.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde", default)]
+pub struct DetailFields {
+    pub metadata: bool,
+    pub royalty: bool,
+    pub approvals: bool,
+    pub operators: bool,
+}
+impl Default for DetailFields {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            metadata: true,
+            royalty: true,
+            approvals: true,
+            operators: true,
+        }
+    }
+}
+
+/// A token in `nft_tokens_detailed`, the parts left out by [`DetailFields`] are none.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenDetailed {
+    pub token_id: TokenId,
+    pub metadata: Option<TokenMetadata>,
+    pub royalty: Option<royalty::Royalty>,
+    pub approvals: Option<Vec<ApprovalView>>,
+}
+
+/// Result of `nft_tokens_detailed`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokensDetailed {
+    pub owner_id: AccountId,
+    /// Approved for every token of the owner, sorted.
+    pub operators: Option<Vec<AccountId>>,
+    pub tokens: Vec<TokenDetailed>,
+}
+
+/// `description`, `extra` and `reference` of a token, see `NonFungibleToken::with_lazy_metadata`.
+#[derive(BorshDeserialize, BorshSerialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct TokenExtras {
+    pub description: Option<String>,
+    pub extra: Option<String>,
+    pub reference: Option<String>,
+    pub reference_hash: Option<Base64VecU8>,
+}
+impl TokenExtras {
+    /// Takes the heavy fields out of `metadata`.
+    pub fn split(mut metadata: TokenMetadata) -> (TokenMetadata, Self) {
+        let extras = Self {
+            description: metadata.description.take(),
+            extra: metadata.extra.take(),
+            reference: metadata.reference.take(),
+            reference_hash: metadata.reference_hash.take(),
+        };
+        (metadata, extras)
+    }
+
+    /// Puts the heavy fields back into `metadata`.
+    pub fn join(self, mut metadata: TokenMetadata) -> TokenMetadata {
+        metadata.description = self.description;
+        metadata.extra = self.extra;
+        metadata.reference = self.reference;
+        metadata.reference_hash = self.reference_hash;
+        metadata
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+}
+
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct NonFungibleToken {
     pub token: NFToken,
     pub metadata: LazyOption<Metadata>,
+    /// Operators approved for all tokens of an owner.
+    pub operators: collections::LookupMap<AccountId, HashSet<AccountId>>,
+    /// Requires `media_hash`/`reference_hash` for `media`/`reference` on mint.
+    pub verify_hashes: bool,
+    /// Heavy token metadata fields, kept apart when lazy metadata is enabled.
+    pub token_extras: Option<collections::LookupMap<TokenId, TokenExtras>>,
+    /// Number of tokens per owner, for `nft_supply_for_owner`.
+    pub owner_counts: utils::counter::CounterMap<AccountId>,
+    /// Last owners of each token, when provenance is enabled.
+    pub provenance: Option<collections::LookupMap<TokenId, Vec<Provenance>>>,
+    /// Expiries of the approvals given by `nft_approve_with_expiry`.
+    pub approval_expiries: collections::LookupMap<TokenId, HashMap<AccountId, ApprovalExpiry>>,
+    /// Tokens per attribute, when the index is enabled.
+    pub attributes: Option<attributes::AttributeIndex>,
 }
 impl NonFungibleToken {
     pub fn new(owner_id: AccountId, metadata: Metadata) -> Self {
-        metadata.assert_valid();
+        assert_valid_metadata(&metadata);
         Self {
             token: NFToken::new(
                 // owner_by_id_prefix: Q,
@@ -319,7 +1043,909 @@ impl NonFungibleToken {
                 Some(StorageKey::Approval),
             ),
             metadata: LazyOption::new(StorageKey::Metadata, Some(&metadata)),
+            operators: collections::LookupMap::new(StorageKey::Operators),
+            verify_hashes: false,
+            token_extras: None,
+            owner_counts: utils::counter::CounterMap::new(StorageKey::OwnerCounts),
+            provenance: None,
+            approval_expiries: collections::LookupMap::new(StorageKey::ApprovalExpiries),
+            attributes: None,
+        }
+    }
+
+    pub fn from_args(args: InitArgs) -> Self {
+        let metadata = args.metadata();
+        let owner_id = args.owner_id.unwrap_or_else(env::predecessor_account_id);
+        let mut this = Self::new(owner_id, metadata);
+        if args.verify_hashes {
+            this = this.with_hash_verification();
+        }
+        if args.provenance {
+            this = this.with_provenance();
+        }
+        if args.attributes {
+            this = this.with_attributes();
+        }
+        this
+    }
+
+    /// Requires every minted `media`/`reference` to come with a 32 bytes sha256 hash.
+    pub fn with_hash_verification(mut self) -> Self {
+        self.verify_hashes = true;
+        self
+    }
+
+    /// Stores `description`, `extra` and `reference` of minted tokens under their own key.
+    pub fn with_lazy_metadata(mut self) -> Self {
+        self.token_extras = Some(collections::LookupMap::new(StorageKey::TokenExtras));
+        self
+    }
+
+    /// Records the owners of the tokens, see [`MAX_PROVENANCE_LENGTH`].
+    pub fn with_provenance(mut self) -> Self {
+        self.provenance = Some(collections::LookupMap::new(StorageKey::Provenance));
+        self
+    }
+
+    /// Indexes the attributes of the minted tokens, see [`attributes`].
+    pub fn with_attributes(mut self) -> Self {
+        self.attributes = Some(attributes::AttributeIndex::new());
+        self
+    }
+
+    /// Page of the tokens with the attribute, empty when the index is disabled.
+    pub fn tokens_by_attribute(
+        &self,
+        key: &str,
+        value: &str,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<Token> {
+        self.attributes
+            .as_ref()
+            .map(|attributes| attributes.token_ids(key, value, from_index, limit))
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|token_id| self.nft_token(token_id))
+            .collect()
+    }
+
+    /// Appends the new owner of the token when provenance is enabled.
+    pub fn record_provenance(
+        &mut self,
+        token_id: &TokenId,
+        owner_id: &AccountId,
+        price: Option<Balance>,
+    ) {
+        let provenance = match self.provenance.as_mut() {
+            Some(provenance) => provenance,
+            None => return,
+        };
+        let mut entries = provenance.get(token_id).unwrap_or_default();
+        if entries.len() >= MAX_PROVENANCE_LENGTH {
+            entries.drain(..=entries.len() - MAX_PROVENANCE_LENGTH);
+        }
+        entries.push(Provenance {
+            owner_id: owner_id.clone(),
+            price: price.map(U128),
+            timestamp: U64(env::block_timestamp()),
+        });
+        provenance.insert(token_id, &entries);
+    }
+
+    /// Owners of the token from the oldest kept, empty when provenance is disabled.
+    pub fn provenance(
+        &self,
+        token_id: &TokenId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<Provenance> {
+        self.provenance
+            .as_ref()
+            .and_then(|provenance| provenance.get(token_id))
+            .unwrap_or_default()
+            .into_iter()
+            .skip(from_index.map_or(0, |index| index.0 as usize))
+            .take(limit.map_or(MAX_PROVENANCE_LENGTH, |limit| limit as usize))
+            .collect()
+    }
+
+    /// Page of the tokens of the owner with the `fields` parts, the royalties come from the
+    /// registry if any.
+    pub fn tokens_detailed(
+        &self,
+        owner_id: AccountId,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+        fields: DetailFields,
+        royalties: Option<&royalty::Registry>,
+    ) -> TokensDetailed {
+        let limit = limit.map_or(MAX_DETAILED_TOKENS, |limit| {
+            (limit as usize).min(MAX_DETAILED_TOKENS)
+        });
+        let token_ids: Vec<TokenId> = self
+            .token
+            .tokens_per_owner
+            .as_ref()
+            .and_then(|tokens_per_owner| tokens_per_owner.get(&owner_id))
+            .map(|token_ids| {
+                token_ids
+                    .iter()
+                    .skip(from_index.map_or(0, |index| index.0 as usize))
+                    .take(limit)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let tokens = token_ids
+            .into_iter()
+            .map(|token_id| TokenDetailed {
+                metadata: fields
+                    .metadata
+                    .then(|| self.token_metadata(&token_id))
+                    .flatten(),
+                royalty: royalties
+                    .filter(|_| fields.royalty)
+                    .map(|royalties| royalties.get(&token_id)),
+                approvals: fields.approvals.then(|| self.approvals(&token_id)),
+                token_id,
+            })
+            .collect();
+        let operators = fields.operators.then(|| {
+            let mut operators: Vec<_> = self
+                .operators
+                .get(&owner_id)
+                .unwrap_or_default()
+                .into_iter()
+                .collect();
+            operators.sort_unstable();
+            operators
+        });
+        TokensDetailed {
+            owner_id,
+            operators,
+            tokens,
+        }
+    }
+
+    /// Metadata of the token with its lazily stored fields.
+    pub fn token_metadata(&self, token_id: &TokenId) -> Option<TokenMetadata> {
+        let metadata = self.token.token_metadata_by_id.as_ref()?.get(token_id)?;
+        Some(self.join_extras(token_id, metadata))
+    }
+
+    /// `nft_token` of the standard with the lazily stored metadata fields.
+    pub fn nft_token(&self, token_id: TokenId) -> Option<Token> {
+        non_fungible_token::core::NonFungibleTokenCore::nft_token(&self.token, token_id)
+            .map(|token| self.with_extras(token))
+    }
+
+    /// Joins the lazily stored metadata fields back into `token`.
+    pub fn with_extras(&self, mut token: Token) -> Token {
+        if self.token_extras.is_some() {
+            token.metadata = token
+                .metadata
+                .map(|metadata| self.join_extras(&token.token_id, metadata));
+        }
+        token
+    }
+
+    fn join_extras(&self, token_id: &TokenId, metadata: TokenMetadata) -> TokenMetadata {
+        match self
+            .token_extras
+            .as_ref()
+            .and_then(|token_extras| token_extras.get(token_id))
+        {
+            Some(extras) => extras.join(metadata),
+            None => metadata,
+        }
+    }
+
+    /// `internal_mint` of the standard that verifies the metadata hashes when enabled.
+    #[inline]
+    pub fn internal_mint(
+        &mut self,
+        token_id: TokenId,
+        receiver_id: AccountId,
+        token_metadata: TokenMetadata,
+    ) -> Token {
+        self.internal_mint_refund_to(token_id, receiver_id, token_metadata, None)
+    }
+
+    /// [`NonFungibleToken::internal_mint`] that refunds the unused deposit to `refund_to`.
+    #[inline]
+    pub fn internal_mint_refund_to(
+        &mut self,
+        token_id: TokenId,
+        receiver_id: AccountId,
+        token_metadata: TokenMetadata,
+        refund_to: Option<AccountId>,
+    ) -> Token {
+        self.internal_mint_with_fee(token_id, receiver_id, token_metadata, 0, refund_to)
+    }
+
+    /// [`NonFungibleToken::internal_mint_refund_to`] that also keeps `fee` of the deposit,
+    /// see `fee::MintFee`.
+    pub fn internal_mint_with_fee(
+        &mut self,
+        token_id: TokenId,
+        receiver_id: AccountId,
+        token_metadata: TokenMetadata,
+        fee: Balance,
+        refund_to: Option<AccountId>,
+    ) -> Token {
+        assert_valid_token_metadata(&token_metadata);
+        if self.verify_hashes {
+            assert_valid_hash("media", &token_metadata.media, &token_metadata.media_hash);
+            assert_valid_hash(
+                "reference",
+                &token_metadata.reference,
+                &token_metadata.reference_hash,
+            );
+        }
+        let token = self.track_holders(&[&receiver_id.clone()], |nft| {
+            let initial_storage_usage = env::storage_usage();
+            let attributes = match (&nft.attributes, &token_metadata.extra) {
+                (Some(_), Some(extra)) => attributes::parse(extra),
+                _ => vec![],
+            };
+            let (metadata, extras) = match nft.token_extras {
+                Some(_) => TokenExtras::split(token_metadata),
+                None => (token_metadata, TokenExtras::default()),
+            };
+            let token =
+                nft.token
+                    .internal_mint_with_refund(token_id, receiver_id, Some(metadata), None);
+            if !extras.is_empty() {
+                nft.token_extras
+                    .as_mut()
+                    .unwrap()
+                    .insert(&token.token_id, &extras);
+            }
+            if let Some(index) = nft.attributes.as_mut() {
+                index.insert(&token.token_id, &attributes);
+            }
+            nft.update_owner_count(&token.owner_id, true);
+            nft.record_provenance(&token.token_id, &token.owner_id, None);
+            refund_deposit_less_fee_to(
+                env::storage_usage() - initial_storage_usage,
+                fee,
+                refund_to,
+            );
+            events::NftMint {
+                owner_id: &token.owner_id,
+                token_ids: &[&token.token_id],
+                memo: None,
+            }
+            .emit();
+            nft.with_extras(token)
+        });
+        stats::record(stats::Counter::Mints);
+        token
+    }
+
+    /// Number of tokens of the owner, from its counter, or its token set until it has one.
+    pub fn supply_for_owner(&self, account_id: &AccountId) -> u64 {
+        self.owner_counts
+            .try_get(account_id)
+            .unwrap_or_else(|| self.token_set_len(account_id))
+    }
+
+    #[inline]
+    fn token_set_len(&self, account_id: &AccountId) -> u64 {
+        self.token
+            .tokens_per_owner
+            .as_ref()
+            .and_then(|tokens_per_owner| tokens_per_owner.get(account_id))
+            .map_or(0, |token_ids| token_ids.len())
+    }
+
+    /// Updates the counter of the owner after the standard added or removed one of its tokens.
+    fn update_owner_count(&mut self, account_id: &AccountId, added: bool) {
+        if !self.owner_counts.contains_key(account_id) {
+            // the standard already updated the token set
+            let count = self.token_set_len(account_id);
+            self.owner_counts.set(account_id, count);
+        } else if added {
+            self.owner_counts.incr(account_id);
+        } else {
+            self.owner_counts.decr(account_id);
+        }
+    }
+
+    /// Stores the counters of owners that got their tokens before counters existed,
+    /// returns how many were stored.
+    pub fn migrate_owner_counts(&mut self, account_ids: &[AccountId]) -> u32 {
+        let mut migrated = 0;
+        for account_id in account_ids {
+            if self.owner_counts.contains_key(account_id) {
+                continue;
+            }
+            let count = self.token_set_len(account_id);
+            if count > 0 {
+                self.owner_counts.set(account_id, count);
+                migrated += 1;
+            }
+        }
+        migrated
+    }
+
+    /// Transfers up to `limit` tokens of `old_account_id` to `new_account_id` and drops the
+    /// operators of `old_account_id`, their storage is refunded to `new_account_id`.
+    /// Returns the transferred token ids and how many tokens are left.
+    pub fn migrate_account(
+        &mut self,
+        old_account_id: &AccountId,
+        new_account_id: &AccountId,
+        limit: u32,
+    ) -> (Vec<TokenId>, u64) {
+        let token_ids: Vec<TokenId> = self
+            .token
+            .tokens_per_owner
+            .as_ref()
+            .unwrap_or_else(|| env::panic_str("Migration requires the enumeration extension"))
+            .get(old_account_id)
+            .map(|token_ids| token_ids.iter().take(limit as usize).collect())
+            .unwrap_or_default();
+        for token_id in &token_ids {
+            self.internal_transfer(
+                old_account_id,
+                new_account_id,
+                token_id,
+                None,
+                Some("Account migration".to_string()),
+            );
+        }
+
+        // operators approved by a compromised account shouldn't follow it
+        let initial_storage_usage = env::storage_usage();
+        self.operators.remove(old_account_id);
+        refund_released_storage(initial_storage_usage, new_account_id.clone());
+
+        (token_ids, self.token_set_len(old_account_id))
+    }
+
+    /// Whether the account owns a token, always false without the enumeration extension.
+    #[inline]
+    pub fn is_holder(&self, account_id: &AccountId) -> bool {
+        self.token
+            .tokens_per_owner
+            .as_ref()
+            .and_then(|tokens_per_owner| tokens_per_owner.get(account_id))
+            .is_some_and(|token_ids| !token_ids.is_empty())
+    }
+
+    /// Runs `f` and records in [`stats`] the accounts that started or stopped holding tokens.
+    /// With the `membership` feature, drops the cached membership tiers of the accounts.
+    pub fn track_holders<R>(
+        &mut self,
+        account_ids: &[&AccountId],
+        f: impl FnOnce(&mut Self) -> R,
+    ) -> R {
+        #[cfg(feature = "membership")]
+        for account_id in account_ids {
+            membership::invalidate(account_id);
+        }
+        if !stats::ENABLED || self.token.tokens_per_owner.is_none() {
+            return f(self);
+        }
+        let were_holders: Vec<bool> = account_ids.iter().map(|id| self.is_holder(id)).collect();
+        let result = f(self);
+        for (account_id, was_holder) in account_ids.iter().zip(were_holders) {
+            stats::record_holder(was_holder, self.is_holder(account_id));
+        }
+        result
+    }
+
+    pub fn is_operator(&self, owner_id: &AccountId, operator_id: &AccountId) -> bool {
+        self.operators
+            .get(owner_id)
+            .is_some_and(|operators| operators.contains(operator_id))
+    }
+
+    /// Approves `operator_id` for all tokens of the predecessor, charging the measured storage.
+    pub fn nft_approve_all(&mut self, operator_id: AccountId, refund_to: Option<AccountId>) {
+        require!(
+            env::attached_deposit() >= 1,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        let owner_id = env::predecessor_account_id();
+        require!(owner_id != operator_id, "Owner can't be its own operator");
+
+        let initial_storage_usage = env::storage_usage();
+        let mut operators = self.operators.get(&owner_id).unwrap_or_default();
+        operators.insert(operator_id.clone());
+        self.operators.insert(&owner_id, &operators);
+        refund_deposit_to(
+            env::storage_usage().saturating_sub(initial_storage_usage),
+            refund_to,
+        );
+
+        log!("@{} approved operator @{}", owner_id, operator_id);
+    }
+
+    /// Revokes `operator_id` of the predecessor, refunding the measured storage released.
+    pub fn nft_revoke_all_operator(
+        &mut self,
+        operator_id: AccountId,
+        refund_to: Option<AccountId>,
+    ) {
+        near_sdk::assert_one_yocto();
+        let owner_id = env::predecessor_account_id();
+        let initial_storage_usage = env::storage_usage();
+
+        if let Some(mut operators) = self.operators.get(&owner_id) {
+            if operators.remove(&operator_id) {
+                if operators.is_empty() {
+                    self.operators.remove(&owner_id);
+                } else {
+                    self.operators.insert(&owner_id, &operators);
+                }
+                log!("@{} revoked operator @{}", owner_id, operator_id);
+            }
+        }
+
+        refund_released_storage(initial_storage_usage, refund_to.unwrap_or(owner_id));
+    }
+
+    /// `internal_transfer` of the standard that also lets operators of the owner transfer.
+    /// Returns the previous owner and approvals.
+    #[inline]
+    pub fn internal_transfer(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        token_id: &TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    ) -> (AccountId, Option<HashMap<AccountId, u64>>) {
+        self.internal_transfer_with_price(sender_id, receiver_id, token_id, approval_id, memo, None)
+    }
+
+    /// [`NonFungibleToken::internal_transfer`] of a sale, the price is kept in the provenance.
+    pub fn internal_transfer_with_price(
+        &mut self,
+        sender_id: &AccountId,
+        receiver_id: &AccountId,
+        token_id: &TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        price: Option<Balance>,
+    ) -> (AccountId, Option<HashMap<AccountId, u64>>) {
+        if let Some(memo) = &memo {
+            strings::assert_max_bytes(memo, strings::MAX_MEMO_LENGTH);
+        }
+        let owner_id = self
+            .token
+            .owner_by_id
+            .get(token_id)
+            .unwrap_or_else(|| env::panic_str("Token not found"));
+        stats::record(stats::Counter::Transfers);
+        if sender_id == &owner_id || !self.is_operator(&owner_id, sender_id) {
+            require!(
+                !self.is_approval_expired(token_id, sender_id),
+                "Approval expired"
+            );
+            let result = self.track_holders(&[&owner_id, receiver_id], |nft| {
+                nft.token
+                    .internal_transfer(sender_id, receiver_id, token_id, approval_id, memo)
+            });
+            self.update_owner_count(&owner_id, false);
+            self.update_owner_count(receiver_id, true);
+            self.record_provenance(token_id, receiver_id, price);
+            return result;
+        }
+
+        let approved_account_ids = self
+            .token
+            .approvals_by_id
+            .as_mut()
+            .and_then(|by_id| by_id.remove(token_id));
+        require!(
+            &owner_id != receiver_id,
+            "Current and next owner must differ"
+        );
+        self.track_holders(&[&owner_id, receiver_id], |nft| {
+            nft.token
+                .internal_transfer_unguarded(token_id, &owner_id, receiver_id)
+        });
+        self.update_owner_count(&owner_id, false);
+        self.update_owner_count(receiver_id, true);
+        self.record_provenance(token_id, receiver_id, price);
+
+        events::NftTransfer {
+            old_owner_id: &owner_id,
+            new_owner_id: receiver_id,
+            token_ids: &[token_id],
+            authorized_id: Some(sender_id),
+            memo: memo.as_deref(),
+        }
+        .emit();
+
+        (owner_id, approved_account_ids)
+    }
+
+    /// Transfers the token without the consent of its owner, E.g. a clawback by `authorized_id`,
+    /// see `clawback`. Drops the approvals of the token, their storage is refunded to the
+    /// previous owner, which is returned.
+    pub fn internal_force_transfer(
+        &mut self,
+        token_id: &TokenId,
+        receiver_id: &AccountId,
+        authorized_id: &AccountId,
+        memo: &str,
+    ) -> AccountId {
+        strings::assert_max_bytes(memo, strings::MAX_MEMO_LENGTH);
+        let owner_id = self
+            .token
+            .owner_by_id
+            .get(token_id)
+            .unwrap_or_else(|| env::panic_str("Token not found"));
+        require!(
+            &owner_id != receiver_id,
+            "Current and next owner must differ"
+        );
+        stats::record(stats::Counter::Transfers);
+
+        let initial_storage_usage = env::storage_usage();
+        if let Some(approvals_by_id) = self.token.approvals_by_id.as_mut() {
+            approvals_by_id.remove(token_id);
+        }
+        self.approval_expiries.remove(token_id);
+        refund_released_storage(initial_storage_usage, owner_id.clone());
+
+        self.track_holders(&[&owner_id, receiver_id], |nft| {
+            nft.token
+                .internal_transfer_unguarded(token_id, &owner_id, receiver_id)
+        });
+        self.update_owner_count(&owner_id, false);
+        self.update_owner_count(receiver_id, true);
+        self.record_provenance(token_id, receiver_id, None);
+
+        events::NftTransfer {
+            old_owner_id: &owner_id,
+            new_owner_id: receiver_id,
+            token_ids: &[token_id],
+            authorized_id: Some(authorized_id),
+            memo: Some(memo),
+        }
+        .emit();
+
+        owner_id
+    }
+
+    pub fn nft_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    ) {
+        near_sdk::assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        self.internal_transfer(&sender_id, &receiver_id, &token_id, approval_id, memo);
+    }
+
+    pub fn nft_transfer_call(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        near_sdk::assert_one_yocto();
+        require!(
+            env::prepaid_gas() > GAS_FOR_NFT_TRANSFER_CALL,
+            "More gas is required"
+        );
+        let sender_id = env::predecessor_account_id();
+        let (old_owner, old_approvals) =
+            self.internal_transfer(&sender_id, &receiver_id, &token_id, approval_id, memo);
+
+        ext_nft_receiver::ext(receiver_id.clone())
+            .with_static_gas(env::prepaid_gas() - GAS_FOR_NFT_TRANSFER_CALL)
+            .nft_on_transfer(sender_id, old_owner.clone(), token_id.clone(), msg)
+            .then(
+                ext_nft_resolver::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                    .nft_resolve_transfer(old_owner, receiver_id, token_id, old_approvals),
+            )
+            .into()
+    }
+
+    /// `nft_resolve_transfer` of the standard that keeps the owner counters.
+    pub fn nft_resolve_transfer(
+        &mut self,
+        previous_owner_id: AccountId,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approved_account_ids: Option<HashMap<AccountId, u64>>,
+    ) -> bool {
+        let transferred = self.track_holders(&[&previous_owner_id, &receiver_id], |nft| {
+            non_fungible_token::core::NonFungibleTokenResolver::nft_resolve_transfer(
+                &mut nft.token,
+                previous_owner_id.clone(),
+                receiver_id.clone(),
+                token_id.clone(),
+                approved_account_ids,
+            )
+        });
+        if !transferred {
+            self.update_owner_count(&receiver_id, false);
+            self.update_owner_count(&previous_owner_id, true);
+            // the token came back
+            self.record_provenance(&token_id, &previous_owner_id, None);
+        }
+        transferred
+    }
+
+    /// URI of the token `media`, or of its `reference` when it has no media.
+    pub fn token_uri(&self, token_id: &TokenId, ipfs_gateway: Option<&str>) -> Option<String> {
+        let metadata = self.token.token_metadata_by_id.as_ref()?.get(token_id)?;
+        let path = match metadata.media {
+            Some(media) => media,
+            None => self.token_metadata(token_id)?.reference?,
+        };
+        let base_uri = self.metadata.get().and_then(|metadata| metadata.base_uri);
+        Some(token_uri(base_uri.as_deref(), &path, ipfs_gateway))
+    }
+
+    fn expect_owner(&self, token_id: &TokenId) -> AccountId {
+        let owner_id = self
+            .token
+            .owner_by_id
+            .get(token_id)
+            .unwrap_or_else(|| env::panic_str("Token not found"));
+        require!(
+            env::predecessor_account_id() == owner_id,
+            "Predecessor must be token owner."
+        );
+        owner_id
+    }
+
+    /// `nft_approve` that charges the measured storage of the approval,
+    /// including the approvals map of the token when it's the first approval.
+    #[inline]
+    pub fn nft_approve(
+        &mut self,
+        token_id: TokenId,
+        account_id: AccountId,
+        msg: Option<String>,
+        refund_to: Option<AccountId>,
+    ) -> Option<Promise> {
+        self.nft_approve_with_expiry(token_id, account_id, None, msg, refund_to)
+    }
+
+    /// [`NonFungibleToken::nft_approve`] until the block timestamp `expires_at`, if any.
+    /// Drops the expired approvals of the token first.
+    pub fn nft_approve_with_expiry(
+        &mut self,
+        token_id: TokenId,
+        account_id: AccountId,
+        expires_at: Option<u64>,
+        msg: Option<String>,
+        refund_to: Option<AccountId>,
+    ) -> Option<Promise> {
+        require!(
+            env::attached_deposit() >= 1,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        let owner_id = self.expect_owner(&token_id);
+        if let Some(expires_at) = expires_at {
+            require!(
+                expires_at > env::block_timestamp(),
+                "Expiry must be in the future"
+            );
+        }
+
+        let next_approval_id_by_id = self
+            .token
+            .next_approval_id_by_id
+            .as_mut()
+            .unwrap_or_else(|| env::panic_str("next_approval_by_id must be set for approval ext"));
+        let approval_id = next_approval_id_by_id.get(&token_id).unwrap_or(1);
+        next_approval_id_by_id.insert(&token_id, &(approval_id + 1));
+
+        // only the approvals maps are charged, it's exactly what the revokes release.
+        let initial_storage_usage = env::storage_usage();
+        self.prune_approvals(&token_id);
+        let approvals_by_id = self
+            .token
+            .approvals_by_id
+            .as_mut()
+            .unwrap_or_else(|| env::panic_str("NFT does not support Approval Management"));
+        let mut approved_account_ids = approvals_by_id.get(&token_id).unwrap_or_default();
+        approved_account_ids.insert(account_id.clone(), approval_id);
+        approvals_by_id.insert(&token_id, &approved_account_ids);
+
+        let mut expiries = self.approval_expiries.get(&token_id).unwrap_or_default();
+        let changed = match expires_at {
+            Some(expires_at) => {
+                let expiry = ApprovalExpiry {
+                    approval_id,
+                    expires_at,
+                };
+                expiries.insert(account_id.clone(), expiry);
+                true
+            }
+            None => expiries.remove(&account_id).is_some(),
+        };
+        if changed {
+            self.set_approval_expiries(&token_id, &expiries);
+        }
+        let storage_used = env::storage_usage().saturating_sub(initial_storage_usage);
+
+        refund_deposit_to(storage_used, refund_to);
+        #[cfg(feature = "expiry")]
+        if let Some(expires_at) = expires_at {
+            expiry::register(
+                expiry::ExpiryKind::NftApproval,
+                token_id.clone(),
+                expires_at,
+            );
+        }
+
+        msg.map(|msg| {
+            approval::ext_nft_approval_receiver::ext(account_id)
+                .with_static_gas(env::prepaid_gas() - GAS_FOR_NFT_APPROVE)
+                .nft_on_approve(token_id, owner_id, approval_id, msg)
+        })
+    }
+
+    /// `nft_revoke` that refunds the measured storage released.
+    pub fn nft_revoke(
+        &mut self,
+        token_id: TokenId,
+        account_id: AccountId,
+        refund_to: Option<AccountId>,
+    ) {
+        near_sdk::assert_one_yocto();
+        let owner_id = self.expect_owner(&token_id);
+        let initial_storage_usage = env::storage_usage();
+
+        let approvals_by_id = self
+            .token
+            .approvals_by_id
+            .as_mut()
+            .unwrap_or_else(|| env::panic_str("NFT does not support Approval Management"));
+        if let Some(mut approved_account_ids) = approvals_by_id.get(&token_id) {
+            if approved_account_ids.remove(&account_id).is_some() {
+                if approved_account_ids.is_empty() {
+                    approvals_by_id.remove(&token_id);
+                } else {
+                    approvals_by_id.insert(&token_id, &approved_account_ids);
+                }
+            }
+        }
+        if let Some(mut expiries) = self.approval_expiries.get(&token_id) {
+            if expiries.remove(&account_id).is_some() {
+                self.set_approval_expiries(&token_id, &expiries);
+            }
+        }
+
+        refund_released_storage(initial_storage_usage, refund_to.unwrap_or(owner_id));
+    }
+
+    /// `nft_revoke_all` that refunds the measured storage released,
+    /// so large approval maps are refunded in full.
+    pub fn nft_revoke_all(&mut self, token_id: TokenId, refund_to: Option<AccountId>) {
+        near_sdk::assert_one_yocto();
+        let owner_id = self.expect_owner(&token_id);
+        let initial_storage_usage = env::storage_usage();
+
+        self.token
+            .approvals_by_id
+            .as_mut()
+            .unwrap_or_else(|| env::panic_str("NFT does not support Approval Management"))
+            .remove(&token_id);
+        self.approval_expiries.remove(&token_id);
+
+        refund_released_storage(initial_storage_usage, refund_to.unwrap_or(owner_id));
+    }
+
+    #[inline]
+    fn set_approval_expiries(
+        &mut self,
+        token_id: &TokenId,
+        expiries: &HashMap<AccountId, ApprovalExpiry>,
+    ) {
+        if expiries.is_empty() {
+            self.approval_expiries.remove(token_id);
+        } else {
+            self.approval_expiries.insert(token_id, expiries);
+        }
+    }
+
+    /// Block timestamp from which the current approval of the account expires, if it does.
+    pub fn approval_expiry(&self, token_id: &TokenId, account_id: &AccountId) -> Option<u64> {
+        let approval_id = *self
+            .token
+            .approvals_by_id
+            .as_ref()?
+            .get(token_id)?
+            .get(account_id)?;
+        // an expiry of a revoked or replaced approval doesn't apply
+        self.approval_expiries
+            .get(token_id)?
+            .get(account_id)
+            .filter(|expiry| expiry.approval_id == approval_id)
+            .map(|expiry| expiry.expires_at)
+    }
+
+    #[inline]
+    pub fn is_approval_expired(&self, token_id: &TokenId, account_id: &AccountId) -> bool {
+        self.approval_expiry(token_id, account_id)
+            .is_some_and(|expires_at| env::block_timestamp() >= expires_at)
+    }
+
+    /// Approvals of the token that haven't expired.
+    pub fn approvals(&self, token_id: &TokenId) -> Vec<ApprovalView> {
+        let approved_account_ids = self
+            .token
+            .approvals_by_id
+            .as_ref()
+            .and_then(|by_id| by_id.get(token_id))
+            .unwrap_or_default();
+        let mut approvals: Vec<_> = approved_account_ids
+            .into_iter()
+            .filter(|(account_id, _)| !self.is_approval_expired(token_id, account_id))
+            .map(|(account_id, approval_id)| ApprovalView {
+                expires_at: self.approval_expiry(token_id, &account_id).map(U64),
+                account_id,
+                approval_id,
+            })
+            .collect();
+        approvals.sort_unstable_by_key(|approval| approval.approval_id);
+        approvals
+    }
+
+    /// Drops the expired approvals of the token and the expiries that no longer apply,
+    /// returns how many approvals were dropped.
+    pub fn prune_approvals(&mut self, token_id: &TokenId) -> u32 {
+        let expiries = match self.approval_expiries.get(token_id) {
+            Some(expiries) => expiries,
+            None => return 0,
+        };
+        let approvals_by_id = match self.token.approvals_by_id.as_mut() {
+            Some(approvals_by_id) => approvals_by_id,
+            None => return 0,
+        };
+        let mut approved_account_ids = approvals_by_id.get(token_id).unwrap_or_default();
+        let now = env::block_timestamp();
+        let mut kept = HashMap::new();
+        let mut pruned = 0;
+        for (account_id, expiry) in expiries {
+            if approved_account_ids.get(&account_id) != Some(&expiry.approval_id) {
+                // revoked, replaced or cleared by a transfer
+                continue;
+            }
+            if now >= expiry.expires_at {
+                approved_account_ids.remove(&account_id);
+                pruned += 1;
+            } else {
+                kept.insert(account_id, expiry);
+            }
         }
+        if pruned > 0 {
+            if approved_account_ids.is_empty() {
+                approvals_by_id.remove(token_id);
+            } else {
+                approvals_by_id.insert(token_id, &approved_account_ids);
+            }
+        }
+        self.set_approval_expiries(token_id, &kept);
+        pruned
+    }
+}
+
+/// Gas kept by `nft_approve` when it calls `nft_on_approve`.
+pub const GAS_FOR_NFT_APPROVE: Gas = Gas(10_000_000_000_000);
+
+/// Refunds the storage released since `initial_storage_usage` to `account_id`.
+pub fn refund_released_storage(initial_storage_usage: u64, account_id: AccountId) {
+    let released = initial_storage_usage.saturating_sub(env::storage_usage());
+    if released > 0 {
+        Promise::new(account_id).transfer(protocol::storage_cost(released));
     }
 }
 
@@ -336,7 +1962,9 @@ macro_rules! impl_non_fungible_token_contract {
                     approval_id: Option<u64>,
                     memo: Option<String>,
                 ) {
-                    self.$nft.token.nft_transfer(receiver_id, token_id, approval_id, memo)
+                    #[cfg(feature = "feature_flags")]
+                    $crate::features::require_enabled($crate::features::Feature::NftTransfer);
+                    self.$nft.nft_transfer(receiver_id, token_id, approval_id, memo)
                 }
 
                 #[payable]
@@ -348,11 +1976,13 @@ macro_rules! impl_non_fungible_token_contract {
                     memo: Option<String>,
                     msg: String,
                 ) -> PromiseOrValue<bool> {
-                    self.$nft.token.nft_transfer_call(receiver_id, token_id, approval_id, memo, msg)
+                    #[cfg(feature = "feature_flags")]
+                    $crate::features::require_enabled($crate::features::Feature::NftTransferCall);
+                    self.$nft.nft_transfer_call(receiver_id, token_id, approval_id, memo, msg)
                 }
 
                 fn nft_token(&self, token_id: $crate::nft::TokenId) -> Option<$crate::nft::Token> {
-                    self.$nft.token.nft_token(token_id)
+                    self.$nft.nft_token(token_id)
                 }
             }
 
@@ -366,7 +1996,7 @@ macro_rules! impl_non_fungible_token_contract {
                     token_id: $crate::nft::TokenId,
                     approved_account_ids: Option<std::collections::HashMap<AccountId, u64>>,
                 ) -> bool {
-                    self.$nft.token.nft_resolve_transfer(
+                    self.$nft.nft_resolve_transfer(
                         previous_owner_id,
                         receiver_id,
                         token_id,
@@ -385,17 +2015,19 @@ macro_rules! impl_non_fungible_token_contract {
                     account_id: AccountId,
                     msg: Option<String>,
                 ) -> Option<Promise> {
-                    self.$nft.token.nft_approve(token_id, account_id, msg)
+                    #[cfg(feature = "feature_flags")]
+                    $crate::features::require_enabled($crate::features::Feature::NftApprove);
+                    self.$nft.nft_approve(token_id, account_id, msg, None)
                 }
 
                 #[payable]
                 fn nft_revoke(&mut self, token_id: $crate::nft::TokenId, account_id: AccountId) {
-                    self.$nft.token.nft_revoke(token_id, account_id)
+                    self.$nft.nft_revoke(token_id, account_id, None)
                 }
 
                 #[payable]
                 fn nft_revoke_all(&mut self, token_id: $crate::nft::TokenId) {
-                    self.$nft.token.nft_revoke_all(token_id)
+                    self.$nft.nft_revoke_all(token_id, None)
                 }
 
                 fn nft_is_approved(
@@ -404,7 +2036,8 @@ macro_rules! impl_non_fungible_token_contract {
                     approved_account_id: AccountId,
                     approval_id: Option<u64>,
                 ) -> bool {
-                    self.$nft.token.nft_is_approved(token_id, approved_account_id, approval_id)
+                    !self.$nft.is_approval_expired(&token_id, &approved_account_id)
+                        && self.$nft.token.nft_is_approved(token_id, approved_account_id, approval_id)
                 }
             }
         };
@@ -420,11 +2053,16 @@ macro_rules! impl_non_fungible_token_contract {
                     from_index: Option<U128>,
                     limit: Option<u64>,
                 ) -> Vec<$crate::nft::Token> {
-                    self.$nft.token.nft_tokens(from_index, limit)
+                    self.$nft
+                        .token
+                        .nft_tokens(from_index, limit)
+                        .into_iter()
+                        .map(|token| self.$nft.with_extras(token))
+                        .collect()
                 }
 
                 fn nft_supply_for_owner(&self, account_id: AccountId) -> U128 {
-                    self.$nft.token.nft_supply_for_owner(account_id)
+                    U128(self.$nft.supply_for_owner(&account_id).into())
                 }
 
                 fn nft_tokens_for_owner(
@@ -433,11 +2071,143 @@ macro_rules! impl_non_fungible_token_contract {
                     from_index: Option<U128>,
                     limit: Option<u64>,
                 ) -> Vec<$crate::nft::Token> {
-                    self.$nft.token.nft_tokens_for_owner(account_id, from_index, limit)
+                    self.$nft
+                        .token
+                        .nft_tokens_for_owner(account_id, from_index, limit)
+                        .into_iter()
+                        .map(|token| self.$nft.with_extras(token))
+                        .collect()
                 }
             }
         };
-        ($contract:ident, $nft:ident) => {
+        (@IMPL_PAYOUT $contract:ident, $nft:ident, $payouts:ident) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_non_fungible_token_contract!(.., payouts = ..)`.
+                pub const NFT_PAYOUT_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("payout"),
+                    methods: &[
+                        $crate::manifest::Method::payable("nft_set_royalty").role("owner"),
+                        $crate::manifest::Method::view("nft_royalty"),
+                        $crate::manifest::Method::view("nft_payout"),
+                        $crate::manifest::Method::payable("nft_transfer_payout"),
+                    ],
+                    storage: &[],
+                };
+            }
+            #[near_bindgen]
+            impl $contract {
+                /// The owner of the contract sets the split of the token, `None` falls back to the default.
+                /// Charges the storage of the split, refunds it when the split shrinks or is removed.
+                #[payable]
+                pub fn nft_set_royalty(
+                    &mut self,
+                    token_id: $crate::nft::TokenId,
+                    royalty: Option<$crate::royalty::Royalty>,
+                ) {
+                    require!(
+                        env::attached_deposit() >= 1,
+                        "Requires attached deposit of at least 1 yoctoNEAR"
+                    );
+                    require!(
+                        env::predecessor_account_id() == self.$nft.token.owner_id,
+                        "Only the owner can set royalties"
+                    );
+                    require!(
+                        self.$nft.token.owner_by_id.contains_key(&token_id),
+                        "Token not found"
+                    );
+                    let initial_storage_usage = env::storage_usage();
+                    self.$payouts.set_token(&token_id, royalty);
+                    if env::storage_usage() >= initial_storage_usage {
+                        $crate::nft::refund_deposit(env::storage_usage() - initial_storage_usage);
+                    } else {
+                        $crate::nft::refund_released_storage(
+                            initial_storage_usage,
+                            env::predecessor_account_id(),
+                        );
+                    }
+                }
+
+                pub fn nft_royalty(&self, token_id: $crate::nft::TokenId) -> $crate::royalty::Royalty {
+                    self.$payouts.get(&token_id)
+                }
+
+                /// NEP-199 payout of a sale of the token at `balance` yoctoNEAR.
+                pub fn nft_payout(
+                    &self,
+                    token_id: $crate::nft::TokenId,
+                    balance: U128,
+                    max_len_payout: Option<u32>,
+                ) -> $crate::nft::Payout {
+                    let owner_id = self
+                        .$nft
+                        .token
+                        .owner_by_id
+                        .get(&token_id)
+                        .unwrap_or_else(|| env::panic_str("Token not found"));
+                    self.$payouts.nft_payout(&token_id, &owner_id, balance.0, max_len_payout)
+                }
+
+                /// `nft_transfer` of a sale at `balance` yoctoNEAR, returns its NEP-199 payout.
+                #[payable]
+                pub fn nft_transfer_payout(
+                    &mut self,
+                    receiver_id: AccountId,
+                    token_id: $crate::nft::TokenId,
+                    approval_id: Option<u64>,
+                    memo: Option<String>,
+                    balance: U128,
+                    max_len_payout: Option<u32>,
+                ) -> $crate::nft::Payout {
+                    #[cfg(feature = "feature_flags")]
+                    $crate::features::require_enabled($crate::features::Feature::NftTransfer);
+                    self.$payouts.nft_transfer_payout(
+                        &mut self.$nft,
+                        receiver_id,
+                        token_id,
+                        approval_id,
+                        memo,
+                        balance.0,
+                        max_len_payout,
+                    )
+                }
+            }
+        };
+        ($contract:ident, $nft:ident $(, ipfs_gateway = $ipfs_gateway:expr)? $(, mint_fee = $mint_fee:ident)? $(, payouts = $payouts:ident)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_non_fungible_token_contract!`.
+                pub const NFT_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("nft"),
+                    methods: &[
+                        $crate::manifest::Method::payable("nft_mint"),
+                        $crate::manifest::Method::payable("nft_transfer"),
+                        $crate::manifest::Method::payable("nft_transfer_call"),
+                        $crate::manifest::Method::view("nft_token"),
+                        $crate::manifest::Method::private("nft_resolve_transfer"),
+                        $crate::manifest::Method::payable("nft_approve"),
+                        $crate::manifest::Method::payable("nft_revoke"),
+                        $crate::manifest::Method::payable("nft_revoke_all"),
+                        $crate::manifest::Method::view("nft_is_approved"),
+                        $crate::manifest::Method::view("nft_total_supply"),
+                        $crate::manifest::Method::view("nft_tokens"),
+                        $crate::manifest::Method::view("nft_supply_for_owner"),
+                        $crate::manifest::Method::view("nft_tokens_for_owner"),
+                        $crate::manifest::Method::view("nft_metadata"),
+                        $crate::manifest::Method::payable("nft_approve_all"),
+                        $crate::manifest::Method::payable("nft_revoke_all_operator"),
+                        $crate::manifest::Method::view("nft_is_approved_operator"),
+                        $crate::manifest::Method::view("nft_token_uri"),
+                        $crate::manifest::Method::view("nft_provenance"),
+                        $crate::manifest::Method::payable("nft_approve_with_expiry"),
+                        $crate::manifest::Method::view("nft_approvals"),
+                        $crate::manifest::Method::call("nft_prune_approvals"),
+                    ],
+                    storage: &[
+                        // 4 + 64 (account id) + 8 (approval id)
+                        $crate::manifest::StorageEstimate::new("nft_approve", 76),
+                    ],
+                };
+            }
             #[near_bindgen]
             impl $contract {
                 #[payable]
@@ -447,12 +2217,88 @@ macro_rules! impl_non_fungible_token_contract {
                     receiver_id: AccountId,
                     token_metadata: $crate::nft::TokenMetadata,
                 ) -> $crate::nft::Token {
-                    self.$nft.token.internal_mint(token_id, receiver_id, Some(token_metadata))
+                    #[cfg(feature = "feature_flags")]
+                    $crate::features::require_enabled($crate::features::Feature::NftMint);
+                    #[allow(unused_variables)]
+                    let fee: Balance = 0;
+                    $(let fee = self.$mint_fee.charge(&env::predecessor_account_id(), None);)?
+                    self.$nft.internal_mint_with_fee(token_id, receiver_id, token_metadata, fee, None)
+                }
+
+                /// Approves `operator_id` to transfer every token of the predecessor.
+                /// The unused deposit goes to `refund_to`, the predecessor by default.
+                #[payable]
+                pub fn nft_approve_all(&mut self, operator_id: AccountId, refund_to: Option<AccountId>) {
+                    #[cfg(feature = "feature_flags")]
+                    $crate::features::require_enabled($crate::features::Feature::NftApprove);
+                    self.$nft.nft_approve_all(operator_id, refund_to)
+                }
+
+                /// The released storage goes to `refund_to`, the predecessor by default.
+                #[payable]
+                pub fn nft_revoke_all_operator(&mut self, operator_id: AccountId, refund_to: Option<AccountId>) {
+                    self.$nft.nft_revoke_all_operator(operator_id, refund_to)
+                }
+
+                pub fn nft_is_approved_operator(&self, owner_id: AccountId, operator_id: AccountId) -> bool {
+                    self.$nft.is_operator(&owner_id, &operator_id)
+                }
+
+                pub fn nft_token_uri(&self, token_id: $crate::nft::TokenId) -> Option<String> {
+                    #[allow(unused_variables)]
+                    let ipfs_gateway: Option<&str> = None;
+                    $(let ipfs_gateway = Some($ipfs_gateway);)?
+                    self.$nft.token_uri(&token_id, ipfs_gateway)
+                }
+
+                /// Last owners of the token from the oldest, with the price of sales.
+                pub fn nft_provenance(
+                    &self,
+                    token_id: $crate::nft::TokenId,
+                    from_index: Option<U128>,
+                    limit: Option<u64>,
+                ) -> Vec<$crate::nft::Provenance> {
+                    self.$nft.provenance(&token_id, from_index, limit)
+                }
+
+                /// `nft_approve` until the block timestamp (nanoseconds) `expires_at`.
+                #[payable]
+                pub fn nft_approve_with_expiry(
+                    &mut self,
+                    token_id: $crate::nft::TokenId,
+                    account_id: AccountId,
+                    expires_at: U64,
+                    msg: Option<String>,
+                ) -> Option<Promise> {
+                    #[cfg(feature = "feature_flags")]
+                    $crate::features::require_enabled($crate::features::Feature::NftApprove);
+                    self.$nft
+                        .nft_approve_with_expiry(token_id, account_id, Some(expires_at.0), msg, None)
+                }
+
+                /// Approvals of the token that haven't expired, by approval id.
+                pub fn nft_approvals(&self, token_id: $crate::nft::TokenId) -> Vec<$crate::nft::ApprovalView> {
+                    self.$nft.approvals(&token_id)
+                }
+
+                /// Anyone can drop the expired approvals of a token, the storage goes to its owner.
+                pub fn nft_prune_approvals(&mut self, token_id: $crate::nft::TokenId) -> u32 {
+                    let owner_id = self
+                        .$nft
+                        .token
+                        .owner_by_id
+                        .get(&token_id)
+                        .unwrap_or_else(|| env::panic_str("Token not found"));
+                    let initial_storage_usage = env::storage_usage();
+                    let pruned = self.$nft.prune_approvals(&token_id);
+                    $crate::nft::refund_released_storage(initial_storage_usage, owner_id);
+                    pruned
                 }
             }
             impl_non_fungible_token_contract!(@IMPL_CORE $contract, $nft);
             impl_non_fungible_token_contract!(@IMPL_APPROVAL $contract, $nft);
             impl_non_fungible_token_contract!(@IMPL_ENUMERATION $contract, $nft);
+            $(impl_non_fungible_token_contract!(@IMPL_PAYOUT $contract, $nft, $payouts);)?
             #[near_bindgen]
             impl $crate::nft::metadata::NonFungibleTokenMetadataProvider for $contract {
                 fn nft_metadata(&self) -> $crate::nft::Metadata {
@@ -462,3 +2308,41 @@ macro_rules! impl_non_fungible_token_contract {
         };
     }
 pub use impl_non_fungible_token_contract;
+
+#[macro_export]
+macro_rules! impl_non_fungible_token_detailed {
+        ($contract:ident, $nft:ident $(, royalties = $royalties:ident)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_non_fungible_token_detailed!`.
+                pub const NFT_DETAILED_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("nft"),
+                    methods: &[$crate::manifest::Method::view("nft_tokens_detailed")],
+                    storage: &[],
+                };
+            }
+            #[near_bindgen]
+            impl $contract {
+                /// Tokens of the owner with their metadata, royalty and approvals, and its operators,
+                /// at most `MAX_DETAILED_TOKENS`. `fields` selects the parts, all by default.
+                pub fn nft_tokens_detailed(
+                    &self,
+                    account_id: AccountId,
+                    from_index: Option<U128>,
+                    limit: Option<u64>,
+                    fields: Option<$crate::nft::DetailFields>,
+                ) -> $crate::nft::TokensDetailed {
+                    #[allow(unused_variables)]
+                    let royalties: Option<&$crate::royalty::Registry> = None;
+                    $(let royalties = Some(&self.$royalties);)?
+                    self.$nft.tokens_detailed(
+                        account_id,
+                        from_index,
+                        limit,
+                        fields.unwrap_or_default(),
+                        royalties,
+                    )
+                }
+            }
+        };
+    }
+pub use impl_non_fungible_token_detailed;
@@ -0,0 +1,347 @@
+/*!
+Index of the tokens by attribute, for trait filtering on-chain.
+
+The attributes of a token are parsed from the `extra` of its metadata on mint, either the
+common `{"attributes": [{"trait_type": "color", "value": "red"}, ..]}` list or a flat
+`{"color": "red", ..}` object, and every key/value pair gets the set of its tokens.
+`nft_tokens_by_attribute` pages through a set.
+
+# NOTES:
+  - The index is off by default, enable it with `NonFungibleToken::with_attributes()`
+    (or `InitArgs::attributes`). Tokens minted before aren't indexed.
+  - The index entries are part of the mint's storage, paid by the minter. Only the first
+    [`MAX_INDEXED_ATTRIBUTES`] pairs of a token are indexed, longer values are cut at
+    [`MAX_ATTRIBUTE_LENGTH`] bytes.
+  - Numbers and booleans are indexed as their JSON text, E.g. `"level": 3` as `("level", "3")`.
+    An `extra` that isn't a JSON object has no attributes.
+  - Removing a token from the set swaps the last one into its slot, so pages shift while
+    tokens are removed.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    nft: nft::NonFungibleToken,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            nft: nft::NonFungibleToken::from_args(nft::InitArgs {
+                owner_id: Some(owner_id),
+                name: "Traits".to_string(),
+                symbol: "TRT".to_string(),
+                attributes: true,
+                ..Default::default()
+            }),
+        }
+    }
+}
+
+nft::impl_non_fungible_token_contract!(Contract, nft);
+// impl_non_fungible_token_attributes!(Contract, nft);
+nft::attributes::impl_non_fungible_token_attributes!(Contract, nft);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    const MINT_STORAGE_COST: Balance = 100_000_000_000_000_000_000_000;
+
+    fn mint(vm: &mut VMContextBuilder, contract: &mut Contract, token_id: &str, extra: &str) {
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST),
+        );
+        contract.nft_mint(
+            token_id.to_string(),
+            accounts(1),
+            nft::TokenMetadata {
+                title: Some(token_id.into()),
+                description: None,
+                media: None,
+                media_hash: None,
+                copies: None,
+                issued_at: None,
+                expires_at: None,
+                starts_at: None,
+                updated_at: None,
+                extra: Some(extra.into()),
+                reference: None,
+                reference_hash: None,
+            },
+        );
+    }
+
+    #[test]
+    fn test_tokens_by_attribute() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+
+        mint(
+            &mut vm,
+            &mut contract,
+            "0",
+            r#"{"attributes": [{"trait_type": "color", "value": "red"}, {"trait_type": "level", "value": 3}]}"#,
+        );
+        mint(&mut vm, &mut contract, "1", r#"{"color": "blue", "level": 3}"#);
+        mint(&mut vm, &mut contract, "2", "not json");
+
+        let token_ids = |key: &str, value: &str| -> Vec<String> {
+            contract
+                .nft_tokens_by_attribute(key.into(), value.into(), None, None)
+                .into_iter()
+                .map(|token| token.token_id)
+                .collect()
+        };
+        assert_eq!(token_ids("color", "red"), vec!["0"]);
+        assert_eq!(token_ids("level", "3"), vec!["0", "1"]);
+        assert!(token_ids("color", "green").is_empty());
+        assert_eq!(
+            contract.nft_attribute_supply("level".into(), "3".into()),
+            U64(2)
+        );
+        assert_eq!(contract.nft_token_attributes("2".into()), vec![]);
+    }
+}
+```
+*/
+
+use super::super::*;
+use super::TokenId;
+
+use near_sdk::{collections::UnorderedSet, serde_json, CryptoHash};
+
+/// Pairs indexed per token, the rest are ignored.
+pub const MAX_INDEXED_ATTRIBUTES: usize = 20;
+/// Bytes kept of a key or a value.
+pub const MAX_ATTRIBUTE_LENGTH: usize = 64;
+/// Default and max number of tokens of `nft_tokens_by_attribute`.
+pub const MAX_ATTRIBUTE_TOKENS: usize = 50;
+
+raw_storage_key!(pub enum StorageKey in storage_key::ATTRIBUTES {
+    Attributes,
+    AttributeTokens,
+});
+
+/// A key/value pair, E.g. `("color", "red")`.
+pub type Attribute = (String, String);
+
+fn truncate(s: &str) -> String {
+    let mut end = s.len().min(MAX_ATTRIBUTE_LENGTH);
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    s[..end].to_string()
+}
+
+fn value_text(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) => Some(truncate(s)),
+        serde_json::Value::Number(_) | serde_json::Value::Bool(_) => {
+            Some(truncate(&value.to_string()))
+        }
+        _ => None,
+    }
+}
+
+/// The attributes of `extra`, without duplicates, see the module notes.
+pub fn parse(extra: &str) -> Vec<Attribute> {
+    let object = match serde_json::from_str::<serde_json::Value>(extra) {
+        Ok(serde_json::Value::Object(object)) => object,
+        _ => return vec![],
+    };
+    let pairs: Vec<Attribute> = match object.get("attributes") {
+        Some(serde_json::Value::Array(attributes)) => attributes
+            .iter()
+            .filter_map(|attribute| {
+                let key = attribute.get("trait_type")?.as_str()?;
+                Some((truncate(key), value_text(attribute.get("value")?)?))
+            })
+            .collect(),
+        _ => object
+            .iter()
+            .filter_map(|(key, value)| Some((truncate(key), value_text(value)?)))
+            .collect(),
+    };
+    let mut attributes = Vec::with_capacity(pairs.len().min(MAX_INDEXED_ATTRIBUTES));
+    for pair in pairs {
+        if attributes.len() == MAX_INDEXED_ATTRIBUTES {
+            break;
+        }
+        if !attributes.contains(&pair) {
+            attributes.push(pair);
+        }
+    }
+    attributes
+}
+
+/// Key of a pair, the length of the key keeps `("ab", "c")` apart from `("a", "bc")`.
+fn attribute_hash(key: &str, value: &str) -> CryptoHash {
+    let mut bytes = (key.len() as u32).to_le_bytes().to_vec();
+    bytes.extend_from_slice(key.as_bytes());
+    bytes.extend_from_slice(value.as_bytes());
+    env::sha256_array(&bytes)
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AttributeIndex {
+    /// Tokens of every indexed pair.
+    pub tokens: collections::LookupMap<CryptoHash, UnorderedSet<TokenId>>,
+}
+impl Default for AttributeIndex {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl AttributeIndex {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            tokens: collections::LookupMap::new(StorageKey::Attributes),
+        }
+    }
+
+    /// Adds the token to the sets of its attributes.
+    pub fn insert(&mut self, token_id: &TokenId, attributes: &[Attribute]) {
+        for (key, value) in attributes {
+            let hash = attribute_hash(key, value);
+            let mut tokens = self.tokens.get(&hash).unwrap_or_else(|| {
+                let mut prefix =
+                    near_sdk::IntoStorageKey::into_storage_key(StorageKey::AttributeTokens);
+                prefix.extend_from_slice(&hash);
+                UnorderedSet::new(prefix)
+            });
+            tokens.insert(token_id);
+            self.tokens.insert(&hash, &tokens);
+        }
+    }
+
+    /// Removes the token from the sets of its attributes, dropping the emptied ones.
+    pub fn remove(&mut self, token_id: &TokenId, attributes: &[Attribute]) {
+        for (key, value) in attributes {
+            let hash = attribute_hash(key, value);
+            let mut tokens = match self.tokens.get(&hash) {
+                Some(tokens) => tokens,
+                None => continue,
+            };
+            if !tokens.remove(token_id) {
+                continue;
+            }
+            if tokens.is_empty() {
+                self.tokens.remove(&hash);
+            } else {
+                self.tokens.insert(&hash, &tokens);
+            }
+        }
+    }
+
+    /// Number of tokens with the pair.
+    pub fn supply(&self, key: &str, value: &str) -> u64 {
+        self.tokens
+            .get(&attribute_hash(key, value))
+            .map_or(0, |tokens| tokens.len())
+    }
+
+    /// Page of the tokens with the pair, up to [`MAX_ATTRIBUTE_TOKENS`].
+    pub fn token_ids(
+        &self,
+        key: &str,
+        value: &str,
+        from_index: Option<U128>,
+        limit: Option<u64>,
+    ) -> Vec<TokenId> {
+        let tokens = match self.tokens.get(&attribute_hash(key, value)) {
+            Some(tokens) => tokens,
+            None => return vec![],
+        };
+        let limit = limit.map_or(MAX_ATTRIBUTE_TOKENS, |limit| {
+            (limit as usize).min(MAX_ATTRIBUTE_TOKENS)
+        });
+        tokens
+            .as_vector()
+            .iter()
+            .skip(from_index.map_or(0, |index| index.0 as usize))
+            .take(limit)
+            .collect()
+    }
+}
+
+/// One attribute of `nft_token_attributes`.
+#[derive(near_sdk::serde::Serialize, near_sdk::serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AttributeView {
+    pub key: String,
+    pub value: String,
+}
+
+#[macro_export]
+macro_rules! impl_non_fungible_token_attributes {
+    ($contract:ident, $nft:ident $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_non_fungible_token_attributes!`.
+            pub const NFT_ATTRIBUTES_MANIFEST: $crate::manifest::Component =
+                $crate::manifest::Component {
+                    feature: Some("nft"),
+                    methods: &[
+                        $crate::manifest::Method::view("nft_tokens_by_attribute"),
+                        $crate::manifest::Method::view("nft_attribute_supply"),
+                        $crate::manifest::Method::view("nft_token_attributes"),
+                    ],
+                    storage: &[
+                        // set entry and index of a 64 bytes token ID, per indexed attribute
+                        $crate::manifest::StorageEstimate::new("nft_mint", 220),
+                    ],
+                };
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Page of the tokens with the attribute, empty without the index.
+            pub fn nft_tokens_by_attribute(
+                &self,
+                key: String,
+                value: String,
+                from_index: Option<U128>,
+                limit: Option<u64>,
+            ) -> Vec<$crate::nft::Token> {
+                self.$nft
+                    .tokens_by_attribute(&key, &value, from_index, limit)
+            }
+
+            /// Number of tokens with the attribute.
+            pub fn nft_attribute_supply(&self, key: String, value: String) -> U64 {
+                self.$nft
+                    .attributes
+                    .as_ref()
+                    .map_or(0, |attributes| attributes.supply(&key, &value))
+                    .into()
+            }
+
+            /// The attributes parsed from the `extra` of the token.
+            pub fn nft_token_attributes(
+                &self,
+                token_id: $crate::nft::TokenId,
+            ) -> Vec<$crate::nft::attributes::AttributeView> {
+                self.$nft
+                    .token_metadata(&token_id)
+                    .and_then(|metadata| metadata.extra)
+                    .map(|extra| $crate::nft::attributes::parse(&extra))
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|(key, value)| $crate::nft::attributes::AttributeView { key, value })
+                    .collect()
+            }
+        }
+    };
+}
+pub use impl_non_fungible_token_attributes;
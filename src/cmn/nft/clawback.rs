@@ -0,0 +1,317 @@
+#![cfg(feature = "clawback")]
+/*!
+Clawback of NFTs by a regulator, E.g. for real world assets under compliance rules.
+
+The owner appoints a regulator, who can move any token to another account with
+`nft_force_transfer(token_id, receiver_id, memo)`, or up to [`MAX_FORCE_TRANSFER_TOKENS`] tokens
+at once with `nft_force_transfer_many`. The memo is mandatory.
+
+# NOTES:
+  - The capability is off until a regulator is set. `nft_clawback_renounce` turns it off for
+    good: no regulator can be set again and no token can be forced anymore.
+  - Forced transfers drop the approvals of the token, their storage is refunded to the previous
+    owner.
+  - Events are logged as `EVENT_JSON:` with the `nft_clawback` standard, along with the
+    `nft_transfer` of nep171 authorized by the regulator.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    nft: nft::NonFungibleToken,
+    clawback: nft::clawback::Clawback,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId, regulator_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            owner_id: owner_id.clone(),
+            nft: nft::NonFungibleToken::from_args(nft::InitArgs {
+                owner_id: Some(owner_id),
+                name: "Deeds".to_string(),
+                symbol: "DEED".to_string(),
+                ..Default::default()
+            }),
+            clawback: nft::clawback::Clawback::new(Some(regulator_id)),
+        }
+    }
+}
+
+nft::impl_non_fungible_token_contract!(Contract, nft);
+// impl_non_fungible_token_clawback!(Contract, nft, clawback[, owner = <AccountId field; default: the contract itself>]);
+nft::clawback::impl_non_fungible_token_clawback!(Contract, nft, clawback, owner = owner_id);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    use nft::core::NonFungibleTokenCore;
+
+    const MINT_STORAGE_COST: Balance = 100_000_000_000_000_000_000_000;
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0), accounts(3));
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST),
+        );
+        contract.nft_mint(
+            "0".to_string(),
+            accounts(1),
+            nft::TokenMetadata {
+                title: Some("Deed".into()),
+                description: None,
+                media: None,
+                media_hash: None,
+                copies: None,
+                issued_at: None,
+                expires_at: None,
+                starts_at: None,
+                updated_at: None,
+                extra: None,
+                reference: None,
+                reference_hash: None,
+            },
+        );
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_force_transfer() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(3)),
+        );
+        contract.nft_force_transfer("0".to_string(), accounts(2), "Court order 42".to_string());
+        assert_eq!(contract.nft_token("0".to_string()).unwrap().owner_id, accounts(2));
+        assert!(get_logs().last().unwrap().contains(r#""event":"force_transfer""#));
+    }
+
+    #[test]
+    #[should_panic(expected = "Clawback is renounced")]
+    fn test_renounce() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.nft_clawback_renounce();
+        run_vm(vm.predecessor_account_id(accounts(3)));
+        contract.nft_force_transfer("0".to_string(), accounts(2), "Court order 42".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the regulator can force transfers")]
+    fn test_force_transfer_regulator() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.nft_force_transfer("0".to_string(), accounts(0), "Mine".to_string());
+    }
+}
+```
+*/
+
+use super::super::*;
+use super::{NonFungibleToken, TokenId};
+
+use near_sdk::{
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+
+/// Max number of tokens of `nft_force_transfer_many`.
+pub const MAX_FORCE_TRANSFER_TOKENS: usize = 50;
+
+/// Logs an `nft_clawback` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    events::emit(events::standards::NFT_CLAWBACK, event, data);
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ClawbackView {
+    pub regulator_id: Option<AccountId>,
+    pub renounced: bool,
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Clawback {
+    /// Can force transfers, none while the capability is off.
+    pub regulator_id: Option<AccountId>,
+    /// Set for good by `renounce`.
+    pub renounced: bool,
+}
+impl Clawback {
+    #[inline]
+    pub fn new(regulator_id: Option<AccountId>) -> Self {
+        Self {
+            regulator_id,
+            renounced: false,
+        }
+    }
+
+    #[inline]
+    fn require_not_renounced(&self) {
+        require!(!self.renounced, "Clawback is renounced");
+    }
+
+    /// `None` turns the capability off until a regulator is set again.
+    pub fn set_regulator(&mut self, regulator_id: Option<AccountId>) {
+        self.require_not_renounced();
+        self.regulator_id = regulator_id;
+        emit(
+            "set_regulator",
+            serde_json::json!({ "regulator_id": self.regulator_id }),
+        );
+    }
+
+    /// Turns the capability off for good.
+    pub fn renounce(&mut self) {
+        self.require_not_renounced();
+        self.regulator_id = None;
+        self.renounced = true;
+        emit("renounce", serde_json::json!({}));
+    }
+
+    /// Moves the tokens to `receiver_id` on behalf of the regulator, the predecessor.
+    /// Returns the previous owners, in the order of `token_ids`.
+    pub fn force_transfer(
+        &self,
+        nft: &mut NonFungibleToken,
+        token_ids: &[TokenId],
+        receiver_id: &AccountId,
+        memo: &str,
+    ) -> Vec<AccountId> {
+        self.require_not_renounced();
+        let regulator_id = env::predecessor_account_id();
+        require!(
+            self.regulator_id.as_ref() == Some(&regulator_id),
+            "Only the regulator can force transfers"
+        );
+        require!(!memo.is_empty(), "Memo is required");
+        require!(
+            !token_ids.is_empty() && token_ids.len() <= MAX_FORCE_TRANSFER_TOKENS,
+            format!(
+                "Can force 1 to {} tokens, got {}",
+                MAX_FORCE_TRANSFER_TOKENS,
+                token_ids.len()
+            )
+        );
+
+        let old_owner_ids: Vec<AccountId> = token_ids
+            .iter()
+            .map(|token_id| nft.internal_force_transfer(token_id, receiver_id, &regulator_id, memo))
+            .collect();
+        emit(
+            "force_transfer",
+            serde_json::json!({
+                "regulator_id": regulator_id,
+                "old_owner_ids": old_owner_ids,
+                "receiver_id": receiver_id,
+                "token_ids": token_ids,
+                "memo": memo,
+            }),
+        );
+        old_owner_ids
+    }
+
+    #[inline]
+    pub fn view(&self) -> ClawbackView {
+        ClawbackView {
+            regulator_id: self.regulator_id.clone(),
+            renounced: self.renounced,
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! impl_non_fungible_token_clawback {
+    ($contract:ident, $nft:ident, $clawback:ident $(, owner = $owner:ident)? $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_non_fungible_token_clawback!`.
+            pub const NFT_CLAWBACK_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: Some("clawback"),
+                methods: &[
+                    $crate::manifest::Method::payable("nft_force_transfer").role("regulator"),
+                    $crate::manifest::Method::payable("nft_force_transfer_many").role("regulator"),
+                    $crate::manifest::Method::payable("nft_clawback_set_regulator").role("owner"),
+                    $crate::manifest::Method::payable("nft_clawback_renounce").role("owner"),
+                    $crate::manifest::Method::view("nft_clawback"),
+                ],
+                storage: &[],
+            };
+
+            fn assert_clawback_owner(&self) {
+                near_sdk::assert_one_yocto();
+                let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                    .cloned()
+                    .unwrap_or_else(env::current_account_id);
+                require!(
+                    env::predecessor_account_id() == owner_id,
+                    "Only the owner can manage the clawback"
+                );
+            }
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Returns the previous owner.
+            #[payable]
+            pub fn nft_force_transfer(
+                &mut self,
+                token_id: $crate::nft::TokenId,
+                receiver_id: AccountId,
+                memo: String,
+            ) -> AccountId {
+                near_sdk::assert_one_yocto();
+                self.$clawback
+                    .force_transfer(&mut self.$nft, &[token_id], &receiver_id, &memo)
+                    .remove(0)
+            }
+
+            /// Returns the previous owners, in the order of `token_ids`.
+            #[payable]
+            pub fn nft_force_transfer_many(
+                &mut self,
+                token_ids: Vec<$crate::nft::TokenId>,
+                receiver_id: AccountId,
+                memo: String,
+            ) -> Vec<AccountId> {
+                near_sdk::assert_one_yocto();
+                self.$clawback
+                    .force_transfer(&mut self.$nft, &token_ids, &receiver_id, &memo)
+            }
+
+            /// `None` turns the clawback off until a regulator is set again.
+            #[payable]
+            pub fn nft_clawback_set_regulator(&mut self, regulator_id: Option<AccountId>) {
+                self.assert_clawback_owner();
+                self.$clawback.set_regulator(regulator_id);
+            }
+
+            /// Turns the clawback off for good.
+            #[payable]
+            pub fn nft_clawback_renounce(&mut self) {
+                self.assert_clawback_owner();
+                self.$clawback.renounce();
+            }
+
+            pub fn nft_clawback(&self) -> $crate::nft::clawback::ClawbackView {
+                self.$clawback.view()
+            }
+        }
+    };
+}
+pub use impl_non_fungible_token_clawback;
@@ -0,0 +1,306 @@
+#![cfg(feature = "mint_fee")]
+/*!
+Platform fee on mints, E.g. for a hosted minting platform.
+
+The fee is a flat amount plus basis points of a declared price, taken from the attached deposit
+on top of the storage and sent to the treasury account. Exempt accounts (E.g. the platform's
+own minters) pay no fee.
+
+# NOTES:
+  - `impl_non_fungible_token_contract!(.., mint_fee = <field>)` charges `nft_mint`, which has no
+    price, so only the flat part. Sales charge the basis points of their price with
+    [`MintFee::charge`].
+  - The contract pays the storage of the exemptions, they're set by the owner.
+  - Quote the deposit to attach with `nft_mint_fee_for`.
+  - Members get a discount with [`MintFee::charge_with_discount`] and the
+    `membership` tier of the payer.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    nft: nft::NonFungibleToken,
+    mint_fee: nft::fee::MintFee,
+}
+
+const MILLI_NEAR: Balance = 1_000_000_000_000_000_000_000;
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId, treasury_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            owner_id: owner_id.clone(),
+            nft: nft::NonFungibleToken::from_args(nft::InitArgs {
+                owner_id: Some(owner_id),
+                name: "Hosted".to_string(),
+                symbol: "HOST".to_string(),
+                ..Default::default()
+            }),
+            // 10 mNEAR + 2.5% of the price
+            mint_fee: nft::fee::MintFee::new(10 * MILLI_NEAR, 250, treasury_id),
+        }
+    }
+}
+
+nft::impl_non_fungible_token_contract!(Contract, nft, mint_fee = mint_fee);
+// impl_non_fungible_token_mint_fee!(Contract, mint_fee[, owner = <AccountId field; default: the contract itself>]);
+nft::fee::impl_non_fungible_token_mint_fee!(Contract, mint_fee, owner = owner_id);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    const MINT_STORAGE_COST: Balance = 10 * MILLI_NEAR;
+
+    fn token_metadata() -> nft::TokenMetadata {
+        nft::TokenMetadata {
+            title: Some("Hosted".into()),
+            description: None,
+            media: None,
+            media_hash: None,
+            copies: None,
+            issued_at: None,
+            expires_at: None,
+            starts_at: None,
+            updated_at: None,
+            extra: None,
+            reference: None,
+            reference_hash: None,
+        }
+    }
+
+    #[test]
+    fn test_mint_fee() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0), accounts(4));
+
+        let price = Some(U128(1_000 * MILLI_NEAR));
+        assert_eq!(contract.nft_mint_fee_for(accounts(1), price).0, 35 * MILLI_NEAR);
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST + 10 * MILLI_NEAR)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.nft_mint("0".to_string(), accounts(1), token_metadata());
+        assert_eq!(get_transfers_to(&accounts(4)), 10 * MILLI_NEAR);
+        assert_eq!(contract.nft_mint_fee().collected.0, 10 * MILLI_NEAR);
+    }
+
+    #[test]
+    fn test_mint_fee_exempt() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0), accounts(4));
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.nft_set_mint_fee_exempt(accounts(1), true);
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.nft_mint("0".to_string(), accounts(1), token_metadata());
+        assert_eq!(get_transfers_to(&accounts(4)), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "yoctoNEAR of fee")]
+    fn test_mint_without_fee() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0), accounts(4));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.nft_mint("0".to_string(), accounts(1), token_metadata());
+    }
+}
+```
+*/
+
+use super::super::*;
+
+use near_sdk::{
+    collections::UnorderedSet,
+    serde::{Deserialize, Serialize},
+};
+
+use royalty::{royalty_share, ONE_HUNDRED_PERCENT_BPS};
+
+raw_storage_key!(pub enum StorageKey in storage_key::MINT_FEE {
+    MintFeeExempt,
+});
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MintFeeView {
+    pub flat: U128,
+    pub bps: u32,
+    pub treasury_id: AccountId,
+    /// Sent to the treasury so far.
+    pub collected: U128,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MintFee {
+    /// yoctoNEAR per mint.
+    pub flat: Balance,
+    /// Of the declared price.
+    pub bps: u32,
+    pub treasury_id: AccountId,
+    pub exempt: UnorderedSet<AccountId>,
+    pub collected: Balance,
+}
+impl MintFee {
+    pub fn new(flat: Balance, bps: u32, treasury_id: AccountId) -> Self {
+        let mut this = Self {
+            flat: 0,
+            bps: 0,
+            treasury_id: treasury_id.clone(),
+            exempt: UnorderedSet::new(StorageKey::MintFeeExempt),
+            collected: 0,
+        };
+        this.set(flat, bps, treasury_id);
+        this
+    }
+
+    pub fn set(&mut self, flat: Balance, bps: u32, treasury_id: AccountId) {
+        require!(
+            bps <= ONE_HUNDRED_PERCENT_BPS,
+            "Fee can't be over 10000 bps"
+        );
+        self.flat = flat;
+        self.bps = bps;
+        self.treasury_id = treasury_id;
+    }
+
+    /// Fee of `payer_id` for a mint or a sale at `price`.
+    #[inline]
+    pub fn fee(&self, payer_id: &AccountId, price: Option<Balance>) -> Balance {
+        self.fee_with_discount(payer_id, price, 0)
+    }
+
+    /// [`MintFee::fee`] less `discount_bps` of it, E.g. of the
+    /// `membership` tier of the payer.
+    pub fn fee_with_discount(
+        &self,
+        payer_id: &AccountId,
+        price: Option<Balance>,
+        discount_bps: u32,
+    ) -> Balance {
+        if self.exempt.contains(payer_id) {
+            return 0;
+        }
+        let fee = self
+            .flat
+            .saturating_add(royalty_share(price.unwrap_or_default(), self.bps));
+        fee - royalty_share(fee, discount_bps.min(ONE_HUNDRED_PERCENT_BPS))
+    }
+
+    /// Sends the fee of `payer_id` to the treasury, returns it so it's kept from the deposit.
+    #[inline]
+    pub fn charge(&mut self, payer_id: &AccountId, price: Option<Balance>) -> Balance {
+        self.charge_with_discount(payer_id, price, 0)
+    }
+
+    /// [`MintFee::charge`] of [`MintFee::fee_with_discount`].
+    pub fn charge_with_discount(
+        &mut self,
+        payer_id: &AccountId,
+        price: Option<Balance>,
+        discount_bps: u32,
+    ) -> Balance {
+        let fee = self.fee_with_discount(payer_id, price, discount_bps);
+        if fee > 0 {
+            self.collected += fee;
+            Promise::new(self.treasury_id.clone()).transfer(fee);
+        }
+        fee
+    }
+
+    #[inline]
+    pub fn view(&self) -> MintFeeView {
+        MintFeeView {
+            flat: U128(self.flat),
+            bps: self.bps,
+            treasury_id: self.treasury_id.clone(),
+            collected: U128(self.collected),
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! impl_non_fungible_token_mint_fee {
+    ($contract:ident, $mint_fee:ident $(, owner = $owner:ident)? $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_non_fungible_token_mint_fee!`.
+            pub const NFT_MINT_FEE_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: Some("mint_fee"),
+                methods: &[
+                    $crate::manifest::Method::payable("nft_set_mint_fee").role("owner"),
+                    $crate::manifest::Method::payable("nft_set_mint_fee_exempt").role("owner"),
+                    $crate::manifest::Method::view("nft_mint_fee"),
+                    $crate::manifest::Method::view("nft_mint_fee_for"),
+                    $crate::manifest::Method::view("nft_mint_fee_exempt"),
+                ],
+                storage: &[],
+            };
+
+            fn assert_mint_fee_owner(&self) {
+                near_sdk::assert_one_yocto();
+                let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                    .cloned()
+                    .unwrap_or_else(env::current_account_id);
+                require!(
+                    env::predecessor_account_id() == owner_id,
+                    "Only the owner can set the mint fee"
+                );
+            }
+        }
+        #[near_bindgen]
+        impl $contract {
+            #[payable]
+            pub fn nft_set_mint_fee(&mut self, flat: U128, bps: u32, treasury_id: AccountId) {
+                self.assert_mint_fee_owner();
+                self.$mint_fee.set(flat.0, bps, treasury_id);
+                log!("Mint fee is {} yoctoNEAR + {} bps", flat.0, bps);
+            }
+
+            #[payable]
+            pub fn nft_set_mint_fee_exempt(&mut self, account_id: AccountId, exempt: bool) {
+                self.assert_mint_fee_owner();
+                if exempt {
+                    self.$mint_fee.exempt.insert(&account_id);
+                } else {
+                    self.$mint_fee.exempt.remove(&account_id);
+                }
+            }
+
+            pub fn nft_mint_fee(&self) -> $crate::nft::fee::MintFeeView {
+                self.$mint_fee.view()
+            }
+
+            /// Fee the account pays on top of the storage, for a mint or a sale at `price`.
+            pub fn nft_mint_fee_for(&self, account_id: AccountId, price: Option<U128>) -> U128 {
+                U128(self.$mint_fee.fee(&account_id, price.map(|price| price.0)))
+            }
+
+            pub fn nft_mint_fee_exempt(&self, account_id: AccountId) -> bool {
+                self.$mint_fee.exempt.contains(&account_id)
+            }
+        }
+    };
+}
+pub use impl_non_fungible_token_mint_fee;
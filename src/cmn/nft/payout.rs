@@ -0,0 +1,237 @@
+#![cfg(feature = "payout")]
+/*!
+NEP-199 payouts, the royalties of a token that marketplaces pay on a sale.
+
+[`Payouts`] is a [`royalty::Registry`](super::super::royalty): each token has the split in basis
+points (1/100 of a percent) per account set for it, or the default split of the collection.
+A payout gives the shares of a balance to the accounts of the split, the owner of the token gets
+the rest.
+
+# NOTES:
+  - `impl_non_fungible_token_contract!(.., payouts = <field>)` adds `nft_set_royalty`,
+    `nft_royalty`, `nft_payout` and `nft_transfer_payout`.
+  - Pass the same field to `impl_royalty_registry!` and to `impl_non_fungible_token_detailed!`,
+    the royalties shown to marketplaces are the ones paid out. `nft_set_royalty` and `nft_royalty`
+    are `royalty_set_token` and `royalty_of` of the registry.
+  - Splits are checked against `max_total_bps` and `max_recipients` when they're set, so payouts
+    never have to fail on a bad split. A payout longer than `max_len_payout` fails.
+  - `nft_transfer_payout` keeps the balance as the price in the provenance of the token.
+  - Splits charge their measured storage like approvals do.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    nft: nft::NonFungibleToken,
+    payouts: nft::Payouts,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            nft: nft::NonFungibleToken::from_args(nft::InitArgs {
+                owner_id: Some(owner_id),
+                name: "Royalties".to_string(),
+                symbol: "ROYAL".to_string(),
+                ..Default::default()
+            }),
+            // at most 20% to at most 5 accounts
+            payouts: nft::Payouts::new(2_000, 5),
+        }
+    }
+}
+
+nft::impl_non_fungible_token_contract!(Contract, nft, payouts = payouts);
+events::impl_supported_standards!(Contract, NEP171, NEP177, NEP178, NEP181, NEP199, NEP297);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    use nft::core::NonFungibleTokenCore;
+    use std::collections::HashMap;
+
+    const MINT_STORAGE_COST: Balance = 10_000_000_000_000_000_000_000;
+
+    fn token_metadata() -> nft::TokenMetadata {
+        nft::TokenMetadata {
+            title: Some("Royal".into()),
+            description: None,
+            media: None,
+            media_hash: None,
+            copies: None,
+            issued_at: None,
+            expires_at: None,
+            starts_at: None,
+            updated_at: None,
+            extra: None,
+            reference: None,
+            reference_hash: None,
+        }
+    }
+
+    fn minted(vm: &mut VMContextBuilder) -> Contract {
+        run_vm(&*vm);
+        let mut contract = Contract::new(accounts(0));
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST),
+        );
+        contract.nft_mint("0".to_string(), accounts(1), token_metadata());
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(MINT_STORAGE_COST),
+        );
+        contract.nft_set_royalty(
+            "0".to_string(),
+            Some(HashMap::from([(accounts(0), 1_000), (accounts(2), 250)])),
+        );
+        contract
+    }
+
+    #[test]
+    fn test_payout() {
+        let mut vm = vm!(accounts(0));
+        let contract = minted(&mut vm);
+
+        let payout = contract.nft_payout("0".to_string(), U128(1_001), Some(3)).payout;
+        assert_eq!(payout[&accounts(0)], U128(100));
+        assert_eq!(payout[&accounts(2)], U128(25));
+        assert_eq!(payout[&accounts(1)], U128(876));
+    }
+
+    #[test]
+    fn test_transfer_payout() {
+        let mut vm = vm!(accounts(0));
+        let mut contract = minted(&mut vm);
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(1)),
+        );
+        let payout = contract
+            .nft_transfer_payout(accounts(3), "0".to_string(), None, None, U128(1_000), None)
+            .payout;
+        assert_eq!(payout[&accounts(1)], U128(875));
+        assert_eq!(contract.nft_token("0".to_string()).unwrap().owner_id, accounts(3));
+
+        // the royalties stay with the token
+        let payout = contract.nft_payout("0".to_string(), U128(1_000), None).payout;
+        assert_eq!(payout[&accounts(3)], U128(875));
+        assert!(!payout.contains_key(&accounts(1)));
+    }
+
+    #[test]
+    fn test_default_payout() {
+        let mut vm = vm!(accounts(0));
+        let mut contract = minted(&mut vm);
+        contract.payouts.set_default(HashMap::from([(accounts(2), 500)]));
+
+        run_vm(vm.attached_deposit(1));
+        contract.nft_set_royalty("0".to_string(), None);
+        assert_eq!(
+            contract.nft_royalty("0".to_string()),
+            HashMap::from([(accounts(2), 500)])
+        );
+        let payout = contract.nft_payout("0".to_string(), U128(1_000), None).payout;
+        assert_eq!(payout[&accounts(2)], U128(50));
+        assert_eq!(payout[&accounts(1)], U128(950));
+    }
+
+    #[test]
+    #[should_panic(expected = "Market cannot payout to that many receivers")]
+    fn test_payout_too_long() {
+        let mut vm = vm!(accounts(0));
+        let contract = minted(&mut vm);
+
+        contract.nft_payout("0".to_string(), U128(1_000), Some(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can set royalties")]
+    fn test_set_royalty_not_owner() {
+        let mut vm = vm!(accounts(0));
+        let mut contract = minted(&mut vm);
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.nft_set_royalty("0".to_string(), None);
+    }
+}
+```
+*/
+
+use super::super::*;
+
+use near_sdk::serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::{NonFungibleToken, TokenId};
+
+/// Royalties of the payouts, the splits of `royalty_set_token` and the default split included.
+pub type Payouts = royalty::Registry;
+
+/// NEP-199 payout, yoctoNEAR per account.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Payout {
+    pub payout: HashMap<AccountId, U128>,
+}
+
+impl royalty::Registry {
+    /// NEP-199 payout of `balance` for the token, fails when it's longer than `max_len_payout`.
+    pub fn nft_payout(
+        &self,
+        token_id: &TokenId,
+        owner_id: &AccountId,
+        balance: Balance,
+        max_len_payout: Option<u32>,
+    ) -> Payout {
+        let payout = self.payout(token_id, owner_id, balance);
+        if let Some(max_len_payout) = max_len_payout {
+            require!(
+                payout.len() as u32 <= max_len_payout,
+                "Market cannot payout to that many receivers"
+            );
+        }
+        Payout { payout }
+    }
+
+    /// Transfers the token like `nft_transfer`, with `balance` as the price of the sale,
+    /// and gives the payout of its previous owner.
+    #[allow(clippy::too_many_arguments)]
+    pub fn nft_transfer_payout(
+        &self,
+        nft: &mut NonFungibleToken,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+        balance: Balance,
+        max_len_payout: Option<u32>,
+    ) -> Payout {
+        near_sdk::assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        let (owner_id, _) = nft.internal_transfer_with_price(
+            &sender_id,
+            &receiver_id,
+            &token_id,
+            approval_id,
+            memo,
+            Some(balance),
+        );
+        self.nft_payout(&token_id, &owner_id, balance, max_len_payout)
+    }
+}
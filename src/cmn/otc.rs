@@ -0,0 +1,669 @@
+#![cfg(feature = "otc")]
+#![allow(dead_code)]
+/*!
+Fixed-price swaps between two fungible tokens (OTC orders).
+
+A maker posts an order selling an amount of token A for an amount of token B, funds it with
+`ft_transfer_call` of token A, then takers fill it partially or fully with `ft_transfer_call` of
+token B, at the same ratio. `msg` is the order id in both cases.
+
+# NOTES:
+  - The maker pays the storage of the order in NEAR when posting it, it's refunded when the order
+    is filled or cancelled. Extra tokens of the funding or of a fill are returned.
+  - A partial fill gives `sell_amount * amount / buy_amount` rounded down, so the ratio never
+    moves against the maker.
+  - Expired orders can't be filled, and anyone can cancel them. The maker can cancel anytime,
+    the tokens left are sent back. With the `expiry` feature and index on, keepers cancel them
+    in batches.
+  - A failed `ft_transfer` (E.g. the receiver isn't registered in the token) is kept as unclaimed
+    for the receiver, `otc_withdraw` sends it again.
+  - Fills send two transfers with their callbacks, attach 100 TGas to the `ft_transfer_call`.
+  - `impl_otc!` generates `ft_on_transfer`, it can't be combined with another one (E.g. of bounty).
+  - Events are logged as `EVENT_JSON:` with the `otc` standard.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    otc: otc::Otc,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new() -> Self {
+        require_init!();
+        Self { otc: otc::Otc::new() }
+    }
+}
+
+otc::impl_otc!(Contract, otc);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+    fn token_a() -> AccountId {
+        "a.token".parse().unwrap()
+    }
+
+    fn token_b() -> AccountId {
+        "b.token".parse().unwrap()
+    }
+
+    fn setup() -> (VMContextBuilder, Contract) {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new();
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(NEAR / 100));
+        let id = contract.otc_make(token_a(), U128(1_000), token_b(), U128(500), None);
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(0)
+                .predecessor_account_id(token_a()),
+        );
+        let unused = contract.ft_on_transfer(accounts(0), U128(1_200), id.0.to_string());
+        assert!(matches!(unused, PromiseOrValue::Value(U128(200))));
+        (vm, contract)
+    }
+
+    #[test]
+    fn test_fill() {
+        let (mut vm, mut contract) = setup();
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(token_b()),
+        );
+        let unused = contract.ft_on_transfer(accounts(1), U128(100), "0".to_string());
+        assert!(matches!(unused, PromiseOrValue::Value(U128(0))));
+        let order = contract.otc_order(U64(0)).unwrap();
+        assert_eq!(order.sell_amount, U128(800));
+        assert_eq!(order.buy_amount, U128(400));
+
+        // more than asked, the rest is returned
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(token_b()),
+        );
+        let unused = contract.ft_on_transfer(accounts(2), U128(450), "0".to_string());
+        assert!(matches!(unused, PromiseOrValue::Value(U128(50))));
+        assert!(contract.otc_order(U64(0)).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Order is expired")]
+    fn test_fill_expired() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new();
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(NEAR / 100));
+        contract.otc_make(token_a(), U128(1_000), token_b(), U128(500), Some(U64(10)));
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(0)
+                .predecessor_account_id(token_a()),
+        );
+        contract.ft_on_transfer(accounts(0), U128(1_000), "0".to_string());
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .block_timestamp(10)
+                .predecessor_account_id(token_b()),
+        );
+        contract.ft_on_transfer(accounts(1), U128(100), "0".to_string());
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::{
+    collections::{LookupMap, UnorderedMap},
+    ext_contract,
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+
+/// Gas for the `ft_transfer` of a swap.
+pub const GAS_FOR_FT_TRANSFER: Gas = Gas(10_000_000_000_000);
+/// Gas for the `otc_resolve_transfer` callback.
+pub const GAS_FOR_RESOLVE_TRANSFER: Gas = Gas(5_000_000_000_000);
+
+raw_storage_key!(pub enum StorageKey in storage_key::OTC {
+    OtcOrders,
+    OtcUnclaimed,
+});
+
+#[ext_contract(ext_ft)]
+pub trait FtTransfer {
+    fn ft_transfer(&mut self, receiver_id: AccountId, amount: U128, memo: Option<String>);
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Order {
+    pub maker_id: AccountId,
+    pub sell_token_id: AccountId,
+    /// Tokens left to sell.
+    pub sell_amount: U128,
+    pub buy_token_id: AccountId,
+    /// Tokens asked for the tokens left.
+    pub buy_amount: U128,
+    /// Block timestamp (nanoseconds).
+    pub expires_at: Option<U64>,
+    /// Whether the maker has transferred the tokens to sell.
+    pub funded: bool,
+    /// Storage paid by the maker.
+    pub storage_deposit: U128,
+}
+impl Order {
+    #[inline]
+    pub fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| env::block_timestamp() >= expires_at.0)
+    }
+
+    /// Tokens to sell for `amount` of the tokens asked, rounded down.
+    #[inline]
+    pub fn sell_amount_for(&self, amount: Balance) -> Balance {
+        if amount >= self.buy_amount.0 {
+            return self.sell_amount.0;
+        }
+        u256::mul_div(self.sell_amount.0, amount, self.buy_amount.0)
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OrderView {
+    pub id: U64,
+    #[serde(flatten)]
+    pub order: Order,
+}
+
+/// Logs an `otc` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    events::emit(events::standards::OTC, event, data);
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Otc {
+    pub orders: UnorderedMap<u64, Order>,
+    /// Failed transfers by (account, token).
+    pub unclaimed: LookupMap<(AccountId, AccountId), Balance>,
+    pub next_id: u64,
+}
+impl Default for Otc {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Otc {
+    pub fn new() -> Self {
+        Self {
+            orders: UnorderedMap::new(StorageKey::OtcOrders),
+            unclaimed: LookupMap::new(StorageKey::OtcUnclaimed),
+            next_id: 0,
+        }
+    }
+
+    #[inline]
+    pub fn expect(&self, id: u64) -> Order {
+        self.orders
+            .get(&id)
+            .unwrap_or_else(|| env::panic_str("Order not found"))
+    }
+
+    /// Posts an order of the predecessor, charging its storage from the attached deposit.
+    pub fn make(
+        &mut self,
+        sell_token_id: AccountId,
+        sell_amount: U128,
+        buy_token_id: AccountId,
+        buy_amount: U128,
+        expires_at: Option<U64>,
+    ) -> u64 {
+        require!(
+            sell_token_id != buy_token_id,
+            "Tokens to sell and to buy must differ"
+        );
+        require!(
+            sell_amount.0 > 0 && buy_amount.0 > 0,
+            "Amounts should be positive numbers"
+        );
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let initial_storage_usage = env::storage_usage();
+        let mut order = Order {
+            maker_id: env::predecessor_account_id(),
+            sell_token_id,
+            sell_amount,
+            buy_token_id,
+            buy_amount,
+            expires_at,
+            funded: false,
+            storage_deposit: U128(0),
+        };
+        self.orders.insert(&id, &order);
+        let storage_deposit = protocol::storage_cost(env::storage_usage() - initial_storage_usage);
+        // the deposit amount has the same size whatever it is
+        order.storage_deposit = U128(storage_deposit);
+        self.orders.insert(&id, &order);
+        refund_deposit_to(env::storage_usage() - initial_storage_usage, None);
+        #[cfg(feature = "expiry")]
+        if let Some(expires_at) = order.expires_at {
+            expiry::register(expiry::ExpiryKind::OtcOrder, id.to_string(), expires_at.0);
+        }
+
+        emit(
+            "otc_make",
+            serde_json::json!({ "id": U64(id), "order": order }),
+        );
+        id
+    }
+
+    /// Funds or fills the order from `ft_on_transfer`, returns the unused amount.
+    pub fn on_transfer(&mut self, id: u64, sender_id: AccountId, amount: U128) -> U128 {
+        let token_id = env::predecessor_account_id();
+        let mut order = self.expect(id);
+        require!(!order.is_expired(), "Order is expired");
+
+        if !order.funded {
+            require!(token_id == order.sell_token_id, "Wrong token to fund");
+            require!(sender_id == order.maker_id, "Only the maker can fund");
+            require!(amount.0 >= order.sell_amount.0, "Not enough tokens to fund");
+            order.funded = true;
+            self.orders.insert(&id, &order);
+            emit("otc_fund", serde_json::json!({ "id": U64(id) }));
+            return U128(amount.0 - order.sell_amount.0);
+        }
+
+        require!(token_id == order.buy_token_id, "Wrong token to fill");
+        let sold = order.sell_amount_for(amount.0);
+        require!(sold > 0, "Amount is too small to fill");
+        let used = amount.0.min(order.buy_amount.0);
+        order.sell_amount.0 -= sold;
+        order.buy_amount.0 -= used;
+
+        let maker_id = order.maker_id.clone();
+        if order.buy_amount.0 == 0 {
+            self.orders.remove(&id);
+            Promise::new(maker_id.clone()).transfer(order.storage_deposit.0);
+        } else {
+            self.orders.insert(&id, &order);
+        }
+        self.transfer(order.sell_token_id.clone(), sender_id.clone(), sold);
+        self.transfer(token_id, maker_id, used);
+
+        emit(
+            "otc_fill",
+            serde_json::json!({
+                "id": U64(id),
+                "taker_id": sender_id,
+                "sold": U128(sold),
+                "bought": U128(used),
+            }),
+        );
+        U128(amount.0 - used)
+    }
+
+    /// Removes the order and sends back its tokens and storage to the maker.
+    /// Anyone can cancel an expired order.
+    pub fn cancel(&mut self, id: u64) {
+        let order = self.expect(id);
+        require!(
+            order.maker_id == env::predecessor_account_id() || order.is_expired(),
+            "Only the maker can cancel"
+        );
+        self.orders.remove(&id);
+        Promise::new(order.maker_id.clone()).transfer(order.storage_deposit.0);
+        if order.funded && order.sell_amount.0 > 0 {
+            self.transfer(order.sell_token_id, order.maker_id, order.sell_amount.0);
+        }
+        emit("otc_cancel", serde_json::json!({ "id": U64(id) }));
+    }
+
+    /// Sends the unclaimed tokens of the predecessor again.
+    pub fn withdraw(&mut self, token_id: AccountId) {
+        let account_id = env::predecessor_account_id();
+        let amount = self
+            .unclaimed
+            .remove(&(account_id.clone(), token_id.clone()))
+            .unwrap_or_else(|| env::panic_str("Nothing to withdraw"));
+        self.transfer(token_id, account_id, amount);
+    }
+
+    /// `ft_transfer` of `amount`, kept as unclaimed if it fails.
+    fn transfer(&mut self, token_id: AccountId, receiver_id: AccountId, amount: Balance) {
+        if amount == 0 {
+            return;
+        }
+        ext_ft::ext(token_id.clone())
+            .with_attached_deposit(1)
+            .with_static_gas(GAS_FOR_FT_TRANSFER)
+            .ft_transfer(receiver_id.clone(), U128(amount), Some("OTC".to_string()))
+            .then(
+                ext_otc_resolver::ext(env::current_account_id())
+                    .with_static_gas(GAS_FOR_RESOLVE_TRANSFER)
+                    .otc_resolve_transfer(token_id, receiver_id, U128(amount)),
+            );
+    }
+
+    /// Result of a transfer.
+    pub fn resolve_transfer(
+        &mut self,
+        token_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> bool {
+        let succeeded = matches!(env::promise_result(0), PromiseResult::Successful(_));
+        if !succeeded {
+            log!(
+                "Transfer of {} {} to @{} failed, it can be withdrawn",
+                amount.0,
+                token_id,
+                receiver_id
+            );
+            let key = (receiver_id, token_id);
+            let unclaimed = self.unclaimed.get(&key).unwrap_or(0);
+            self.unclaimed.insert(&key, &(unclaimed + amount.0));
+        }
+        succeeded
+    }
+
+    pub fn views(&self, from_index: Option<U64>, limit: Option<u64>) -> Vec<OrderView> {
+        self.orders
+            .iter()
+            .skip(from_index.map_or(0, |from_index| from_index.0 as usize))
+            .take(limit.map_or(usize::MAX, |limit| limit as usize))
+            .map(|(id, order)| OrderView { id: U64(id), order })
+            .collect()
+    }
+}
+
+#[ext_contract(ext_otc_resolver)]
+pub trait OtcResolver {
+    fn otc_resolve_transfer(
+        &mut self,
+        token_id: AccountId,
+        receiver_id: AccountId,
+        amount: U128,
+    ) -> bool;
+}
+
+#[macro_export]
+macro_rules! impl_otc {
+    ($contract:ident, $otc:ident) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_otc!`.
+            pub const OTC_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: Some("otc"),
+                methods: &[
+                    $crate::manifest::Method::payable("otc_make"),
+                    $crate::manifest::Method::call("ft_on_transfer"),
+                    $crate::manifest::Method::payable("otc_cancel"),
+                    $crate::manifest::Method::call("otc_withdraw"),
+                    $crate::manifest::Method::private("otc_resolve_transfer"),
+                    $crate::manifest::Method::view("otc_order"),
+                    $crate::manifest::Method::view("otc_orders"),
+                    $crate::manifest::Method::view("otc_unclaimed"),
+                ],
+                storage: &[
+                    // an order of 64 bytes accounts and tokens
+                    $crate::manifest::StorageEstimate::new("otc_make", 400),
+                ],
+            };
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Returns the order id, fund it with `ft_transfer_call` of `sell_token_id`.
+            #[payable]
+            pub fn otc_make(
+                &mut self,
+                sell_token_id: AccountId,
+                sell_amount: U128,
+                buy_token_id: AccountId,
+                buy_amount: U128,
+                expires_at: Option<U64>,
+            ) -> U64 {
+                #[cfg(feature = "decommission")]
+                $crate::decommission::require_active();
+                U64(self.$otc.make(
+                    sell_token_id,
+                    sell_amount,
+                    buy_token_id,
+                    buy_amount,
+                    expires_at,
+                ))
+            }
+
+            /// Funds or fills the order with the id in `msg`.
+            pub fn ft_on_transfer(
+                &mut self,
+                sender_id: AccountId,
+                amount: U128,
+                msg: String,
+            ) -> PromiseOrValue<U128> {
+                #[cfg(feature = "decommission")]
+                $crate::decommission::require_active();
+                let id = msg
+                    .parse()
+                    .unwrap_or_else(|_| env::panic_str("msg must be an order id"));
+                PromiseOrValue::Value(self.$otc.on_transfer(id, sender_id, amount))
+            }
+
+            #[payable]
+            pub fn otc_cancel(&mut self, id: U64) {
+                near_sdk::assert_one_yocto();
+                self.$otc.cancel(id.0)
+            }
+
+            pub fn otc_withdraw(&mut self, token_id: AccountId) {
+                self.$otc.withdraw(token_id)
+            }
+
+            #[private]
+            pub fn otc_resolve_transfer(
+                &mut self,
+                token_id: AccountId,
+                receiver_id: AccountId,
+                amount: U128,
+            ) -> bool {
+                self.$otc.resolve_transfer(token_id, receiver_id, amount)
+            }
+
+            pub fn otc_order(&self, id: U64) -> Option<$crate::otc::Order> {
+                self.$otc.orders.get(&id.0)
+            }
+
+            pub fn otc_orders(
+                &self,
+                from_index: Option<U64>,
+                limit: Option<u64>,
+            ) -> Vec<$crate::otc::OrderView> {
+                self.$otc.views(from_index, limit)
+            }
+
+            pub fn otc_unclaimed(&self, account_id: AccountId, token_id: AccountId) -> U128 {
+                U128(
+                    self.$otc
+                        .unclaimed
+                        .get(&(account_id, token_id))
+                        .unwrap_or(0),
+                )
+            }
+        }
+    };
+}
+pub use impl_otc;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+    fn token_a() -> AccountId {
+        "a.token".parse().unwrap()
+    }
+
+    fn token_b() -> AccountId {
+        "b.token".parse().unwrap()
+    }
+
+    /// `ft_transfer`s of the receipts created in the current VM, as (token, args).
+    fn ft_transfers() -> Vec<(AccountId, String)> {
+        get_created_receipts()
+            .into_iter()
+            .flat_map(|receipt| {
+                let receiver_id = receipt.receiver_id.clone();
+                receipt
+                    .actions
+                    .into_iter()
+                    .filter_map(move |action| match action {
+                        VmAction::FunctionCall {
+                            function_name,
+                            args,
+                            ..
+                        } if function_name == "ft_transfer" => {
+                            Some((receiver_id.clone(), String::from_utf8(args).unwrap()))
+                        }
+                        _ => None,
+                    })
+            })
+            .collect()
+    }
+
+    /// A funded order of 1000 A for 500 B by accounts(0).
+    fn funded(vm: &mut VMContextBuilder) -> Otc {
+        run_vm(&*vm);
+        let mut otc = Otc::new();
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR / 100),
+        );
+        let id = otc.make(token_a(), U128(1_000), token_b(), U128(500), None);
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(0)
+                .predecessor_account_id(token_a()),
+        );
+        assert_eq!(otc.on_transfer(id, accounts(0), U128(1_000)), U128(0));
+        otc
+    }
+
+    #[test]
+    fn check_fill() {
+        let mut vm = vm!(accounts(0));
+        let mut otc = funded(&mut vm);
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(token_b()),
+        );
+        assert_eq!(otc.on_transfer(0, accounts(1), U128(100)), U128(0));
+        assert_eq!(
+            ft_transfers(),
+            [
+                (
+                    token_a(),
+                    r#"{"receiver_id":"bob","amount":"200","memo":"OTC"}"#.to_string()
+                ),
+                (
+                    token_b(),
+                    r#"{"receiver_id":"alice","amount":"100","memo":"OTC"}"#.to_string()
+                ),
+            ]
+        );
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(token_b()),
+        );
+        assert_eq!(otc.on_transfer(0, accounts(1), U128(500)), U128(100));
+        assert!(otc.orders.get(&0).is_none());
+        assert!(get_transfers_to(&accounts(0)) > 0, "storage refunded");
+    }
+
+    #[test]
+    fn check_failed_transfer_withdrawn() {
+        let mut vm = vm!(accounts(0));
+        let mut otc = funded(&mut vm);
+
+        run_vm_with_results(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(accounts(0)),
+            vec![PromiseResult::Failed],
+        );
+        assert!(!otc.resolve_transfer(token_b(), accounts(1), U128(100)));
+        assert_eq!(otc.unclaimed.get(&(accounts(1), token_b())), Some(100));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(accounts(1)),
+        );
+        otc.withdraw(token_b());
+        assert_eq!(otc.unclaimed.get(&(accounts(1), token_b())), None);
+        assert_eq!(
+            ft_transfers(),
+            [(
+                token_b(),
+                r#"{"receiver_id":"bob","amount":"100","memo":"OTC"}"#.to_string()
+            )]
+        );
+    }
+
+    #[test]
+    fn check_cancel() {
+        let mut vm = vm!(accounts(0));
+        let mut otc = funded(&mut vm);
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(accounts(0)),
+        );
+        otc.cancel(0);
+        assert!(otc.orders.get(&0).is_none());
+        assert_eq!(
+            ft_transfers(),
+            [(
+                token_a(),
+                r#"{"receiver_id":"alice","amount":"1000","memo":"OTC"}"#.to_string()
+            )]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the maker can fund")]
+    fn check_fund_not_maker() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut otc = Otc::new();
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(NEAR / 100),
+        );
+        let id = otc.make(token_a(), U128(1_000), token_b(), U128(500), None);
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(0)
+                .predecessor_account_id(token_a()),
+        );
+        otc.on_transfer(id, accounts(1), U128(1_000));
+    }
+}
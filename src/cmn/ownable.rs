@@ -0,0 +1,200 @@
+#![allow(dead_code)]
+/*!
+Single owner access control.
+
+An [`Owner`] field keeps the owner of the contract, [`Owner::assert_owner`] guards the owner only
+methods, and `impl_ownable!` generates the views and the calls to hand the contract over.
+
+# NOTES:
+  - Transfers and renouncements need 1 yoctoNEAR, so a function call access key can't make them.
+  - Once renounced, nobody can pass [`Owner::assert_owner`] anymore, for good.
+  - The owner of the other components (`owner = <field>` of their macros) stays an `AccountId`
+    field, `owner()` gives the same account to them.
+  - Events are logged as `EVENT_JSON:` with the `ownable` standard.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner: ownable::Owner,
+    value: u64,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            owner: ownable::Owner::new(owner_id),
+            value: 0,
+        }
+    }
+
+    pub fn set_value(&mut self, value: u64) {
+        self.owner.assert_owner();
+        self.value = value;
+    }
+}
+
+// impl_ownable!(Contract, <ownable::Owner field>);
+ownable::impl_ownable!(Contract, owner);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn test_transfer_ownership() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+        contract.set_value(1);
+
+        run_vm(vm.attached_deposit(1));
+        contract.transfer_ownership(accounts(1));
+        assert_eq!(contract.owner(), Some(accounts(1)));
+
+        run_vm(vm.predecessor_account_id(accounts(1)));
+        contract.set_value(2);
+        contract.renounce_ownership();
+        assert_eq!(contract.owner(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_not_owner() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+
+        run_vm(vm.predecessor_account_id(accounts(1)));
+        contract.set_value(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_renounced() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+
+        run_vm(vm.attached_deposit(1));
+        contract.renounce_ownership();
+        contract.set_value(1);
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::serde_json;
+
+/// Logs an `ownable` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    events::emit(events::standards::OWNABLE, event, data);
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Clone, PartialEq, Eq)]
+pub struct Owner {
+    /// None once renounced.
+    pub owner_id: Option<AccountId>,
+}
+impl Owner {
+    #[inline]
+    pub fn new(owner_id: AccountId) -> Self {
+        Self {
+            owner_id: Some(owner_id),
+        }
+    }
+
+    #[inline]
+    pub fn owner(&self) -> Option<&AccountId> {
+        self.owner_id.as_ref()
+    }
+
+    #[inline]
+    pub fn is_owner(&self, account_id: &AccountId) -> bool {
+        self.owner() == Some(account_id)
+    }
+
+    /// Panics unless the predecessor is the owner.
+    #[inline]
+    pub fn assert_owner(&self) {
+        require!(
+            self.is_owner(&env::predecessor_account_id()),
+            "Only the owner can call this method"
+        );
+    }
+
+    /// The owner hands the contract over to `new_owner_id`.
+    pub fn transfer_ownership(&mut self, new_owner_id: AccountId) {
+        self.assert_owner();
+        emit(
+            "ownership_transferred",
+            serde_json::json!({ "old_owner_id": self.owner_id, "new_owner_id": new_owner_id }),
+        );
+        self.owner_id = Some(new_owner_id);
+    }
+
+    /// The owner gives up the contract, nobody owns it anymore.
+    pub fn renounce_ownership(&mut self) {
+        self.assert_owner();
+        emit(
+            "ownership_renounced",
+            serde_json::json!({ "old_owner_id": self.owner_id }),
+        );
+        self.owner_id = None;
+    }
+}
+
+impl From<Owner> for Option<AccountId> {
+    #[inline]
+    fn from(owner: Owner) -> Self {
+        owner.owner_id
+    }
+}
+
+#[macro_export]
+macro_rules! impl_ownable {
+    ($contract:ident, $owner:ident $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_ownable!`.
+            pub const OWNABLE_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: None,
+                methods: &[
+                    $crate::manifest::Method::view("owner"),
+                    $crate::manifest::Method::payable("transfer_ownership").role("owner"),
+                    $crate::manifest::Method::payable("renounce_ownership").role("owner"),
+                ],
+                storage: &[],
+            };
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// None once renounced.
+            pub fn owner(&self) -> Option<AccountId> {
+                self.$owner.owner().cloned()
+            }
+
+            #[payable]
+            pub fn transfer_ownership(&mut self, new_owner_id: AccountId) {
+                near_sdk::assert_one_yocto();
+                self.$owner.transfer_ownership(new_owner_id)
+            }
+
+            #[payable]
+            pub fn renounce_ownership(&mut self) {
+                near_sdk::assert_one_yocto();
+                self.$owner.renounce_ownership()
+            }
+        }
+    };
+}
+pub use impl_ownable;
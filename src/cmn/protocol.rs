@@ -0,0 +1,164 @@
+#![allow(dead_code)]
+/*!
+Protocol economics the storage deposits are computed with.
+
+Every storage charge and refund of the modules goes through [`storage_cost`] instead of
+`env::storage_byte_cost()`, so the byte cost can be raised ahead of a protocol change, or in a
+test, by storing a [`ProtocolConfig`] under its own key.
+
+# NOTES:
+  - Without a stored config the runtime's byte cost applies.
+  - A stored byte cost only applies above the runtime's one, the contract never charges less
+    than the storage it has to stake.
+  - The standards charge their own storage (E.g. the ft `storage_deposit` minimum) with the
+    runtime's byte cost.
+
+# EXAMPLE:
+```
+# use cmn::*;
+// impl_protocol_config!(Contract[, owner = <AccountId field; default: the contract itself>]);
+impl_protocol_config!(Contract, owner = owner_id);
+// set_protocol_config({"config": {"storage_byte_cost": "20000000000000000000"}}) => deposits cost twice as much
+
+// in a test, simulate the change before it happens
+protocol::ProtocolConfig { storage_byte_cost: U128(2 * env::storage_byte_cost()) }.save();
+```
+*/
+
+use super::*;
+
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Storage key of the config.
+pub const PROTOCOL_CONFIG_KEY: &[u8] = b"PROTOCOL_CONFIG";
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq,
+)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct ProtocolConfig {
+    /// yoctoNEAR per byte.
+    pub storage_byte_cost: U128,
+}
+impl ProtocolConfig {
+    /// The runtime's config.
+    #[inline]
+    pub fn runtime() -> Self {
+        Self {
+            storage_byte_cost: U128(env::storage_byte_cost()),
+        }
+    }
+
+    /// The stored config, none if the runtime's one applies.
+    #[inline]
+    pub fn stored() -> Option<Self> {
+        env::storage_read(PROTOCOL_CONFIG_KEY)
+            .map(|bytes| Self::try_from_slice(&bytes).unwrap_or_else(|_| env::abort()))
+    }
+
+    /// The config in effect, the stored one where it's over the runtime's.
+    pub fn load() -> Self {
+        let runtime = Self::runtime();
+        match Self::stored() {
+            Some(stored) => Self {
+                storage_byte_cost: runtime.storage_byte_cost.max(stored.storage_byte_cost),
+            },
+            None => runtime,
+        }
+    }
+
+    #[inline]
+    pub fn save(&self) {
+        env::storage_write(PROTOCOL_CONFIG_KEY, &self.try_to_vec().unwrap());
+    }
+
+    /// Back to the runtime's config.
+    #[inline]
+    pub fn clear() {
+        env::storage_remove(PROTOCOL_CONFIG_KEY);
+    }
+}
+
+/// yoctoNEAR per byte of storage, see [`ProtocolConfig::load`].
+#[inline]
+pub fn storage_byte_cost() -> Balance {
+    ProtocolConfig::load().storage_byte_cost.0
+}
+
+/// Cost of `bytes` of storage.
+#[inline]
+pub fn storage_cost(bytes: u64) -> Balance {
+    Balance::from(bytes) * storage_byte_cost()
+}
+
+#[macro_export]
+macro_rules! impl_protocol_config {
+        ($contract:ident $(, owner = $owner:ident)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_protocol_config!`.
+                pub const PROTOCOL_CONFIG_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: None,
+                    methods: &[
+                        $crate::manifest::Method::payable("set_protocol_config").role("owner"),
+                        $crate::manifest::Method::view("protocol_config"),
+                    ],
+                    storage: &[],
+                };
+            }
+            #[near_bindgen]
+            impl $contract {
+                /// `None` goes back to the runtime's config.
+                #[payable]
+                pub fn set_protocol_config(&mut self, config: Option<$crate::protocol::ProtocolConfig>) {
+                    near_sdk::assert_one_yocto();
+                    let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                        .cloned()
+                        .unwrap_or_else(env::current_account_id);
+                    require!(
+                        env::predecessor_account_id() == owner_id,
+                        "Only the owner can set the protocol config"
+                    );
+                    match config {
+                        Some(config) => config.save(),
+                        None => $crate::protocol::ProtocolConfig::clear(),
+                    }
+                }
+
+                /// The config in effect.
+                pub fn protocol_config(&self) -> $crate::protocol::ProtocolConfig {
+                    $crate::protocol::ProtocolConfig::load()
+                }
+            }
+        };
+    }
+pub use impl_protocol_config;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_storage_cost() {
+        run_vm(vm!("alice.near"));
+        let runtime = env::storage_byte_cost();
+        assert_eq!(storage_cost(100), 100 * runtime);
+
+        ProtocolConfig {
+            storage_byte_cost: U128(2 * runtime),
+        }
+        .save();
+        assert_eq!(storage_cost(100), 200 * runtime);
+
+        // never under the runtime's
+        ProtocolConfig {
+            storage_byte_cost: U128(runtime / 2),
+        }
+        .save();
+        assert_eq!(storage_byte_cost(), runtime);
+
+        ProtocolConfig::clear();
+        assert_eq!(ProtocolConfig::load(), ProtocolConfig::runtime());
+    }
+}
@@ -0,0 +1,326 @@
+#![cfg(feature = "reputation")]
+#![allow(dead_code)]
+/*!
+Per-account reputation score, E.g. to gate who can claim bounties or trade.
+
+Other modules adjust the score of an account on outcomes (an approved bounty, a lost dispute ..)
+through [`Reputation::adjust`], moderators adjust it by hand, and it decays toward 0 over time
+so that old outcomes weigh less than recent ones.
+
+# NOTES:
+  - The score halves every `half_life`, linearly in between two halvings.
+  - `impl_bounty!(.., reputation = <field>)` adds [`points::BOUNTY_COMPLETED`] to the claimant of an approved bounty.
+  - Gate a method with [`Reputation::require_at_least`].
+  - The contract pays the storage of the scores, moderators are set by the owner.
+  - Events are logged as `EVENT_JSON:` with the `reputation` standard.
+
+# EXAMPLE:
+Needs the `bounty` feature too.
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    bounties: bounty::Bounties,
+    reputation: reputation::Reputation,
+}
+
+const DAY: u64 = 24 * 60 * 60 * 1_000_000_000;
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            owner_id,
+            bounties: bounty::Bounties::new(),
+            // halves in 30 days
+            reputation: reputation::Reputation::new(30 * DAY),
+        }
+    }
+}
+
+bounty::impl_bounty!(Contract, bounties, reputation = reputation);
+// impl_reputation!(Contract, reputation[, owner = <AccountId field; default: the contract itself>]);
+reputation::impl_reputation!(Contract, reputation, owner = owner_id);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    const NEAR: Balance = 1_000_000_000_000_000_000_000_000;
+
+    #[test]
+    fn test_bounty_completed() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(2 * NEAR));
+        let id = contract.bounty_post(
+            "Fix the bug".to_string(),
+            asset::Asset::Near(NEAR.into()),
+            U64(DAY),
+            None,
+        );
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(0)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.bounty_claim(id);
+        contract.bounty_submit(id, "sha256:work".to_string());
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(0)),
+        );
+        contract.bounty_approve(id);
+        assert_eq!(
+            contract.reputation_of(accounts(1)),
+            I64(reputation::points::BOUNTY_COMPLETED)
+        );
+
+        // halved after 30 days, a quarter after 60
+        run_vm(vm.block_timestamp(30 * DAY));
+        assert_eq!(
+            contract.reputation_of(accounts(1)),
+            I64(reputation::points::BOUNTY_COMPLETED / 2)
+        );
+        run_vm(vm.block_timestamp(60 * DAY));
+        assert_eq!(
+            contract.reputation_of(accounts(1)),
+            I64(reputation::points::BOUNTY_COMPLETED / 4)
+        );
+    }
+
+    #[test]
+    fn test_moderator_adjust() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+
+        run_vm(vm.storage_usage(env::storage_usage()).attached_deposit(1));
+        contract.reputation_add_moderator(accounts(2));
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .predecessor_account_id(accounts(2)),
+        );
+        contract.reputation_adjust(accounts(1), I64(-40), "Spam".to_string());
+        assert_eq!(contract.reputation_of(accounts(1)), I64(-40));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only a moderator can adjust the reputation")]
+    fn test_adjust_not_moderator() {
+        let mut vm = vm!(accounts(0));
+        run_vm(&vm);
+        let mut contract = Contract::new(accounts(0));
+
+        run_vm(
+            vm.storage_usage(env::storage_usage())
+                .attached_deposit(1)
+                .predecessor_account_id(accounts(1)),
+        );
+        contract.reputation_adjust(accounts(1), I64(100), "Me".to_string());
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::{
+    collections::LookupMap,
+    serde::{Deserialize, Serialize},
+    serde_json,
+};
+
+pub const MAX_MODERATORS: usize = 10;
+pub const MAX_REASON_LENGTH: usize = 140;
+
+/// Score changes of the outcomes of other modules.
+pub mod points {
+    pub const BOUNTY_COMPLETED: i64 = 100;
+}
+
+raw_storage_key!(pub enum StorageKey in storage_key::REPUTATION {
+    Scores,
+});
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Score {
+    /// At `updated_at`, before the decay since.
+    pub value: I64,
+    /// Block timestamp (nanoseconds).
+    pub updated_at: U64,
+}
+
+/// Logs a `reputation` standard event.
+pub fn emit(event: &str, data: serde_json::Value) {
+    events::emit(events::standards::REPUTATION, event, data);
+}
+
+/// `value` decayed over `elapsed` nanoseconds.
+pub fn decay(value: i64, elapsed: u64, half_life: u64) -> i64 {
+    let halvings = elapsed / half_life;
+    if halvings >= 63 {
+        return 0;
+    }
+    let value = value / (1 << halvings);
+    // 2^-x ~ 1 - x/2 between two halvings
+    let rest = (elapsed % half_life) as i128;
+    (value as i128 - value as i128 * rest / (2 * half_life as i128)) as i64
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Reputation {
+    pub scores: LookupMap<AccountId, Score>,
+    pub moderators: Vec<AccountId>,
+    /// Nanoseconds.
+    pub half_life: u64,
+}
+impl Reputation {
+    pub fn new(half_life: u64) -> Self {
+        require!(half_life > 0, "Half life must be positive");
+        Self {
+            scores: LookupMap::new(StorageKey::Scores),
+            moderators: vec![],
+            half_life,
+        }
+    }
+
+    /// The decayed score of the account, 0 if none.
+    pub fn get(&self, account_id: &AccountId) -> i64 {
+        self.scores.get(account_id).map_or(0, |score| {
+            decay(
+                score.value.0,
+                env::block_timestamp() - score.updated_at.0,
+                self.half_life,
+            )
+        })
+    }
+
+    /// Adds `delta` to the decayed score, returns the new score.
+    pub fn adjust(&mut self, account_id: &AccountId, delta: i64, reason: &str) -> i64 {
+        let value = self.get(account_id).saturating_add(delta);
+        self.scores.insert(
+            account_id,
+            &Score {
+                value: I64(value),
+                updated_at: U64(env::block_timestamp()),
+            },
+        );
+        emit(
+            "reputation_adjust",
+            serde_json::json!({
+                "account_id": account_id,
+                "delta": I64(delta),
+                "score": I64(value),
+                "reason": reason,
+            }),
+        );
+        value
+    }
+
+    #[inline]
+    pub fn require_at_least(&self, account_id: &AccountId, min: i64) {
+        require!(
+            self.get(account_id) >= min,
+            format!("Reputation of at least {} is required", min)
+        );
+    }
+
+    #[inline]
+    pub fn is_moderator(&self, account_id: &AccountId) -> bool {
+        self.moderators.contains(account_id)
+    }
+
+    pub fn add_moderator(&mut self, account_id: AccountId) {
+        if !self.is_moderator(&account_id) {
+            require!(
+                self.moderators.len() < MAX_MODERATORS,
+                format!("Can't have more than {} moderators", MAX_MODERATORS)
+            );
+            self.moderators.push(account_id);
+        }
+    }
+
+    pub fn remove_moderator(&mut self, account_id: &AccountId) {
+        self.moderators.retain(|moderator| moderator != account_id);
+    }
+}
+
+#[macro_export]
+macro_rules! impl_reputation {
+    ($contract:ident, $reputation:ident $(, owner = $owner:ident)? $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_reputation!`.
+            pub const REPUTATION_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: Some("reputation"),
+                methods: &[
+                    $crate::manifest::Method::call("reputation_adjust").role("moderator"),
+                    $crate::manifest::Method::payable("reputation_add_moderator").role("owner"),
+                    $crate::manifest::Method::payable("reputation_remove_moderator").role("owner"),
+                    $crate::manifest::Method::view("reputation_of"),
+                    $crate::manifest::Method::view("reputation_moderators"),
+                ],
+                storage: &[],
+            };
+
+            fn assert_reputation_owner(&self) {
+                let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                    .cloned()
+                    .unwrap_or_else(env::current_account_id);
+                require!(
+                    env::predecessor_account_id() == owner_id,
+                    "Only the owner can manage the moderators"
+                );
+            }
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Moderators adjust the score by hand, returns the new score.
+            pub fn reputation_adjust(&mut self, account_id: AccountId, delta: I64, reason: String) -> I64 {
+                require!(
+                    self.$reputation.is_moderator(&env::predecessor_account_id()),
+                    "Only a moderator can adjust the reputation"
+                );
+                $crate::strings::assert_max_bytes(&reason, $crate::reputation::MAX_REASON_LENGTH);
+                I64(self.$reputation.adjust(&account_id, delta.0, &reason))
+            }
+
+            #[payable]
+            pub fn reputation_add_moderator(&mut self, account_id: AccountId) {
+                near_sdk::assert_one_yocto();
+                self.assert_reputation_owner();
+                self.$reputation.add_moderator(account_id)
+            }
+
+            #[payable]
+            pub fn reputation_remove_moderator(&mut self, account_id: AccountId) {
+                near_sdk::assert_one_yocto();
+                self.assert_reputation_owner();
+                self.$reputation.remove_moderator(&account_id)
+            }
+
+            /// The score decayed until now.
+            pub fn reputation_of(&self, account_id: AccountId) -> I64 {
+                I64(self.$reputation.get(&account_id))
+            }
+
+            pub fn reputation_moderators(&self) -> Vec<AccountId> {
+                self.$reputation.moderators.clone()
+            }
+        }
+    };
+}
+pub use impl_reputation;
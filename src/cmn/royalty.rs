@@ -0,0 +1,287 @@
+#![cfg(feature = "nft")]
+#![allow(dead_code)]
+/*!
+Royalty registry shared across collections.
+
+Royalties are splits in basis points (1/100 of a percent) per account. A token without its own
+split falls back to the default split of the registry.
+
+# NOTES:
+  - Every split is checked against `max_total_bps` and `max_recipients` when it's set,
+    so payouts never have to fail on a bad split.
+  - Per-token splits charge their measured storage like approvals do.
+  - [`Registry::payout`] gives the royalty shares of a balance, the owner gets the rest.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    royalties: royalty::Registry,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            owner_id,
+            // at most 20% to at most 5 accounts
+            royalties: royalty::Registry::new(2_000, 5),
+        }
+    }
+}
+
+// impl_royalty_registry!(Contract, royalties[, owner = <AccountId field; default: the contract itself>]);
+royalty::impl_royalty_registry!(Contract, royalties, owner = owner_id);
+```
+*/
+
+use super::*;
+
+use near_sdk::collections::LookupMap;
+use std::collections::HashMap;
+
+/// 100% in basis points.
+pub const ONE_HUNDRED_PERCENT_BPS: u32 = contract_core::bps::ONE_HUNDRED_PERCENT;
+
+/// Basis points per account.
+pub type Royalty = HashMap<AccountId, u32>;
+
+raw_storage_key!(pub enum StorageKey in storage_key::ROYALTY {
+    RoyaltyByToken,
+});
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Registry {
+    pub default: Royalty,
+    pub by_token: LookupMap<nft::TokenId, Royalty>,
+    pub max_total_bps: u32,
+    pub max_recipients: u32,
+}
+impl Registry {
+    pub fn new(max_total_bps: u32, max_recipients: u32) -> Self {
+        require!(
+            max_total_bps <= ONE_HUNDRED_PERCENT_BPS,
+            "max_total_bps can't be over 10000"
+        );
+        Self {
+            default: HashMap::new(),
+            by_token: LookupMap::new(StorageKey::RoyaltyByToken),
+            max_total_bps,
+            max_recipients,
+        }
+    }
+
+    pub fn assert_valid(&self, royalty: &Royalty) {
+        assert_valid_royalty(royalty, self.max_total_bps, self.max_recipients);
+    }
+
+    pub fn set_default(&mut self, royalty: Royalty) {
+        self.assert_valid(&royalty);
+        self.default = royalty;
+    }
+
+    /// `None` removes the split of the token, so it falls back to the default.
+    pub fn set_token(&mut self, token_id: &nft::TokenId, royalty: Option<Royalty>) {
+        match royalty {
+            Some(royalty) => {
+                self.assert_valid(&royalty);
+                self.by_token.insert(token_id, &royalty);
+            }
+            None => {
+                self.by_token.remove(token_id);
+            }
+        }
+    }
+
+    /// Split of the token, or the default split.
+    #[inline]
+    pub fn get(&self, token_id: &nft::TokenId) -> Royalty {
+        self.by_token
+            .get(token_id)
+            .unwrap_or_else(|| self.default.clone())
+    }
+
+    /// Shares of `balance` for the royalty accounts of the token, the rest goes to `owner_id`.
+    pub fn payout(
+        &self,
+        token_id: &nft::TokenId,
+        owner_id: &AccountId,
+        balance: Balance,
+    ) -> HashMap<AccountId, U128> {
+        payout(self.get(token_id), owner_id, balance)
+    }
+}
+
+/// Panics unless the split has at most `max_recipients` accounts and `max_total_bps` in total.
+pub fn assert_valid_royalty(royalty: &Royalty, max_total_bps: u32, max_recipients: u32) {
+    require!(
+        royalty.len() as u32 <= max_recipients,
+        format!("Royalty can't have more than {} recipients", max_recipients)
+    );
+    let total_bps = royalty
+        .values()
+        .try_fold(0u32, |total, bps| total.checked_add(*bps));
+    require!(
+        total_bps.is_some_and(|total_bps| total_bps <= max_total_bps),
+        format!("Royalty can't be more than {} bps in total", max_total_bps)
+    );
+}
+
+/// Shares of `balance` for the accounts of the split, the rest goes to `owner_id`.
+pub fn payout(
+    royalty: Royalty,
+    owner_id: &AccountId,
+    balance: Balance,
+) -> HashMap<AccountId, U128> {
+    let mut payout = HashMap::new();
+    let mut rest = balance;
+    for (account_id, bps) in royalty {
+        let share = royalty_share(balance, bps);
+        rest -= share;
+        payout.insert(account_id, U128(share));
+    }
+    payout.entry(owner_id.clone()).or_insert(U128(0)).0 += rest;
+    payout
+}
+
+/// `bps` basis points of `balance`, rounded down.
+#[inline]
+pub fn royalty_share(balance: Balance, bps: u32) -> Balance {
+    contract_core::bps::share(balance, bps)
+}
+
+#[macro_export]
+macro_rules! impl_royalty_registry {
+        ($contract:ident, $royalties:ident $(, owner = $owner:ident)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_royalty_registry!`.
+                pub const ROYALTY_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("nft"),
+                    methods: &[
+                        $crate::manifest::Method::payable("royalty_set_default").role("owner"),
+                        $crate::manifest::Method::payable("royalty_set_token").role("owner"),
+                        $crate::manifest::Method::view("royalty_default"),
+                        $crate::manifest::Method::view("royalty_of"),
+                    ],
+                    storage: &[],
+                };
+
+                fn assert_royalty_owner(&self) {
+                    let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                        .cloned()
+                        .unwrap_or_else(env::current_account_id);
+                    require!(
+                        env::predecessor_account_id() == owner_id,
+                        "Only the owner can set royalties"
+                    );
+                }
+            }
+            #[near_bindgen]
+            impl $contract {
+                #[payable]
+                pub fn royalty_set_default(&mut self, royalty: $crate::royalty::Royalty) {
+                    near_sdk::assert_one_yocto();
+                    self.assert_royalty_owner();
+                    self.$royalties.set_default(royalty);
+                }
+
+                /// Charges the storage of the split, refunds it when the split shrinks or is removed.
+                #[payable]
+                pub fn royalty_set_token(
+                    &mut self,
+                    token_id: $crate::nft::TokenId,
+                    royalty: Option<$crate::royalty::Royalty>,
+                ) {
+                    require!(
+                        env::attached_deposit() >= 1,
+                        "Requires attached deposit of at least 1 yoctoNEAR"
+                    );
+                    self.assert_royalty_owner();
+                    let initial_storage_usage = env::storage_usage();
+                    self.$royalties.set_token(&token_id, royalty);
+                    if env::storage_usage() >= initial_storage_usage {
+                        $crate::nft::refund_deposit(env::storage_usage() - initial_storage_usage);
+                    } else {
+                        $crate::nft::refund_released_storage(
+                            initial_storage_usage,
+                            env::predecessor_account_id(),
+                        );
+                    }
+                }
+
+                pub fn royalty_default(&self) -> $crate::royalty::Royalty {
+                    self.$royalties.default.clone()
+                }
+
+                pub fn royalty_of(&self, token_id: $crate::nft::TokenId) -> $crate::royalty::Royalty {
+                    self.$royalties.get(&token_id)
+                }
+            }
+        };
+    }
+pub use impl_royalty_registry;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    fn royalty(splits: &[(usize, u32)]) -> Royalty {
+        splits
+            .iter()
+            .map(|(account, bps)| (accounts(*account), *bps))
+            .collect()
+    }
+
+    #[test]
+    fn check_payout() {
+        run_vm(vm!("alice.near"));
+
+        let mut registry = Registry::new(2_000, 2);
+        registry.set_default(royalty(&[(1, 500)]));
+        registry.set_token(&"1".to_string(), Some(royalty(&[(1, 1_000), (2, 250)])));
+
+        let payout = registry.payout(&"0".to_string(), &accounts(0), 1_000);
+        assert_eq!(payout[&accounts(1)], U128(50));
+        assert_eq!(payout[&accounts(0)], U128(950));
+
+        let payout = registry.payout(&"1".to_string(), &accounts(0), 1_001);
+        assert_eq!(payout[&accounts(1)], U128(100));
+        assert_eq!(payout[&accounts(2)], U128(25));
+        assert_eq!(payout[&accounts(0)], U128(876));
+
+        registry.set_token(&"1".to_string(), None);
+        assert_eq!(registry.get(&"1".to_string()), royalty(&[(1, 500)]));
+    }
+
+    #[test]
+    fn check_share_does_not_overflow() {
+        assert_eq!(royalty_share(u128::MAX, 10_000), u128::MAX);
+        assert_eq!(royalty_share(u128::MAX, 0), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Royalty can't be more than 2000 bps in total")]
+    fn check_max_total_bps() {
+        run_vm(vm!("alice.near"));
+
+        let mut registry = Registry::new(2_000, 5);
+        registry.set_default(royalty(&[(1, 1_500), (2, 600)]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Royalty can't have more than 1 recipients")]
+    fn check_max_recipients() {
+        run_vm(vm!("alice.near"));
+
+        let mut registry = Registry::new(2_000, 1);
+        registry.set_default(royalty(&[(1, 100), (2, 100)]));
+    }
+}
@@ -0,0 +1,152 @@
+#![cfg(feature = "schema")]
+#![allow(dead_code)]
+/*!
+JSON Schemas for metadata structs, init args and NEP-297 event payloads.
+
+The standard metadata structs already derive [`JsonSchema`] (through the `abi` feature of
+near-contract-standards), and so do `ft::InitArgs`/`nft::InitArgs` with this feature.
+The event payloads are borrowed structs there, so this module provides owned mirrors of them
+that only exist for schema generation.
+
+# NOTES:
+  - Schemas are written by the `schema` bin, E.g. `make schema` or
+    `cargo run --features schema --bin schema -- ./build/schema`.
+  - Event schemas describe the JSON after the `EVENT_JSON:` prefix of the log.
+
+# EXAMPLE:
+```
+# use cmn::*;
+for (name, schema) in schema::schemas() {
+    println!("{}: {}", name, near_sdk::serde_json::to_string_pretty(&schema).unwrap());
+}
+```
+*/
+
+use super::*;
+
+pub use schemars::{
+    schema::{RootSchema, Schema},
+    schema_for, JsonSchema,
+};
+
+/// NEP-297 event log. `standard`, `version` and `event` are pinned by [`event_schema`].
+#[derive(JsonSchema)]
+pub struct EventLog<T> {
+    pub standard: String,
+    pub version: String,
+    pub event: String,
+    pub data: Vec<T>,
+}
+
+/// Payload of the `ft_mint` event.
+#[derive(JsonSchema)]
+pub struct FtMintData {
+    pub owner_id: AccountId,
+    pub amount: U128,
+    pub memo: Option<String>,
+}
+
+/// Payload of the `ft_transfer` event.
+#[derive(JsonSchema)]
+pub struct FtTransferData {
+    pub old_owner_id: AccountId,
+    pub new_owner_id: AccountId,
+    pub amount: U128,
+    pub memo: Option<String>,
+}
+
+/// Payload of the `ft_burn` event.
+#[derive(JsonSchema)]
+pub struct FtBurnData {
+    pub owner_id: AccountId,
+    pub amount: U128,
+    pub memo: Option<String>,
+}
+
+/// Payload of the `nft_mint` event.
+#[derive(JsonSchema)]
+pub struct NftMintData {
+    pub owner_id: AccountId,
+    pub token_ids: Vec<String>,
+    pub memo: Option<String>,
+}
+
+/// Payload of the `nft_transfer` event.
+#[derive(JsonSchema)]
+pub struct NftTransferData {
+    pub old_owner_id: AccountId,
+    pub new_owner_id: AccountId,
+    pub token_ids: Vec<String>,
+    pub authorized_id: Option<AccountId>,
+    pub memo: Option<String>,
+}
+
+/// Payload of the `nft_burn` event.
+#[derive(JsonSchema)]
+pub struct NftBurnData {
+    pub owner_id: AccountId,
+    pub token_ids: Vec<String>,
+    pub authorized_id: Option<AccountId>,
+    pub memo: Option<String>,
+}
+
+/// Schema of an [`EventLog`] with `standard`, `version` and `event` pinned to the given values.
+pub fn event_schema<T: JsonSchema>(standard: events::Standard, event: &str) -> RootSchema {
+    let mut root = schema_for!(EventLog<T>);
+    let props = &mut root.schema.object().properties;
+    for (key, value) in [
+        ("standard", standard.standard),
+        ("version", standard.version),
+        ("event", event),
+    ] {
+        if let Some(Schema::Object(prop)) = props.get_mut(key) {
+            prop.const_value = Some(value.into());
+        }
+    }
+    root
+}
+
+/// All schemas of the enabled components, keyed by a file-friendly name.
+pub fn schemas() -> Vec<(&'static str, RootSchema)> {
+    #[allow(unused_mut)]
+    let mut schemas = vec![];
+
+    #[cfg(feature = "ft")]
+    schemas.extend([
+        ("ft_metadata", schema_for!(ft::Metadata)),
+        ("ft_init_args", schema_for!(ft::InitArgs)),
+        (
+            "ft_mint_event",
+            event_schema::<FtMintData>(events::standards::NEP141, "ft_mint"),
+        ),
+        (
+            "ft_transfer_event",
+            event_schema::<FtTransferData>(events::standards::NEP141, "ft_transfer"),
+        ),
+        (
+            "ft_burn_event",
+            event_schema::<FtBurnData>(events::standards::NEP141, "ft_burn"),
+        ),
+    ]);
+
+    #[cfg(feature = "nft")]
+    schemas.extend([
+        ("nft_metadata", schema_for!(nft::Metadata)),
+        ("nft_token_metadata", schema_for!(nft::TokenMetadata)),
+        ("nft_init_args", schema_for!(nft::InitArgs)),
+        (
+            "nft_mint_event",
+            event_schema::<NftMintData>(events::standards::NEP171, "nft_mint"),
+        ),
+        (
+            "nft_transfer_event",
+            event_schema::<NftTransferData>(events::standards::NEP171, "nft_transfer"),
+        ),
+        (
+            "nft_burn_event",
+            event_schema::<NftBurnData>(events::standards::NEP171, "nft_burn"),
+        ),
+    ]);
+
+    schemas
+}
@@ -0,0 +1,226 @@
+#![cfg(feature = "signed_config")]
+#![allow(dead_code)]
+/*!
+Off-chain signed config updates.
+
+The owner registers an ed25519 signer key once, after that anyone (E.g. an oracle bot) can relay
+config patches signed by that key, so no full access key is needed for parameter updates.
+
+# NOTES:
+  - The signed message is `"{contract_id}:{nonce}:{payload}"`, so a signature can't be replayed
+    on another contract, and `nonce` has to be greater than the last applied one.
+  - `payload` is the JSON of [`ApplyConfig::Patch`], signed in its [`canonical_json`] form
+    (sorted keys, no whitespace), so its formatting in the args doesn't matter.
+  - near-sdk 4.1 has no `ed25519_verify` host function, so the signature is verified in wasm
+    with ed25519-dalek, which costs noticeably more gas than a host function would.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    fee_bps: u16,
+    signed_config: signed_config::SignedConfig,
+}
+
+#[derive(near_sdk::serde::Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ConfigPatch {
+    fee_bps: Option<u16>,
+}
+
+impl signed_config::ApplyConfig for Contract {
+    type Patch = ConfigPatch;
+    fn apply_config(&mut self, patch: ConfigPatch) {
+        if let Some(fee_bps) = patch.fee_bps {
+            self.fee_bps = fee_bps;
+        }
+    }
+}
+
+// impl_signed_config!(Contract, signed_config[, owner = <AccountId field; default: the contract itself>]);
+signed_config::impl_signed_config!(Contract, signed_config, owner = owner_id);
+```
+*/
+
+use super::*;
+
+use ed25519_dalek::{PublicKey as Ed25519PublicKey, Signature, Verifier};
+use near_sdk::{serde::de::DeserializeOwned, CurveType, PublicKey};
+
+/// Applies a verified config patch to the contract.
+pub trait ApplyConfig {
+    type Patch: DeserializeOwned;
+    fn apply_config(&mut self, patch: Self::Patch);
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Debug, Default)]
+pub struct SignedConfig {
+    pub signer: Option<PublicKey>,
+    /// The last applied nonce.
+    pub nonce: u64,
+}
+impl SignedConfig {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_signer(&mut self, signer: PublicKey) {
+        require!(
+            signer.curve_type() == CurveType::ED25519,
+            "Signer must be an ed25519 key"
+        );
+        self.signer = Some(signer);
+    }
+
+    /// The bytes the signer has to sign, `payload` being canonical JSON.
+    #[inline]
+    pub fn message(payload: &str, nonce: u64) -> Vec<u8> {
+        format!("{}:{}:{}", env::current_account_id(), nonce, payload).into_bytes()
+    }
+
+    /// Canonical form of a JSON payload.
+    pub fn canonical_payload(payload: &str) -> String {
+        let value = near_sdk::serde_json::from_str(payload)
+            .unwrap_or_else(|_| env::panic_str("Invalid config payload"));
+        // canonical JSON is valid UTF-8
+        String::from_utf8(canonical_json(&value)).unwrap()
+    }
+
+    /// Verifies the signature and consumes the nonce.
+    pub fn verify(&mut self, payload: &str, signature: &[u8], nonce: u64) {
+        let signer = self
+            .signer
+            .as_ref()
+            .unwrap_or_else(|| env::panic_str("Config signer is not set"));
+        require!(nonce > self.nonce, "Nonce is already used");
+
+        // the first byte is the curve type
+        let public_key = Ed25519PublicKey::from_bytes(&signer.as_bytes()[1..])
+            .unwrap_or_else(|_| env::panic_str("Invalid signer key"));
+        let signature = Signature::from_bytes(signature)
+            .unwrap_or_else(|_| env::panic_str("Invalid signature"));
+        let payload = Self::canonical_payload(payload);
+        require!(
+            public_key
+                .verify(&Self::message(&payload, nonce), &signature)
+                .is_ok(),
+            "Wrong signature"
+        );
+
+        self.nonce = nonce;
+    }
+}
+
+#[macro_export]
+macro_rules! impl_signed_config {
+        ($contract:ident, $sc:ident $(, owner = $owner:ident)?) => {
+            #[near_bindgen]
+            impl $contract {
+                pub fn set_config_signer(&mut self, public_key: near_sdk::PublicKey) {
+                    let owner_id = None::<&AccountId>$(.or(Some(&self.$owner)))?
+                        .cloned()
+                        .unwrap_or_else(env::current_account_id);
+                    require!(
+                        env::predecessor_account_id() == owner_id,
+                        "Only the owner can set the config signer"
+                    );
+                    self.$sc.set_signer(public_key);
+                }
+
+                pub fn config_signer(&self) -> Option<near_sdk::PublicKey> {
+                    self.$sc.signer.clone()
+                }
+
+                pub fn config_nonce(&self) -> U64 {
+                    self.$sc.nonce.into()
+                }
+
+                pub fn apply_signed_config(&mut self, payload: String, signature: Base64VecU8, nonce: U64) {
+                    self.$sc.verify(&payload, &signature.0, nonce.0);
+                    let patch = near_sdk::serde_json::from_str(&payload)
+                        .unwrap_or_else(|_| env::panic_str("Invalid config payload"));
+                    $crate::signed_config::ApplyConfig::apply_config(self, patch);
+                    log!("Signed config #{} applied", nonce.0);
+                }
+            }
+        };
+    }
+pub use impl_signed_config;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    use ed25519_dalek::{Keypair, SecretKey, Signer};
+
+    fn keypair() -> Keypair {
+        let secret = SecretKey::from_bytes(&[7; 32]).unwrap();
+        let public = (&secret).into();
+        Keypair { secret, public }
+    }
+
+    fn signer() -> PublicKey {
+        let mut bytes = vec![0];
+        bytes.extend_from_slice(keypair().public.as_bytes());
+        PublicKey::try_from(bytes).unwrap()
+    }
+
+    #[test]
+    fn check_verify() {
+        run_vm(vm!("alice.near"));
+
+        let mut config = SignedConfig::new();
+        config.set_signer(signer());
+
+        let payload = r#"{"fee_bps":30}"#;
+        let signature = keypair().sign(&SignedConfig::message(payload, 1));
+        config.verify(payload, &signature.to_bytes(), 1);
+        assert_eq!(config.nonce, 1);
+    }
+
+    #[test]
+    fn check_verify_canonical() {
+        run_vm(vm!("alice.near"));
+
+        let mut config = SignedConfig::new();
+        config.set_signer(signer());
+
+        let signature = keypair().sign(&SignedConfig::message(r#"{"a":1,"fee_bps":30}"#, 1));
+        config.verify(r#"{ "fee_bps": 30, "a": 1 }"#, &signature.to_bytes(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Nonce is already used")]
+    fn check_replay() {
+        run_vm(vm!("alice.near"));
+
+        let mut config = SignedConfig::new();
+        config.set_signer(signer());
+
+        let payload = r#"{"fee_bps":30}"#;
+        let signature = keypair()
+            .sign(&SignedConfig::message(payload, 1))
+            .to_bytes();
+        config.verify(payload, &signature, 1);
+        config.verify(payload, &signature, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Wrong signature")]
+    fn check_tampered_payload() {
+        run_vm(vm!("alice.near"));
+
+        let mut config = SignedConfig::new();
+        config.set_signer(signer());
+
+        let signature = keypair().sign(&SignedConfig::message(r#"{"fee_bps":30}"#, 1));
+        config.verify(r#"{"fee_bps":3000}"#, &signature.to_bytes(), 1);
+    }
+}
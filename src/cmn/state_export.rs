@@ -0,0 +1,215 @@
+#![cfg(feature = "state_export")]
+#![allow(dead_code)]
+/*!
+Raw state export and import, to clone the data of a deployed contract into a testnet instance,
+E.g. to replay an upgrade against the production data.
+
+# NOTES:
+  - The runtime doesn't iterate the storage (the `storage_iter_*` host functions are deprecated)
+    and the state of a contract is public anyway, so the export is off-chain: the `export_state`
+    bin pages the `view_state` RPC result of the contract with [`export_state`], see `make export_state`.
+  - `import_state` writes the exported entries as is, `STATE` included. Before initialization
+    only the contract account can call it, after it only the owner and while the contract is
+    paused. Without a `paused` field, an initialized contract refuses imports.
+  - The contract pays the storage of the imported entries.
+  - A batch is limited by the gas and the size of the arguments, pages of
+    [`DEFAULT_EXPORT_LIMIT`] entries fit in one call.
+
+# EXAMPLE:
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    paused: bool,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[init]
+    pub fn new(owner_id: AccountId) -> Self {
+        require_init!();
+        Self {
+            owner_id,
+            paused: false,
+        }
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        require!(env::predecessor_account_id() == self.owner_id, "Only the owner can pause");
+        self.paused = paused;
+    }
+}
+
+// impl_state_import!(Contract[, owner = <AccountId field; default: the contract itself>][, paused = <bool field>]);
+impl_state_import!(Contract, owner = owner_id, paused = paused);
+
+#[cfg(test)]
+mod tests {
+    use super::test_utils::*;
+    use super::*;
+
+    fn exported(owner_id: AccountId) -> Vec<state_export::StateEntry> {
+        let state = Contract {
+            owner_id,
+            paused: false,
+        };
+        vec![(
+            b"STATE".to_vec().into(),
+            state.try_to_vec().unwrap().into(),
+        )]
+    }
+
+    #[test]
+    fn test_import_uninitialized() {
+        run_vm(vm!(accounts(0)).current_account_id(accounts(0)));
+
+        assert_eq!(Contract::import_state(exported(accounts(1))), 1);
+        let contract = env::state_read::<Contract>().unwrap();
+        assert_eq!(contract.owner_id, accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the contract can import its state")]
+    fn test_import_uninitialized_not_contract() {
+        run_vm(vm!(accounts(1)).current_account_id(accounts(0)));
+
+        Contract::import_state(exported(accounts(1)));
+    }
+
+    #[test]
+    fn test_import_paused() {
+        run_vm(vm!(accounts(1)).current_account_id(accounts(0)));
+        let mut contract = Contract::new(accounts(1));
+        contract.set_paused(true);
+        env::state_write(&contract);
+
+        Contract::import_state(exported(accounts(2)));
+        assert_eq!(env::state_read::<Contract>().unwrap().owner_id, accounts(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "The contract must be paused to import state")]
+    fn test_import_not_paused() {
+        run_vm(vm!(accounts(1)).current_account_id(accounts(0)));
+        env::state_write(&Contract::new(accounts(1)));
+
+        Contract::import_state(exported(accounts(2)));
+    }
+}
+```
+*/
+
+use super::*;
+
+/// A raw storage entry, key and value.
+pub type StateEntry = (Base64VecU8, Base64VecU8);
+
+/// Entries of an export page when no limit is given.
+pub const DEFAULT_EXPORT_LIMIT: usize = 100;
+
+/// Page of `entries` with keys starting with `prefix`, ordered by key, after the `from` key.
+/// The last key of a page is the `from` of the next one.
+pub fn export_state(
+    entries: impl IntoIterator<Item = StateEntry>,
+    prefix: &[u8],
+    from: Option<&[u8]>,
+    limit: Option<usize>,
+) -> Vec<StateEntry> {
+    let mut page: Vec<StateEntry> = entries
+        .into_iter()
+        .filter(|(key, _)| key.0.starts_with(prefix) && from.map_or(true, |from| key.0[..] > *from))
+        .collect();
+    page.sort_by(|(a, _), (b, _)| a.0.cmp(&b.0));
+    page.truncate(limit.unwrap_or(DEFAULT_EXPORT_LIMIT));
+    page
+}
+
+/// Writes the entries to the storage, returns how many.
+pub fn import_state(entries: &[StateEntry]) -> u32 {
+    for (key, value) in entries {
+        env::storage_write(&key.0, &value.0);
+    }
+    entries.len() as u32
+}
+
+#[macro_export]
+macro_rules! impl_state_import {
+        ($contract:ident $(, owner = $owner:ident)? $(, paused = $paused:ident)? $(,)?) => {
+            impl $contract {
+                /// Manifest entries of the methods generated by `impl_state_import!`.
+                pub const STATE_IMPORT_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                    feature: Some("state_export"),
+                    methods: &[$crate::manifest::Method::call("import_state").role("owner")],
+                    storage: &[],
+                };
+            }
+            #[near_bindgen]
+            impl $contract {
+                /// Writes the raw entries of `export_state`. It doesn't take `self`, so it also
+                /// works before initialization.
+                pub fn import_state(entries: Vec<$crate::state_export::StateEntry>) -> u32 {
+                    match env::state_read::<$contract>() {
+                        None => require!(
+                            env::predecessor_account_id() == env::current_account_id(),
+                            "Only the contract can import its state"
+                        ),
+                        #[allow(unused_variables)]
+                        Some(state) => {
+                            #[allow(unused_variables)]
+                            let paused = false;
+                            $(let paused = state.$paused;)?
+                            require!(paused, "The contract must be paused to import state");
+                            let owner_id = None::<&AccountId>$(.or(Some(&state.$owner)))?
+                                .cloned()
+                                .unwrap_or_else(env::current_account_id);
+                            require!(
+                                env::predecessor_account_id() == owner_id,
+                                "Only the owner can import state"
+                            );
+                        }
+                    }
+                    $crate::state_export::import_state(&entries)
+                }
+            }
+        };
+    }
+pub use impl_state_import;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    fn entry(key: &[u8], value: &[u8]) -> StateEntry {
+        (key.to_vec().into(), value.to_vec().into())
+    }
+
+    #[test]
+    fn check_export_pages() {
+        let entries = vec![
+            entry(b"b2", b"4"),
+            entry(b"STATE", b"0"),
+            entry(b"b0", b"2"),
+            entry(b"a", b"1"),
+            entry(b"b1", b"3"),
+        ];
+
+        let page = export_state(entries.clone(), b"b", None, Some(2));
+        assert_eq!(page, vec![entry(b"b0", b"2"), entry(b"b1", b"3")]);
+        let page = export_state(entries.clone(), b"b", Some(b"b1"), Some(2));
+        assert_eq!(page, vec![entry(b"b2", b"4")]);
+        assert_eq!(export_state(entries, b"", None, None).len(), 5);
+    }
+
+    #[test]
+    fn check_import() {
+        run_vm(vm!("alice.near"));
+
+        assert_eq!(import_state(&[entry(b"a", b"1"), entry(b"b", b"2")]), 2);
+        assert_eq!(env::storage_read(b"b"), Some(b"2".to_vec()));
+    }
+}
@@ -0,0 +1,1296 @@
+#![cfg(feature = "state_schema")]
+#![allow(dead_code)]
+/*!
+Borsh schemas of the stored state, so migrations and off-chain state readers (E.g. a
+state-viewer) can decode the raw trie values without the Rust types.
+
+The near-sdk collections, the json types and most types of this crate don't implement the
+`BorshSchema` of borsh, so [`StateSchema`] gives the same declarations and definitions for them,
+[`impl_state_schema!`] describes a struct or an enum, and [`components`] lists the records of every
+enabled component: the prefix of each collection or value and the borsh of its keys and values.
+
+# NOTES:
+  - Written by the `state_schema` bin, E.g. `make state_schema` or
+    `cargo run --features state_schema --bin state_schema > ./build/state_schema.json`.
+  - The output is deterministic, definitions and records are sorted by name.
+  - Prefixes are base64, like the keys of the `view_state` RPC. A key is the prefix followed by
+    the borsh of the key declaration (none for a single value), a value is the borsh of the value
+    declaration.
+  - The prefixes of nested collections, E.g. the set of tokens of an owner, are stored in the
+    struct of the parent value, they aren't listed.
+  - The `ft` records are listed without a namespace, see `FungibleToken::new_in`.
+  - The declarations are the borsh ones, E.g. `string`, `Vec<u8>` and `Option<u64>`, so the
+    definitions can be fed to the borsh schema tooling.
+
+# EXAMPLE:
+Needs the `ft` feature too.
+```
+mod cmn;
+use cmn::*;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    owner_id: AccountId,
+    ft: ft::FungibleToken,
+}
+
+// impl_state_schema!(<type>[ as "<declaration>"] { <field>: <type>, .. });
+impl_state_schema!(Contract {
+    owner_id: AccountId,
+    ft: ft::FungibleToken,
+});
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_schema() {
+        let json = state_schema::Schema::new::<Contract>()
+            .components(state_schema::components())
+            .to_json();
+        assert_eq!(json["state"]["key"], "U1RBVEU=");
+        assert_eq!(
+            json["definitions"]["Contract"]["Struct"]["fields"]["NamedFields"][1],
+            near_sdk::serde_json::json!(["ft", "FungibleToken"])
+        );
+        assert_eq!(
+            json["components"]["ft"]["accounts"]["entries"][0],
+            near_sdk::serde_json::json!({ "prefix": "AA==", "key": "AccountId", "value": "u128" })
+        );
+    }
+}
+```
+*/
+
+use super::*;
+
+use near_sdk::{
+    borsh::schema::{Declaration, Definition, Fields},
+    collections::{LookupMap, LookupSet, UnorderedMap, UnorderedSet, Vector},
+    serde_json, CryptoHash, IntoStorageKey,
+};
+use std::collections::{hash_map::Entry as MapEntry, BTreeMap, HashMap, HashSet};
+
+pub use near_sdk::borsh::schema;
+
+pub type Definitions = HashMap<Declaration, Definition>;
+
+/// Borsh declaration and definitions of a stored type, like `BorshSchema`.
+pub trait StateSchema {
+    fn declaration() -> Declaration;
+
+    /// Adds the definitions of the type and of the types it holds.
+    fn add_definitions_recursively(definitions: &mut Definitions);
+}
+
+/// Adds a definition, panics if another one has the same declaration.
+pub fn add_definition(
+    declaration: Declaration,
+    definition: Definition,
+    definitions: &mut Definitions,
+) {
+    match definitions.entry(declaration) {
+        MapEntry::Occupied(entry) => assert_eq!(
+            entry.get(),
+            &definition,
+            "Two definitions of `{}`, rename one with `as`",
+            entry.key()
+        ),
+        MapEntry::Vacant(entry) => {
+            entry.insert(definition);
+        }
+    }
+}
+
+/// `declaration` or the last segment of the path, used by [`impl_state_schema!`].
+#[doc(hidden)]
+pub fn declaration_of(path: &str, declaration: Option<&str>) -> Declaration {
+    declaration
+        .unwrap_or_else(|| path.rsplit("::").next().unwrap_or(path))
+        .trim()
+        .to_string()
+}
+
+/// The fields of a struct or of an enum variant.
+#[doc(hidden)]
+pub fn fields(unnamed: Vec<Declaration>, named: Vec<(&str, Declaration)>) -> Fields {
+    if !named.is_empty() {
+        Fields::NamedFields(
+            named
+                .into_iter()
+                .map(|(name, declaration)| (name.to_string(), declaration))
+                .collect(),
+        )
+    } else if !unnamed.is_empty() {
+        Fields::UnnamedFields(unnamed)
+    } else {
+        Fields::Empty
+    }
+}
+
+macro_rules! impl_for_borsh_schema {
+    ($($type:ty),+) => {
+        $(
+            impl StateSchema for $type {
+                fn declaration() -> Declaration {
+                    <$type as near_sdk::borsh::BorshSchema>::declaration()
+                }
+                fn add_definitions_recursively(definitions: &mut Definitions) {
+                    <$type as near_sdk::borsh::BorshSchema>::add_definitions_recursively(definitions)
+                }
+            }
+        )+
+    };
+}
+impl_for_borsh_schema!((), bool, u8, u16, u32, u64, u128, i64, String, AccountId);
+
+impl<const N: usize> StateSchema for strings::BoundedString<N> {
+    /// The same borsh as a `String`.
+    fn declaration() -> Declaration {
+        String::declaration()
+    }
+    fn add_definitions_recursively(_definitions: &mut Definitions) {}
+}
+
+impl<T: StateSchema, const N: usize> StateSchema for [T; N] {
+    fn declaration() -> Declaration {
+        format!("Array<{}, {}>", T::declaration(), N)
+    }
+    fn add_definitions_recursively(definitions: &mut Definitions) {
+        let definition = Definition::Array {
+            length: N as u32,
+            elements: T::declaration(),
+        };
+        add_definition(Self::declaration(), definition, definitions);
+        T::add_definitions_recursively(definitions);
+    }
+}
+
+impl<T: StateSchema> StateSchema for Option<T> {
+    fn declaration() -> Declaration {
+        format!("Option<{}>", T::declaration())
+    }
+    fn add_definitions_recursively(definitions: &mut Definitions) {
+        let definition = Definition::Enum {
+            variants: vec![
+                ("None".to_string(), <()>::declaration()),
+                ("Some".to_string(), T::declaration()),
+            ],
+        };
+        add_definition(Self::declaration(), definition, definitions);
+        T::add_definitions_recursively(definitions);
+    }
+}
+
+impl<T: StateSchema> StateSchema for Vec<T> {
+    fn declaration() -> Declaration {
+        format!("Vec<{}>", T::declaration())
+    }
+    fn add_definitions_recursively(definitions: &mut Definitions) {
+        let definition = Definition::Sequence {
+            elements: T::declaration(),
+        };
+        add_definition(Self::declaration(), definition, definitions);
+        T::add_definitions_recursively(definitions);
+    }
+}
+
+impl<T: StateSchema> StateSchema for HashSet<T> {
+    fn declaration() -> Declaration {
+        format!("HashSet<{}>", T::declaration())
+    }
+    fn add_definitions_recursively(definitions: &mut Definitions) {
+        let definition = Definition::Sequence {
+            elements: T::declaration(),
+        };
+        add_definition(Self::declaration(), definition, definitions);
+        T::add_definitions_recursively(definitions);
+    }
+}
+
+impl<K: StateSchema, V: StateSchema> StateSchema for HashMap<K, V> {
+    fn declaration() -> Declaration {
+        format!("HashMap<{}, {}>", K::declaration(), V::declaration())
+    }
+    fn add_definitions_recursively(definitions: &mut Definitions) {
+        let definition = Definition::Sequence {
+            elements: <(K, V)>::declaration(),
+        };
+        add_definition(Self::declaration(), definition, definitions);
+        <(K, V)>::add_definitions_recursively(definitions);
+    }
+}
+
+impl<T0: StateSchema, T1: StateSchema> StateSchema for (T0, T1) {
+    fn declaration() -> Declaration {
+        format!("Tuple<{}, {}>", T0::declaration(), T1::declaration())
+    }
+    fn add_definitions_recursively(definitions: &mut Definitions) {
+        let definition = Definition::Tuple {
+            elements: vec![T0::declaration(), T1::declaration()],
+        };
+        add_definition(Self::declaration(), definition, definitions);
+        T0::add_definitions_recursively(definitions);
+        T1::add_definitions_recursively(definitions);
+    }
+}
+
+/// A collection of near-sdk, stored under its prefix.
+pub trait Collection: StateSchema {
+    /// The records of the collection under `prefix`.
+    fn entries(prefix: &[u8]) -> Vec<Entry>;
+}
+
+/// Declares a near-sdk collection, `$field`s are the ones it holds in the parent value.
+macro_rules! impl_collection {
+    (
+        $collection:ident<$($param:ident),+> { $($field:ident: $field_type:ty),* },
+        |$prefix:ident| $entries:expr
+    ) => {
+        impl<$($param: StateSchema),+> StateSchema for $collection<$($param),+> {
+            fn declaration() -> Declaration {
+                let params: Vec<_> = vec![$($param::declaration()),+];
+                format!("{}<{}>", stringify!($collection), params.join(", "))
+            }
+            fn add_definitions_recursively(definitions: &mut Definitions) {
+                let fields = vec![$((stringify!($field), <$field_type>::declaration())),*];
+                let definition = Definition::Struct {
+                    fields: self::fields(vec![], fields),
+                };
+                add_definition(Self::declaration(), definition, definitions);
+                $(<$field_type>::add_definitions_recursively(definitions);)*
+                $($param::add_definitions_recursively(definitions);)+
+            }
+        }
+        impl<$($param: StateSchema),+> Collection for $collection<$($param),+> {
+            fn entries($prefix: &[u8]) -> Vec<Entry> {
+                $entries
+            }
+        }
+    };
+}
+
+impl_collection!(LookupMap<K, V> { key_prefix: Vec<u8> }, |prefix| vec![
+    Entry::new::<K, V>(prefix)
+]);
+impl_collection!(LookupSet<T> { element_prefix: Vec<u8> }, |prefix| vec![
+    Entry::new::<T, ()>(prefix)
+]);
+impl_collection!(Vector<T> { len: u64, prefix: Vec<u8> }, |prefix| vec![
+    Entry::new::<u64, T>(prefix)
+]);
+impl_collection!(
+    UnorderedMap<K, V> { key_index_prefix: Vec<u8>, keys: Vector<K>, values: Vector<V> },
+    |prefix| vec![
+        Entry::new::<K, u64>(&[prefix, b"i"].concat()),
+        Entry::new::<u64, K>(&[prefix, b"k"].concat()),
+        Entry::new::<u64, V>(&[prefix, b"v"].concat()),
+    ]
+);
+impl_collection!(
+    UnorderedSet<T> { element_index_prefix: Vec<u8>, elements: Vector<T> },
+    |prefix| vec![
+        Entry::new::<T, u64>(&[prefix, b"i"].concat()),
+        Entry::new::<u64, T>(&[prefix, b"e"].concat()),
+    ]
+);
+impl_collection!(
+    TreeMap<K, V> { root: u64, val: LookupMap<K, V>, tree: Vector<TreeNode<K>> },
+    |prefix| vec![
+        Entry::new::<K, V>(&[prefix, b"v"].concat()),
+        Entry::new::<u64, TreeNode<K>>(&[prefix, b"n"].concat()),
+    ]
+);
+impl_collection!(LazyOption<T> { storage_key: Vec<u8> }, |prefix| vec![Entry {
+    prefix: prefix.to_vec(),
+    key: None,
+    value: T::declaration(),
+}]);
+
+/// `Node` of the near-sdk `TreeMap`, private there.
+pub struct TreeNode<K>(std::marker::PhantomData<K>);
+impl<K: StateSchema> StateSchema for TreeNode<K> {
+    fn declaration() -> Declaration {
+        format!("Node<{}>", K::declaration())
+    }
+    fn add_definitions_recursively(definitions: &mut Definitions) {
+        let fields = vec![
+            ("id", u64::declaration()),
+            ("key", K::declaration()),
+            ("lft", <Option<u64>>::declaration()),
+            ("rgt", <Option<u64>>::declaration()),
+            ("ht", u64::declaration()),
+        ];
+        let definition = Definition::Struct {
+            fields: self::fields(vec![], fields),
+        };
+        add_definition(Self::declaration(), definition, definitions);
+        K::add_definitions_recursively(definitions);
+        <Option<u64>>::add_definitions_recursively(definitions);
+    }
+}
+
+/// The borsh of a `LookupMap<K, u64>`.
+impl<K: StateSchema + BorshSerialize> StateSchema for utils::counter::CounterMap<K> {
+    fn declaration() -> Declaration {
+        <LookupMap<K, u64>>::declaration()
+    }
+    fn add_definitions_recursively(definitions: &mut Definitions) {
+        <LookupMap<K, u64>>::add_definitions_recursively(definitions)
+    }
+}
+impl<K: StateSchema + BorshSerialize> Collection for utils::counter::CounterMap<K> {
+    fn entries(prefix: &[u8]) -> Vec<Entry> {
+        <LookupMap<K, u64>>::entries(prefix)
+    }
+}
+
+/// Describes the borsh of a struct, a tuple struct or an enum.
+///
+/// # Example
+/// ```
+/// # use cmn::*;
+/// #[derive(BorshDeserialize, BorshSerialize)]
+/// pub struct Offer {
+///     pub maker_id: AccountId,
+///     pub price: Price,
+///     pub expires_at: Option<U64>,
+/// }
+/// #[derive(BorshDeserialize, BorshSerialize)]
+/// pub enum Price {
+///     Free,
+///     Near(U128),
+///     Ft { token_id: AccountId, amount: U128 },
+/// }
+/// #[derive(BorshDeserialize, BorshSerialize)]
+/// pub struct OfferId(pub u64);
+///
+/// // impl_state_schema!(<type>[ as "<declaration>"] { <field>: <type>, .. });
+/// impl_state_schema!(Offer as "MarketOffer" {
+///     maker_id: AccountId,
+///     price: Price,
+///     expires_at: Option<U64>,
+/// });
+/// // impl_state_schema!(enum <type>[ as "<declaration>"] { <variant>[(<type>, ..) | { <field>: <type>, .. }], .. });
+/// impl_state_schema!(enum Price {
+///     Free,
+///     Near(U128),
+///     Ft { token_id: AccountId, amount: U128 },
+/// });
+/// // impl_state_schema!(<type>[ as "<declaration>"] (<type>, ..));
+/// impl_state_schema!(OfferId(u64));
+/// ```
+#[macro_export]
+macro_rules! impl_state_schema {
+    (
+        $($type:ident)::+ $(as $declaration:literal)?
+        { $($field:ident: $field_type:ty),* $(,)? }
+    ) => {
+        impl $crate::state_schema::StateSchema for $($type)::+ {
+            fn declaration() -> $crate::state_schema::schema::Declaration {
+                $crate::state_schema::declaration_of(
+                    stringify!($($type)::+),
+                    None$(.or(Some($declaration)))?,
+                )
+            }
+            fn add_definitions_recursively(definitions: &mut $crate::state_schema::Definitions) {
+                let fields = vec![$((stringify!($field), <$field_type as $crate::state_schema::StateSchema>::declaration())),*];
+                let definition = $crate::state_schema::schema::Definition::Struct {
+                    fields: $crate::state_schema::fields(vec![], fields),
+                };
+                $crate::state_schema::add_definition(<Self as $crate::state_schema::StateSchema>::declaration(), definition, definitions);
+                $(<$field_type as $crate::state_schema::StateSchema>::add_definitions_recursively(definitions);)*
+            }
+        }
+    };
+    (
+        $($type:ident)::+ $(as $declaration:literal)?
+        ($($field_type:ty),* $(,)?)
+    ) => {
+        impl $crate::state_schema::StateSchema for $($type)::+ {
+            fn declaration() -> $crate::state_schema::schema::Declaration {
+                $crate::state_schema::declaration_of(
+                    stringify!($($type)::+),
+                    None$(.or(Some($declaration)))?,
+                )
+            }
+            fn add_definitions_recursively(definitions: &mut $crate::state_schema::Definitions) {
+                let fields = vec![$(<$field_type as $crate::state_schema::StateSchema>::declaration()),*];
+                let definition = $crate::state_schema::schema::Definition::Struct {
+                    fields: $crate::state_schema::fields(fields, vec![]),
+                };
+                $crate::state_schema::add_definition(<Self as $crate::state_schema::StateSchema>::declaration(), definition, definitions);
+                $(<$field_type as $crate::state_schema::StateSchema>::add_definitions_recursively(definitions);)*
+            }
+        }
+    };
+    (
+        enum $($type:ident)::+ $(as $declaration:literal)? {
+            $(
+                $variant:ident
+                $(($($unnamed_type:ty),* $(,)?))?
+                $({ $($named:ident: $named_type:ty),* $(,)? })?
+            ),* $(,)?
+        }
+    ) => {
+        impl $crate::state_schema::StateSchema for $($type)::+ {
+            fn declaration() -> $crate::state_schema::schema::Declaration {
+                $crate::state_schema::declaration_of(
+                    stringify!($($type)::+),
+                    None$(.or(Some($declaration)))?,
+                )
+            }
+            fn add_definitions_recursively(definitions: &mut $crate::state_schema::Definitions) {
+                // one struct per variant, named after the enum, like the derive of borsh
+                let mut variants = vec![];
+                $(
+                    let declaration = format!("{}{}", <Self as $crate::state_schema::StateSchema>::declaration(), stringify!($variant));
+                    let fields = $crate::state_schema::fields(
+                        vec![$($(<$unnamed_type as $crate::state_schema::StateSchema>::declaration()),*)?],
+                        vec![$($((stringify!($named), <$named_type as $crate::state_schema::StateSchema>::declaration())),*)?],
+                    );
+                    $crate::state_schema::add_definition(
+                        declaration.clone(),
+                        $crate::state_schema::schema::Definition::Struct { fields },
+                        definitions,
+                    );
+                    $($(<$unnamed_type as $crate::state_schema::StateSchema>::add_definitions_recursively(definitions);)*)?
+                    $($(<$named_type as $crate::state_schema::StateSchema>::add_definitions_recursively(definitions);)*)?
+                    variants.push((stringify!($variant).to_string(), declaration));
+                )*
+                $crate::state_schema::add_definition(
+                    <Self as $crate::state_schema::StateSchema>::declaration(),
+                    $crate::state_schema::schema::Definition::Enum { variants },
+                    definitions,
+                );
+            }
+        }
+    };
+}
+pub use impl_state_schema;
+
+impl_state_schema!(U64(u64));
+impl_state_schema!(U128(u128));
+impl_state_schema!(I64(i64));
+impl_state_schema!(Base64VecU8(Vec<u8>));
+
+/// Records under a prefix: the key is the prefix followed by the borsh of `key`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    pub prefix: Vec<u8>,
+    /// None for a single value, stored at the prefix.
+    pub key: Option<Declaration>,
+    pub value: Declaration,
+}
+impl Entry {
+    #[inline]
+    pub fn new<K: StateSchema, V: StateSchema>(prefix: &[u8]) -> Self {
+        Self {
+            prefix: prefix.to_vec(),
+            key: Some(K::declaration()),
+            value: V::declaration(),
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "prefix": Base64VecU8(self.prefix.clone()),
+            "key": self.key,
+            "value": self.value,
+        })
+    }
+}
+
+/// A collection or a single value of a component.
+pub struct Record {
+    pub name: &'static str,
+    pub declaration: Declaration,
+    pub entries: Vec<Entry>,
+    add_definitions: fn(&mut Definitions),
+}
+impl Record {
+    /// A near-sdk collection under `prefix`.
+    pub fn collection<C: Collection>(name: &'static str, prefix: impl IntoStorageKey) -> Self {
+        Self {
+            name,
+            declaration: C::declaration(),
+            entries: C::entries(&prefix.into_storage_key()),
+            add_definitions: C::add_definitions_recursively,
+        }
+    }
+
+    /// A value written at `key` with `env::storage_write`.
+    pub fn value<T: StateSchema>(name: &'static str, key: impl IntoStorageKey) -> Self {
+        Self {
+            name,
+            declaration: T::declaration(),
+            entries: vec![Entry {
+                prefix: key.into_storage_key(),
+                key: None,
+                value: T::declaration(),
+            }],
+            add_definitions: T::add_definitions_recursively,
+        }
+    }
+}
+
+/// The records of a component.
+pub struct Component {
+    pub name: &'static str,
+    pub records: Vec<Record>,
+}
+impl Component {
+    #[inline]
+    pub fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            records: vec![],
+        }
+    }
+
+    #[inline]
+    pub fn record(mut self, record: Record) -> Self {
+        self.records.push(record);
+        self
+    }
+}
+
+/// The schema of the `STATE` value of a contract, with the records of its components.
+pub struct Schema {
+    pub state: Declaration,
+    pub components: Vec<Component>,
+    pub definitions: Definitions,
+}
+impl Schema {
+    pub fn new<State: StateSchema>() -> Self {
+        let mut definitions = Definitions::new();
+        State::add_definitions_recursively(&mut definitions);
+        Self {
+            state: State::declaration(),
+            components: vec![],
+            definitions,
+        }
+    }
+
+    pub fn component(mut self, component: Component) -> Self {
+        for record in &component.records {
+            (record.add_definitions)(&mut self.definitions);
+        }
+        self.components.push(component);
+        self
+    }
+
+    #[inline]
+    pub fn components(self, components: impl IntoIterator<Item = Component>) -> Self {
+        components.into_iter().fold(self, Self::component)
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        let components: BTreeMap<_, BTreeMap<_, _>> = self
+            .components
+            .iter()
+            .map(|component| {
+                let records = component
+                    .records
+                    .iter()
+                    .map(|record| {
+                        let entries: Vec<_> = record.entries.iter().map(Entry::to_json).collect();
+                        (
+                            record.name,
+                            serde_json::json!({
+                                "declaration": record.declaration,
+                                "entries": entries,
+                            }),
+                        )
+                    })
+                    .collect();
+                (component.name, records)
+            })
+            .collect();
+        let definitions: BTreeMap<_, _> = self
+            .definitions
+            .iter()
+            .map(|(declaration, definition)| (declaration, definition_json(definition)))
+            .collect();
+        serde_json::json!({
+            "state": {
+                "key": Base64VecU8(b"STATE".to_vec()),
+                "declaration": self.state,
+            },
+            "components": components,
+            "definitions": definitions,
+        })
+    }
+}
+
+/// A definition in the externally tagged form of serde, E.g. `{"Sequence": {"elements": "u8"}}`.
+pub fn definition_json(definition: &Definition) -> serde_json::Value {
+    match definition {
+        Definition::Array { length, elements } => {
+            serde_json::json!({ "Array": { "length": length, "elements": elements } })
+        }
+        Definition::Sequence { elements } => {
+            serde_json::json!({ "Sequence": { "elements": elements } })
+        }
+        Definition::Tuple { elements } => serde_json::json!({ "Tuple": { "elements": elements } }),
+        Definition::Enum { variants } => serde_json::json!({ "Enum": { "variants": variants } }),
+        Definition::Struct { fields } => {
+            let fields = match fields {
+                Fields::NamedFields(fields) => serde_json::json!({ "NamedFields": fields }),
+                Fields::UnnamedFields(fields) => serde_json::json!({ "UnnamedFields": fields }),
+                Fields::Empty => serde_json::json!("Empty"),
+            };
+            serde_json::json!({ "Struct": { "fields": fields } })
+        }
+    }
+}
+
+#[cfg(feature = "feature_flags")]
+impl_state_schema!(features::FeatureFlags(u64));
+impl_state_schema!(protocol::ProtocolConfig {
+    storage_byte_cost: U128,
+});
+#[cfg(feature = "decommission")]
+impl_state_schema!(decommission::Decommission {
+    beneficiary_id: AccountId,
+    started_at: U64,
+    claims_end_at: U64,
+    purged_keys: U64,
+    disabled_before: u64,
+});
+impl_state_schema!(stats::Counters {
+    transfers: U64,
+    mints: U64,
+    unique_holders: U64,
+    guesses: U64,
+    sales: U64,
+});
+impl_state_schema!(enum asset::Asset {
+    Near(U128),
+    Ft { token_id: AccountId, amount: U128 },
+});
+#[cfg(feature = "attest")]
+impl_state_schema!(attest::Claim as "AttestationClaim" {
+    issuer_id: AccountId,
+    data_hash: Base64VecU8,
+    issued_at: U64,
+    expires_at: Option<U64>,
+    storage_deposit: U128,
+});
+#[cfg(feature = "bounty")]
+impl_state_schema!(enum bounty::Status {
+    Funding,
+    Open,
+    Claimed,
+    Submitted,
+});
+#[cfg(feature = "bounty")]
+impl_state_schema!(bounty::Claim as "BountyClaim" {
+    account_id: AccountId,
+    deadline: U64,
+    work_hash: Option<String>,
+});
+#[cfg(feature = "bounty")]
+impl_state_schema!(bounty::Bounty {
+    poster: AccountId,
+    description: String,
+    reward: asset::Asset,
+    claim_period: U64,
+    reviewer: Option<AccountId>,
+    status: bounty::Status,
+    claim: Option<bounty::Claim>,
+    storage_deposit: U128,
+});
+#[cfg(feature = "donation")]
+impl_state_schema!(donation::DonorRecord {
+    total: U128,
+    message: Option<donation::Message>,
+    last_donated_at: U64,
+});
+#[cfg(feature = "expiry")]
+impl_state_schema!(enum expiry::ExpiryKind {
+    OtcOrder,
+    NftApproval,
+    Other(String),
+});
+#[cfg(feature = "expiry")]
+impl_state_schema!(expiry::ExpiryEntry {
+    kind: expiry::ExpiryKind,
+    key: String,
+    expires_at: U64,
+});
+#[cfg(feature = "expiry")]
+impl_state_schema!(expiry::Index as "ExpiryIndex" {
+    buckets: TreeMap<u64, Vec<expiry::ExpiryEntry>>,
+    len: u64,
+});
+#[cfg(feature = "inbox")]
+impl_state_schema!(enum inbox::NotificationKind {
+    OfferReceived,
+    AuctionOutbid,
+    ProposalCreated,
+    Other(String),
+});
+#[cfg(feature = "inbox")]
+impl_state_schema!(inbox::Notification {
+    id: U64,
+    kind: inbox::NotificationKind,
+    sender_id: AccountId,
+    payload: Option<String>,
+    created_at: U64,
+});
+#[cfg(feature = "inbox")]
+impl_state_schema!(inbox::InboxRecord {
+    next_id: u64,
+    notifications: Vec<inbox::Notification>,
+});
+#[cfg(feature = "keeper")]
+impl_state_schema!(keeper::Task {
+    method_name: String,
+    args: Base64VecU8,
+    interval: U64,
+    gas: U64,
+    reward: U128,
+    budget: U128,
+    last_run_at: U64,
+});
+#[cfg(feature = "membership")]
+impl_state_schema!(membership::CachedTier {
+    version: u32,
+    tier: Option<u8>,
+});
+#[cfg(feature = "names")]
+impl_state_schema!(names::NameRecord {
+    owner_id: AccountId,
+    target_id: AccountId,
+    expires_at: U64,
+    storage_deposit: U128,
+});
+#[cfg(feature = "reputation")]
+impl_state_schema!(reputation::Score {
+    value: I64,
+    updated_at: U64,
+});
+impl_state_schema!(enum sealed::Algorithm {
+    X25519XSalsa20Poly1305,
+    X25519XChaCha20Poly1305,
+});
+impl_state_schema!(sealed::Envelope {
+    algorithm: sealed::Algorithm,
+    sender_key: Base64VecU8,
+    recipient_key: Base64VecU8,
+    nonce: Base64VecU8,
+    ciphertext: Base64VecU8,
+    metadata: Option<sealed::Metadata>,
+});
+impl_state_schema!(sealed::SealedBox {
+    sender_id: AccountId,
+    recipient_id: AccountId,
+    envelope: sealed::Envelope,
+    created_at: U64,
+});
+
+impl_state_schema!(ownable::Owner {
+    owner_id: Option<AccountId>,
+});
+
+#[cfg(feature = "otc")]
+impl_state_schema!(otc::Order {
+    maker_id: AccountId,
+    sell_token_id: AccountId,
+    sell_amount: U128,
+    buy_token_id: AccountId,
+    buy_amount: U128,
+    expires_at: Option<U64>,
+    funded: bool,
+    storage_deposit: U128,
+});
+
+#[cfg(feature = "ft")]
+mod for_ft {
+    use super::*;
+
+    impl_state_schema!(ft::Metadata as "FungibleTokenMetadata" {
+        spec: String,
+        name: String,
+        symbol: String,
+        icon: Option<String>,
+        reference: Option<String>,
+        reference_hash: Option<Base64VecU8>,
+        decimals: u8,
+    });
+    impl_state_schema!(ft::Token as "Nep141FungibleToken" {
+        accounts: LookupMap<AccountId, Balance>,
+        total_supply: Balance,
+        account_storage_usage: u64,
+    });
+    impl_state_schema!(ft::PendingMaxSupply {
+        max_supply: Option<U128>,
+        unlocks_at: U64,
+    });
+    impl_state_schema!(ft::lockup::Lockup {
+        amount: U128,
+        starts_at: U64,
+        cliff_at: U64,
+        ends_at: U64,
+    });
+    impl_state_schema!(ft::FungibleToken {
+        token: ft::Token,
+        metadata: LazyOption<ft::Metadata>,
+        max_supply: Option<Balance>,
+        pending_max_supply: Option<ft::PendingMaxSupply>,
+        lockups: Option<LookupMap<AccountId, ft::lockup::Lockup>>,
+    });
+    #[cfg(feature = "breaker")]
+    impl_state_schema!(ft::breaker::BreakerRules {
+        max_block_transfer_bps: Option<u32>,
+        audit_mismatch: bool,
+    });
+    #[cfg(feature = "breaker")]
+    impl_state_schema!(ft::breaker::CircuitBreaker {
+        rules: ft::breaker::BreakerRules,
+        block_height: u64,
+        block_volume: Balance,
+    });
+    #[cfg(feature = "sale")]
+    impl_state_schema!(ft::sale::Contribution {
+        amount: U128,
+        tokens: U128,
+        storage_deposit: U128,
+    });
+}
+
+#[cfg(feature = "nft")]
+mod for_nft {
+    use super::*;
+
+    impl_state_schema!(nft::Metadata as "NFTContractMetadata" {
+        spec: String,
+        name: String,
+        symbol: String,
+        icon: Option<String>,
+        base_uri: Option<String>,
+        reference: Option<String>,
+        reference_hash: Option<Base64VecU8>,
+    });
+    impl_state_schema!(nft::TokenMetadata {
+        title: Option<String>,
+        description: Option<String>,
+        media: Option<String>,
+        media_hash: Option<Base64VecU8>,
+        copies: Option<u64>,
+        issued_at: Option<String>,
+        expires_at: Option<String>,
+        starts_at: Option<String>,
+        updated_at: Option<String>,
+        extra: Option<String>,
+        reference: Option<String>,
+        reference_hash: Option<Base64VecU8>,
+    });
+    impl_state_schema!(nft::NFToken as "Nep171NonFungibleToken" {
+        owner_id: AccountId,
+        extra_storage_in_bytes_per_token: u64,
+        owner_by_id: TreeMap<nft::TokenId, AccountId>,
+        token_metadata_by_id: Option<LookupMap<nft::TokenId, nft::TokenMetadata>>,
+        tokens_per_owner: Option<LookupMap<AccountId, UnorderedSet<nft::TokenId>>>,
+        approvals_by_id: Option<LookupMap<nft::TokenId, HashMap<AccountId, u64>>>,
+        next_approval_id_by_id: Option<LookupMap<nft::TokenId, u64>>,
+    });
+    impl_state_schema!(nft::TokenExtras {
+        description: Option<String>,
+        extra: Option<String>,
+        reference: Option<String>,
+        reference_hash: Option<Base64VecU8>,
+    });
+    impl_state_schema!(nft::Provenance {
+        owner_id: AccountId,
+        price: Option<U128>,
+        timestamp: U64,
+    });
+    impl_state_schema!(nft::ApprovalExpiry {
+        approval_id: u64,
+        expires_at: u64,
+    });
+    impl_state_schema!(nft::attributes::AttributeIndex {
+        tokens: LookupMap<CryptoHash, UnorderedSet<nft::TokenId>>,
+    });
+    impl_state_schema!(nft::NonFungibleToken {
+        token: nft::NFToken,
+        metadata: LazyOption<nft::Metadata>,
+        operators: LookupMap<AccountId, HashSet<AccountId>>,
+        verify_hashes: bool,
+        token_extras: Option<LookupMap<nft::TokenId, nft::TokenExtras>>,
+        owner_counts: utils::counter::CounterMap<AccountId>,
+        provenance: Option<LookupMap<nft::TokenId, Vec<nft::Provenance>>>,
+        approval_expiries: LookupMap<nft::TokenId, HashMap<AccountId, nft::ApprovalExpiry>>,
+        attributes: Option<nft::attributes::AttributeIndex>,
+    });
+}
+
+#[cfg(feature = "mt")]
+mod for_mt {
+    use super::*;
+
+    impl_state_schema!(mt::Metadata as "MtContractMetadata" {
+        spec: String,
+        name: String,
+        icon: Option<String>,
+        base_uri: Option<String>,
+        reference: Option<String>,
+        reference_hash: Option<Base64VecU8>,
+    });
+    impl_state_schema!(mt::TokenMetadata as "MtTokenMetadata" {
+        title: Option<String>,
+        description: Option<String>,
+        media: Option<String>,
+        media_hash: Option<Base64VecU8>,
+        issued_at: Option<String>,
+        expires_at: Option<String>,
+        starts_at: Option<String>,
+        updated_at: Option<String>,
+        extra: Option<String>,
+        reference: Option<String>,
+        reference_hash: Option<Base64VecU8>,
+    });
+    impl_state_schema!(mt::Approval as "MtApproval" {
+        approval_id: u64,
+        amount: U128,
+    });
+    impl_state_schema!(mt::MultiToken {
+        owner_id: AccountId,
+        metadata: LazyOption<mt::Metadata>,
+        token_metadata: LookupMap<mt::TokenId, mt::TokenMetadata>,
+        supplies: UnorderedMap<mt::TokenId, Balance>,
+        balances: LookupMap<(mt::TokenId, AccountId), Balance>,
+        tokens_per_owner: LookupMap<AccountId, UnorderedSet<mt::TokenId>>,
+        approvals: LookupMap<(mt::TokenId, AccountId), HashMap<AccountId, mt::Approval>>,
+        next_approval_id: u64,
+    });
+}
+
+/// The records of every enabled component, at their default prefixes.
+pub fn components() -> Vec<Component> {
+    #[allow(unused_mut)]
+    let mut components = vec![
+        Component::new("protocol").record(Record::value::<protocol::ProtocolConfig>(
+            "config",
+            protocol::PROTOCOL_CONFIG_KEY,
+        )),
+        Component::new("stats").record(Record::value::<stats::Counters>(
+            "counters",
+            stats::StorageKey::Stats,
+        )),
+        Component::new("utils")
+            .record(Record::collection::<UnorderedSet<AccountId>>(
+                "allowlist",
+                allowlist::StorageKey::Allowlist,
+            ))
+            .record(Record::collection::<LookupMap<AccountId, u64>>(
+                "nonces",
+                nonce::StorageKey::Nonces,
+            ))
+            .record(Record::collection::<LookupMap<u64, sealed::SealedBox>>(
+                "sealed_boxes",
+                sealed::StorageKey::SealedBoxes,
+            )),
+    ];
+
+    #[cfg(feature = "attest")]
+    components.push(
+        Component::new("attest")
+            .record(Record::collection::<UnorderedSet<AccountId>>(
+                "issuers",
+                attest::StorageKey::Issuers,
+            ))
+            .record(Record::collection::<
+                LookupMap<(AccountId, attest::SchemaId), Vec<attest::Claim>>,
+            >("claims", attest::StorageKey::Claims)),
+    );
+
+    #[cfg(feature = "bounty")]
+    components.push(Component::new("bounty").record(Record::collection::<
+        UnorderedMap<u64, bounty::Bounty>,
+    >(
+        "bounties", bounty::StorageKey::Bounties
+    )));
+
+    #[cfg(feature = "decommission")]
+    components.push(Component::new("decommission").record(Record::value::<
+        decommission::Decommission,
+    >(
+        "decommission",
+        decommission::DECOMMISSION_KEY,
+    )));
+
+    #[cfg(feature = "deposits")]
+    components.push(Component::new("deposits").record(Record::collection::<
+        LookupMap<AccountId, Balance>,
+    >(
+        "balances",
+        deposits::StorageKey::NearVaultBalances,
+    )));
+
+    #[cfg(feature = "donation")]
+    components.push(Component::new("donation").record(Record::collection::<
+        UnorderedMap<AccountId, donation::DonorRecord>,
+    >(
+        "donors", donation::StorageKey::Donors
+    )));
+
+    #[cfg(feature = "expiry")]
+    components.push(
+        Component::new("expiry")
+            .record(Record::value::<expiry::Index>(
+                "index",
+                expiry::StorageKey::Expiry,
+            ))
+            .record(
+                Record::collection::<TreeMap<u64, Vec<expiry::ExpiryEntry>>>(
+                    "buckets",
+                    expiry::StorageKey::ExpiryBuckets,
+                ),
+            ),
+    );
+
+    #[cfg(feature = "feature_flags")]
+    components.push(
+        Component::new("features").record(Record::value::<features::FeatureFlags>(
+            "flags",
+            features::FEATURES_KEY,
+        )),
+    );
+
+    #[cfg(feature = "inbox")]
+    components.push(Component::new("inbox").record(Record::collection::<
+        LookupMap<AccountId, inbox::InboxRecord>,
+    >("records", inbox::StorageKey::Inbox)));
+
+    #[cfg(feature = "keeper")]
+    components.push(Component::new("keeper").record(Record::collection::<
+        UnorderedMap<String, keeper::Task>,
+    >("tasks", keeper::StorageKey::Tasks)));
+
+    #[cfg(feature = "membership")]
+    components.push(Component::new("membership").record(Record::collection::<
+        LookupMap<AccountId, membership::CachedTier>,
+    >(
+        "cache",
+        membership::StorageKey::TierCache,
+    )));
+
+    #[cfg(feature = "names")]
+    components.push(Component::new("names").record(Record::collection::<
+        UnorderedMap<String, names::NameRecord>,
+    >("names", names::StorageKey::Names)));
+
+    #[cfg(feature = "reputation")]
+    components.push(Component::new("reputation").record(Record::collection::<
+        LookupMap<AccountId, reputation::Score>,
+    >(
+        "scores",
+        reputation::StorageKey::Scores,
+    )));
+
+    #[cfg(feature = "otc")]
+    components.push(
+        Component::new("otc")
+            .record(Record::collection::<UnorderedMap<u64, otc::Order>>(
+                "orders",
+                otc::StorageKey::OtcOrders,
+            ))
+            .record(Record::collection::<
+                LookupMap<(AccountId, AccountId), Balance>,
+            >("unclaimed", otc::StorageKey::OtcUnclaimed)),
+    );
+
+    #[cfg(feature = "ft")]
+    components.extend([
+        Component::new("ft")
+            .record(Record::collection::<LookupMap<AccountId, Balance>>(
+                "accounts",
+                ft::StorageKey::Token,
+            ))
+            .record(Record::collection::<LazyOption<ft::Metadata>>(
+                "metadata",
+                ft::StorageKey::Metadata,
+            ))
+            .record(
+                Record::collection::<LookupMap<AccountId, ft::lockup::Lockup>>(
+                    "lockups",
+                    ft::lockup::StorageKey::Lockups,
+                ),
+            ),
+        Component::new("ft.audit").record(Record::value::<Balance>(
+            "balances",
+            ft::audit::StorageKey::Audit,
+        )),
+    ]);
+
+    #[cfg(feature = "bridge")]
+    components.push(
+        Component::new("ft.bridge")
+            .record(Record::collection::<UnorderedSet<AccountId>>(
+                "minters",
+                ft::bridge::StorageKey::BridgeMinters,
+            ))
+            .record(Record::collection::<LookupSet<String>>(
+                "used_proofs",
+                ft::bridge::StorageKey::BridgeProofs,
+            )),
+    );
+
+    #[cfg(feature = "redeem")]
+    components.push(Component::new("ft.redeem").record(
+        Record::collection::<LookupSet<CryptoHash>>(
+            "used_codes",
+            ft::redeem::StorageKey::RedeemedCodes,
+        ),
+    ));
+
+    #[cfg(feature = "sponsor")]
+    components.push(
+        Component::new("ft.sponsor")
+            .record(Record::collection::<LookupSet<AccountId>>(
+                "allowlist",
+                ft::sponsor::StorageKey::SponsorAllowlist,
+            ))
+            .record(Record::collection::<LookupSet<AccountId>>(
+                "sponsored",
+                ft::sponsor::StorageKey::Sponsored,
+            )),
+    );
+
+    #[cfg(feature = "breaker")]
+    components.push(Component::new("ft.breaker").record(Record::value::<
+        ft::breaker::CircuitBreaker,
+    >(
+        "breaker",
+        ft::breaker::StorageKey::Breaker,
+    )));
+
+    #[cfg(feature = "freeze")]
+    components.push(
+        Component::new("ft.freeze")
+            .record(Record::collection::<UnorderedSet<AccountId>>(
+                "freezers",
+                ft::freeze::StorageKey::Freezers,
+            ))
+            .record(Record::collection::<LookupSet<AccountId>>(
+                "frozen",
+                ft::freeze::StorageKey::Frozen,
+            )),
+    );
+
+    #[cfg(feature = "sale")]
+    components.push(Component::new("ft.sale").record(Record::collection::<
+        LookupMap<AccountId, ft::sale::Contribution>,
+    >(
+        "contributions",
+        ft::sale::StorageKey::SaleContributions,
+    )));
+
+    #[cfg(feature = "nft")]
+    components.extend([
+        Component::new("nft")
+            .record(Record::collection::<TreeMap<nft::TokenId, AccountId>>(
+                "owner_by_id",
+                nft::StorageKey::Token,
+            ))
+            .record(Record::collection::<
+                LookupMap<nft::TokenId, nft::TokenMetadata>,
+            >(
+                "token_metadata_by_id", nft::StorageKey::TokenMetadata
+            ))
+            .record(Record::collection::<
+                LookupMap<AccountId, UnorderedSet<nft::TokenId>>,
+            >(
+                "tokens_per_owner", nft::StorageKey::Enumeration
+            ))
+            .record(Record::collection::<
+                LookupMap<nft::TokenId, HashMap<AccountId, u64>>,
+            >("approvals_by_id", nft::StorageKey::Approval))
+            .record(Record::collection::<LookupMap<nft::TokenId, u64>>(
+                "next_approval_id_by_id",
+                [
+                    IntoStorageKey::into_storage_key(nft::StorageKey::Approval),
+                    b"n".to_vec(),
+                ]
+                .concat(),
+            ))
+            .record(Record::collection::<LazyOption<nft::Metadata>>(
+                "metadata",
+                nft::StorageKey::Metadata,
+            ))
+            .record(
+                Record::collection::<LookupMap<AccountId, HashSet<AccountId>>>(
+                    "operators",
+                    nft::StorageKey::Operators,
+                ),
+            )
+            .record(Record::collection::<
+                LookupMap<nft::TokenId, nft::TokenExtras>,
+            >("token_extras", nft::StorageKey::TokenExtras))
+            .record(Record::collection::<utils::counter::CounterMap<AccountId>>(
+                "owner_counts",
+                nft::StorageKey::OwnerCounts,
+            ))
+            .record(Record::collection::<
+                LookupMap<nft::TokenId, Vec<nft::Provenance>>,
+            >("provenance", nft::StorageKey::Provenance))
+            .record(Record::collection::<
+                LookupMap<nft::TokenId, HashMap<AccountId, nft::ApprovalExpiry>>,
+            >(
+                "approval_expiries", nft::StorageKey::ApprovalExpiries
+            )),
+        Component::new("nft.attributes").record(Record::collection::<
+            LookupMap<CryptoHash, UnorderedSet<nft::TokenId>>,
+        >(
+            "tokens",
+            nft::attributes::StorageKey::Attributes,
+        )),
+        Component::new("royalty").record(Record::collection::<
+            LookupMap<nft::TokenId, royalty::Royalty>,
+        >("by_token", royalty::StorageKey::RoyaltyByToken)),
+    ]);
+
+    #[cfg(feature = "mint_fee")]
+    components.push(Component::new("nft.fee").record(
+        Record::collection::<UnorderedSet<AccountId>>(
+            "exempt",
+            nft::fee::StorageKey::MintFeeExempt,
+        ),
+    ));
+
+    #[cfg(feature = "mt")]
+    components.push(
+        Component::new("mt")
+            .record(Record::collection::<LazyOption<mt::Metadata>>(
+                "metadata",
+                mt::StorageKey::Metadata,
+            ))
+            .record(Record::collection::<
+                LookupMap<mt::TokenId, mt::TokenMetadata>,
+            >(
+                "token_metadata", mt::StorageKey::TokenMetadata
+            ))
+            .record(Record::collection::<UnorderedMap<mt::TokenId, Balance>>(
+                "supplies",
+                mt::StorageKey::Supplies,
+            ))
+            .record(Record::collection::<
+                LookupMap<(mt::TokenId, AccountId), Balance>,
+            >("balances", mt::StorageKey::Balances))
+            .record(Record::collection::<
+                LookupMap<AccountId, UnorderedSet<mt::TokenId>>,
+            >(
+                "tokens_per_owner", mt::StorageKey::TokensPerOwner
+            ))
+            .record(Record::collection::<
+                LookupMap<(mt::TokenId, AccountId), HashMap<AccountId, mt::Approval>>,
+            >("approvals", mt::StorageKey::Approvals)),
+    );
+
+    components
+}
@@ -0,0 +1,176 @@
+#![allow(dead_code)]
+/*!
+Basic statistics of the contract, so dashboards don't need an indexer for them.
+
+With the `stats` feature the ft and nft modules record their transfers, mints and holders,
+`impl_stats!` generates a `get_stats()` view of all the counters.
+
+# NOTES:
+  - Without the `stats` feature the hooks do nothing and the counters stay 0.
+  - The counters are one record under their own storage key, paid by the contract (~60 bytes).
+  - Unique holders of nft are only tracked with the enumeration extension (`tokens_per_owner`).
+  - Guesses and sales have no module of their own, record them from the contract methods.
+    Views can't write the state, so only a call method can count, E.g. not `guess_solution`.
+
+# EXAMPLE:
+```
+# use cmn::*;
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn buy(&mut self, token_id: nft::TokenId) {
+        // ..
+        stats::record(stats::Counter::Sales);
+    }
+}
+
+impl_stats!(Contract);
+// get_stats() => {"transfers":"12","mints":"3","unique_holders":"2","guesses":"0","sales":"1"}
+```
+*/
+
+use super::*;
+
+use near_sdk::serde::{Deserialize, Serialize};
+
+/// Whether the hooks record anything, the `stats` feature.
+pub const ENABLED: bool = cfg!(feature = "stats");
+
+raw_storage_key!(pub enum StorageKey in storage_key::STATS {
+    Stats,
+});
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Counters {
+    pub transfers: U64,
+    pub mints: U64,
+    /// Accounts holding a non-zero ft balance or at least one nft.
+    pub unique_holders: U64,
+    pub guesses: U64,
+    pub sales: U64,
+}
+impl Default for Counters {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            transfers: U64(0),
+            mints: U64(0),
+            unique_holders: U64(0),
+            guesses: U64(0),
+            sales: U64(0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Counter {
+    Transfers,
+    Mints,
+    Guesses,
+    Sales,
+}
+
+#[inline]
+fn key() -> Vec<u8> {
+    near_sdk::IntoStorageKey::into_storage_key(StorageKey::Stats)
+}
+
+/// Current counters.
+pub fn get() -> Counters {
+    env::storage_read(&key())
+        .map(|bytes| Counters::try_from_slice(&bytes).unwrap())
+        .unwrap_or_default()
+}
+
+fn update(f: impl FnOnce(&mut Counters)) {
+    if !ENABLED {
+        return;
+    }
+    let mut counters = get();
+    f(&mut counters);
+    env::storage_write(&key(), &counters.try_to_vec().unwrap());
+}
+
+/// Adds 1 to `counter`.
+#[inline]
+pub fn record(counter: Counter) {
+    update(|counters| {
+        let count = match counter {
+            Counter::Transfers => &mut counters.transfers,
+            Counter::Mints => &mut counters.mints,
+            Counter::Guesses => &mut counters.guesses,
+            Counter::Sales => &mut counters.sales,
+        };
+        count.0 += 1;
+    });
+}
+
+/// Updates the unique holders when an account starts or stops holding tokens.
+#[inline]
+pub fn record_holder(was_holder: bool, is_holder: bool) {
+    if was_holder == is_holder {
+        return;
+    }
+    update(|counters| {
+        let holders = &mut counters.unique_holders.0;
+        *holders = if is_holder {
+            *holders + 1
+        } else {
+            holders.saturating_sub(1)
+        };
+    });
+}
+
+#[macro_export]
+macro_rules! impl_stats {
+    ($contract:ident) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_stats!`.
+            pub const STATS_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: None,
+                methods: &[$crate::manifest::Method::view("get_stats")],
+                storage: &[],
+            };
+        }
+        #[near_bindgen]
+        impl $contract {
+            pub fn get_stats(&self) -> $crate::stats::Counters {
+                $crate::stats::get()
+            }
+        }
+    };
+}
+pub use impl_stats;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_record() {
+        run_vm(vm!("alice.near"));
+        assert_eq!(key(), vec![18]);
+
+        record(Counter::Transfers);
+        record(Counter::Sales);
+        record_holder(false, true);
+        record_holder(false, true);
+        record_holder(true, false);
+        record_holder(true, true);
+
+        let expected = if ENABLED {
+            Counters {
+                transfers: 1.into(),
+                sales: 1.into(),
+                unique_holders: 1.into(),
+                ..Default::default()
+            }
+        } else {
+            Counters::default()
+        };
+        assert_eq!(get(), expected);
+    }
+}
@@ -0,0 +1,91 @@
+#![allow(dead_code)]
+/*!
+Failure telemetry for indexers.
+
+[`fail!`](crate::fail) panics like [`require!`], but with the `telemetry` feature it first logs an
+`error` event of the `telemetry` standard, so failure rates of a deployed contract can be
+monitored from its logs. The logs of a failed receipt are kept in its execution outcome.
+
+# NOTES:
+  - The runtime doesn't expose the name of the called method, the event has the source location
+    of the `fail!` instead.
+  - The error code is the `Debug` of the error, E.g. the variant of a fieldless enum.
+
+# EXAMPLE:
+```
+# use cmn::*;
+#[derive(Debug)]
+pub enum Error {
+    NotEnoughBalance,
+}
+
+if balance < amount {
+    fail!(Error::NotEnoughBalance, "The account doesn't have enough balance");
+}
+// EVENT_JSON:{"standard":"telemetry","version":"1.0.0","event":"error","data":[{"code":"NotEnoughBalance","context":"The account doesn't have enough balance","caller":"alice.near","location":"contract::cmn::ft:42"}]}
+// panics with "NotEnoughBalance: The account doesn't have enough balance"
+```
+*/
+
+#[cfg(feature = "telemetry")]
+use near_sdk::{env, serde_json};
+
+/// Logs the `error` event, only with the `telemetry` feature.
+#[inline]
+pub fn emit_error(code: &str, context: &str, location: &str) {
+    #[cfg(feature = "telemetry")]
+    super::events::emit(
+        super::events::standards::TELEMETRY,
+        "error",
+        serde_json::json!({
+            "code": code,
+            "context": context,
+            "caller": env::predecessor_account_id(),
+            "location": location,
+        }),
+    );
+    #[cfg(not(feature = "telemetry"))]
+    let _ = (code, context, location);
+}
+
+/// Panics with `"{code:?}: {context}"`, logging a telemetry event first.
+///
+/// # Example
+/// ```
+/// # use cmn::*;
+/// // fail!(error code, context);
+/// fail!(Error::Unauthorized, format!("{} can't mint", account_id));
+/// ```
+#[macro_export]
+macro_rules! fail {
+    ($code:expr, $context:expr $(,)?) => {{
+        let code = format!("{:?}", $code);
+        let context: &str = &$context;
+        $crate::telemetry::emit_error(&code, context, concat!(module_path!(), ":", line!()));
+        near_sdk::require!(false, format!("{}: {}", code, context));
+        unreachable!()
+    }};
+}
+pub use fail;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+
+    #[derive(Debug)]
+    enum Error {
+        Unauthorized,
+    }
+
+    #[test]
+    fn check_fail() {
+        run_vm(vm!("alice.near"));
+
+        let result =
+            std::panic::catch_unwind(|| -> () { fail!(Error::Unauthorized, "Only the owner") });
+        assert!(result.is_err());
+
+        #[cfg(feature = "telemetry")]
+        assert!(get_logs()[0].contains(r#""code":"Unauthorized","context":"Only the owner""#));
+    }
+}
@@ -7,11 +7,16 @@ use core::{
 };
 
 pub use near_sdk::{
+    env,
     //
+    mock::VmAction,
     test_utils::*,
     testing_env,
     AccountId,
+    Balance,
+    Gas,
     ParseAccountIdError,
+    PromiseResult,
     VMContext,
 };
 
@@ -42,6 +47,42 @@ pub fn get_context_builder(predecessor: AccountId) -> VMContextBuilder {
     builder
 }
 
+/// Gas price of mainnet (yoctoNEAR per gas). The mocked VM doesn't charge gas, see [gas_cost].
+pub const MAINNET_GAS_PRICE: Balance = 100_000_000;
+/// Balance of the contract account in [mainnet_vm!]: 5 NEAR.
+pub const MAINNET_CONTRACT_BALANCE: Balance = 5_000_000_000_000_000_000_000_000;
+/// Storage usage of the contract account in [mainnet_vm!]: a ~200KB wasm and its account record.
+pub const MAINNET_CONTRACT_STORAGE_USAGE: u64 = 200 * 1024 + 100;
+
+/// Helper function to create a context like a fresh mainnet deployment.
+/// The contract (`contract.near`) holds [MAINNET_CONTRACT_BALANCE] with [MAINNET_CONTRACT_STORAGE_USAGE]
+/// staked for storage, instead of the 100 NEAR and 300KB of the mocked defaults.
+///
+/// Storage byte cost (1E19 yoctoNEAR) and the fees config of the mocked VM are already the
+/// mainnet ones, only the gas price isn't modeled.
+#[inline]
+pub fn get_mainnet_context_builder(predecessor: AccountId) -> VMContextBuilder {
+    let mut builder = get_context_builder(predecessor);
+    builder
+        .current_account_id("contract.near".parse().unwrap())
+        .account_balance(MAINNET_CONTRACT_BALANCE)
+        .storage_usage(MAINNET_CONTRACT_STORAGE_USAGE);
+    builder
+}
+
+/// Cost of burning `gas` at [MAINNET_GAS_PRICE].
+#[inline]
+pub fn gas_cost(gas: Gas) -> Balance {
+    Balance::from(gas.0) * MAINNET_GAS_PRICE
+}
+
+/// Balance not locked by the storage staking of the current account.
+#[inline]
+pub fn available_balance() -> Balance {
+    env::account_balance()
+        .saturating_sub(Balance::from(env::storage_usage()) * env::storage_byte_cost())
+}
+
 /// Run VM with given context.
 ///
 /// # Example
@@ -55,6 +96,25 @@ pub fn run_vm(vm: impl Borrow<VMContextBuilder>) {
     testing_env!(vm.borrow().build());
 }
 
+/// [`run_vm`] for a callback, `promise_results` are the results of the promises it follows.
+///
+/// # Example
+///
+/// ```
+/// # use cmn::test_utils::*;
+/// run_vm_with_results(vm!("alice.near"), vec![PromiseResult::Successful(b"true".to_vec())]);
+/// ```
+#[inline]
+pub fn run_vm_with_results(vm: impl Borrow<VMContextBuilder>, promise_results: Vec<PromiseResult>) {
+    testing_env!(
+        vm.borrow().build(),
+        near_sdk::VMConfig::test(),
+        near_sdk::RuntimeFeesConfig::test(),
+        Default::default(),
+        promise_results,
+    );
+}
+
 /// A Container for logs that can be asserted against the logs
 /// produced by the contract in the VM.
 ///
@@ -110,6 +170,110 @@ impl<'a> From<Vec<&'a str>> for Logs<'a> {
     }
 }
 
+/// Sum of the deposits transferred to `account_id` by the receipts created in the current VM.
+/// Useful to check storage refunds.
+///
+/// # Example
+/// ```
+/// # use cmn::test_utils::*;
+/// contract.nft_revoke_all(token_id); // refunds the approvals storage
+/// assert_eq!(get_transfers_to(&accounts(0)), released_bytes * env::storage_byte_cost());
+/// ```
+pub fn get_transfers_to(account_id: &AccountId) -> Balance {
+    get_created_receipts()
+        .into_iter()
+        .filter(|receipt| &receipt.receiver_id == account_id)
+        .flat_map(|receipt| receipt.actions)
+        .map(|action| match action {
+            VmAction::Transfer { deposit } => deposit,
+            _ => 0,
+        })
+        .sum()
+}
+
+/// Asserts that at least `deposit` was transferred back to `account_id`,
+/// E.g. after a payable method rejected its input with [require_or_refund!](crate::require_or_refund).
+#[track_caller]
+pub fn assert_deposit_refunded(account_id: &AccountId, deposit: Balance) {
+    let refunded = get_transfers_to(account_id);
+    assert!(
+        refunded >= deposit,
+        "{} kept {} of the {} yoctoNEAR deposit of {}",
+        env::current_account_id(),
+        deposit - refunded,
+        deposit,
+        account_id
+    );
+}
+
+/// Runs a `*_transfer_call` flow receipt by receipt, with a receiver re-entering the contract
+/// before the resolve callback, E.g. to spend or pass on what it just received.
+///
+/// The contract commits the state of the call before the receiver runs, so there's no lock to
+/// take: whatever the receiver does in between, the resolve callback has to leave the balances
+/// consistent. Assert them after [`ReentrancyProbe::resolve`].
+///
+/// # Example
+/// ```
+/// # use cmn::test_utils::*;
+/// let mut probe = ReentrancyProbe::new(vm);
+/// probe.call(accounts(2), || contract.ft_transfer_call(accounts(1), U128(100), None, "".into()));
+/// // the receiver passes the tokens on in `ft_on_transfer`
+/// probe.reenter(accounts(1), || contract.ft_transfer(accounts(3), U128(100), None));
+/// // and claims to have used none of them
+/// probe.resolve(U128(100), || contract.ft_resolve_transfer(accounts(2), accounts(1), U128(100)));
+/// assert_eq!(contract.ft_balance_of(accounts(2)).0, TOTAL_SUPPLY - 100);
+/// ```
+pub struct ReentrancyProbe {
+    vm: VMContextBuilder,
+}
+impl ReentrancyProbe {
+    #[inline]
+    pub fn new(vm: impl Borrow<VMContextBuilder>) -> Self {
+        Self {
+            vm: vm.borrow().clone(),
+        }
+    }
+
+    fn next_receipt(&mut self, predecessor_id: AccountId, attached_deposit: Balance) {
+        self.vm
+            .storage_usage(env::storage_usage())
+            .predecessor_account_id(predecessor_id)
+            .attached_deposit(attached_deposit);
+    }
+
+    /// The `*_transfer_call` of `sender_id`, with 1 yoctoNEAR.
+    pub fn call<R>(&mut self, sender_id: AccountId, f: impl FnOnce() -> R) -> R {
+        self.next_receipt(sender_id, 1);
+        run_vm(&self.vm);
+        f()
+    }
+
+    /// A call of the receiver while it handles the transfer, with 1 yoctoNEAR.
+    pub fn reenter<R>(&mut self, receiver_id: AccountId, f: impl FnOnce() -> R) -> R {
+        self.next_receipt(receiver_id, 1);
+        run_vm(&self.vm);
+        f()
+    }
+
+    /// The resolve callback, called by the contract itself with what the receiver returned.
+    pub fn resolve<R>(
+        &mut self,
+        returned: impl near_sdk::serde::Serialize,
+        f: impl FnOnce() -> R,
+    ) -> R {
+        let current_account_id = self.vm.context.current_account_id.clone();
+        self.next_receipt(current_account_id, 0);
+        run_vm_with_results(
+            &self.vm,
+            vec![PromiseResult::Successful(
+                near_sdk::serde_json::to_vec(&returned).unwrap(),
+            )],
+        );
+        f()
+    }
+}
+
 /// Create a VMContextBuilder with given account id as a predecessor.
 /// It uses [get_context_builder].
 /// # Example
@@ -131,6 +295,22 @@ macro_rules! vm {
     }
 pub use vm;
 
+/// Create a VMContextBuilder like a fresh mainnet deployment with given account id as a predecessor.
+/// It uses [get_mainnet_context_builder].
+/// # Example
+/// ```
+/// # use cmn::test_utils::*;
+/// run_vm(mainnet_vm!("alice.near"));
+/// assert!(available_balance() < MAINNET_CONTRACT_BALANCE);
+/// ```
+#[macro_export]
+macro_rules! mainnet_vm {
+        ($predecessor:expr) => {
+            $crate::test_utils::get_mainnet_context_builder(vm!(@GET_ACCOUNT $predecessor))
+        };
+    }
+pub use mainnet_vm;
+
 /// Create a container for logs mocks.
 ///
 /// # Example
@@ -0,0 +1,84 @@
+/*!
+Deterministic lists of accounts, E.g. member sets and recipients of splits.
+
+Lists of accounts given as args can repeat an account, which double counts its share or its
+vote. Either canonicalize them with [`sort_dedup`] or reject them with [`assert_unique`].
+
+# EXAMPLE:
+```
+# use cmn::*;
+// ["carol.near", "alice.near", "carol.near"] => ["alice.near", "carol.near"]
+let members = accounts::sort_dedup_max(members, MAX_MEMBERS);
+
+accounts::assert_unique(splits.iter().map(|split| &split.account_id));
+```
+*/
+
+use near_sdk::{require, AccountId};
+
+/// Sorted by the bytes of the account ids, without duplicates.
+#[inline]
+pub fn sort_dedup(mut account_ids: Vec<AccountId>) -> Vec<AccountId> {
+    account_ids.sort_unstable();
+    account_ids.dedup();
+    account_ids
+}
+
+/// [`sort_dedup`] that panics if more than `max` accounts are left.
+#[track_caller]
+pub fn sort_dedup_max(account_ids: Vec<AccountId>, max: usize) -> Vec<AccountId> {
+    let account_ids = sort_dedup(account_ids);
+    assert_max_len(account_ids.len(), max);
+    account_ids
+}
+
+/// Panics if `len` is over `max` accounts.
+#[inline]
+#[track_caller]
+pub fn assert_max_len(len: usize, max: usize) {
+    require!(
+        len <= max,
+        format!("Can't have more than {} accounts, got {}", max, len)
+    );
+}
+
+/// Panics on the first account listed twice.
+#[track_caller]
+pub fn assert_unique<'a>(account_ids: impl IntoIterator<Item = &'a AccountId>) {
+    let mut account_ids: Vec<_> = account_ids.into_iter().collect();
+    account_ids.sort_unstable();
+    if let Some(pair) = account_ids.windows(2).find(|pair| pair[0] == pair[1]) {
+        require!(false, format!("Duplicate account {}", pair[0]));
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_sort_dedup() {
+        assert_eq!(
+            sort_dedup(vec![accounts(2), accounts(0), accounts(2), accounts(1)]),
+            vec![accounts(0), accounts(1), accounts(2)]
+        );
+        assert_eq!(
+            sort_dedup_max(vec![accounts(1), accounts(1), accounts(0)], 2),
+            vec![accounts(0), accounts(1)]
+        );
+        assert_unique(&[accounts(1), accounts(0)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Duplicate account bob")]
+    fn check_assert_unique() {
+        assert_unique(&[accounts(1), accounts(0), accounts(1)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't have more than 1 accounts, got 2")]
+    fn check_max_len() {
+        sort_dedup_max(vec![accounts(1), accounts(0)], 1);
+    }
+}
@@ -0,0 +1,270 @@
+/*!
+Enumerable allowlists of accounts, E.g. for a presale or an access filter.
+
+Lists of thousands of accounts don't fit in one call, so they're imported and exported in
+batches of up to [`MAX_ALLOWLIST_BATCH`] accounts (or the given `max_accounts`), and the owner
+pays the storage of every imported batch with the attached deposit.
+
+# NOTES:
+  - Importing an account twice is a no-op, only the new entries are charged.
+  - Removing accounts refunds the released storage to the owner.
+  - Removals swap the last account into the removed slot, so an export running between two
+    removals can miss or repeat accounts. Export after the list is settled.
+
+# EXAMPLE:
+```
+# use cmn::*;
+#[near_bindgen]
+impl Contract {
+    pub fn presale_buy(&mut self) {
+        require!(
+            self.allowlist.contains(&env::predecessor_account_id()),
+            "The account is not allowlisted"
+        );
+        // ..
+    }
+}
+
+// impl_allowlist!(Contract, allowlist[, owner = <AccountId field; default: the contract itself>][, max_accounts = <usize; default: MAX_ALLOWLIST_BATCH>]);
+allowlist::impl_allowlist!(Contract, allowlist, owner = owner_id);
+// import_accounts({"account_ids": ["alice.near", ..]}) => 100 new accounts, storage charged
+// export_accounts({"from_index": "100", "limit": 100}) => the next batch
+```
+*/
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::UnorderedSet,
+    env, AccountId, IntoStorageKey, Promise,
+};
+
+/// Default max accounts imported, removed or exported in one call.
+pub const MAX_ALLOWLIST_BATCH: usize = 100;
+
+super::raw_storage_key!(pub enum StorageKey in super::storage_key::ALLOWLIST {
+    Allowlist,
+});
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Allowlist {
+    accounts: UnorderedSet<AccountId>,
+}
+impl Default for Allowlist {
+    #[inline]
+    fn default() -> Self {
+        Self::new(StorageKey::Allowlist)
+    }
+}
+impl Allowlist {
+    /// A contract with more than one allowlist gives each its own prefix.
+    #[inline]
+    pub fn new(prefix: impl IntoStorageKey) -> Self {
+        Self {
+            accounts: UnorderedSet::new(prefix),
+        }
+    }
+
+    #[inline]
+    pub fn contains(&self, account_id: &AccountId) -> bool {
+        self.accounts.contains(account_id)
+    }
+
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.accounts.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Adds the accounts, returns how many weren't listed yet.
+    pub fn import(&mut self, account_ids: &[AccountId], max_accounts: usize) -> u32 {
+        super::accounts::assert_max_len(account_ids.len(), max_accounts);
+        account_ids
+            .iter()
+            .filter(|account_id| self.accounts.insert(account_id))
+            .count() as u32
+    }
+
+    /// Removes the accounts, returns how many were listed.
+    pub fn remove(&mut self, account_ids: &[AccountId], max_accounts: usize) -> u32 {
+        super::accounts::assert_max_len(account_ids.len(), max_accounts);
+        account_ids
+            .iter()
+            .filter(|account_id| self.accounts.remove(account_id))
+            .count() as u32
+    }
+
+    /// Up to `max_accounts` accounts from `from_index`, in storage order.
+    pub fn export(
+        &self,
+        from_index: u64,
+        limit: Option<u64>,
+        max_accounts: usize,
+    ) -> Vec<AccountId> {
+        let limit = limit.map_or(max_accounts, |limit| (limit as usize).min(max_accounts));
+        self.accounts
+            .as_vector()
+            .iter()
+            .skip(from_index as usize)
+            .take(limit)
+            .collect()
+    }
+}
+
+/// Imports `account_ids`, the attached deposit pays the storage of the new entries and the
+/// rest is refunded to the predecessor.
+pub fn import_paid(
+    allowlist: &mut Allowlist,
+    account_ids: &[AccountId],
+    max_accounts: usize,
+) -> u32 {
+    let initial_storage_usage = env::storage_usage();
+    let imported = allowlist.import(account_ids, max_accounts);
+    super::refund_deposit_to(env::storage_usage() - initial_storage_usage, None);
+    imported
+}
+
+/// Removes `account_ids`, the released storage is refunded to `owner_id`.
+pub fn remove_refunded(
+    allowlist: &mut Allowlist,
+    account_ids: &[AccountId],
+    max_accounts: usize,
+    owner_id: AccountId,
+) -> u32 {
+    let initial_storage_usage = env::storage_usage();
+    let removed = allowlist.remove(account_ids, max_accounts);
+    let released = initial_storage_usage.saturating_sub(env::storage_usage());
+    if released > 0 {
+        Promise::new(owner_id).transfer(super::super::protocol::storage_cost(released));
+    }
+    removed
+}
+
+#[macro_export]
+macro_rules! impl_allowlist {
+    ($contract:ident, $allowlist:ident $(, owner = $owner:ident)? $(, max_accounts = $max_accounts:expr)? $(,)?) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_allowlist!`.
+            pub const ALLOWLIST_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: None,
+                methods: &[
+                    $crate::manifest::Method::payable("import_accounts").role("owner"),
+                    $crate::manifest::Method::payable("remove_accounts").role("owner"),
+                    $crate::manifest::Method::view("export_accounts"),
+                    $crate::manifest::Method::view("allowlist_len"),
+                    $crate::manifest::Method::view("is_allowlisted"),
+                ],
+                storage: &[
+                    // entry of a 64 bytes account in the set and its index, per account
+                    $crate::manifest::StorageEstimate::new("import_accounts", 220),
+                ],
+            };
+
+            fn allowlist_owner_id(&self) -> AccountId {
+                None::<&AccountId>$(.or(Some(&self.$owner)))?
+                    .cloned()
+                    .unwrap_or_else(env::current_account_id)
+            }
+
+            fn assert_allowlist_owner(&self) {
+                require!(
+                    env::predecessor_account_id() == self.allowlist_owner_id(),
+                    "Only the owner can manage the allowlist"
+                );
+            }
+
+            #[inline]
+            fn allowlist_max_accounts() -> usize {
+                #[allow(unused_variables)]
+                let max_accounts = $crate::allowlist::MAX_ALLOWLIST_BATCH;
+                $(let max_accounts = $max_accounts;)?
+                max_accounts
+            }
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// Adds a batch of accounts, requires the storage deposit of the new entries.
+            /// Returns how many weren't listed yet.
+            #[payable]
+            pub fn import_accounts(&mut self, account_ids: Vec<AccountId>) -> u32 {
+                self.assert_allowlist_owner();
+                $crate::allowlist::import_paid(
+                    &mut self.$allowlist,
+                    &account_ids,
+                    Self::allowlist_max_accounts(),
+                )
+            }
+
+            /// Removes a batch of accounts, the released storage is refunded to the owner.
+            /// Returns how many were listed.
+            #[payable]
+            pub fn remove_accounts(&mut self, account_ids: Vec<AccountId>) -> u32 {
+                near_sdk::assert_one_yocto();
+                self.assert_allowlist_owner();
+                let owner_id = self.allowlist_owner_id();
+                $crate::allowlist::remove_refunded(
+                    &mut self.$allowlist,
+                    &account_ids,
+                    Self::allowlist_max_accounts(),
+                    owner_id,
+                )
+            }
+
+            /// A batch of the listed accounts, `limit` is capped to the max batch.
+            pub fn export_accounts(&self, from_index: Option<U64>, limit: Option<u64>) -> Vec<AccountId> {
+                self.$allowlist.export(
+                    from_index.map_or(0, |from_index| from_index.0),
+                    limit,
+                    Self::allowlist_max_accounts(),
+                )
+            }
+
+            pub fn allowlist_len(&self) -> U64 {
+                self.$allowlist.len().into()
+            }
+
+            pub fn is_allowlisted(&self, account_id: AccountId) -> bool {
+                self.$allowlist.contains(&account_id)
+            }
+        }
+    };
+}
+pub use impl_allowlist;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_import_export() {
+        run_vm(vm!("alice.near"));
+
+        let mut allowlist = Allowlist::default();
+        let account_ids: Vec<_> = (0..5).map(accounts).collect();
+        assert_eq!(allowlist.import(&account_ids[..3], 3), 3);
+        // listed twice
+        assert_eq!(allowlist.import(&account_ids[2..], 3), 2);
+        assert_eq!(allowlist.len(), 5);
+
+        assert_eq!(allowlist.export(0, Some(2), 3), account_ids[..2]);
+        // capped to the max batch
+        assert_eq!(allowlist.export(2, None, 3), account_ids[2..]);
+
+        assert_eq!(allowlist.remove(&account_ids[..2], 3), 2);
+        assert!(!allowlist.contains(&accounts(0)));
+        assert_eq!(allowlist.len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't have more than 2 accounts, got 3")]
+    fn check_max_batch() {
+        run_vm(vm!("alice.near"));
+
+        let account_ids: Vec<_> = (0..3).map(accounts).collect();
+        Allowlist::default().import(&account_ids, 2);
+    }
+}
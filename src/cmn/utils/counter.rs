@@ -0,0 +1,140 @@
+/*!
+Counters per key, E.g. tokens per owner, mints per wallet or attempts per account.
+
+A [`CounterMap`] is a `LookupMap<K, u64>` that reads 0 for a missing key, panics instead of
+wrapping around, and removes a key when its counter gets back to 0, so it never pays storage for
+zeros.
+
+# NOTES:
+  - Its Borsh representation is the one of the `LookupMap<K, u64>`, so it can replace one
+    without a migration.
+  - [`CounterMap::try_get`] tells a missing key from a stored count, E.g. for counters that
+    are filled lazily.
+
+# EXAMPLE:
+```
+# use cmn::*;
+use counter::CounterMap;
+
+let mut mints = CounterMap::new(b"m");
+mints.incr(&account_id);
+require!(mints.get(&account_id) <= MAX_MINTS_PER_WALLET, "Mint limit reached");
+```
+*/
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::LookupMap,
+    require, IntoStorageKey,
+};
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct CounterMap<K: BorshSerialize> {
+    counts: LookupMap<K, u64>,
+}
+impl<K: BorshSerialize> CounterMap<K> {
+    #[inline]
+    pub fn new(prefix: impl IntoStorageKey) -> Self {
+        Self {
+            counts: LookupMap::new(prefix),
+        }
+    }
+
+    /// The counter of `key`, 0 if none.
+    #[inline]
+    pub fn get(&self, key: &K) -> u64 {
+        self.counts.get(key).unwrap_or_default()
+    }
+
+    /// The stored counter of `key`, never `Some(0)`.
+    #[inline]
+    pub fn try_get(&self, key: &K) -> Option<u64> {
+        self.counts.get(key)
+    }
+
+    #[inline]
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.counts.contains_key(key)
+    }
+
+    /// Sets the counter, removing the key at 0.
+    pub fn set(&mut self, key: &K, count: u64) {
+        if count == 0 {
+            self.counts.remove(key);
+        } else {
+            self.counts.insert(key, &count);
+        }
+    }
+
+    /// Adds `n`, returns the new counter.
+    pub fn add(&mut self, key: &K, n: u64) -> u64 {
+        let count = self.get(key).checked_add(n);
+        require!(count.is_some(), "Counter overflow");
+        let count = count.unwrap();
+        self.set(key, count);
+        count
+    }
+
+    /// Subtracts `n`, returns the new counter.
+    pub fn sub(&mut self, key: &K, n: u64) -> u64 {
+        let count = self.get(key).checked_sub(n);
+        require!(count.is_some(), "Counter underflow");
+        let count = count.unwrap();
+        self.set(key, count);
+        count
+    }
+
+    #[inline]
+    pub fn incr(&mut self, key: &K) -> u64 {
+        self.add(key, 1)
+    }
+
+    #[inline]
+    pub fn decr(&mut self, key: &K) -> u64 {
+        self.sub(key, 1)
+    }
+
+    /// Removes the key, returns its counter.
+    #[inline]
+    pub fn remove(&mut self, key: &K) -> u64 {
+        self.counts.remove(key).unwrap_or_default()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_counter() {
+        run_vm(vm!("alice.near"));
+
+        let mut counts = CounterMap::new(b"c");
+        assert_eq!(counts.incr(&accounts(0)), 1);
+        assert_eq!(counts.add(&accounts(0), 2), 3);
+        assert_eq!(counts.get(&accounts(1)), 0);
+        assert_eq!(counts.sub(&accounts(0), 3), 0);
+        // no storage for zeros
+        assert_eq!(counts.try_get(&accounts(0)), None);
+        assert!(!counts.contains_key(&accounts(0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "Counter underflow")]
+    fn check_underflow() {
+        run_vm(vm!("alice.near"));
+
+        CounterMap::new(b"c").decr(&accounts(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "Counter overflow")]
+    fn check_overflow() {
+        run_vm(vm!("alice.near"));
+
+        let mut counts = CounterMap::new(b"c");
+        counts.set(&accounts(0), u64::MAX);
+        counts.incr(&accounts(0));
+    }
+}
@@ -0,0 +1,170 @@
+/*!
+EVM addresses, E.g. for the receivers of a bridge or addresses in `msg` payloads.
+
+# NOTES:
+  - Parsing accepts all-lowercase, all-uppercase or EIP-55 checksummed addresses,
+    a mixed case address with a wrong checksum is rejected.
+  - JSON is the checksummed `0x` form, Borsh the 20 bytes.
+  - [`EvmAddress::from_near_account`] is the address Aurora gives to a NEAR account.
+
+# EXAMPLE:
+```
+# use cmn::*;
+use evm::EvmAddress;
+
+let address: EvmAddress = "0xfb6916095ca1df60bb79ce92ce3ea74c37c5d359".parse().unwrap();
+assert_eq!(address.to_checksum(), "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359");
+let aurora_address = EvmAddress::from_near_account(&env::predecessor_account_id());
+```
+*/
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    env,
+    serde::{de, Deserialize, Deserializer, Serialize, Serializer},
+    AccountId,
+};
+use std::{fmt, str::FromStr};
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub struct EvmAddress(pub [u8; 20]);
+
+impl EvmAddress {
+    /// Aurora-implicit address of a NEAR account: the last 20 bytes of `keccak256(account_id)`.
+    #[inline]
+    pub fn from_near_account(account_id: &AccountId) -> Self {
+        let hash = env::keccak256_array(account_id.as_bytes());
+        let mut address = [0; 20];
+        address.copy_from_slice(&hash[12..]);
+        Self(address)
+    }
+
+    /// `0x` and 40 lowercase hex characters.
+    pub fn to_hex(self) -> String {
+        let mut hex = String::with_capacity(42);
+        hex.push_str("0x");
+        for byte in self.0 {
+            hex.push(HEX[(byte >> 4) as usize] as char);
+            hex.push(HEX[(byte & 0xf) as usize] as char);
+        }
+        hex
+    }
+
+    /// EIP-55 checksummed form.
+    pub fn to_checksum(self) -> String {
+        let hex = self.to_hex();
+        let hash = env::keccak256_array(&hex.as_bytes()[2..]);
+        let mut checksum = String::with_capacity(42);
+        checksum.push_str("0x");
+        for (i, c) in hex[2..].chars().enumerate() {
+            let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0xf;
+            checksum.push(if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            });
+        }
+        checksum
+    }
+}
+
+const HEX: &[u8; 16] = b"0123456789abcdef";
+
+impl FromStr for EvmAddress {
+    type Err = String;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = s
+            .strip_prefix("0x")
+            .ok_or_else(|| "EVM address must start with 0x".to_string())?;
+        if hex.len() != 40 || !hex.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err("EVM address must be 40 hex characters".to_string());
+        }
+        let mut address = [0; 20];
+        for (i, byte) in address.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        let address = Self(address);
+
+        let mixed_case = hex.bytes().any(|b| b.is_ascii_lowercase())
+            && hex.bytes().any(|b| b.is_ascii_uppercase());
+        if mixed_case && address.to_checksum() != s {
+            return Err("EVM address has a wrong checksum".to_string());
+        }
+        Ok(address)
+    }
+}
+
+impl fmt::Display for EvmAddress {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_checksum())
+    }
+}
+
+impl Serialize for EvmAddress {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_checksum())
+    }
+}
+
+impl<'de> Deserialize<'de> for EvmAddress {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        <String as Deserialize>::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Whether `s` is a valid EVM address, see [`EvmAddress::from_str`].
+#[inline]
+pub fn is_valid_address(s: &str) -> bool {
+    s.parse::<EvmAddress>().is_ok()
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_checksum() {
+        run_vm(vm!("alice.near"));
+
+        // EIP-55 test vectors
+        for checksummed in [
+            "0x52908400098527886E0F7030069857D2E4169EE7",
+            "0xde709f2102306220921060314715629080e2fb77",
+            "0xfB6916095ca1df60bB79Ce92cE3Ea74c37c5d359",
+        ] {
+            let address: EvmAddress = checksummed.to_lowercase().parse().unwrap();
+            assert_eq!(address.to_checksum(), checksummed);
+            assert!(is_valid_address(checksummed));
+        }
+        assert!(!is_valid_address(
+            "0xFb6916095ca1df60bB79Ce92cE3Ea74c37c5d359"
+        ));
+        assert!(!is_valid_address(
+            "fb6916095ca1df60bb79ce92ce3ea74c37c5d359"
+        ));
+        assert!(!is_valid_address(
+            "0xfb6916095ca1df60bb79ce92ce3ea74c37c5d3"
+        ));
+    }
+
+    #[test]
+    fn check_from_near_account() {
+        run_vm(vm!("alice.near"));
+
+        let address = EvmAddress::from_near_account(&accounts(0));
+        assert_eq!(address.0, env::keccak256_array(b"alice")[12..]);
+        let json = near_sdk::serde_json::to_string(&address).unwrap();
+        assert_eq!(
+            near_sdk::serde_json::from_str::<EvmAddress>(&json).unwrap(),
+            address
+        );
+    }
+}
@@ -0,0 +1,157 @@
+/*!
+Account keys hashed for the trie, like the ft and nft standards do for their accounts.
+
+Account ids are up to 64 bytes and share prefixes, so maps keyed by them build deep tries.
+[`HashedAccountKey`] is the sha256 of the account id, 32 bytes and evenly spread.
+
+# NOTES:
+  - [`HashedAccountMap`] can read an older `LookupMap<AccountId, V>` under another prefix,
+    entries are moved to the hashed map when they are written, or in batches with `migrate`.
+  - Hashed keys can't be iterated back to account ids, keep the account id in the value if needed.
+
+# EXAMPLE:
+```
+# use cmn::*;
+use hashed_key::HashedAccountMap;
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    // was `LookupMap<AccountId, u64>` under `b"s"`
+    scores: HashedAccountMap<u64>,
+}
+
+#[near_bindgen]
+impl Contract {
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        Self {
+            scores: HashedAccountMap::new(b"h".to_vec()).with_legacy(b"s".to_vec()),
+        }
+    }
+
+    #[private]
+    pub fn migrate_scores(&mut self, account_ids: Vec<AccountId>) -> u32 {
+        self.scores.migrate(&account_ids)
+    }
+}
+```
+*/
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::LookupMap,
+    env, AccountId, IntoStorageKey,
+};
+
+/// sha256 of an account id.
+#[derive(
+    BorshDeserialize, BorshSerialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+pub struct HashedAccountKey(pub [u8; 32]);
+
+impl From<&AccountId> for HashedAccountKey {
+    #[inline]
+    fn from(account_id: &AccountId) -> Self {
+        Self(env::sha256_array(account_id.as_bytes()))
+    }
+}
+
+/// `LookupMap` keyed by hashed account ids, optionally reading an unhashed legacy map.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct HashedAccountMap<V: BorshSerialize + BorshDeserialize> {
+    pub map: LookupMap<HashedAccountKey, V>,
+    pub legacy: Option<LookupMap<AccountId, V>>,
+}
+impl<V: BorshSerialize + BorshDeserialize> HashedAccountMap<V> {
+    #[inline]
+    pub fn new<S: IntoStorageKey>(prefix: S) -> Self {
+        Self {
+            map: LookupMap::new(prefix),
+            legacy: None,
+        }
+    }
+
+    /// Falls back to the `LookupMap<AccountId, V>` under `prefix`.
+    #[inline]
+    pub fn with_legacy<S: IntoStorageKey>(mut self, prefix: S) -> Self {
+        self.legacy = Some(LookupMap::new(prefix));
+        self
+    }
+
+    #[inline]
+    pub fn get(&self, account_id: &AccountId) -> Option<V> {
+        self.map.get(&account_id.into()).or_else(|| {
+            self.legacy
+                .as_ref()
+                .and_then(|legacy| legacy.get(account_id))
+        })
+    }
+
+    #[inline]
+    pub fn contains_key(&self, account_id: &AccountId) -> bool {
+        self.map.contains_key(&account_id.into())
+            || self
+                .legacy
+                .as_ref()
+                .is_some_and(|legacy| legacy.contains_key(account_id))
+    }
+
+    /// Inserts under the hashed key, removing the legacy entry. Returns the previous value.
+    pub fn insert(&mut self, account_id: &AccountId, value: &V) -> Option<V> {
+        let legacy = self.remove_legacy(account_id);
+        self.map.insert(&account_id.into(), value).or(legacy)
+    }
+
+    pub fn remove(&mut self, account_id: &AccountId) -> Option<V> {
+        let legacy = self.remove_legacy(account_id);
+        self.map.remove(&account_id.into()).or(legacy)
+    }
+
+    /// Moves the legacy entries of `account_ids` to hashed keys, returns how many were moved.
+    pub fn migrate(&mut self, account_ids: &[AccountId]) -> u32 {
+        let mut migrated = 0;
+        for account_id in account_ids {
+            if let Some(value) = self.remove_legacy(account_id) {
+                self.map.insert(&account_id.into(), &value);
+                migrated += 1;
+            }
+        }
+        migrated
+    }
+
+    #[inline]
+    fn remove_legacy(&mut self, account_id: &AccountId) -> Option<V> {
+        self.legacy
+            .as_mut()
+            .and_then(|legacy| legacy.remove(account_id))
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_legacy_migration() {
+        run_vm(vm!("alice.near"));
+
+        let mut legacy = LookupMap::<AccountId, u64>::new(b"s".to_vec());
+        legacy.insert(&accounts(0), &1);
+        legacy.insert(&accounts(1), &2);
+
+        let mut map = HashedAccountMap::<u64>::new(b"h".to_vec()).with_legacy(b"s".to_vec());
+        assert_eq!(map.get(&accounts(0)), Some(1));
+
+        assert_eq!(map.insert(&accounts(0), &3), Some(1));
+        assert!(!legacy.contains_key(&accounts(0)));
+        assert_eq!(map.get(&accounts(0)), Some(3));
+
+        assert_eq!(map.migrate(&[accounts(1), accounts(2)]), 1);
+        assert!(!legacy.contains_key(&accounts(1)));
+        assert_eq!(map.get(&accounts(1)), Some(2));
+        assert!(map.map.contains_key(&HashedAccountKey::from(&accounts(1))));
+    }
+}
@@ -0,0 +1,99 @@
+/*!
+Sha256 merkle trees, E.g. to commit to an allowlist or an airdrop in one hash.
+
+# NOTES:
+  - Leaves are `sha256(0x00 || data)` and nodes `sha256(0x01 || min(a, b) || max(a, b))`,
+    the prefixes keep a node from passing as a leaf, and the sorted pairs make proofs
+    independent of the side of the siblings.
+  - The last node of an odd level is promoted as it is, the root of no leaves is all zeros.
+  - `cargo run --bin vectors` prints roots of sample trees to check other implementations,
+    off-chain code can use `contract_core::merkle` with any sha256.
+
+# EXAMPLE:
+```
+# use cmn::*;
+let leaves: Vec<_> = ["alice.near", "bob.near", "carol.near"]
+    .iter()
+    .map(|account_id| merkle::leaf(account_id.as_bytes()))
+    .collect();
+let root = merkle::root(&leaves);
+let proof = merkle::proof(&leaves, 1);
+assert!(merkle::verify(&leaves[1], &proof, &root));
+```
+*/
+
+use contract_core::merkle as core;
+use near_sdk::env;
+
+pub use core::Hash;
+
+#[inline]
+fn sha256(bytes: &[u8]) -> Hash {
+    env::sha256_array(bytes)
+}
+
+#[inline]
+pub fn leaf(data: &[u8]) -> Hash {
+    core::leaf(data, sha256)
+}
+
+#[inline]
+pub fn node(a: &Hash, b: &Hash) -> Hash {
+    core::node(a, b, sha256)
+}
+
+#[inline]
+pub fn root(leaves: &[Hash]) -> Hash {
+    core::root(leaves, sha256)
+}
+
+/// Siblings of the leaf at `index` from the bottom, empty for a promoted node.
+#[inline]
+pub fn proof(leaves: &[Hash], index: usize) -> Vec<Hash> {
+    core::proof(leaves, index, sha256)
+}
+
+#[inline]
+pub fn verify(leaf: &Hash, proof: &[Hash], root: &Hash) -> bool {
+    core::verify(leaf, proof, root, sha256)
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::super::test_utils::*;
+    use super::*;
+
+    fn leaves(n: usize) -> Vec<Hash> {
+        (0..n).map(|i| leaf(&[i as u8])).collect()
+    }
+
+    #[test]
+    fn check_proofs() {
+        run_vm(vm!("alice.near"));
+
+        for n in 1..=7 {
+            let leaves = leaves(n);
+            let root = root(&leaves);
+            for (i, leaf) in leaves.iter().enumerate() {
+                assert!(verify(leaf, &proof(&leaves, i), &root));
+            }
+        }
+    }
+
+    #[test]
+    fn check_root() {
+        run_vm(vm!("alice.near"));
+
+        let leaves = leaves(3);
+        assert_eq!(root(&[]), [0; 32]);
+        assert_eq!(root(&leaves[..1]), leaves[0]);
+        let root = root(&leaves);
+        assert_eq!(root, node(&node(&leaves[0], &leaves[1]), &leaves[2]));
+        // the node of two leaves isn't the leaf of their concatenation
+        assert_ne!(
+            node(&leaves[0], &leaves[1]),
+            leaf(&[leaves[0], leaves[1]].concat())
+        );
+        assert!(!verify(&leaves[0], &proof(&leaves, 1), &root));
+    }
+}
@@ -0,0 +1,270 @@
+#![allow(dead_code)]
+
+use near_sdk::{env, require, serde::Serialize, serde_json, AccountId, Balance, Promise};
+
+pub mod accounts;
+pub mod allowlist;
+pub mod counter;
+pub mod evm;
+pub mod hashed_key;
+pub mod merkle;
+pub mod nonce;
+pub mod sealed;
+pub mod storage_key;
+pub mod strings;
+pub mod u256;
+
+pub use storage_key::raw_storage_key;
+
+/// Helper functions for hashing
+///
+/// # Example
+/// ```
+/// # use cmn::*;
+/// let hashed = hash("hello world", env::sha256);
+/// let hex = hashed.encode_hex::<String>();
+/// ```
+pub use contract_core::hash;
+
+/// `log!` of near-sdk, a no-op in release builds with the `strip-logs` feature to save the
+/// size and the gas of the messages. Events don't go through it, they're always logged.
+///
+/// # Example
+/// ```
+/// # use cmn::*;
+/// log!("Contract initialized");
+/// ```
+#[macro_export]
+macro_rules! log {
+    ($($arg:tt)*) => {{
+        #[cfg(not(all(feature = "strip-logs", not(debug_assertions))))]
+        near_sdk::log!($($arg)*);
+        // never formatted, keeps the arguments used
+        #[cfg(all(feature = "strip-logs", not(debug_assertions)))]
+        if false {
+            let _ = format!($($arg)*);
+        }
+    }};
+}
+pub use log;
+
+/// Assert when the contract has been initialized.
+///
+/// # Example
+/// ```
+/// # use cmn::*;
+/// // assert_init!([panic message; default: "Already initialized"]);
+/// assert_init!();
+/// // == assert_init!("Already initialized");
+/// ```
+#[macro_export]
+macro_rules! assert_init {
+    ($message:expr) => {
+        assert!(!env::state_exists(), $message);
+    };
+    () => {
+        assert_init!("Already initialized");
+    };
+}
+pub use assert_init;
+
+/// Assert when the contract has not been initialized. [require!] version.
+///
+/// # Example
+/// ```
+/// # use cmn::*;
+/// // require_init!([panic message; default: "Already initialized"]);
+/// require_init!();
+/// // == require_init!("Already initialized");
+/// ```
+#[macro_export]
+macro_rules! require_init {
+    ($message:expr) => {
+        require!(!env::state_exists(), $message);
+    };
+    () => {
+        require_init!("Already initialized");
+    };
+}
+pub use require_init;
+
+/// Sends the attached deposit back to the predecessor and returns it.
+#[inline]
+pub fn refund_attached_deposit() -> Balance {
+    let deposit = env::attached_deposit();
+    if deposit > 0 {
+        Promise::new(env::predecessor_account_id()).transfer(deposit);
+    }
+    deposit
+}
+
+/// Charges `storage_used` bytes from the attached deposit and sends the rest to `refund_to`,
+/// the predecessor by default. A relayer that attached the deposit of a meta transaction
+/// is refunded by passing it as `refund_to`.
+#[inline]
+pub fn refund_deposit_to(storage_used: u64, refund_to: Option<AccountId>) {
+    refund_deposit_less_fee_to(storage_used, 0, refund_to)
+}
+
+/// [`refund_deposit_to`] that also keeps `fee` of the attached deposit, E.g. a platform fee.
+pub fn refund_deposit_less_fee_to(storage_used: u64, fee: Balance, refund_to: Option<AccountId>) {
+    let required_cost = super::protocol::storage_cost(storage_used);
+    let attached_deposit = env::attached_deposit();
+    require!(
+        required_cost + fee <= attached_deposit,
+        match fee {
+            0 => format!("Must attach {} yoctoNEAR to cover storage", required_cost),
+            _ => format!(
+                "Must attach {} yoctoNEAR to cover storage and {} yoctoNEAR of fee",
+                required_cost, fee
+            ),
+        }
+    );
+
+    let refund = attached_deposit - required_cost - fee;
+    if refund > 1 {
+        Promise::new(refund_to.unwrap_or_else(env::predecessor_account_id)).transfer(refund);
+    }
+}
+
+/// [require!] for payable methods that must not fail, E.g. callbacks or `ft_on_transfer`:
+/// refunds the attached deposit, logs the message and returns instead of panicking.
+///
+/// A panic already refunds the deposit of the failed call, but also reverts everything else,
+/// so use this only when the method has to finish.
+///
+/// # Example
+/// ```
+/// # use cmn::*;
+/// // require_or_refund!(condition, message[, return value; default: ()]);
+/// require_or_refund!(env::attached_deposit() >= price, "Not enough deposit", false);
+/// ```
+#[macro_export]
+macro_rules! require_or_refund {
+    ($cond:expr, $message:expr, $ret:expr) => {
+        if !$cond {
+            $crate::refund_attached_deposit();
+            near_sdk::log!("{}", $message);
+            return $ret;
+        }
+    };
+    ($cond:expr, $message:expr) => {
+        require_or_refund!($cond, $message, ())
+    };
+}
+pub use require_or_refund;
+
+/// A contract specific NEP-297 event, logged as
+/// `EVENT_JSON:{"data":[<the serialized event>],"event":..,"standard":..,"version":..}`,
+/// the keys sorted like every event of [`events::emit`](super::events::emit).
+/// Implemented by [`event!`].
+pub trait Event: Serialize {
+    const STANDARD: super::events::Standard;
+    const EVENT: &'static str;
+
+    #[inline]
+    fn emit(&self) {
+        super::events::emit(Self::STANDARD, Self::EVENT, self)
+    }
+}
+
+/// Implements [`Event`] for a serializable type.
+///
+/// # Example
+/// ```
+/// # use cmn::*;
+/// #[derive(near_sdk::serde::Serialize)]
+/// #[serde(crate = "near_sdk::serde")]
+/// pub struct PuzzleSolved<'a> {
+///     pub account_id: &'a AccountId,
+/// }
+///
+/// // event!(<type>, <events::Standard>, "<event>");
+/// event!(PuzzleSolved<'_>, events::Standard::new("puzzle", "1.0.0"), "puzzle_solved");
+///
+/// PuzzleSolved { account_id: &env::predecessor_account_id() }.emit();
+/// // EVENT_JSON:{"data":[{"account_id":"alice.near"}],"event":"puzzle_solved","standard":"puzzle","version":"1.0.0"}
+/// ```
+#[macro_export]
+macro_rules! event {
+    ($event:ty, $standard:expr, $name:literal $(,)?) => {
+        impl $crate::Event for $event {
+            const STANDARD: $crate::events::Standard = $standard;
+            const EVENT: &'static str = $name;
+        }
+    };
+}
+pub use event;
+
+/// Canonical JSON bytes of `value`: object keys sorted by their bytes, no whitespace.
+/// Off-chain signers serializing the same way get the same bytes to sign.
+///
+/// # Example
+/// ```
+/// # use cmn::*;
+/// let value = near_sdk::serde_json::json!({ "b": 1, "a": [true, null] });
+/// assert_eq!(canonical_json(&value), br#"{"a":[true,null],"b":1}"#);
+/// ```
+pub use contract_core::json::canonical_json;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::test_utils::*;
+    use super::*;
+
+    fn buy(price: Balance) -> bool {
+        require_or_refund!(
+            env::attached_deposit() >= price,
+            "Not enough deposit",
+            false
+        );
+        true
+    }
+
+    #[test]
+    fn check_require_or_refund() {
+        run_vm(vm!(accounts(0)).attached_deposit(10));
+
+        assert!(buy(10));
+        assert_eq!(get_transfers_to(&accounts(0)), 0);
+
+        assert!(!buy(11));
+        assert_deposit_refunded(&accounts(0), 10);
+        logs!["Not enough deposit"].assert();
+    }
+
+    #[derive(Serialize)]
+    #[serde(crate = "near_sdk::serde")]
+    struct PuzzleSolved<'a> {
+        account_id: &'a AccountId,
+        tries: u32,
+    }
+    event!(
+        PuzzleSolved<'_>,
+        super::super::events::Standard::new("puzzle", "1.0.0"),
+        "puzzle_solved"
+    );
+
+    #[test]
+    fn check_event() {
+        run_vm(vm!(accounts(0)));
+
+        PuzzleSolved {
+            account_id: &accounts(0),
+            tries: 2,
+        }
+        .emit();
+        logs![r#"EVENT_JSON:{"data":[{"account_id":"alice","tries":2}],"event":"puzzle_solved","standard":"puzzle","version":"1.0.0"}"#]
+            .assert();
+    }
+
+    #[test]
+    fn check_canonical_json() {
+        let value: serde_json::Value =
+            serde_json::from_str(r#"{ "b": {"y": 1, "x": "é\n"}, "a": [2, 1.5, null] }"#).unwrap();
+        assert_eq!(
+            String::from_utf8(canonical_json(&value)).unwrap(),
+            r#"{"a":[2,1.5,null],"b":{"x":"é\n","y":1}}"#
+        );
+    }
+}
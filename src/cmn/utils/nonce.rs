@@ -0,0 +1,166 @@
+/*!
+Replay protection for signed messages, E.g. meta transactions, permits and signed orders.
+
+Every account has an incrementing nonce: a message has to carry a nonce greater than the last
+consumed one, and within `window` of it, so a signer can't burn the nonce space with a huge one.
+
+# NOTES:
+  - Nonces can be skipped (an expired or cancelled message simply never gets consumed),
+    but never reused.
+  - `expires_at` is checked by [`Nonces::consume_until`] for messages that carry a deadline.
+
+# EXAMPLE:
+```
+# use cmn::*;
+#[near_bindgen]
+impl Contract {
+    pub fn permit(&mut self, owner_id: AccountId, nonce: U64, deadline: U64, signature: Base64VecU8) {
+        self.nonces.consume_until(&owner_id, nonce.0, deadline.0);
+        // verify the signature of the message with the nonce ..
+    }
+}
+
+// nonce_of({"account_id": "alice.near"}) => "3"
+nonce::impl_nonces!(Contract, nonces);
+```
+*/
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::LookupMap,
+    env, require, AccountId,
+};
+
+/// Default max gap between the last consumed nonce and the next one.
+pub const NONCE_WINDOW: u64 = 1_000_000;
+
+super::raw_storage_key!(pub enum StorageKey in super::storage_key::NONCE {
+    Nonces,
+});
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Nonces {
+    /// The last consumed nonce per account.
+    pub last: LookupMap<AccountId, u64>,
+    pub window: u64,
+}
+impl Default for Nonces {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Nonces {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            last: LookupMap::new(StorageKey::Nonces),
+            window: NONCE_WINDOW,
+        }
+    }
+
+    #[inline]
+    pub fn with_window(mut self, window: u64) -> Self {
+        require!(window > 0, "Nonce window can't be 0");
+        self.window = window;
+        self
+    }
+
+    /// The last consumed nonce of the account, 0 if none.
+    #[inline]
+    pub fn get(&self, account_id: &AccountId) -> u64 {
+        self.last.get(account_id).unwrap_or(0)
+    }
+
+    /// Whether `nonce` can be consumed by the account.
+    #[inline]
+    pub fn is_valid(&self, account_id: &AccountId, nonce: u64) -> bool {
+        let last = self.get(account_id);
+        nonce > last && nonce - last <= self.window
+    }
+
+    /// Consumes `nonce`, every nonce up to it can't be used anymore.
+    pub fn consume(&mut self, account_id: &AccountId, nonce: u64) {
+        let last = self.get(account_id);
+        require!(nonce > last, "Nonce is already used");
+        require!(
+            nonce - last <= self.window,
+            format!("Nonce can't be more than {} ahead", self.window)
+        );
+        self.last.insert(account_id, &nonce);
+    }
+
+    /// [`Nonces::consume`] for a message valid until `expires_at` (block timestamp, nanoseconds).
+    pub fn consume_until(&mut self, account_id: &AccountId, nonce: u64, expires_at: u64) {
+        require!(env::block_timestamp() < expires_at, "Message is expired");
+        self.consume(account_id, nonce);
+    }
+}
+
+#[macro_export]
+macro_rules! impl_nonces {
+    ($contract:ident, $nonces:ident) => {
+        impl $contract {
+            /// Manifest entries of the methods generated by `impl_nonces!`.
+            pub const NONCES_MANIFEST: $crate::manifest::Component = $crate::manifest::Component {
+                feature: None,
+                methods: &[
+                    $crate::manifest::Method::view("nonce_of"),
+                    $crate::manifest::Method::view("nonce_window"),
+                ],
+                storage: &[],
+            };
+        }
+        #[near_bindgen]
+        impl $contract {
+            /// The last consumed nonce of the account, the next message needs a greater one.
+            pub fn nonce_of(&self, account_id: AccountId) -> U64 {
+                self.$nonces.get(&account_id).into()
+            }
+
+            pub fn nonce_window(&self) -> U64 {
+                self.$nonces.window.into()
+            }
+        }
+    };
+}
+pub use impl_nonces;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_consume() {
+        run_vm(vm!("alice.near"));
+
+        let mut nonces = Nonces::new().with_window(10);
+        assert!(nonces.is_valid(&accounts(0), 1));
+        assert!(!nonces.is_valid(&accounts(0), 11));
+
+        nonces.consume(&accounts(0), 3);
+        assert_eq!(nonces.get(&accounts(0)), 3);
+        assert!(!nonces.is_valid(&accounts(0), 2));
+        assert!(nonces.is_valid(&accounts(1), 2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Nonce is already used")]
+    fn check_replay() {
+        run_vm(vm!("alice.near"));
+
+        let mut nonces = Nonces::new();
+        nonces.consume(&accounts(0), 1);
+        nonces.consume(&accounts(0), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Message is expired")]
+    fn check_expired() {
+        run_vm(vm!("alice.near").block_timestamp(100));
+
+        let mut nonces = Nonces::new();
+        nonces.consume_until(&accounts(0), 1, 100);
+    }
+}
@@ -0,0 +1,246 @@
+/*!
+Envelopes of data encrypted off-chain for a given viewer, E.g. private messages or sealed bids.
+
+The contract can't decrypt anything, it only checks that an [`Envelope`] is well formed for its
+[`Algorithm`] and bounded in size, and keeps it in [`SealedBoxes`] for the recipient to read.
+
+# NOTES:
+  - Keys are X25519, the sender key is ephemeral for a box that only the recipient can open.
+  - `ciphertext` includes the authentication tag, so it's at least [`Algorithm::tag_length`] bytes.
+  - Whoever stores a box pays its storage, which is refunded when it's removed.
+
+# EXAMPLE:
+```
+# use cmn::*;
+use sealed::{Envelope, SealedBoxes};
+
+#[near_bindgen]
+impl Contract {
+    #[payable]
+    pub fn send_private_message(&mut self, recipient_id: AccountId, envelope: Envelope) -> U64 {
+        let initial_storage_usage = env::storage_usage();
+        let id = self.boxes.insert(&env::predecessor_account_id(), &recipient_id, envelope);
+        refund_deposit_to(env::storage_usage() - initial_storage_usage, None);
+        U64(id)
+    }
+}
+// envelope: {"algorithm": "x25519_xsalsa20_poly1305", "sender_key": "<base64>", "recipient_key": "<base64>",
+//            "nonce": "<base64>", "ciphertext": "<base64>", "metadata": "text/plain"}
+```
+*/
+
+use super::strings::BoundedString;
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    collections::LookupMap,
+    env,
+    json_types::{Base64VecU8, U64},
+    require,
+    serde::{Deserialize, Serialize},
+    AccountId,
+};
+
+/// Default max bytes of a ciphertext.
+pub const MAX_CIPHERTEXT_LENGTH: usize = 4096;
+pub const MAX_METADATA_LENGTH: usize = 256;
+/// X25519 public keys.
+pub const KEY_LENGTH: usize = 32;
+
+pub type Metadata = BoundedString<MAX_METADATA_LENGTH>;
+
+super::raw_storage_key!(pub enum StorageKey in super::storage_key::SEALED {
+    SealedBoxes,
+});
+
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq,
+)]
+#[serde(crate = "near_sdk::serde", rename_all = "snake_case")]
+pub enum Algorithm {
+    /// NaCl `crypto_box`, E.g. `tweetnacl.box`.
+    X25519XSalsa20Poly1305,
+    /// X25519 key agreement with XChaCha20-Poly1305, E.g. libsodium `crypto_box_curve25519xchacha20poly1305`.
+    X25519XChaCha20Poly1305,
+}
+impl Algorithm {
+    #[inline]
+    pub const fn nonce_length(self) -> usize {
+        match self {
+            Algorithm::X25519XSalsa20Poly1305 | Algorithm::X25519XChaCha20Poly1305 => 24,
+        }
+    }
+
+    #[inline]
+    pub const fn tag_length(self) -> usize {
+        match self {
+            Algorithm::X25519XSalsa20Poly1305 | Algorithm::X25519XChaCha20Poly1305 => 16,
+        }
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Envelope {
+    pub algorithm: Algorithm,
+    /// Public key of the sender, ephemeral for an anonymous box.
+    pub sender_key: Base64VecU8,
+    /// Public key of the viewer who can open it.
+    pub recipient_key: Base64VecU8,
+    pub nonce: Base64VecU8,
+    pub ciphertext: Base64VecU8,
+    /// Plaintext hint for the viewer, E.g. a content type.
+    pub metadata: Option<Metadata>,
+}
+impl Envelope {
+    /// Panics unless the lengths match the algorithm and the ciphertext is at most `max_ciphertext_length` bytes.
+    pub fn assert_valid(&self, max_ciphertext_length: usize) {
+        require!(
+            self.sender_key.0.len() == KEY_LENGTH && self.recipient_key.0.len() == KEY_LENGTH,
+            format!("Keys must be {} bytes", KEY_LENGTH)
+        );
+        require!(
+            self.nonce.0.len() == self.algorithm.nonce_length(),
+            format!("Nonce must be {} bytes", self.algorithm.nonce_length())
+        );
+        let len = self.ciphertext.0.len();
+        require!(
+            len >= self.algorithm.tag_length(),
+            "Ciphertext is shorter than its tag"
+        );
+        require!(
+            len <= max_ciphertext_length,
+            format!(
+                "Ciphertext can't be more than {} bytes, got {}",
+                max_ciphertext_length, len
+            )
+        );
+    }
+}
+
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct SealedBox {
+    pub sender_id: AccountId,
+    pub recipient_id: AccountId,
+    pub envelope: Envelope,
+    /// Block timestamp (nanoseconds).
+    pub created_at: U64,
+}
+
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct SealedBoxes {
+    pub boxes: LookupMap<u64, SealedBox>,
+    pub next_id: u64,
+    pub max_ciphertext_length: usize,
+}
+impl Default for SealedBoxes {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl SealedBoxes {
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            boxes: LookupMap::new(StorageKey::SealedBoxes),
+            next_id: 0,
+            max_ciphertext_length: MAX_CIPHERTEXT_LENGTH,
+        }
+    }
+
+    #[inline]
+    pub fn with_max_ciphertext_length(mut self, max_ciphertext_length: usize) -> Self {
+        self.max_ciphertext_length = max_ciphertext_length;
+        self
+    }
+
+    /// Validates and stores the envelope, returns its id.
+    pub fn insert(
+        &mut self,
+        sender_id: &AccountId,
+        recipient_id: &AccountId,
+        envelope: Envelope,
+    ) -> u64 {
+        envelope.assert_valid(self.max_ciphertext_length);
+        let id = self.next_id;
+        self.next_id += 1;
+        self.boxes.insert(
+            &id,
+            &SealedBox {
+                sender_id: sender_id.clone(),
+                recipient_id: recipient_id.clone(),
+                envelope,
+                created_at: U64(env::block_timestamp()),
+            },
+        );
+        id
+    }
+
+    #[inline]
+    pub fn get(&self, id: u64) -> Option<SealedBox> {
+        self.boxes.get(&id)
+    }
+
+    /// Removes the box, only its sender or recipient can. Returns it and the released bytes.
+    pub fn remove(&mut self, id: u64, account_id: &AccountId) -> (SealedBox, u64) {
+        let sealed = self.boxes.get(&id).expect("Sealed box not found");
+        require!(
+            account_id == &sealed.sender_id || account_id == &sealed.recipient_id,
+            "Only the sender or the recipient can remove the box"
+        );
+        let initial_storage_usage = env::storage_usage();
+        self.boxes.remove(&id);
+        (sealed, initial_storage_usage - env::storage_usage())
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::super::test_utils::*;
+    use super::*;
+
+    fn envelope(ciphertext_length: usize) -> Envelope {
+        Envelope {
+            algorithm: Algorithm::X25519XSalsa20Poly1305,
+            sender_key: Base64VecU8(vec![1; KEY_LENGTH]),
+            recipient_key: Base64VecU8(vec![2; KEY_LENGTH]),
+            nonce: Base64VecU8(vec![3; 24]),
+            ciphertext: Base64VecU8(vec![4; ciphertext_length]),
+            metadata: Some(Metadata::new("text/plain")),
+        }
+    }
+
+    #[test]
+    fn check_insert_and_remove() {
+        run_vm(vm!("alice.near"));
+
+        let mut boxes = SealedBoxes::new();
+        let id = boxes.insert(&accounts(0), &accounts(1), envelope(16));
+        assert_eq!(boxes.get(id).unwrap().envelope, envelope(16));
+
+        let (sealed, released) = boxes.remove(id, &accounts(1));
+        assert_eq!(sealed.sender_id, accounts(0));
+        assert!(released > 0);
+        assert!(boxes.get(id).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Ciphertext can't be more than 32 bytes, got 33")]
+    fn check_max_ciphertext_length() {
+        run_vm(vm!("alice.near"));
+
+        let mut boxes = SealedBoxes::new().with_max_ciphertext_length(32);
+        boxes.insert(&accounts(0), &accounts(1), envelope(33));
+    }
+
+    #[test]
+    #[should_panic(expected = "Nonce must be 24 bytes")]
+    fn check_nonce_length() {
+        run_vm(vm!("alice.near"));
+
+        let mut envelope = envelope(16);
+        envelope.nonce = Base64VecU8(vec![3; 12]);
+        envelope.assert_valid(MAX_CIPHERTEXT_LENGTH);
+    }
+}
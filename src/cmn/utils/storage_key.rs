@@ -0,0 +1,106 @@
+#![allow(dead_code)]
+/*!
+Storage key prefixes of the components, one byte each.
+
+`BorshStorageKey` writes the variant index of the enum, so the first key of every component would
+be `[0]`. The components declare their keys with [`raw_storage_key!`](crate::raw_storage_key)
+instead, numbered from the start of their range below, so no two components share a prefix.
+
+# NOTES:
+  - A new component takes the range after the last one, a new key of a component takes the next
+    byte of its range. The numbers are stored in deployed contracts, never reuse or move them.
+  - `raw_storage_key!` fails to compile when the keys outgrow their range.
+
+# EXAMPLE:
+```
+# use cmn::*;
+raw_storage_key!(pub enum StorageKey in storage_key::OTC {
+    OtcOrders,
+    OtcUnclaimed,
+});
+assert_eq!(near_sdk::IntoStorageKey::into_storage_key(StorageKey::OtcUnclaimed), [23]);
+```
+*/
+
+use std::ops::Range;
+
+/// `ft::StorageKey` and `nft::StorageKey`, `BorshStorageKey` enums writing their variant index.
+pub const FT_NFT: Range<u8> = 0..10;
+pub const ROYALTY: Range<u8> = 10..11;
+pub const BOUNTY: Range<u8> = 11..12;
+pub const NAMES: Range<u8> = 12..13;
+pub const ATTEST: Range<u8> = 13..15;
+pub const NONCE: Range<u8> = 15..16;
+pub const BRIDGE: Range<u8> = 16..18;
+pub const STATS: Range<u8> = 18..19;
+pub const SPONSOR: Range<u8> = 19..21;
+pub const DONATION: Range<u8> = 21..22;
+pub const OTC: Range<u8> = 22..24;
+pub const SALE: Range<u8> = 24..25;
+pub const KEEPER: Range<u8> = 25..26;
+pub const SEALED: Range<u8> = 26..27;
+pub const REPUTATION: Range<u8> = 27..28;
+pub const MINT_FEE: Range<u8> = 28..29;
+pub const LOCKUP: Range<u8> = 29..30;
+pub const AUDIT: Range<u8> = 30..31;
+pub const BREAKER: Range<u8> = 31..32;
+pub const ALLOWLIST: Range<u8> = 32..33;
+pub const ATTRIBUTES: Range<u8> = 33..35;
+pub const INBOX: Range<u8> = 35..36;
+pub const EXPIRY: Range<u8> = 36..38;
+pub const FREEZE: Range<u8> = 38..40;
+pub const REDEEM: Range<u8> = 40..41;
+pub const MEMBERSHIP: Range<u8> = 41..42;
+pub const MT: Range<u8> = 42..49;
+pub const DEPOSITS: Range<u8> = 49..50;
+
+/// A `#[repr(u8)]` storage key enum written as its discriminant, numbered from the start of
+/// `range`.
+///
+/// # Example
+/// ```
+/// # use cmn::*;
+/// // raw_storage_key!(<vis> enum <name> in <storage_key range> { <variant>, .. });
+/// raw_storage_key!(pub enum StorageKey in storage_key::ATTEST {
+///     Issuers,
+///     Claims,
+/// });
+/// ```
+#[macro_export]
+macro_rules! raw_storage_key {
+    ($vis:vis enum $name:ident in $range:path { $first:ident $(, $variant:ident)* $(,)? }) => {
+        #[repr(u8)]
+        #[derive(Clone, Copy)]
+        $vis enum $name {
+            $first = $range.start,
+            $($variant,)*
+        }
+        impl near_sdk::IntoStorageKey for $name {
+            fn into_storage_key(self) -> Vec<u8> {
+                vec![self as u8]
+            }
+        }
+        const _: () = {
+            $(assert!(($name::$variant as u8) < $range.end, "Storage key out of its range");)*
+        };
+    };
+}
+pub use raw_storage_key;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_ranges_disjoint() {
+        let ranges = [
+            FT_NFT, ROYALTY, BOUNTY, NAMES, ATTEST, NONCE, BRIDGE, STATS, SPONSOR, DONATION, OTC,
+            SALE, KEEPER, SEALED, REPUTATION, MINT_FEE, LOCKUP, AUDIT, BREAKER, ALLOWLIST,
+            ATTRIBUTES, INBOX, EXPIRY, FREEZE, REDEEM, MEMBERSHIP, MT, DEPOSITS,
+        ];
+        for pair in ranges.windows(2) {
+            assert!(!pair[0].is_empty());
+            assert_eq!(pair[0].end, pair[1].start);
+        }
+    }
+}
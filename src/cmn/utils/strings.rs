@@ -0,0 +1,187 @@
+/*!
+Byte-length guards for strings that get stored or logged.
+
+A `String` arg can be megabytes, so everything kept in the state should be bounded,
+either with [`assert_max_bytes`] or by taking a [`BoundedString`] in the first place.
+
+# EXAMPLE:
+```
+# use cmn::*;
+use strings::{assert_max_bytes, BoundedString, MAX_MEMO_LENGTH};
+
+if let Some(memo) = &memo {
+    assert_max_bytes(memo, MAX_MEMO_LENGTH);
+}
+
+#[near_bindgen]
+#[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
+pub struct Contract {
+    // a 65th byte fails to deserialize, from the JSON args and from the state
+    hint: BoundedString<64>,
+}
+```
+*/
+
+use near_sdk::{
+    borsh::{self, BorshDeserialize, BorshSerialize},
+    require,
+    serde::{de, Deserialize, Deserializer, Serialize, Serializer},
+};
+use std::{
+    fmt,
+    io::{self, Write},
+    ops::Deref,
+};
+
+/// Memos of transfers.
+pub const MAX_MEMO_LENGTH: usize = 256;
+
+/// Panics if `s` is over `max` bytes, which can be less than `max` characters.
+#[inline]
+#[track_caller]
+pub fn assert_max_bytes(s: &str, max: usize) {
+    require!(s.len() <= max, max_bytes_error(s.len(), max));
+}
+
+#[inline]
+fn max_bytes_error(len: usize, max: usize) -> String {
+    format!("String can't be more than {} bytes, got {}", max, len)
+}
+
+/// `String` of at most `N` bytes, checked whenever it's built or deserialized.
+/// Borsh and JSON representations are the ones of `String`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BoundedString<const N: usize>(String);
+
+impl<const N: usize> BoundedString<N> {
+    pub const MAX_BYTES: usize = N;
+
+    /// Panics if `s` is over `N` bytes.
+    #[inline]
+    pub fn new(s: impl Into<String>) -> Self {
+        Self::try_from(s.into()).unwrap_or_else(|e| near_sdk::env::panic_str(&e))
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl<const N: usize> TryFrom<String> for BoundedString<N> {
+    type Error = String;
+    #[inline]
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        if s.len() > N {
+            return Err(max_bytes_error(s.len(), N));
+        }
+        Ok(Self(s))
+    }
+}
+
+impl<const N: usize> From<BoundedString<N>> for String {
+    #[inline]
+    fn from(s: BoundedString<N>) -> Self {
+        s.0
+    }
+}
+
+impl<const N: usize> Deref for BoundedString<N> {
+    type Target = str;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<const N: usize> AsRef<str> for BoundedString<N> {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<const N: usize> fmt::Display for BoundedString<N> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<const N: usize> BorshSerialize for BoundedString<N> {
+    #[inline]
+    fn serialize<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        BorshSerialize::serialize(&self.0, writer)
+    }
+}
+
+impl<const N: usize> BorshDeserialize for BoundedString<N> {
+    #[inline]
+    fn deserialize(buf: &mut &[u8]) -> io::Result<Self> {
+        Self::try_from(<String as BorshDeserialize>::deserialize(buf)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+impl<const N: usize> Serialize for BoundedString<N> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Serialize::serialize(&self.0, serializer)
+    }
+}
+
+impl<'de, const N: usize> Deserialize<'de> for BoundedString<N> {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Self::try_from(<String as Deserialize>::deserialize(deserializer)?)
+            .map_err(de::Error::custom)
+    }
+}
+
+#[cfg(feature = "schema")]
+impl<const N: usize> schemars::JsonSchema for BoundedString<N> {
+    fn schema_name() -> String {
+        format!("BoundedString{}", N)
+    }
+
+    fn json_schema(_: &mut schemars::gen::SchemaGenerator) -> schemars::schema::Schema {
+        schemars::schema::SchemaObject {
+            instance_type: Some(schemars::schema::InstanceType::String.into()),
+            // bytes, but the closest JSON Schema has
+            string: Some(Box::new(schemars::schema::StringValidation {
+                max_length: Some(N as u32),
+                ..Default::default()
+            })),
+            ..Default::default()
+        }
+        .into()
+    }
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+    use near_sdk::serde_json;
+
+    #[test]
+    fn check_bounded_string() {
+        let s: BoundedString<4> = serde_json::from_str(r#""abcd""#).unwrap();
+        assert_eq!(&*s, "abcd");
+        assert_eq!(serde_json::to_string(&s).unwrap(), r#""abcd""#);
+        assert_eq!(
+            s.try_to_vec().unwrap(),
+            "abcd".to_string().try_to_vec().unwrap()
+        );
+
+        // "ééé" is 3 chars but 6 bytes
+        assert!(serde_json::from_str::<BoundedString<4>>(r#""ééé""#).is_err());
+        let bytes = "abcde".to_string().try_to_vec().unwrap();
+        assert!(BoundedString::<4>::try_from_slice(&bytes).is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "String can't be more than 4 bytes, got 5")]
+    fn check_assert_max_bytes() {
+        assert_max_bytes("abcde", 4);
+    }
+}
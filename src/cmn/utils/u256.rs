@@ -0,0 +1,42 @@
+#![cfg(feature = "uint")]
+/*!
+256 bits integers, for the products of two balances.
+
+# EXAMPLE:
+```
+# use cmn::*;
+// tokens for `deposit` yoctoNEAR at `tokens_per_near`, rounded down
+let tokens = u256::mul_div(deposit, tokens_per_near, 10u128.pow(24));
+```
+*/
+
+pub use contract_core::math::U256;
+
+/// `a * b / c` rounded down, panics if it doesn't fit in a `u128`.
+#[inline]
+pub fn mul_div(a: u128, b: u128, c: u128) -> u128 {
+    let result = contract_core::math::mul_div(a, b, c);
+    near_sdk::require!(result.is_some(), "Amount overflow");
+    result.unwrap()
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_mul_div() {
+        assert_eq!(mul_div(u128::MAX, 3, 4), u128::MAX / 4 * 3 + 2);
+        assert_eq!(
+            mul_div(10u128.pow(30), 10u128.pow(30), 10u128.pow(24)),
+            10u128.pow(36)
+        );
+        assert_eq!(mul_div(7, 1, 2), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "Amount overflow")]
+    fn check_mul_div_overflow() {
+        mul_div(u128::MAX, 2, 1);
+    }
+}
@@ -0,0 +1,173 @@
+#![allow(dead_code)]
+/*!
+Validation of JSON args.
+
+Every combinator gives the reason of the failure, [`validate!`](crate::validate) puts the name of
+the field in front of it, so all components fail with the same messages.
+
+# NOTES:
+  - Lengths are in bytes, they're what the storage is paid for.
+  - `?field` skips the checks when the field is `None`.
+
+# EXAMPLE:
+```
+# use cmn::*;
+use validate::*;
+
+validate!(metadata.name => non_empty, max_len(MAX_NAME_LENGTH));
+validate!(?metadata.icon => valid_data_uri, max_len(MAX_ICON_LENGTH));
+validate!(amount.0 => range(1..=cap));
+// panics with "`metadata.name` can't be empty" on an empty name
+```
+*/
+
+use std::{fmt::Display, ops::RangeInclusive};
+
+/// Names, symbols and titles.
+pub const MAX_NAME_LENGTH: usize = 64;
+/// Descriptions.
+pub const MAX_DESCRIPTION_LENGTH: usize = 1024;
+/// URLs and paths.
+pub const MAX_URL_LENGTH: usize = 512;
+/// Data URIs of icons.
+pub const MAX_ICON_LENGTH: usize = 16 * 1024;
+
+pub type Validation = Result<(), String>;
+
+#[inline]
+pub fn non_empty(s: &str) -> Validation {
+    if s.is_empty() {
+        return Err("can't be empty".to_string());
+    }
+    Ok(())
+}
+
+#[inline]
+pub fn max_len(s: &str, max: usize) -> Validation {
+    if s.len() > max {
+        return Err(format!("can't be more than {} bytes", max));
+    }
+    Ok(())
+}
+
+#[inline]
+pub fn range<T: PartialOrd + Display>(value: &T, range: RangeInclusive<T>) -> Validation {
+    if !range.contains(value) {
+        return Err(format!(
+            "must be between {} and {}",
+            range.start(),
+            range.end()
+        ));
+    }
+    Ok(())
+}
+
+/// `http(s)://`, `ipfs://` or `ar://` URL without whitespace.
+pub fn valid_url(s: &str) -> Validation {
+    let rest = ["https://", "http://", "ipfs://", "ar://"]
+        .iter()
+        .find_map(|scheme| s.strip_prefix(scheme));
+    match rest {
+        Some(rest) if !rest.is_empty() && !rest.chars().any(char::is_whitespace) => Ok(()),
+        _ => Err("must be a http(s), ipfs or ar URL".to_string()),
+    }
+}
+
+/// `data:[<media type>][;base64],<data>`
+pub fn valid_data_uri(s: &str) -> Validation {
+    match s
+        .strip_prefix("data:")
+        .and_then(|rest| rest.split_once(','))
+    {
+        Some((media_type, _)) if !media_type.chars().any(char::is_whitespace) => Ok(()),
+        _ => Err("must be a data URI".to_string()),
+    }
+}
+
+/// Checks a field with combinators, panicking with "\`field\` reason" on the first failure.
+///
+/// # Example
+/// ```
+/// # use cmn::*;
+/// // validate!([?]field => combinator[(args..)], ..);
+/// validate::validate!(description => validate::non_empty, validate::max_len(1024));
+/// ```
+#[macro_export]
+macro_rules! validate {
+    (@CHECK $name:expr, $value:expr, $($check:ident)::+ $(($($arg:expr),*))?) => {
+        if let Err(reason) = $($check)::+($value $(, $($arg),*)?) {
+            near_sdk::require!(false, format!("`{}` {}", $name, reason));
+        }
+    };
+    (@FIELD $optional:tt $path:tt, $($($check:ident)::+ $(($($arg:expr),*))?),+ $(,)?) => {
+        let name = stringify!($path)
+            .trim_matches(|c| c == '(' || c == ')')
+            .replace(' ', "");
+        $crate::validate::validate!(@VALUE $optional $path, value, {
+            $($crate::validate::validate!(@CHECK name, value, $($check)::+ $(($($arg),*))?);)+
+        });
+    };
+    (@VALUE ? $path:tt, $value:ident, $checks:block) => {
+        if let Some($value) = &$path $checks
+    };
+    (@VALUE ! $path:tt, $value:ident, $checks:block) => {
+        let $value = &$path;
+        $checks
+    };
+    (? $field:ident $(. $rest:tt)* => $($checks:tt)+) => {
+        $crate::validate::validate!(@FIELD ? ($field $(.$rest)*), $($checks)+)
+    };
+    ($field:ident $(. $rest:tt)* => $($checks:tt)+) => {
+        $crate::validate::validate!(@FIELD ! ($field $(.$rest)*), $($checks)+)
+    };
+}
+pub use validate;
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::*;
+
+    struct Metadata {
+        name: String,
+        icon: Option<String>,
+        reference: Option<String>,
+    }
+
+    #[test]
+    fn check_combinators() {
+        assert!(valid_url("https://example.com/a.json").is_ok());
+        assert!(valid_url("ipfs://bafy").is_ok());
+        assert!(valid_url("https://").is_err());
+        assert!(valid_url("javascript:alert(1)").is_err());
+        assert!(valid_data_uri("data:image/svg+xml,%3Csvg").is_ok());
+        assert!(valid_data_uri("data:image/png;base64,iVBOR").is_ok());
+        assert!(valid_data_uri("https://example.com/icon.png").is_err());
+        assert!(range(&5u128, 1..=10).is_ok());
+        assert!(range(&0u128, 1..=10).is_err());
+    }
+
+    #[test]
+    fn check_validate() {
+        let metadata = Metadata {
+            name: "Token".to_string(),
+            icon: None,
+            reference: Some("https://example.com/a.json".to_string()),
+        };
+        validate!(metadata.name => non_empty, max_len(MAX_NAME_LENGTH));
+        validate!(?metadata.icon => valid_data_uri);
+        validate!(?metadata.reference => valid_url, max_len(MAX_URL_LENGTH));
+        let amount = 10u128;
+        validate!(amount => range(1..=10));
+    }
+
+    #[test]
+    #[should_panic(expected = "`metadata.name` can't be more than 3 bytes")]
+    fn check_validate_message() {
+        let metadata = Metadata {
+            name: "Token".to_string(),
+            icon: None,
+            reference: None,
+        };
+        validate!(metadata.name => non_empty, max_len(3));
+    }
+}
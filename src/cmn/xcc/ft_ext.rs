@@ -0,0 +1,112 @@
+#![cfg(feature = "standards")]
+#![allow(dead_code)]
+/*!
+NEP-141 views of other token contracts.
+
+`fetch_*` return the promise of the view, chain your callback with `.then(..)` and take the typed
+result with `#[callback_result]`.
+
+# NOTES:
+  - A contract method returning `PromiseOrValue<T>` can answer from a cache and only fetch
+    when it has to, see the example.
+  - The views get [`GAS_FOR_FT_VIEW`], use the `ext_ft_view` trait directly for anything else.
+
+# EXAMPLE:
+```
+# use cmn::*;
+use xcc::ft_ext::{self, FungibleTokenMetadata};
+
+#[near_bindgen]
+impl Contract {
+    pub fn token_decimals(&self, token_id: AccountId) -> PromiseOrValue<u8> {
+        if let Some(decimals) = self.decimals.get(&token_id) {
+            return PromiseOrValue::Value(*decimals);
+        }
+        ft_ext::fetch_metadata(token_id.clone())
+            .then(Self::ext(env::current_account_id()).on_token_metadata(token_id))
+            .into()
+    }
+
+    #[private]
+    pub fn on_token_metadata(
+        &mut self,
+        token_id: AccountId,
+        #[callback_result] metadata: Result<FungibleTokenMetadata, PromiseError>,
+    ) -> u8 {
+        let decimals = metadata.unwrap_or_else(|_| env::panic_str("No metadata")).decimals;
+        self.decimals.insert(token_id, decimals);
+        decimals
+    }
+}
+```
+*/
+
+use super::super::*;
+
+use near_sdk::ext_contract;
+
+pub use near_contract_standards::fungible_token::metadata::FungibleTokenMetadata;
+
+/// Gas for a NEP-141 view.
+pub const GAS_FOR_FT_VIEW: Gas = Gas(5_000_000_000_000);
+
+#[ext_contract(ext_ft_view)]
+pub trait FtView {
+    fn ft_metadata(&self) -> FungibleTokenMetadata;
+    fn ft_balance_of(&self, account_id: AccountId) -> U128;
+    fn ft_total_supply(&self) -> U128;
+}
+
+/// `ft_metadata` of the token, resolves to [`FungibleTokenMetadata`].
+#[inline]
+pub fn fetch_metadata(token_id: AccountId) -> Promise {
+    ext_ft_view::ext(token_id)
+        .with_static_gas(GAS_FOR_FT_VIEW)
+        .ft_metadata()
+}
+
+/// `ft_balance_of` the account on the token, resolves to `U128`.
+#[inline]
+pub fn fetch_balance(token_id: AccountId, account_id: AccountId) -> Promise {
+    ext_ft_view::ext(token_id)
+        .with_static_gas(GAS_FOR_FT_VIEW)
+        .ft_balance_of(account_id)
+}
+
+/// `ft_total_supply` of the token, resolves to `U128`.
+#[inline]
+pub fn fetch_total_supply(token_id: AccountId) -> Promise {
+    ext_ft_view::ext(token_id)
+        .with_static_gas(GAS_FOR_FT_VIEW)
+        .ft_total_supply()
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_fetch_balance() {
+        run_vm(vm!("alice.near"));
+
+        let _ = fetch_balance(accounts(1), accounts(2));
+
+        let receipts = get_created_receipts();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].receiver_id, accounts(1));
+        match &receipts[0].actions[0] {
+            VmAction::FunctionCall {
+                function_name,
+                args,
+                gas,
+                ..
+            } => {
+                assert_eq!(function_name, "ft_balance_of");
+                assert_eq!(args, br#"{"account_id":"charlie"}"#);
+                assert_eq!(*gas, GAS_FOR_FT_VIEW);
+            }
+            action => panic!("Unexpected action: {:?}", action),
+        }
+    }
+}
@@ -0,0 +1,160 @@
+#![cfg(feature = "known")]
+#![allow(dead_code)]
+/*!
+`#[ext_contract]` interfaces of well-known mainnet contracts.
+
+# NOTES:
+  - Only the methods integrations usually need are here, the types follow the JSON of the
+    contracts so they can be taken with `#[callback_result]`.
+  - The account ids are the mainnet ones, testnet deployments have their own.
+  - NEP-141 views of `wrap.near` are in [`super::ft_ext`].
+
+# EXAMPLE:
+```
+# use cmn::*;
+use xcc::known::{staking_pool, wrap};
+
+// wraps 1 NEAR then stakes 1 NEAR with a pool
+wrap::ext_wrap::ext(wrap::WRAP_NEAR.parse().unwrap())
+    .with_attached_deposit(10u128.pow(24))
+    .near_deposit();
+staking_pool::ext_staking_pool::ext("astro-stakers.poolv1.near".parse().unwrap())
+    .with_attached_deposit(10u128.pow(24))
+    .deposit_and_stake();
+```
+*/
+
+/// wrap.near, NEAR wrapped as NEP-141.
+pub mod wrap {
+    use super::super::super::*;
+    use near_sdk::ext_contract;
+
+    pub const WRAP_NEAR: &str = "wrap.near";
+
+    #[ext_contract(ext_wrap)]
+    pub trait Wrap {
+        /// Wraps the attached deposit.
+        fn near_deposit(&mut self);
+        /// Unwraps `amount`, requires 1 yoctoNEAR.
+        fn near_withdraw(&mut self, amount: U128) -> Promise;
+    }
+}
+
+/// Ref Finance exchange views.
+pub mod ref_finance {
+    use super::super::super::*;
+    use near_sdk::{
+        ext_contract,
+        serde::{Deserialize, Serialize},
+    };
+    use std::collections::HashMap;
+
+    pub const REF_FINANCE: &str = "v2.ref-finance.near";
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    #[serde(crate = "near_sdk::serde")]
+    pub struct PoolInfo {
+        pub pool_kind: String,
+        pub token_account_ids: Vec<AccountId>,
+        pub amounts: Vec<U128>,
+        /// Basis points.
+        pub total_fee: u32,
+        pub shares_total_supply: U128,
+        /// Amplification of the stable pools, 0 otherwise.
+        pub amp: u64,
+    }
+
+    #[ext_contract(ext_ref_finance)]
+    pub trait RefFinance {
+        fn get_number_of_pools(&self) -> u64;
+        fn get_pool(&self, pool_id: u64) -> PoolInfo;
+        fn get_pools(&self, from_index: u64, limit: u64) -> Vec<PoolInfo>;
+        /// Amount of `token_out` a swap of `amount_in` would return.
+        fn get_return(
+            &self,
+            pool_id: u64,
+            token_in: AccountId,
+            amount_in: U128,
+            token_out: AccountId,
+        ) -> U128;
+        /// Tokens the account has deposited in the exchange.
+        fn get_deposits(&self, account_id: AccountId) -> HashMap<AccountId, U128>;
+    }
+}
+
+/// priceoracle.near, the NEAR price oracle.
+pub mod price_oracle {
+    use super::super::super::*;
+    use near_sdk::{
+        ext_contract,
+        serde::{Deserialize, Serialize},
+    };
+
+    pub const PRICE_ORACLE: &str = "priceoracle.near";
+
+    /// `multiplier / 10^decimals` of the asset per smallest unit, E.g. per yoctoNEAR.
+    #[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+    #[serde(crate = "near_sdk::serde")]
+    pub struct Price {
+        pub multiplier: U128,
+        pub decimals: u8,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    #[serde(crate = "near_sdk::serde")]
+    pub struct AssetOptionalPrice {
+        pub asset_id: String,
+        pub price: Option<Price>,
+    }
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    #[serde(crate = "near_sdk::serde")]
+    pub struct PriceData {
+        /// Nanoseconds.
+        pub timestamp: U64,
+        pub recency_duration_sec: u32,
+        pub prices: Vec<AssetOptionalPrice>,
+    }
+
+    #[ext_contract(ext_price_oracle)]
+    pub trait PriceOracle {
+        /// `None` gives the prices of every asset.
+        fn get_price_data(&self, asset_ids: Option<Vec<String>>) -> PriceData;
+    }
+}
+
+/// Staking pools of the core `staking-pool` contract, E.g. `*.poolv1.near`.
+pub mod staking_pool {
+    use super::super::super::*;
+    use near_sdk::{
+        ext_contract,
+        serde::{Deserialize, Serialize},
+    };
+
+    #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+    #[serde(crate = "near_sdk::serde")]
+    pub struct HumanReadableAccount {
+        pub account_id: AccountId,
+        pub unstaked_balance: U128,
+        pub staked_balance: U128,
+        /// Whether the unstaked balance can be withdrawn.
+        pub can_withdraw: bool,
+    }
+
+    #[ext_contract(ext_staking_pool)]
+    pub trait StakingPool {
+        fn get_account(&self, account_id: AccountId) -> HumanReadableAccount;
+        fn get_account_staked_balance(&self, account_id: AccountId) -> U128;
+        fn get_account_unstaked_balance(&self, account_id: AccountId) -> U128;
+        fn get_account_total_balance(&self, account_id: AccountId) -> U128;
+        fn is_account_unstaked_balance_available(&self, account_id: AccountId) -> bool;
+
+        fn deposit(&mut self);
+        fn deposit_and_stake(&mut self);
+        fn stake(&mut self, amount: U128);
+        fn unstake(&mut self, amount: U128);
+        fn unstake_all(&mut self);
+        fn withdraw(&mut self, amount: U128);
+        fn withdraw_all(&mut self);
+    }
+}
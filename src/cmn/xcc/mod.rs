@@ -0,0 +1,11 @@
+#![allow(dead_code)]
+/*!
+Cross-contract call helpers.
+
+Typed wrappers around the views and calls of other contracts, so components don't have to
+write their own `#[ext_contract]` traits for them.
+*/
+
+pub mod ft_ext;
+pub mod known;
+pub mod results;
@@ -0,0 +1,154 @@
+#![allow(dead_code)]
+/*!
+Typed results of joined promises.
+
+`#[callback_result]` takes one result per argument, `join_results` reads the results of a
+`Promise::and` join by index instead, E.g. when the callback is shared by joins of different
+lengths or takes them from `env::promise_results_count()`.
+
+# NOTES:
+  - A result that doesn't deserialize is a [`ResultError::Invalid`] rather than a panic,
+    so a misbehaving contract can't fail the callback that has to refund or unlock.
+  - The callback panics if the number of results isn't the one of the join.
+
+# EXAMPLE:
+```
+# use cmn::*;
+use xcc::{ft_ext, results};
+
+#[near_bindgen]
+impl Contract {
+    pub fn pool_balances(&self, token_a: AccountId, token_b: AccountId) -> Promise {
+        let pool_id = env::current_account_id();
+        ft_ext::fetch_balance(token_a, pool_id.clone())
+            .and(ft_ext::fetch_balance(token_b, pool_id))
+            .then(Self::ext(env::current_account_id()).on_pool_balances())
+    }
+
+    #[private]
+    pub fn on_pool_balances(&mut self) -> (U128, U128) {
+        match results::join_results::<U128, U128>() {
+            (Ok(a), Ok(b)) => (a, b),
+            _ => env::panic_str("Can't read the balances"),
+        }
+    }
+}
+```
+*/
+
+use super::super::*;
+
+use near_sdk::serde::de::DeserializeOwned;
+use near_sdk::serde_json;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResultError {
+    /// The promise failed (or isn't ready, which the protocol never returns).
+    Failed,
+    /// The promise succeeded with a value that isn't a `T`, with the error of serde.
+    Invalid(String),
+}
+impl From<PromiseError> for ResultError {
+    #[inline]
+    fn from(_: PromiseError) -> Self {
+        Self::Failed
+    }
+}
+
+/// Result of the promise at `index`, deserialized from JSON.
+pub fn promise_result<T: DeserializeOwned>(index: u64) -> Result<T, ResultError> {
+    match env::promise_result(index) {
+        PromiseResult::Successful(bytes) => {
+            serde_json::from_slice(&bytes).map_err(|error| ResultError::Invalid(error.to_string()))
+        }
+        PromiseResult::Failed | PromiseResult::NotReady => Err(ResultError::Failed),
+    }
+}
+
+/// Whether the promise at `index` succeeded, whatever it returned.
+#[inline]
+pub fn succeeded(index: u64) -> bool {
+    matches!(env::promise_result(index), PromiseResult::Successful(_))
+}
+
+/// Panics unless the callback follows a join of `count` promises.
+#[inline]
+pub fn require_results_count(count: u64) {
+    let actual = env::promise_results_count();
+    require!(
+        actual == count,
+        format!("Expected {} promise results, got {}", count, actual)
+    );
+}
+
+/// Results of a join of two promises.
+pub fn join_results<T1, T2>() -> (Result<T1, ResultError>, Result<T2, ResultError>)
+where
+    T1: DeserializeOwned,
+    T2: DeserializeOwned,
+{
+    require_results_count(2);
+    (promise_result(0), promise_result(1))
+}
+
+/// Results of a join of three promises.
+pub fn join_results3<T1, T2, T3>() -> (
+    Result<T1, ResultError>,
+    Result<T2, ResultError>,
+    Result<T3, ResultError>,
+)
+where
+    T1: DeserializeOwned,
+    T2: DeserializeOwned,
+    T3: DeserializeOwned,
+{
+    require_results_count(3);
+    (promise_result(0), promise_result(1), promise_result(2))
+}
+
+/// Results of a join of any number of promises of the same type.
+pub fn join_all<T: DeserializeOwned>() -> Vec<Result<T, ResultError>> {
+    (0..env::promise_results_count())
+        .map(promise_result)
+        .collect()
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::super::super::test_utils::*;
+    use super::*;
+
+    #[test]
+    fn check_join_results() {
+        run_vm_with_results(
+            vm!("alice.near"),
+            vec![
+                PromiseResult::Successful(br#""100""#.to_vec()),
+                PromiseResult::Successful(b"not json".to_vec()),
+                PromiseResult::Failed,
+            ],
+        );
+
+        let (a, b, c) = join_results3::<U128, U128, U128>();
+        assert_eq!(a, Ok(U128(100)));
+        assert!(matches!(b, Err(ResultError::Invalid(_))));
+        assert_eq!(c, Err(ResultError::Failed));
+        assert_eq!(join_all::<U128>().len(), 3);
+        assert!(succeeded(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "Expected 2 promise results, got 3")]
+    fn check_results_count() {
+        run_vm_with_results(
+            vm!("alice.near"),
+            vec![
+                PromiseResult::Failed,
+                PromiseResult::Failed,
+                PromiseResult::Failed,
+            ],
+        );
+
+        let _ = join_results::<U128, U128>();
+    }
+}
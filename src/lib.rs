@@ -5,13 +5,50 @@ use cmn::*;
 #[derive(PanicOnDefault, BorshDeserialize, BorshSerialize)]
 pub struct Contract {
     // contract state
-    solution: String,
+    /// sha256 hex of the solution.
+    solution: strings::BoundedString<64>,
+    owner: ownable::Owner,
 }
 
+/// State of the contract before it had an owner, read by `migrate`.
+#[derive(BorshDeserialize, BorshSerialize)]
+struct ContractV0 {
+    solution: strings::BoundedString<64>,
+}
+
+impl_health!(Contract, owner = owner);
+impl_contract_metadata!(Contract);
+impl_method_acl!(Contract);
+ownable::impl_ownable!(Contract, owner);
+#[cfg(feature = "state_export")]
+impl_state_import!(Contract);
+#[cfg(feature = "state_schema")]
+impl_state_schema!(Contract {
+    solution: strings::BoundedString<64>,
+    owner: ownable::Owner,
+});
+
 impl Contract {
     fn hash(s: String) -> String {
         hash(s, env::sha256).encode_hex::<String>()
     }
+
+    /// Deployment manifest, see `make manifest`.
+    pub fn manifest() -> manifest::Manifest {
+        manifest::Manifest::new()
+            .init(
+                "new",
+                near_sdk::serde_json::json!({ "solution": "<sha256 hex of the solution>" }),
+            )
+            .component(Self::HEALTH_MANIFEST)
+            .component(Self::CONTRACT_METADATA_MANIFEST)
+            .component(Self::METHOD_ACL_MANIFEST)
+            .component(Self::OWNABLE_MANIFEST)
+            .method(manifest::Method::view("get_solution"))
+            .method(manifest::Method::call("set_solution").role("owner"))
+            .method(manifest::Method::private("migrate"))
+            .method(manifest::Method::view("guess_solution"))
+    }
 }
 
 #[near_bindgen]
@@ -20,19 +57,36 @@ impl Contract {
     #[init]
     pub fn new(solution: String) -> Self {
         log!("Contract initialized");
-        Self { solution }
+        Self {
+            solution: strings::BoundedString::new(solution),
+            owner: ownable::Owner::new(env::predecessor_account_id()),
+        }
+    }
+
+    /// Upgrades the state of a contract deployed before the owner was added, `owner_id` becomes
+    /// its owner.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate(owner_id: AccountId) -> Self {
+        let old = env::state_read::<ContractV0>()
+            .unwrap_or_else(|| env::panic_str("The contract is not initialized"));
+        Self {
+            solution: old.solution,
+            owner: ownable::Owner::new(owner_id),
+        }
     }
 
     pub fn get_solution(&self) -> String {
-        self.solution.clone()
+        self.solution.to_string()
     }
 
     pub fn set_solution(&mut self, solution: String) {
-        self.solution = solution;
+        self.owner.assert_owner();
+        self.solution = strings::BoundedString::new(solution);
     }
 
     pub fn guess_solution(&self, text: String) -> bool {
-        if self.solution == Self::hash(text) {
+        if self.solution.as_ref() == Self::hash(text) {
             log!("You guessed the password!");
             true
         } else {
@@ -68,4 +122,69 @@ mod tests {
         logs.assert();
         assert!(guess_result, "Expectation: This is correct");
     }
+
+    #[test]
+    fn check_health() {
+        run_vm(vm!("dohalee.testnet"));
+
+        let health = Contract::health();
+        assert!(!health.initialized);
+        assert_eq!(health.version, env!("CARGO_PKG_VERSION"));
+
+        let contract = Contract::new("solution".to_string());
+        env::state_write(&contract);
+
+        let health = Contract::health();
+        assert!(health.initialized);
+        assert!(!health.paused);
+        assert_eq!(health.owner, Some("dohalee.testnet".parse().unwrap()));
+    }
+
+    #[test]
+    fn check_contract_source_metadata() {
+        let metadata = Contract::contract_source_metadata();
+        assert_eq!(metadata.version.as_deref(), Some(env!("CARGO_PKG_VERSION")));
+        assert_eq!(metadata.standards, [events::standards::NEP330]);
+    }
+
+    #[test]
+    fn check_method_acl() {
+        let acl = Contract::method_acl();
+        assert!(acl.view_methods.contains(&"get_solution"));
+        assert_eq!(acl.change_methods, ["set_solution"]);
+        assert_eq!(
+            acl.payable_methods,
+            ["transfer_ownership", "renounce_ownership"]
+        );
+        assert_eq!(acl.roles.len(), 3);
+    }
+
+    #[test]
+    fn check_migrate() {
+        run_vm(vm!("dohalee.testnet"));
+
+        env::state_write(&ContractV0 {
+            solution: strings::BoundedString::new("solution".to_string()),
+        });
+        let contract = Contract::migrate("alice.testnet".parse().unwrap());
+        assert_eq!(contract.get_solution(), "solution");
+        assert_eq!(
+            contract.owner.owner(),
+            Some(&"alice.testnet".parse().unwrap())
+        );
+        assert!(Contract::method_acl().private_methods.contains(&"migrate"));
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn check_set_solution_owner_only() {
+        let mut vm = vm!("dohalee.testnet");
+        run_vm(&vm);
+
+        let mut contract = Contract::new("solution".to_string());
+        contract.set_solution("owner's solution".to_string());
+
+        run_vm(vm.predecessor_account_id("alice.testnet".parse().unwrap()));
+        contract.set_solution("alice's solution".to_string());
+    }
 }